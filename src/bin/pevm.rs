@@ -0,0 +1,840 @@
+//! The `pevm` command line, to execute, check, snapshot, benchmark, replay and
+//! trace blocks from an RPC provider or snapshot files, or to follow the chain
+//! head.
+//!
+//! ```sh
+//! $ pevm check --rpc https://eth.llamarpc.com --block 19426587
+//! $ pevm snapshot --rpc https://eth.llamarpc.com --block 19426587
+//! $ pevm snapshot --rpc https://eth.llamarpc.com --block 19426580 --to 19426587 --jobs 8
+//! $ pevm check --range data/ranges/19426580-19426587.bin.zst
+//! $ pevm bench --snapshot data/blocks/19426587/snapshot.bin.zst
+//! $ pevm trace --snapshot data/blocks/19426587/snapshot.bin.zst --out events.csv
+//! $ pevm follow --rpc https://eth.llamarpc.com --out blocks.jsonl
+//! $ pevm replay --snapshot data/blocks --iterations 3 --format json --out stats.json
+//! ```
+
+use std::{
+    cell::Cell,
+    env,
+    fs::{self, File, OpenOptions},
+    io::{self, BufWriter, Write},
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use alloy_primitives::{Bloom, B256};
+use alloy_provider::{Provider, ProviderBuilder};
+use alloy_rpc_types::{Block, BlockId, BlockTransactionsKind};
+use pevm::{
+    chain::{PevmChain, PevmEthereum},
+    BlockRangeSnapshot, BlockSnapshot, CommittedStorage, FallbackReason, Pevm,
+    PevmBlockExecutionResult, PevmStrategy, PevmTxExecutionResult, RpcStorage, Storage, TaskEvent,
+};
+use reqwest::Url;
+use tokio::runtime::Runtime;
+
+const USAGE: &str = "\
+Usage: pevm <COMMAND> [FLAGS]
+
+Commands:
+  execute   Execute a block and report its gas and time
+  check     Check that parallel execution matches sequential execution and the block header
+  snapshot  Fetch a block or a range of blocks from RPC and write a snapshot of it
+  bench     Benchmark sequential against parallel execution
+  replay    Benchmark blocks like bench and write their statistics as CSV or JSON
+  trace     Record the timed tasks of a parallel execution as CSV
+  follow    Follow the chain head from RPC, checking each new block on top of the previous ones
+
+Flags:
+  --chain <CHAIN>        The chain of the block [default: mainnet]
+  --rpc <URL>            Fetch the block and its state from an RPC provider
+  --block <NUMBER>       The block to fetch from RPC [default: the latest block for follow]
+  --to <NUMBER>          The last block of a range to fetch from RPC (snapshot, follow, replay)
+  --jobs <N>             The number of blocks to fetch concurrently (snapshot) [default: 4]
+  --snapshot <PATH>      Read the block and its state from a snapshot file, or the blocks of
+                         a directory of snapshots (replay)
+  --range <PATH>         Read consecutive blocks and their state from a range snapshot (check, replay)
+  --concurrency <LEVEL>  The number of worker threads [default: available parallelism]
+  --sequential           Execute sequentially (execute)
+  --iterations <N>       The number of timed runs (bench, replay) [default: 10]
+  --format <FORMAT>      The format of the statistics, csv or json (replay) [default: csv]
+  --out <PATH>           The output file (snapshot, trace, follow, replay)
+                         [default: stdout for trace and replay]";
+
+// The interval to poll the RPC provider for the next block when following
+// the chain head.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+// The flags shared by all commands.
+struct Flags {
+    chain: String,
+    rpc_url: Option<Url>,
+    block_number: Option<u64>,
+    last_block_number: Option<u64>,
+    jobs: NonZeroUsize,
+    snapshot_path: Option<PathBuf>,
+    range_path: Option<PathBuf>,
+    concurrency_level: NonZeroUsize,
+    sequential: bool,
+    iterations: usize,
+    json: bool,
+    out_path: Option<PathBuf>,
+}
+
+impl Flags {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut flags = Flags {
+            chain: String::from("mainnet"),
+            rpc_url: None,
+            block_number: None,
+            last_block_number: None,
+            jobs: NonZeroUsize::new(4).unwrap(),
+            snapshot_path: None,
+            range_path: None,
+            concurrency_level: thread::available_parallelism().unwrap_or(NonZeroUsize::MIN),
+            sequential: false,
+            iterations: 10,
+            json: false,
+            out_path: None,
+        };
+        while let Some(flag) = args.next() {
+            if flag == "--sequential" {
+                flags.sequential = true;
+                continue;
+            }
+            let value = args
+                .next()
+                .ok_or_else(|| format!("missing value for {flag}"))?;
+            let invalid =
+                |err: &dyn ToString| format!("invalid {flag} {value}: {}", err.to_string());
+            match flag.as_str() {
+                "--chain" => flags.chain = value,
+                "--rpc" => flags.rpc_url = Some(value.parse().map_err(|err| invalid(&err))?),
+                "--block" => flags.block_number = Some(value.parse().map_err(|err| invalid(&err))?),
+                "--to" => {
+                    flags.last_block_number = Some(value.parse().map_err(|err| invalid(&err))?)
+                }
+                "--jobs" => flags.jobs = value.parse().map_err(|err| invalid(&err))?,
+                "--snapshot" => flags.snapshot_path = Some(value.into()),
+                "--range" => flags.range_path = Some(value.into()),
+                "--concurrency" => {
+                    flags.concurrency_level = value.parse().map_err(|err| invalid(&err))?
+                }
+                "--iterations" => flags.iterations = value.parse().map_err(|err| invalid(&err))?,
+                "--format" => match value.as_str() {
+                    "csv" => flags.json = false,
+                    "json" => flags.json = true,
+                    _ => return Err(invalid(&"expected csv or json")),
+                },
+                "--out" => flags.out_path = Some(value.into()),
+                _ => return Err(format!("unknown flag {flag}")),
+            }
+        }
+        Ok(flags)
+    }
+
+    fn chain(&self) -> Result<PevmEthereum, String> {
+        match self.chain.as_str() {
+            "mainnet" => Ok(PevmEthereum::mainnet()),
+            chain => Err(format!("unsupported chain {chain}")),
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let mut args = env::args().skip(1);
+    let Some(command) = args.next() else {
+        eprintln!("{USAGE}");
+        return ExitCode::FAILURE;
+    };
+    if command == "help" || command == "--help" || command == "-h" {
+        println!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+    match Flags::parse(args).and_then(|flags| run(&command, &flags)) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {err}\n\n{USAGE}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(command: &str, flags: &Flags) -> Result<(), String> {
+    let chain = flags.chain()?;
+    if command == "snapshot" {
+        return snapshot(flags, &chain);
+    }
+    if command == "follow" {
+        return follow(flags, &chain);
+    }
+    if command == "replay" {
+        return replay(flags, &chain);
+    }
+    if let Some(range_path) = &flags.range_path {
+        if command != "check" {
+            return Err(format!("--range isn't supported by {command}"));
+        }
+        return check_range(flags, &chain, range_path);
+    }
+    match (&flags.snapshot_path, &flags.rpc_url) {
+        (Some(snapshot_path), None) => {
+            let snapshot = BlockSnapshot::read(snapshot_path).map_err(|err| err.to_string())?;
+            run_on(
+                command,
+                flags,
+                &chain,
+                snapshot.block.clone(),
+                &snapshot.storage(),
+            )
+        }
+        (None, Some(rpc_url)) => {
+            let block_number = flags.block_number.ok_or("expected --block with --rpc")?;
+            let (block, storage) = fetch_block(rpc_url, block_number, &chain)?;
+            run_on(command, flags, &chain, block, &storage)
+        }
+        _ => Err(String::from("expected either --snapshot or --rpc")),
+    }
+}
+
+fn run_on<S: Storage + Send + Sync>(
+    command: &str,
+    flags: &Flags,
+    chain: &PevmEthereum,
+    block: Block,
+    storage: &S,
+) -> Result<(), String> {
+    match command {
+        "execute" => execute(flags, chain, block, storage),
+        "check" => check(flags, chain, block, storage),
+        "bench" => bench(flags, chain, block, storage),
+        "trace" => trace(flags, chain, block, storage),
+        _ => Err(format!("unknown command {command}")),
+    }
+}
+
+// Fetch a block with its transactions and a storage of the state before it.
+fn fetch_block(
+    rpc_url: &Url,
+    block_number: u64,
+    chain: &PevmEthereum,
+) -> Result<(Block, RpcStorage<impl alloy_provider::Network>), String> {
+    let runtime = Runtime::new().map_err(|err| err.to_string())?;
+    let provider = ProviderBuilder::new().on_http(rpc_url.clone());
+    let block = runtime
+        .block_on(provider.get_block(BlockId::number(block_number), BlockTransactionsKind::Full))
+        .map_err(|err| err.to_string())?
+        .ok_or_else(|| format!("block {block_number} not found"))?;
+    let spec_id = chain
+        .get_block_spec(&block.header)
+        .map_err(|err| format!("{err:?}"))?;
+    let storage = RpcStorage::new(
+        provider,
+        spec_id,
+        BlockId::number(block_number.saturating_sub(1)),
+    );
+    Ok((block, storage))
+}
+
+// The gas used by the block's transactions.
+fn block_gas_used(block_result: &PevmBlockExecutionResult) -> u128 {
+    block_result
+        .tx_results
+        .last()
+        .map(|tx_result| tx_result.receipt.cumulative_gas_used())
+        .unwrap_or_default()
+}
+
+fn execute<S: Storage + Send + Sync>(
+    flags: &Flags,
+    chain: &PevmEthereum,
+    block: Block,
+    storage: &S,
+) -> Result<(), String> {
+    let block_number = block.header.number.unwrap_or_default();
+    let mut pevm = Pevm::default();
+    let started_at = Instant::now();
+    let block_result = pevm
+        .execute(
+            storage,
+            chain,
+            block,
+            flags.concurrency_level,
+            flags.sequential,
+        )
+        .map_err(|err| err.to_string())?;
+    let elapsed = started_at.elapsed();
+    let gas_used = block_gas_used(&block_result);
+    println!(
+        "block {block_number}: {} txs, {gas_used} gas in {elapsed:?} ({:.3} Ggas/s)",
+        block_result.tx_results.len(),
+        gas_used as f64 / elapsed.as_secs_f64() / 1e9
+    );
+    if let Some(fallback) = pevm.fallback() {
+        println!(
+            "fell back to sequential execution at transaction {}: {:?}",
+            fallback.tx_idx, fallback.reason
+        );
+    }
+    Ok(())
+}
+
+// Check that parallel execution matches sequential execution, and that the
+// results match the block header.
+fn check<S: Storage + Send + Sync>(
+    flags: &Flags,
+    chain: &PevmEthereum,
+    block: Block,
+    storage: &S,
+) -> Result<(), String> {
+    let block_number = block.header.number.unwrap_or_default();
+    let block_result = check_block(flags, chain, block, storage)?;
+    println!(
+        "block {block_number}: ok ({} txs)",
+        block_result.tx_results.len()
+    );
+    Ok(())
+}
+
+fn check_block<S: Storage + Send + Sync>(
+    flags: &Flags,
+    chain: &PevmEthereum,
+    block: Block,
+    storage: &S,
+) -> Result<PevmBlockExecutionResult, String> {
+    let block_number = block.header.number.unwrap_or_default();
+    let mut pevm = Pevm::default();
+    let sequential_result =
+        pevm.execute(storage, chain, block.clone(), flags.concurrency_level, true);
+    let parallel_result = pevm.execute(
+        storage,
+        chain,
+        block.clone(),
+        flags.concurrency_level,
+        false,
+    );
+    if sequential_result != parallel_result {
+        return Err(format!(
+            "block {block_number}: parallel execution doesn't match sequential execution"
+        ));
+    }
+    let block_result = sequential_result.map_err(|err| err.to_string())?;
+    check_header(chain, &block, &block_result.tx_results)?;
+    Ok(block_result)
+}
+
+// Check that the results of a block match its header.
+fn check_header(
+    chain: &PevmEthereum,
+    block: &Block,
+    tx_results: &[PevmTxExecutionResult],
+) -> Result<(), String> {
+    let header = &block.header;
+    let block_number = header.number.unwrap_or_default();
+    let spec_id = chain
+        .get_block_spec(header)
+        .map_err(|err| format!("{err:?}"))?;
+    // We can only calculate the receipts root from Byzantium, as receipts
+    // had post-transaction state roots before EIP-658.
+    if block_number >= 4370000 {
+        let receipts_root = chain.calculate_receipt_root(spec_id, &block.transactions, tx_results);
+        if receipts_root != header.receipts_root {
+            return Err(format!(
+                "block {block_number}: receipts root {receipts_root} doesn't match {}",
+                header.receipts_root
+            ));
+        }
+    }
+    let logs_bloom = tx_results
+        .iter()
+        .fold(Bloom::default(), |bloom, tx_result| {
+            bloom.bit_or(*tx_result.receipt.logs_bloom())
+        });
+    if logs_bloom != header.logs_bloom {
+        return Err(format!("block {block_number}: logs bloom doesn't match"));
+    }
+    let gas_used: u128 = tx_results
+        .iter()
+        .map(|tx_result| tx_result.gas_used as u128)
+        .sum();
+    if gas_used != header.gas_used {
+        return Err(format!(
+            "block {block_number}: gas used {gas_used} doesn't match {}",
+            header.gas_used
+        ));
+    }
+    Ok(())
+}
+
+// Execute the blocks of a range snapshot in order on top of each other,
+// checking each against its header.
+fn check_range(flags: &Flags, chain: &PevmEthereum, range_path: &Path) -> Result<(), String> {
+    let range = BlockRangeSnapshot::read(range_path).map_err(|err| err.to_string())?;
+    let Some(first_block_number) = range.blocks.first().and_then(|block| block.header.number)
+    else {
+        return Err(String::from("the range snapshot has no blocks"));
+    };
+    let block_at = |block_number: u64| -> Result<&Block, String> {
+        range
+            .blocks
+            .get((block_number - first_block_number) as usize)
+            .ok_or_else(|| format!("block {block_number} is out of the range"))
+    };
+    Pevm::default()
+        .execute_range_of_blocks(
+            &range.storage(),
+            chain,
+            first_block_number..first_block_number + range.blocks.len() as u64,
+            |block_number| block_at(block_number).cloned(),
+            |header, block_result| {
+                let block_number = header.number.unwrap_or_default();
+                check_header(chain, block_at(block_number)?, &block_result.tx_results)?;
+                println!(
+                    "block {block_number}: ok ({} txs)",
+                    block_result.tx_results.len()
+                );
+                Ok(())
+            },
+            flags.concurrency_level,
+        )
+        .map_err(|err| err.to_string())
+}
+
+// Fetch a block from RPC and check it, which fetches the state it reads
+// into the storage's caches to snapshot.
+fn snapshot_block(
+    flags: &Flags,
+    chain: &PevmEthereum,
+    rpc_url: &Url,
+    block_number: u64,
+) -> Result<(BlockSnapshot, PevmBlockExecutionResult), String> {
+    let (block, storage) = fetch_block(rpc_url, block_number, chain)?;
+    let block_result = check_block(flags, chain, block.clone(), &storage)?;
+    let mut bytecodes = storage.get_cache_bytecodes();
+    let mut accounts = storage.get_cache_accounts();
+    for account in accounts.values_mut() {
+        if let (Some(code_hash), Some(code)) = (account.code_hash, account.code.take()) {
+            bytecodes.insert(code_hash, code);
+        }
+    }
+    let snapshot = BlockSnapshot {
+        block,
+        accounts,
+        bytecodes,
+        block_hashes: storage.get_cache_block_hashes(),
+    };
+    Ok((snapshot, block_result))
+}
+
+// Write a snapshot of a block, or of a range of blocks fetched concurrently.
+fn snapshot(flags: &Flags, chain: &PevmEthereum) -> Result<(), String> {
+    let rpc_url = flags.rpc_url.as_ref().ok_or("expected --rpc to snapshot")?;
+    let block_number = flags.block_number.ok_or("expected --block to snapshot")?;
+    let out_path = |default_dir: String, default_file: &str| match &flags.out_path {
+        Some(out_path) => Ok(out_path.clone()),
+        None => {
+            let dir = PathBuf::from(default_dir);
+            fs::create_dir_all(&dir).map_err(|err| err.to_string())?;
+            Ok::<_, String>(dir.join(default_file))
+        }
+    };
+
+    let Some(last_block_number) = flags.last_block_number else {
+        let (snapshot, _) = snapshot_block(flags, chain, rpc_url, block_number)?;
+        let out_path = out_path(format!("data/blocks/{block_number}"), "snapshot.bin.zst")?;
+        snapshot.write(&out_path).map_err(|err| err.to_string())?;
+        println!("wrote {}", out_path.display());
+        return Ok(());
+    };
+    if last_block_number < block_number {
+        return Err(format!(
+            "the last block {last_block_number} is before block {block_number}"
+        ));
+    }
+
+    // Workers take the next block to fetch until all are fetched, as blocks
+    // take very different times to fetch.
+    let block_numbers: Vec<u64> = (block_number..=last_block_number).collect();
+    let next_idx = AtomicUsize::new(0);
+    let snapshots: Vec<_> = block_numbers.iter().map(|_| Mutex::new(None)).collect();
+    thread::scope(|scope| {
+        for _ in 0..flags.jobs.get().min(block_numbers.len()) {
+            scope.spawn(|| {
+                while let Some(block_number) =
+                    block_numbers.get(next_idx.fetch_add(1, Ordering::Relaxed))
+                {
+                    let snapshot = snapshot_block(flags, chain, rpc_url, *block_number);
+                    let idx = (block_number - block_numbers[0]) as usize;
+                    *snapshots[idx].lock().unwrap() = Some(snapshot);
+                }
+            });
+        }
+    });
+    let snapshots = snapshots
+        .into_iter()
+        .map(|snapshot| snapshot.into_inner().unwrap().unwrap())
+        .collect::<Result<Vec<_>, _>>()?;
+    let range =
+        BlockRangeSnapshot::from_block_snapshots(snapshots).map_err(|err| err.to_string())?;
+    let out_path = out_path(
+        String::from("data/ranges"),
+        &format!("{block_number}-{last_block_number}.bin.zst"),
+    )?;
+    range.write(&out_path).map_err(|err| err.to_string())?;
+    println!(
+        "wrote {} blocks to {}",
+        range.blocks.len(),
+        out_path.display()
+    );
+    Ok(())
+}
+
+// Follow the chain head from RPC, executing each new block on top of the
+// changes of the previous blocks over the state before the first block. Each
+// block is checked against its header, then a summary of it is appended to
+// the output file as a JSON line.
+fn follow(flags: &Flags, chain: &PevmEthereum) -> Result<(), String> {
+    let rpc_url = flags.rpc_url.as_ref().ok_or("expected --rpc to follow")?;
+    let runtime = Runtime::new().map_err(|err| err.to_string())?;
+    let provider = ProviderBuilder::new().on_http(rpc_url.clone());
+    let first_block_number = match flags.block_number {
+        Some(block_number) => block_number,
+        None => runtime
+            .block_on(provider.get_block_number())
+            .map_err(|err| err.to_string())?,
+    };
+    let last_block_number = flags.last_block_number.unwrap_or(u64::MAX - 1);
+    // Wait until the provider has the block, as the block may be ahead of
+    // the chain head.
+    let wait_for_block = |block_number: u64| loop {
+        let block = runtime
+            .block_on(
+                provider.get_block(BlockId::number(block_number), BlockTransactionsKind::Full),
+            )
+            .map_err(|err| err.to_string())?;
+        match block {
+            Some(block) => return Ok::<_, String>(block),
+            None => thread::sleep(POLL_INTERVAL),
+        }
+    };
+    let first_block = wait_for_block(first_block_number)?;
+    let spec_id = chain
+        .get_block_spec(&first_block.header)
+        .map_err(|err| format!("{err:?}"))?;
+    let storage = RpcStorage::new(
+        provider.clone(),
+        spec_id,
+        BlockId::number(first_block_number.saturating_sub(1)),
+    );
+    let mut out_file = match &flags.out_path {
+        Some(out_path) => Some(
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(out_path)
+                .map_err(|err| err.to_string())?,
+        ),
+        None => None,
+    };
+
+    let mut first_block = Some(first_block);
+    // The last fetched block, to check its results against its header.
+    let fetched_block = Cell::new(None::<Block>);
+    let parent_hash = Cell::new(None::<B256>);
+    let fetched_at = Cell::new(Instant::now());
+    Pevm::default()
+        .execute_range_of_blocks(
+            &storage,
+            chain,
+            first_block_number..last_block_number + 1,
+            |block_number| {
+                let block = match first_block.take() {
+                    Some(block) => block,
+                    None => wait_for_block(block_number)?,
+                };
+                // The blocks are executed on top of each other, so we can't
+                // follow a reorg of the blocks we already executed.
+                if let Some(parent_hash) = parent_hash.get() {
+                    if block.header.parent_hash != parent_hash {
+                        return Err(format!(
+                            "block {block_number}: reorg of the followed blocks"
+                        ));
+                    }
+                }
+                fetched_block.set(Some(block.clone()));
+                fetched_at.set(Instant::now());
+                Ok(block)
+            },
+            |header, block_result| {
+                let elapsed = fetched_at.get().elapsed();
+                let block_number = header.number.unwrap_or_default();
+                let block = fetched_block
+                    .take()
+                    .ok_or_else(|| format!("block {block_number} wasn't fetched"))?;
+                check_header(chain, &block, &block_result.tx_results)?;
+                parent_hash.set(header.hash);
+                let gas_used = block_gas_used(&block_result);
+                println!(
+                    "block {block_number}: ok ({} txs, {gas_used} gas in {elapsed:?})",
+                    block_result.tx_results.len()
+                );
+                if let Some(out_file) = &mut out_file {
+                    let summary = serde_json::json!({
+                        "number": block_number,
+                        "hash": header.hash,
+                        "txs": block_result.tx_results.len(),
+                        "gas_used": gas_used as u64,
+                        "elapsed_ns": elapsed.as_nanos() as u64,
+                    });
+                    writeln!(out_file, "{summary}").map_err(|err| err.to_string())?;
+                }
+                Ok(())
+            },
+            flags.concurrency_level,
+        )
+        .map_err(|err| err.to_string())
+}
+
+// The median of some durations.
+fn median(mut durations: Vec<Duration>) -> Duration {
+    durations.sort_unstable();
+    durations[durations.len() / 2]
+}
+
+fn bench<S: Storage + Send + Sync>(
+    flags: &Flags,
+    chain: &PevmEthereum,
+    block: Block,
+    storage: &S,
+) -> Result<(), String> {
+    let (stats, _) = time_block(flags, chain, block, storage)?;
+    println!(
+        "block {}: {} gas, sequential {:?}, parallel {:?} ({:.2}x)",
+        stats.block_number,
+        stats.gas_used,
+        stats.sequential_time,
+        stats.parallel_time,
+        stats.speedup()
+    );
+    Ok(())
+}
+
+// The statistics of a benchmarked block.
+struct BlockStats {
+    block_number: u64,
+    txs: usize,
+    gas_used: u128,
+    sequential_time: Duration,
+    parallel_time: Duration,
+    aborts: usize,
+    fallback: Option<&'static str>,
+}
+
+impl BlockStats {
+    const CSV_HEADER: &'static str =
+        "block_number,txs,gas_used,sequential_ns,parallel_ns,speedup,aborts,fallback";
+
+    fn speedup(&self) -> f64 {
+        self.sequential_time.as_secs_f64() / self.parallel_time.as_secs_f64()
+    }
+
+    fn to_csv_row(&self) -> String {
+        format!(
+            "{},{},{},{},{},{:.3},{},{}",
+            self.block_number,
+            self.txs,
+            self.gas_used,
+            self.sequential_time.as_nanos(),
+            self.parallel_time.as_nanos(),
+            self.speedup(),
+            self.aborts,
+            self.fallback.unwrap_or_default()
+        )
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "block_number": self.block_number,
+            "txs": self.txs,
+            "gas_used": self.gas_used as u64,
+            "sequential_ns": self.sequential_time.as_nanos() as u64,
+            "parallel_ns": self.parallel_time.as_nanos() as u64,
+            "speedup": self.speedup(),
+            "aborts": self.aborts,
+            "fallback": self.fallback,
+        })
+    }
+}
+
+// Time the median sequential and parallel executions of a block, with the
+// aborts and fallback of the last parallel execution.
+fn time_block<S: Storage + Send + Sync>(
+    flags: &Flags,
+    chain: &PevmEthereum,
+    block: Block,
+    storage: &S,
+) -> Result<(BlockStats, PevmBlockExecutionResult), String> {
+    if flags.iterations == 0 {
+        return Err(String::from("expected at least one iteration"));
+    }
+    let block_number = block.header.number.unwrap_or_default();
+    let mut pevm = Pevm::default().with_strategy(PevmStrategy {
+        record_report: true,
+        ..PevmStrategy::default()
+    });
+    let mut time = |force_sequential| {
+        let started_at = Instant::now();
+        pevm.execute(
+            storage,
+            chain,
+            block.clone(),
+            flags.concurrency_level,
+            force_sequential,
+        )
+        .map(|block_result| (started_at.elapsed(), block_result))
+        .map_err(|err| err.to_string())
+    };
+    // Warm up storage caches, like of RPC storage.
+    let (_, block_result) = time(true)?;
+    let mut sequential_times = Vec::with_capacity(flags.iterations);
+    let mut parallel_times = Vec::with_capacity(flags.iterations);
+    for _ in 0..flags.iterations {
+        sequential_times.push(time(true)?.0);
+        parallel_times.push(time(false)?.0);
+    }
+    let stats = BlockStats {
+        block_number,
+        txs: block_result.tx_results.len(),
+        gas_used: block_gas_used(&block_result),
+        sequential_time: median(sequential_times),
+        parallel_time: median(parallel_times),
+        aborts: pevm.report().map_or(0, |report| {
+            report
+                .txs
+                .iter()
+                .map(|tx_report| tx_report.aborts.len())
+                .sum()
+        }),
+        fallback: pevm.fallback().map(|fallback| match fallback.reason {
+            FallbackReason::ReadError(_) => "read_error",
+            FallbackReason::RetriesExhausted => "retries_exhausted",
+            FallbackReason::MemoryBudgetExceeded => "memory_budget_exceeded",
+        }),
+    };
+    Ok((stats, block_result))
+}
+
+// Benchmark the blocks of a snapshot file or directory, of a range snapshot
+// on top of each other, or of a range of blocks from RPC, and write their
+// statistics for regression tracking and plotting.
+fn replay(flags: &Flags, chain: &PevmEthereum) -> Result<(), String> {
+    let mut all_stats = Vec::new();
+    if let Some(range_path) = &flags.range_path {
+        let range = BlockRangeSnapshot::read(range_path).map_err(|err| err.to_string())?;
+        let range_storage = range.storage();
+        let mut committed_storage = CommittedStorage::new(&range_storage, &[]);
+        for block in &range.blocks {
+            let block_number = block.header.number.unwrap_or_default();
+            let block_hash = block.header.hash;
+            let (stats, block_result) =
+                time_block(flags, chain, block.clone(), &committed_storage)?;
+            eprintln!("block {block_number}: {:.2}x", stats.speedup());
+            all_stats.push(stats);
+            committed_storage.commit_block(&block_result);
+            if let Some(block_hash) = block_hash {
+                committed_storage.commit_block_hash(block_number, block_hash);
+            }
+        }
+    } else if let Some(snapshot_path) = &flags.snapshot_path {
+        let snapshot_paths = if snapshot_path.is_dir() {
+            let mut snapshot_paths = Vec::new();
+            for entry in fs::read_dir(snapshot_path).map_err(|err| err.to_string())? {
+                let path = entry.map_err(|err| err.to_string())?.path();
+                if path.join("snapshot.bin.zst").exists() {
+                    snapshot_paths.push(path.join("snapshot.bin.zst"));
+                }
+            }
+            snapshot_paths
+        } else {
+            vec![snapshot_path.clone()]
+        };
+        for snapshot_path in snapshot_paths {
+            let snapshot = BlockSnapshot::read(&snapshot_path).map_err(|err| err.to_string())?;
+            let (stats, _) = time_block(flags, chain, snapshot.block.clone(), &snapshot.storage())?;
+            eprintln!("block {}: {:.2}x", stats.block_number, stats.speedup());
+            all_stats.push(stats);
+        }
+        all_stats.sort_by_key(|stats| stats.block_number);
+    } else if let Some(rpc_url) = &flags.rpc_url {
+        let block_number = flags.block_number.ok_or("expected --block with --rpc")?;
+        for block_number in block_number..=flags.last_block_number.unwrap_or(block_number) {
+            let (block, storage) = fetch_block(rpc_url, block_number, chain)?;
+            let (stats, _) = time_block(flags, chain, block, &storage)?;
+            eprintln!("block {block_number}: {:.2}x", stats.speedup());
+            all_stats.push(stats);
+        }
+    } else {
+        return Err(String::from("expected --snapshot, --range or --rpc"));
+    }
+
+    let mut writer: Box<dyn Write> = match &flags.out_path {
+        Some(out_path) => Box::new(BufWriter::new(
+            File::create(out_path).map_err(|err| err.to_string())?,
+        )),
+        None => Box::new(io::stdout().lock()),
+    };
+    if flags.json {
+        let all_stats: Vec<_> = all_stats.iter().map(BlockStats::to_json).collect();
+        serde_json::to_writer_pretty(&mut writer, &all_stats).map_err(|err| err.to_string())?;
+        writeln!(writer).map_err(|err| err.to_string())?;
+    } else {
+        writeln!(writer, "{}", BlockStats::CSV_HEADER).map_err(|err| err.to_string())?;
+        for stats in &all_stats {
+            writeln!(writer, "{}", stats.to_csv_row()).map_err(|err| err.to_string())?;
+        }
+    }
+    writer.flush().map_err(|err| err.to_string())
+}
+
+fn trace<S: Storage + Send + Sync>(
+    flags: &Flags,
+    chain: &PevmEthereum,
+    block: Block,
+    storage: &S,
+) -> Result<(), String> {
+    let mut pevm = Pevm::default().with_strategy(PevmStrategy {
+        record_report: true,
+        record_events: true,
+        ..PevmStrategy::default()
+    });
+    pevm.execute(storage, chain, block, flags.concurrency_level, false)
+        .map_err(|err| err.to_string())?;
+    let events = pevm
+        .take_events()
+        .ok_or("the block didn't execute in parallel")?;
+    let mut writer: Box<dyn Write> = match &flags.out_path {
+        Some(out_path) => Box::new(BufWriter::new(
+            File::create(out_path).map_err(|err| err.to_string())?,
+        )),
+        None => Box::new(io::stdout().lock()),
+    };
+    writeln!(writer, "{}", TaskEvent::CSV_HEADER).map_err(|err| err.to_string())?;
+    for event in &events {
+        writeln!(writer, "{}", event.to_csv_row()).map_err(|err| err.to_string())?;
+    }
+    writer.flush().map_err(|err| err.to_string())?;
+    if let Some(speedup) = pevm
+        .report()
+        .and_then(|report| report.theoretical_speedup())
+    {
+        eprintln!("{} tasks, theoretical speedup {speedup:.2}x", events.len());
+    }
+    Ok(())
+}
@@ -0,0 +1,230 @@
+//! The block representations that PEVM executes, like Alloy RPC blocks and
+//! consensus blocks from a node's database, devp2p or era files, without
+//! converting between them.
+
+use std::{num::NonZeroUsize, thread};
+
+use alloy_consensus::{Header as ConsensusHeader, TxEnvelope, TxType};
+use alloy_primitives::{Address, U256};
+use alloy_rlp::Decodable;
+use alloy_rpc_types::{Block, BlockTransactions, Header, Withdrawal};
+use revm::primitives::{SpecId, TxEnv};
+
+use crate::{
+    chain::PevmChain,
+    compat::{get_rpc_header, get_rpc_transaction, get_tx_env, recover_signer},
+    PevmError,
+};
+
+/// The parts of a block that PEVM executes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockParts {
+    /// The header in the Alloy RPC format, that chains derive the spec and
+    /// irregular state changes from.
+    pub header: Header,
+    /// The types of the transactions, to tag their receipts with.
+    pub tx_types: Vec<Option<TxType>>,
+    /// The REVM envs of the transactions, with their senders.
+    pub txs: Vec<TxEnv>,
+    /// The headers of the block's ommers to credit the block and ommer
+    /// rewards with, [None] to not credit them.
+    pub ommers: Option<Vec<Header>>,
+    /// The withdrawals of the block since Shanghai.
+    pub withdrawals: Option<Vec<Withdrawal>>,
+}
+
+/// A block representation that PEVM can execute via
+/// [crate::Pevm::execute_block].
+pub trait BlockInput {
+    /// Split the block into the parts to execute, like parsing its
+    /// transactions into REVM envs.
+    fn into_parts<C: PevmChain>(self, chain: &C) -> Result<BlockParts, PevmError<C>>;
+
+    /// Recover the senders of the transactions that lack them from their
+    /// signatures, with up to [concurrency_level] threads. [crate::Pevm]
+    /// calls this before [BlockInput::into_parts], which otherwise recovers
+    /// them on the calling thread.
+    fn recover_senders<C: PevmChain>(
+        &mut self,
+        _chain: &C,
+        _concurrency_level: NonZeroUsize,
+    ) -> Result<(), PevmError<C>> {
+        Ok(())
+    }
+}
+
+impl BlockInput for Block {
+    fn into_parts<C: PevmChain>(self, chain: &C) -> Result<BlockParts, PevmError<C>> {
+        let BlockTransactions::Full(txs) = self.transactions else {
+            return Err(PevmError::MissingTransactionData);
+        };
+        let tx_types = txs
+            .iter()
+            .map(|tx| TxType::try_from(tx.transaction_type.unwrap_or_default()).ok())
+            .collect();
+        let txs = txs
+            .into_iter()
+            .map(|tx| get_tx_env(chain, tx))
+            .collect::<Result<_, _>>()
+            .map_err(PevmError::InvalidTransaction)?;
+        Ok(BlockParts {
+            header: self.header,
+            tx_types,
+            txs,
+            // The RPC format only includes the ommers' hashes.
+            ommers: None,
+            withdrawals: self.withdrawals,
+        })
+    }
+}
+
+/// A block in the consensus format, like from a node's database, devp2p or
+/// era files. Its block and ommer rewards are always credited.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConsensusBlock {
+    /// The block header.
+    pub header: ConsensusHeader,
+    /// The total difficulty of the chain up to this block, which chains
+    /// derive specs from but the consensus format lacks.
+    pub total_difficulty: U256,
+    /// The signed transactions.
+    pub transactions: Vec<TxEnvelope>,
+    /// The senders of the transactions if already known, [None] to recover
+    /// them from the signatures.
+    pub senders: Option<Vec<Address>>,
+    /// The ommer headers.
+    pub ommers: Vec<ConsensusHeader>,
+    /// The withdrawals since Shanghai.
+    pub withdrawals: Option<Vec<Withdrawal>>,
+}
+
+impl ConsensusBlock {
+    /// Decode a block of the consensus RLP format with the total difficulty
+    /// of the chain up to it.
+    pub fn decode(mut buf: &[u8], total_difficulty: U256) -> Result<Self, alloy_rlp::Error> {
+        let rlp_header = alloy_rlp::Header::decode(&mut buf)?;
+        if !rlp_header.list {
+            return Err(alloy_rlp::Error::UnexpectedString);
+        }
+        if buf.len() != rlp_header.payload_length {
+            return Err(alloy_rlp::Error::UnexpectedLength);
+        }
+        let header = ConsensusHeader::decode(&mut buf)?;
+        let transactions = Vec::<TxEnvelope>::decode(&mut buf)?;
+        let ommers = Vec::<ConsensusHeader>::decode(&mut buf)?;
+        // Withdrawals are only encoded since Shanghai.
+        let withdrawals = if buf.is_empty() {
+            None
+        } else {
+            Some(Vec::<Withdrawal>::decode(&mut buf)?)
+        };
+        if !buf.is_empty() {
+            return Err(alloy_rlp::Error::UnexpectedLength);
+        }
+        Ok(Self {
+            header,
+            total_difficulty,
+            transactions,
+            senders: None,
+            ommers,
+            withdrawals,
+        })
+    }
+}
+
+impl BlockInput for ConsensusBlock {
+    fn into_parts<C: PevmChain>(mut self, chain: &C) -> Result<BlockParts, PevmError<C>> {
+        self.recover_senders(chain, NonZeroUsize::MIN)?;
+        let senders = match self.senders {
+            Some(senders) if senders.len() == self.transactions.len() => senders,
+            _ => return Err(PevmError::MissingTransactionData),
+        };
+        let tx_types = self
+            .transactions
+            .iter()
+            .map(|tx| Some(tx.tx_type()))
+            .collect();
+        let txs = self
+            .transactions
+            .into_iter()
+            .zip(senders)
+            .map(|(tx, sender)| get_tx_env(chain, get_rpc_transaction(tx, sender)))
+            .collect::<Result<_, _>>()
+            .map_err(PevmError::InvalidTransaction)?;
+        Ok(BlockParts {
+            header: get_rpc_header(self.header, Some(self.total_difficulty)),
+            tx_types,
+            txs,
+            ommers: Some(
+                self.ommers
+                    .into_iter()
+                    .map(|ommer| get_rpc_header(ommer, None))
+                    .collect(),
+            ),
+            withdrawals: self.withdrawals,
+        })
+    }
+
+    // Recover the senders in consecutive chunks of transactions on scoped
+    // threads, like PEVM's workers.
+    fn recover_senders<C: PevmChain>(
+        &mut self,
+        chain: &C,
+        concurrency_level: NonZeroUsize,
+    ) -> Result<(), PevmError<C>> {
+        if self.senders.is_some() {
+            return Ok(());
+        }
+        // Enforce EIP-2 once the chain is past Homestead.
+        let eip2 = chain
+            .get_block_spec(&get_rpc_header(
+                self.header.clone(),
+                Some(self.total_difficulty),
+            ))
+            .map_err(PevmError::BlockSpecError)?
+            .is_enabled_in(SpecId::HOMESTEAD);
+        let recover_chunk = |chunk_start: usize, txs: &[TxEnvelope]| {
+            txs.iter()
+                .enumerate()
+                .map(|(i, tx)| recover_signer(tx, eip2).ok_or(chunk_start + i))
+                .collect::<Result<Vec<_>, usize>>()
+        };
+        let chunk_size = self
+            .transactions
+            .len()
+            .div_ceil(concurrency_level.get())
+            .max(MIN_RECOVERY_CHUNK_SIZE);
+        let senders = if self.transactions.len() <= chunk_size {
+            recover_chunk(0, &self.transactions)
+        } else {
+            let chunk_senders = thread::scope(|scope| {
+                let handles: Vec<_> = self
+                    .transactions
+                    .chunks(chunk_size)
+                    .enumerate()
+                    .map(|(chunk_idx, txs)| {
+                        scope.spawn(move || recover_chunk(chunk_idx * chunk_size, txs))
+                    })
+                    .collect();
+                handles
+                    .into_iter()
+                    .map(|handle| handle.join().unwrap())
+                    .collect::<Vec<_>>()
+            });
+            // The first invalid signature in block order is reported.
+            chunk_senders
+                .into_iter()
+                .try_fold(Vec::new(), |mut senders, chunk| {
+                    senders.extend(chunk?);
+                    Ok(senders)
+                })
+        };
+        let senders = senders.map_err(|tx_idx| PevmError::InvalidSignature { tx_idx })?;
+        self.senders = Some(senders);
+        Ok(())
+    }
+}
+
+// Recovering a signature takes tens of microseconds, so smaller chunks
+// don't pay for spawning their threads.
+const MIN_RECOVERY_CHUNK_SIZE: usize = 64;
@@ -0,0 +1,214 @@
+//! Merge the per-transaction state transitions of a block into a single
+//! block-level change set in the shape of reth's [BundleState], so nodes
+//! can persist the result of a parallel execution directly.
+
+use std::{collections::hash_map::Entry, iter};
+
+use ahash::AHashMap;
+use alloy_primitives::{Address, B256, U256};
+use revm::{
+    db::{AccountStatus, BundleState},
+    primitives::{AccountInfo, Bytecode, HashMap, KECCAK_EMPTY},
+};
+
+use crate::{EvmAccount, PevmBlockExecutionResult, Storage, StorageError};
+
+// An account changed in the block.
+struct BundledAccount {
+    original_info: Option<AccountInfo>,
+    info: Option<AccountInfo>,
+    // The pre-block and latest values of the changed slots.
+    storage: AHashMap<U256, (U256, U256)>,
+    // Whether the account has been self-destructed in the block, which
+    // clears its pre-block storage.
+    destroyed: bool,
+}
+
+// The changes of a state transition to revert: the previous account info
+// if it changed ([Some(None)] for new accounts), the previous values of the
+// changed slots, and whether the pre-transition storage was wiped.
+type AccountRevert = (Option<Option<AccountInfo>>, Vec<(U256, U256)>, bool);
+
+/// Merge the state transitions of a block's execution result into a
+/// [BundleState] on top of the storage it was executed against: the final
+/// state of every changed account with its pre-block state, the deployed
+/// contracts, and the reverts of each transition. The reverts are ordered
+/// like the transitions: the pre-block changes, each transaction, then the
+/// post-block changes.
+pub fn build_bundle_state<S: Storage>(
+    storage: &S,
+    result: &PevmBlockExecutionResult,
+) -> Result<BundleState, StorageError> {
+    let mut accounts: AHashMap<Address, BundledAccount> = AHashMap::new();
+    let mut contracts: AHashMap<B256, Bytecode> = AHashMap::new();
+    let mut reverts = Vec::with_capacity(result.tx_results.len() + 2);
+
+    let states = iter::once(&result.pre_block_state)
+        .chain(result.tx_results.iter().map(|tx_result| &tx_result.state))
+        .chain(iter::once(&result.post_block_state));
+    for state in states {
+        let mut transition_reverts = Vec::with_capacity(state.len());
+        for (address, account) in state {
+            let bundled = match accounts.entry(*address) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    let info = read_account_info(storage, address).map_err(StorageError::new)?;
+                    entry.insert(BundledAccount {
+                        original_info: info.clone(),
+                        info,
+                        storage: AHashMap::new(),
+                        destroyed: false,
+                    })
+                }
+            };
+            let revert = apply_transition(storage, address, bundled, account, &mut contracts)
+                .map_err(StorageError::new)?;
+            if let Some(revert) = revert {
+                transition_reverts.push((*address, revert));
+            }
+        }
+        reverts.push(transition_reverts);
+    }
+
+    // The wiped storages are recorded after building the bundle, as its
+    // constructor only takes plain reverts.
+    let wipes: Vec<Vec<bool>> = reverts
+        .iter()
+        .map(|transition_reverts| {
+            transition_reverts
+                .iter()
+                .map(|(_, (_, _, wipe_storage))| *wipe_storage)
+                .collect()
+        })
+        .collect();
+    let destroyed_accounts: Vec<(Address, bool)> = accounts
+        .iter()
+        .filter(|(_, bundled)| bundled.destroyed)
+        .map(|(address, bundled)| (*address, bundled.info.is_some()))
+        .collect();
+    let mut bundle_state = BundleState::new(
+        accounts.into_iter().map(|(address, bundled)| {
+            (
+                address,
+                bundled.original_info,
+                bundled.info,
+                bundled.storage.into_iter().collect::<HashMap<_, _>>(),
+            )
+        }),
+        reverts.into_iter().map(|transition_reverts| {
+            transition_reverts
+                .into_iter()
+                .map(|(address, (info, storage, _))| (address, info, storage))
+        }),
+        contracts,
+    );
+    for (transition_reverts, wipes) in bundle_state.reverts.iter_mut().zip(wipes) {
+        for ((_, revert), wipe_storage) in transition_reverts.iter_mut().zip(wipes) {
+            revert.wipe_storage = wipe_storage;
+        }
+    }
+    for (address, revived) in destroyed_accounts {
+        if let Some(account) = bundle_state.state.get_mut(&address) {
+            account.status = if revived {
+                AccountStatus::DestroyedChanged
+            } else {
+                AccountStatus::Destroyed
+            };
+        }
+    }
+    Ok(bundle_state)
+}
+
+// Apply a state transition to a bundled account, returning what to revert
+// if it changed anything.
+fn apply_transition<S: Storage>(
+    storage: &S,
+    address: &Address,
+    bundled: &mut BundledAccount,
+    account: &Option<EvmAccount>,
+    contracts: &mut AHashMap<B256, Bytecode>,
+) -> Result<Option<AccountRevert>, S::Error> {
+    let Some(account) = account else {
+        // The storage of self-destructed accounts is wiped, including the
+        // pre-block slots that the block never touched.
+        let wipe_storage = bundled.info.is_some();
+        let mut revert_storage = Vec::new();
+        for (slot, (_, present)) in bundled.storage.iter_mut() {
+            if !present.is_zero() {
+                revert_storage.push((*slot, *present));
+                *present = U256::ZERO;
+            }
+        }
+        let prev_info = bundled.info.take();
+        bundled.destroyed = true;
+        if prev_info.is_none() && revert_storage.is_empty() {
+            return Ok(None);
+        }
+        return Ok(Some((Some(prev_info), revert_storage, wipe_storage)));
+    };
+
+    let mut revert_storage = Vec::new();
+    for (slot, value) in account.storage.iter() {
+        let previous = match bundled.storage.get(slot) {
+            Some((_, present)) => *present,
+            None => {
+                let original = storage.storage(address, slot)?;
+                let previous = if bundled.destroyed {
+                    U256::ZERO
+                } else {
+                    original
+                };
+                bundled.storage.insert(*slot, (original, previous));
+                previous
+            }
+        };
+        if previous != *value {
+            revert_storage.push((*slot, previous));
+            bundled.storage.get_mut(slot).unwrap().1 = *value;
+        }
+    }
+
+    let info = AccountInfo {
+        balance: account.balance,
+        nonce: account.nonce,
+        code_hash: account.code_hash.unwrap_or(KECCAK_EMPTY),
+        code: account.code.clone().map(Bytecode::from),
+    };
+    if let Some(code) = &info.code {
+        if bundled.info.as_ref().map(|info| info.code_hash) != Some(info.code_hash) {
+            contracts
+                .entry(info.code_hash)
+                .or_insert_with(|| code.clone());
+        }
+    }
+    let prev_info = if bundled.info.as_ref() == Some(&info) {
+        None
+    } else {
+        Some(bundled.info.replace(info))
+    };
+    if prev_info.is_none() && revert_storage.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some((prev_info, revert_storage, false)))
+}
+
+// Read the pre-block info of an account from storage.
+fn read_account_info<S: Storage>(
+    storage: &S,
+    address: &Address,
+) -> Result<Option<AccountInfo>, S::Error> {
+    let Some(basic) = storage.basic(address)? else {
+        return Ok(None);
+    };
+    let code_hash = storage.code_hash(address)?;
+    let code = match &code_hash {
+        Some(code_hash) => storage.code_by_hash(code_hash)?,
+        None => None,
+    };
+    Ok(Some(AccountInfo {
+        balance: basic.balance,
+        nonce: basic.nonce,
+        code_hash: code_hash.unwrap_or(KECCAK_EMPTY),
+        code: code.map(Bytecode::from),
+    }))
+}
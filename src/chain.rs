@@ -2,7 +2,7 @@
 
 use std::{collections::HashMap, fmt::Debug};
 
-use alloy_primitives::{B256, U256};
+use alloy_primitives::{Address, B256, U256};
 use alloy_rpc_types::{BlockTransactions, Header, Transaction};
 use revm::{
     primitives::{BlockEnv, SpecId, TxEnv},
@@ -23,6 +23,21 @@ pub enum RewardPolicy {
     Ethereum,
 }
 
+/// Irregular state changes that chains apply outside of transactions,
+/// like hardfork-specific balance moves.
+#[derive(Debug, Clone, PartialEq)]
+pub enum IrregularStateChange {
+    /// Credit an amount to an account's balance.
+    BalanceIncrement(Address, U256),
+    /// Move the whole balance of an account to another account.
+    BalanceTransfer {
+        /// The account to drain.
+        from: Address,
+        /// The account to receive the drained balance.
+        to: Address,
+    },
+}
+
 /// Custom behaviours for different chains & networks
 pub trait PevmChain: Debug {
     /// The error type for [Self::get_block_spec].
@@ -54,6 +69,14 @@ pub trait PevmChain: Debug {
         )
     }
 
+    /// Whether a transaction is a system transaction to execute at the top
+    /// of the block, like OP deposit transactions. The leading ones of a
+    /// block execute sequentially before the rest of the block is released
+    /// to parallel execution on top of their writes.
+    fn is_top_of_block_system_tx(&self, _tx: &TxEnv) -> bool {
+        false
+    }
+
     /// Get [Handler]
     fn get_handler<'a, EXT, DB: revm::Database>(
         &self,
@@ -61,6 +84,29 @@ pub trait PevmChain: Debug {
         with_reward_beneficiary: bool,
     ) -> Handler<'a, revm::Context<EXT, DB>, EXT, DB>;
 
+    /// Get the irregular state changes to apply before the block's
+    /// transactions, which are then executed on top of them.
+    fn get_pre_block_state_changes(&self, _header: &Header) -> Vec<IrregularStateChange> {
+        Vec::new()
+    }
+
+    /// Get the irregular state changes to apply after the block's
+    /// transactions.
+    fn get_post_block_state_changes(&self, _header: &Header) -> Vec<IrregularStateChange> {
+        Vec::new()
+    }
+
+    /// Get the static block and ommer rewards to credit after the block's
+    /// transactions, given the block's ommer (uncle) headers.
+    fn get_block_rewards(
+        &self,
+        _spec_id: SpecId,
+        _header: &Header,
+        _ommers: &[Header],
+    ) -> Vec<IrregularStateChange> {
+        Vec::new()
+    }
+
     /// Get [RewardPolicy]
     fn get_reward_policy(&self, hasher: &ahash::RandomState) -> RewardPolicy;
 
@@ -1,12 +1,9 @@
 //! Ethereum
 
-use std::{
-    collections::{BTreeMap, HashMap},
-    fmt::Debug,
-};
+use std::{collections::BTreeMap, fmt::Debug};
 
 use alloy_chains::NamedChain;
-use alloy_consensus::{ReceiptEnvelope, TxType};
+use alloy_consensus::TxType;
 use alloy_primitives::{B256, U256};
 use alloy_provider::network::eip2718::Encodable2718;
 use alloy_rpc_types::{BlockTransactions, Header, Transaction};
@@ -15,16 +12,18 @@ use revm::{
     Handler,
 };
 
-use super::{PevmChain, RewardPolicy};
+use super::{IrregularStateChange, PevmChain, RewardPolicy};
 use crate::{
-    mv_memory::{LazyAddresses, MvMemory},
-    BuildIdentityHasher, MemoryLocation, PevmTxExecutionResult, TxIdx,
+    mv_memory::{estimate_locations, LazyAddresses, MvMemory},
+    MemoryLocation, PevmTxExecutionResult, TxIdx,
 };
 
 /// Implementation of [PevmChain] for Ethereum
 #[derive(Debug, Clone, PartialEq)]
 pub struct PevmEthereum {
     id: u64,
+    #[cfg(feature = "eof")]
+    eof_timestamp: Option<u64>,
 }
 
 impl PevmEthereum {
@@ -32,9 +31,19 @@ impl PevmEthereum {
     pub fn mainnet() -> Self {
         Self {
             id: NamedChain::Mainnet.into(),
+            #[cfg(feature = "eof")]
+            eof_timestamp: None,
         }
     }
 
+    /// Activate EOF (EIP-7692) from a block timestamp, for experimenting
+    /// on devnets.
+    #[cfg(feature = "eof")]
+    pub fn with_eof_timestamp(mut self, timestamp: u64) -> Self {
+        self.eof_timestamp = Some(timestamp);
+        self
+    }
+
     // TODO: support Ethereum Sepolia and other testnets
 }
 
@@ -80,6 +89,14 @@ impl PevmChain for PevmEthereum {
             .total_difficulty
             .ok_or(EthereumBlockSpecError::MissingTotalDifficulty)?;
 
+        #[cfg(feature = "eof")]
+        if self
+            .eof_timestamp
+            .is_some_and(|timestamp| header.timestamp >= timestamp)
+        {
+            return Ok(SpecId::PRAGUE_EOF);
+        }
+
         Ok(if header.timestamp >= 1710338135 {
             SpecId::CANCUN
         } else if header.timestamp >= 1681338455 {
@@ -136,8 +153,7 @@ impl PevmChain for PevmEthereum {
         let block_size = txs.len();
         let beneficiary_location_hash = hasher.hash_one(MemoryLocation::Basic(block_env.coinbase));
 
-        // TODO: Estimate more locations based on sender, to, etc.
-        let mut estimated_locations = HashMap::with_hasher(BuildIdentityHasher::default());
+        let mut estimated_locations = estimate_locations(hasher, txs);
         estimated_locations.insert(
             beneficiary_location_hash,
             (0..block_size).collect::<Vec<TxIdx>>(),
@@ -157,6 +173,67 @@ impl PevmChain for PevmEthereum {
         Handler::mainnet_with_spec(spec_id, with_reward_beneficiary)
     }
 
+    fn get_pre_block_state_changes(&self, header: &Header) -> Vec<IrregularStateChange> {
+        // Move the DAO accounts' funds to the refund contract right before the
+        // first transaction of the hardfork block.
+        if self.id == u64::from(NamedChain::Mainnet) && header.number == Some(DAO_HARDFORK_BLOCK) {
+            DAO_HARDFORK_ACCOUNTS
+                .iter()
+                .map(|address| IrregularStateChange::BalanceTransfer {
+                    from: *address,
+                    to: DAO_HARDFORK_BENEFICIARY,
+                })
+                .collect()
+        } else {
+            Vec::new()
+        }
+    }
+
+    // Refer to section 11.3. Reward Application in the Ethereum Yellow Paper,
+    // with the base reward reduced by EIP-649 and EIP-1234.
+    fn get_block_rewards(
+        &self,
+        spec_id: SpecId,
+        header: &Header,
+        ommers: &[Header],
+    ) -> Vec<IrregularStateChange> {
+        const ETH: u128 = 1_000_000_000_000_000_000;
+        // Proof-of-stake blocks have no block rewards.
+        if spec_id.is_enabled_in(SpecId::MERGE) {
+            return Vec::new();
+        }
+        let base_reward = U256::from(if spec_id.is_enabled_in(SpecId::CONSTANTINOPLE) {
+            2 * ETH
+        } else if spec_id.is_enabled_in(SpecId::BYZANTIUM) {
+            3 * ETH
+        } else {
+            5 * ETH
+        });
+        let number = U256::from(header.number.unwrap_or_default());
+
+        let mut rewards = Vec::with_capacity(ommers.len() + 1);
+        // The beneficiary gets an extra 1/32 of the base reward per included ommer.
+        rewards.push(IrregularStateChange::BalanceIncrement(
+            header.miner,
+            base_reward + (base_reward >> 5) * U256::from(ommers.len()),
+        ));
+        // Ommer beneficiaries get 1/8 of the base reward less for each block
+        // their ommer is behind the including block. Invalid ommers, which
+        // execution rejects beforehand, get at most 7/8 and never wrap.
+        for ommer in ommers {
+            let ommer_number = U256::from(ommer.number.unwrap_or_default());
+            let reward_eighths = (ommer_number + U256::from(8))
+                .checked_sub(number)
+                .unwrap_or_default()
+                .min(U256::from(7));
+            rewards.push(IrregularStateChange::BalanceIncrement(
+                ommer.miner,
+                (reward_eighths * base_reward) >> 3,
+            ));
+        }
+        rewards
+    }
+
     fn get_reward_policy(&self, _hasher: &ahash::RandomState) -> RewardPolicy {
         RewardPolicy::Ethereum
     }
@@ -166,23 +243,11 @@ impl PevmChain for PevmEthereum {
     fn calculate_receipt_root(
         &self,
         _spec_id: SpecId,
-        txs: &BlockTransactions<Transaction>,
+        _txs: &BlockTransactions<Transaction>,
         tx_results: &[PevmTxExecutionResult],
     ) -> B256 {
         // 1. Create an iterator of ReceiptEnvelope
-        let tx_type_iter = txs
-            .txns()
-            .map(|tx| TxType::try_from(tx.transaction_type.unwrap_or_default()).unwrap());
-
-        let receipt_iter = tx_results.iter().map(|tx| tx.receipt.clone().with_bloom());
-
-        let receipt_envelope_iter =
-            Iterator::zip(tx_type_iter, receipt_iter).map(|(tx_type, receipt)| match tx_type {
-                TxType::Legacy => ReceiptEnvelope::Legacy(receipt),
-                TxType::Eip2930 => ReceiptEnvelope::Eip2930(receipt),
-                TxType::Eip1559 => ReceiptEnvelope::Eip1559(receipt),
-                TxType::Eip4844 => ReceiptEnvelope::Eip4844(receipt),
-            });
+        let receipt_envelope_iter = tx_results.iter().map(|tx| &tx.receipt);
 
         // 2. Create a trie then calculate the root hash
         // We use BTreeMap because the keys must be sorted in ascending order.
@@ -203,3 +268,6 @@ impl PevmChain for PevmEthereum {
         hash_builder.root()
     }
 }
+
+mod dao;
+use dao::{DAO_HARDFORK_ACCOUNTS, DAO_HARDFORK_BENEFICIARY, DAO_HARDFORK_BLOCK};
@@ -1,6 +1,8 @@
 // TODO: Support custom chains like OP & RISE
 // Ideally REVM & Alloy would provide all these.
 
+use alloy_consensus::{Header as ConsensusHeader, TxEip4844Variant, TxEnvelope};
+use alloy_primitives::Address;
 use alloy_rpc_types::{Header, Transaction};
 use revm::primitives::{BlobExcessGasAndPrice, BlockEnv, TransactTo, TxEnv, U256};
 
@@ -66,3 +68,118 @@ pub(crate) fn get_tx_env<C: PevmChain>(
         authorization_list: None, // TODO: Support in the upcoming hardfork
     })
 }
+
+/// Get the Alloy RPC header of a consensus header.
+pub(crate) fn get_rpc_header(header: ConsensusHeader, total_difficulty: Option<U256>) -> Header {
+    Header {
+        hash: Some(header.hash_slow()),
+        parent_hash: header.parent_hash,
+        uncles_hash: header.ommers_hash,
+        miner: header.beneficiary,
+        state_root: header.state_root,
+        transactions_root: header.transactions_root,
+        receipts_root: header.receipts_root,
+        logs_bloom: header.logs_bloom,
+        difficulty: header.difficulty,
+        number: Some(header.number),
+        gas_limit: header.gas_limit,
+        gas_used: header.gas_used,
+        timestamp: header.timestamp,
+        total_difficulty,
+        extra_data: header.extra_data,
+        mix_hash: Some(header.mix_hash),
+        nonce: Some(header.nonce),
+        base_fee_per_gas: header.base_fee_per_gas,
+        withdrawals_root: header.withdrawals_root,
+        blob_gas_used: header.blob_gas_used,
+        excess_blob_gas: header.excess_blob_gas,
+        parent_beacon_block_root: header.parent_beacon_block_root,
+        requests_root: header.requests_root,
+    }
+}
+
+// Half of the secp256k1 curve order, above which signature `s` values are
+// invalid since Homestead (EIP-2) to remove their malleability.
+const SECP256K1N_HALF: U256 = U256::from_be_bytes([
+    0x7F, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+    0x5D, 0x57, 0x6E, 0x73, 0x57, 0xA4, 0x50, 0x1D, 0xDF, 0xE9, 0x2F, 0x46, 0x68, 0x1B, 0x20, 0xA0,
+]);
+
+/// Recover the sender of a signed consensus transaction, rejecting high `s`
+/// values with [eip2].
+pub(crate) fn recover_signer(tx: &TxEnvelope, eip2: bool) -> Option<Address> {
+    let (signature, sender) = match tx {
+        TxEnvelope::Legacy(tx) => (tx.signature(), tx.recover_signer()),
+        TxEnvelope::Eip2930(tx) => (tx.signature(), tx.recover_signer()),
+        TxEnvelope::Eip1559(tx) => (tx.signature(), tx.recover_signer()),
+        TxEnvelope::Eip4844(tx) => (tx.signature(), tx.recover_signer()),
+    };
+    if eip2 && signature.s() > SECP256K1N_HALF {
+        return None;
+    }
+    sender.ok()
+}
+
+/// Get the Alloy RPC transaction of a signed consensus transaction from
+/// [from], with only the fields that execution needs.
+pub(crate) fn get_rpc_transaction(tx: TxEnvelope, from: Address) -> Transaction {
+    let mut rpc_tx = Transaction {
+        hash: *tx.tx_hash(),
+        from,
+        transaction_type: Some(u8::from(tx.tx_type())),
+        ..Transaction::default()
+    };
+    match tx {
+        TxEnvelope::Legacy(tx) => {
+            let tx = tx.strip_signature();
+            rpc_tx.chain_id = tx.chain_id;
+            rpc_tx.nonce = tx.nonce;
+            rpc_tx.gas_price = Some(tx.gas_price);
+            rpc_tx.gas = tx.gas_limit;
+            rpc_tx.to = tx.to.to().copied();
+            rpc_tx.value = tx.value;
+            rpc_tx.input = tx.input;
+        }
+        TxEnvelope::Eip2930(tx) => {
+            let tx = tx.strip_signature();
+            rpc_tx.chain_id = Some(tx.chain_id);
+            rpc_tx.nonce = tx.nonce;
+            rpc_tx.gas_price = Some(tx.gas_price);
+            rpc_tx.gas = tx.gas_limit;
+            rpc_tx.to = tx.to.to().copied();
+            rpc_tx.value = tx.value;
+            rpc_tx.access_list = Some(tx.access_list);
+            rpc_tx.input = tx.input;
+        }
+        TxEnvelope::Eip1559(tx) => {
+            let tx = tx.strip_signature();
+            rpc_tx.chain_id = Some(tx.chain_id);
+            rpc_tx.nonce = tx.nonce;
+            rpc_tx.max_fee_per_gas = Some(tx.max_fee_per_gas);
+            rpc_tx.max_priority_fee_per_gas = Some(tx.max_priority_fee_per_gas);
+            rpc_tx.gas = tx.gas_limit;
+            rpc_tx.to = tx.to.to().copied();
+            rpc_tx.value = tx.value;
+            rpc_tx.access_list = Some(tx.access_list);
+            rpc_tx.input = tx.input;
+        }
+        TxEnvelope::Eip4844(tx) => {
+            let tx = match tx.strip_signature() {
+                TxEip4844Variant::TxEip4844(tx) => tx,
+                TxEip4844Variant::TxEip4844WithSidecar(tx) => tx.tx,
+            };
+            rpc_tx.chain_id = Some(tx.chain_id);
+            rpc_tx.nonce = tx.nonce;
+            rpc_tx.max_fee_per_gas = Some(tx.max_fee_per_gas);
+            rpc_tx.max_priority_fee_per_gas = Some(tx.max_priority_fee_per_gas);
+            rpc_tx.max_fee_per_blob_gas = Some(tx.max_fee_per_blob_gas);
+            rpc_tx.gas = tx.gas_limit;
+            rpc_tx.to = Some(tx.to);
+            rpc_tx.value = tx.value;
+            rpc_tx.access_list = Some(tx.access_list);
+            rpc_tx.blob_versioned_hashes = Some(tx.blob_versioned_hashes);
+            rpc_tx.input = tx.input;
+        }
+    }
+    rpc_tx
+}
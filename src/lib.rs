@@ -59,6 +59,39 @@ impl Hasher for IdentityHasher {
 }
 type BuildIdentityHasher = BuildHasherDefault<IdentityHasher>;
 
+// A cheap multiplicative hasher (like rustc's FxHasher) for keys like
+// storage slots, which are only hashed for a quick lookup of their
+// already-computed memory location hashes.
+#[derive(Default)]
+struct FoldHasher(u64);
+impl FoldHasher {
+    #[inline(always)]
+    fn fold(&mut self, word: u64) {
+        self.0 = (self.0.rotate_left(5) ^ word).wrapping_mul(0x51_7c_c1_b7_27_22_0a_95);
+    }
+}
+impl Hasher for FoldHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        let mut chunks = bytes.chunks_exact(8);
+        for chunk in &mut chunks {
+            self.fold(u64::from_le_bytes(chunk.try_into().unwrap()));
+        }
+        let mut remainder = [0u8; 8];
+        remainder[..chunks.remainder().len()].copy_from_slice(chunks.remainder());
+        self.fold(u64::from_le_bytes(remainder));
+    }
+    fn write_u64(&mut self, word: u64) {
+        self.fold(word);
+    }
+    fn write_usize(&mut self, word: usize) {
+        self.fold(word as u64);
+    }
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+type BuildFoldHasher = BuildHasherDefault<FoldHasher>;
+
 // TODO: It would be nice if we could tie the different cases of
 // memory locations & values at the type level, to prevent lots of
 // matches & potentially dangerous mismatch mistakes.
@@ -160,27 +193,30 @@ type WriteSet = Vec<(MemoryLocationHash, MemoryValue)>;
 type NewLazyAddresses = Vec<Address>;
 
 /// Errors when reading a memory location.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
 pub enum ReadError {
     /// Cannot read memory location from storage.
-    StorageError(String),
+    #[error("storage error: {0}")]
+    StorageError(StorageError),
     /// Memory location not found.
+    #[error("memory location not found")]
     NotFound,
     /// This memory location has been written by a lower transaction.
+    #[error("blocked by transaction {0}")]
     BlockingIndex(TxIdx),
     /// There has been an inconsistent read like reading the same
     /// location from storage in the first call but from [VmMemory] in
     /// the next.
+    #[error("inconsistent read")]
     InconsistentRead,
     /// Found an invalid nonce, like the first transaction of a sender
     /// not having a (+1) nonce from storage.
     /// TODO: Add the address and tx index to the error.
+    #[error("invalid nonce")]
     InvalidNonce,
-    /// Read a self-destructed account that is very hard to handle, as
-    /// there is no performant way to mark all storage slots as cleared.
-    SelfDestructedAccount,
     /// The stored memory value type doesn't match its location type.
     /// TODO: Handle this at the type level?
+    #[error("invalid memory location type")]
     InvalidMemoryLocationType,
 }
 
@@ -188,7 +224,7 @@ pub enum ReadError {
 // TODO: Add more useful work when there are idle workers like near
 // the end of block execution, while waiting for a huge blocking
 // transaction to resolve, etc.
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 enum Task {
     Execution(TxVersion),
     Validation(TxVersion),
@@ -206,16 +242,52 @@ macro_rules! index_mutex {
     };
 }
 
+mod block;
+pub use block::{BlockInput, BlockParts, ConsensusBlock};
+mod bundle_state;
+pub use bundle_state::build_bundle_state;
 pub mod chain;
 mod compat;
+#[cfg(feature = "metrics")]
+pub mod metrics;
 mod mv_memory;
+pub use mv_memory::MvMemory;
 mod pevm;
-pub use pevm::{execute, execute_revm_parallel, execute_revm_sequential, PevmError, PevmResult};
+pub use pevm::{
+    execute, execute_revm_parallel, execute_revm_sequential, execute_with_ommers, BlockOverrides,
+    BlockRangeError, BundleOptions, BundleSimulation, ExecutionHints, ExecutionHook, ExecutionMode,
+    FallbackReason, HintedLocation, HotLocation, MemoryBudget, MemoryUsage, Pevm,
+    PevmBlockExecutionResult, PevmBlockResult, PevmError, PevmOptions, PevmResult, PevmStrategy,
+    SequentialFallback, SimulatedBlock, TxDependencies, TxExecutionError,
+};
 mod scheduler;
+pub use scheduler::{
+    AbortCause, ExecutionReport, RetryPolicy, ScheduleEvent, SchedulingPolicy, TaskEvent, TaskKind,
+    ThreadPinning, TxReport,
+};
+mod snapshot;
+pub use snapshot::{BlockRangeSnapshot, BlockSnapshot, SnapshotError, SNAPSHOT_VERSION};
+#[cfg(feature = "state-root")]
+mod state_root;
+#[cfg(feature = "state-root")]
+pub use state_root::{compute_state_root, AccountProof, ProofStorage, StateRootError};
 mod storage;
 pub use storage::{
-    AccountBasic, Bytecodes, EvmAccount, EvmCode, InMemoryStorage, RpcStorage, Storage,
-    StorageWrapper,
+    AccountBasic, AccountOverride, AsyncStorage, AsyncStorageBridge, Bytecodes, CachedStorage,
+    CommittedStorage, DatabaseAsStorage, EvmAccount, EvmCode, ExecutionWitness, InMemoryStorage,
+    InstrumentedStorage, LruTier, MemoryTier, MethodMetrics, MmapStorage, OverlayStorage,
+    RpcStorage, StateOverrides, Storage, StorageError, StorageMetrics, StorageTier, StorageWrapper,
+    TieredStorage, WitnessStorage, LATENCY_BUCKETS,
+};
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
+mod tracers;
+pub use tracers::{
+    CallTracer, CallTracerInspector, ParityTracer, ParityTracerInspector, PrestateTracer,
+    PrestateTracerInspector,
 };
 mod vm;
-pub use vm::{ExecutionError, PevmTxExecutionResult};
+pub use vm::{
+    EvmStateTransitions, ExecutionError, ExternalContext, ExternalFactory, InspectorFactory,
+    PevmTxExecutionResult,
+};
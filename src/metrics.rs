@@ -0,0 +1,104 @@
+//! Metrics of executions via the [metrics] crate facade, for node operators
+//! to scrape pevm's performance in production by installing a recorder like
+//! `metrics-exporter-prometheus`. Nothing is recorded without a recorder.
+
+use std::time::Duration;
+
+use ::metrics::{counter, describe_counter, describe_histogram, histogram, Unit};
+
+use crate::FallbackReason;
+
+/// The number of executed blocks.
+pub const BLOCKS_EXECUTED: &str = "pevm_blocks_executed_total";
+/// The number of transactions of the executed blocks.
+pub const TXS_EXECUTED: &str = "pevm_txs_executed_total";
+/// The wall time to execute each block.
+pub const BLOCK_EXECUTION_TIME: &str = "pevm_block_execution_seconds";
+/// The executed transactions per second of each block.
+pub const TX_THROUGHPUT: &str = "pevm_tx_throughput";
+/// The number of transaction incarnations executed in parallel.
+pub const INCARNATIONS: &str = "pevm_incarnations_total";
+/// The number of aborted transaction incarnations.
+pub const ABORTS: &str = "pevm_aborts_total";
+/// The ratio of aborted incarnations of each parallel execution.
+pub const ABORT_RATE: &str = "pevm_abort_rate";
+/// The number of incarnations aborted by failing validations.
+pub const VALIDATION_FAILURES: &str = "pevm_validation_failures_total";
+/// The number of parallel executions that fell back to sequential
+/// execution, labelled by `reason`.
+pub const FALLBACKS: &str = "pevm_sequential_fallbacks_total";
+/// The latency of calls to an [crate::InstrumentedStorage], labelled by
+/// storage `method`.
+pub const STORAGE_LATENCY: &str = "pevm_storage_latency_seconds";
+
+/// Describe all metrics to the installed recorder, like for the help texts
+/// and units of Prometheus.
+pub fn describe_metrics() {
+    describe_counter!(BLOCKS_EXECUTED, "The number of executed blocks");
+    describe_counter!(
+        TXS_EXECUTED,
+        "The number of transactions of the executed blocks"
+    );
+    describe_histogram!(
+        BLOCK_EXECUTION_TIME,
+        Unit::Seconds,
+        "The wall time to execute each block"
+    );
+    describe_histogram!(
+        TX_THROUGHPUT,
+        "The executed transactions per second of each block"
+    );
+    describe_counter!(
+        INCARNATIONS,
+        "The number of transaction incarnations executed in parallel"
+    );
+    describe_counter!(ABORTS, "The number of aborted transaction incarnations");
+    describe_histogram!(
+        ABORT_RATE,
+        "The ratio of aborted incarnations of each parallel execution"
+    );
+    describe_counter!(
+        VALIDATION_FAILURES,
+        "The number of incarnations aborted by failing validations"
+    );
+    describe_counter!(
+        FALLBACKS,
+        "The number of parallel executions that fell back to sequential execution"
+    );
+    describe_histogram!(
+        STORAGE_LATENCY,
+        Unit::Seconds,
+        "The latency of calls to an instrumented storage"
+    );
+}
+
+pub(crate) fn record_block(num_txs: usize, elapsed: Duration) {
+    counter!(BLOCKS_EXECUTED).increment(1);
+    counter!(TXS_EXECUTED).increment(num_txs as u64);
+    histogram!(BLOCK_EXECUTION_TIME).record(elapsed);
+    if !elapsed.is_zero() {
+        histogram!(TX_THROUGHPUT).record(num_txs as f64 / elapsed.as_secs_f64());
+    }
+}
+
+pub(crate) fn record_scheduling(incarnations: usize, aborts: usize, validation_failures: usize) {
+    counter!(INCARNATIONS).increment(incarnations as u64);
+    counter!(ABORTS).increment(aborts as u64);
+    counter!(VALIDATION_FAILURES).increment(validation_failures as u64);
+    if incarnations > 0 {
+        histogram!(ABORT_RATE).record(aborts as f64 / incarnations as f64);
+    }
+}
+
+pub(crate) fn record_fallback(reason: &FallbackReason) {
+    let reason = match reason {
+        FallbackReason::ReadError(_) => "read_error",
+        FallbackReason::RetriesExhausted => "retries_exhausted",
+        FallbackReason::MemoryBudgetExceeded => "memory_budget_exceeded",
+    };
+    counter!(FALLBACKS, "reason" => reason).increment(1);
+}
+
+pub(crate) fn record_storage_latency(method: &'static str, latency: Duration) {
+    histogram!(STORAGE_LATENCY, "method" => method).record(latency);
+}
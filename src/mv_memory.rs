@@ -1,12 +1,21 @@
-use std::{collections::BTreeMap, sync::Mutex};
+use std::{
+    collections::{BTreeMap, HashMap},
+    mem::size_of,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
 
 use ahash::AHashSet;
-use alloy_primitives::Address;
+use alloy_primitives::{Address, U256};
 use dashmap::{mapref::one::Ref, DashMap};
+use revm::primitives::{TransactTo, TxEnv};
+use smallvec::SmallVec;
 
 use crate::{
-    BuildAddressHasher, BuildIdentityHasher, MemoryEntry, MemoryLocationHash, NewLazyAddresses,
-    ReadOrigin, ReadSet, TxIdx, TxVersion, WriteSet,
+    BuildAddressHasher, BuildIdentityHasher, MemoryEntry, MemoryLocation, MemoryLocationHash,
+    NewLazyAddresses, ReadOrigin, ReadSet, TxIdx, TxVersion, WriteSet,
 };
 
 #[derive(Default, Debug)]
@@ -16,6 +25,17 @@ struct LastLocations {
     write: Vec<MemoryLocationHash>,
 }
 
+// The contention on a memory location, to find the locations that
+// serialize a block.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct LocationStats {
+    // The number of incarnations that wrote to the location.
+    pub(crate) writes: usize,
+    // The number of reads of the location that failed validation or
+    // blocked on an estimate.
+    pub(crate) conflicts: usize,
+}
+
 #[derive(Debug)]
 pub(crate) struct LazyAddresses(pub(crate) AHashSet<Address, BuildAddressHasher>);
 impl Default for LazyAddresses {
@@ -40,6 +60,73 @@ pub struct MvMemory {
     last_locations: Vec<Mutex<LastLocations>>,
     /// Lazy addresses that need full evaluation at the end of the block
     lazy_addresses: Mutex<LazyAddresses>,
+    /// The approximate number of bytes used by the data and read sets
+    memory_used: AtomicUsize,
+    /// The highest approximate number of bytes used so far
+    peak_memory_used: AtomicUsize,
+    /// The optional cap on the approximate memory used
+    max_memory: Option<usize>,
+    /// The optional contention stats of each memory location
+    location_stats: Option<DashMap<MemoryLocationHash, LocationStats, BuildIdentityHasher>>,
+}
+
+// Approximate sizes for memory accounting, including some overhead for
+// the maps' nodes and buckets.
+const LOCATION_SIZE: usize = 2 * size_of::<(MemoryLocationHash, BTreeMap<TxIdx, MemoryEntry>)>();
+const ENTRY_SIZE: usize = 2 * size_of::<(TxIdx, MemoryEntry)>();
+const READ_SIZE: usize =
+    2 * size_of::<(MemoryLocationHash, Vec<ReadOrigin>)>() + size_of::<ReadOrigin>();
+
+// Estimate the locations that transactions will write to from their
+// senders, recipients and EIP-2930 access lists, to seed [MvMemory] with
+// estimates that block (instead of abort) the first incarnations of later
+// transactions touching them. Only locations touched by a later transaction
+// are kept, as the rest can't cause aborts. Access-listed slots may only be
+// read, in which case the estimates only delay later readers until the
+// transaction is first executed.
+pub(crate) fn estimate_locations(
+    hasher: &ahash::RandomState,
+    txs: &[TxEnv],
+) -> HashMap<MemoryLocationHash, Vec<TxIdx>, BuildIdentityHasher> {
+    // The transactions touching each location, and whether they write to it.
+    let mut touches: HashMap<MemoryLocationHash, Vec<(TxIdx, bool)>, BuildIdentityHasher> =
+        HashMap::default();
+    let mut touch = |location: MemoryLocation, tx_idx: TxIdx, is_write: bool| {
+        let location_touches = touches.entry(hasher.hash_one(location)).or_default();
+        match location_touches.last_mut() {
+            Some((last_idx, was_write)) if *last_idx == tx_idx => *was_write |= is_write,
+            _ => location_touches.push((tx_idx, is_write)),
+        }
+    };
+    for (tx_idx, tx) in txs.iter().enumerate() {
+        // Senders always pay for gas and bump their nonces.
+        touch(MemoryLocation::Basic(tx.caller), tx_idx, true);
+        if let TransactTo::Call(to) = tx.transact_to {
+            touch(MemoryLocation::Basic(to), tx_idx, !tx.value.is_zero());
+        }
+        for item in tx.access_list.iter() {
+            touch(MemoryLocation::Basic(item.address), tx_idx, false);
+            for key in item.storage_keys.iter() {
+                touch(
+                    MemoryLocation::Storage(item.address, U256::from_be_bytes(key.0)),
+                    tx_idx,
+                    true,
+                );
+            }
+        }
+    }
+    touches
+        .into_iter()
+        .filter_map(|(location_hash, location_touches)| {
+            let (last_idx, _) = location_touches.last()?;
+            let tx_idxs: Vec<TxIdx> = location_touches
+                .iter()
+                .filter(|(tx_idx, is_write)| *is_write && tx_idx != last_idx)
+                .map(|(tx_idx, _)| *tx_idx)
+                .collect();
+            (!tx_idxs.is_empty()).then_some((location_hash, tx_idxs))
+        })
+        .collect()
 }
 
 impl MvMemory {
@@ -55,7 +142,16 @@ impl MvMemory {
         // while holding a write lock. Ideally [dashmap] would have a lock-free
         // construction API. This is acceptable for now as it's a non-congested one-time
         // cost.
+        let mut last_locations: Vec<LastLocations> =
+            (0..block_size).map(|_| LastLocations::default()).collect();
+        let mut memory_used = 0;
         for (location_hash, estimated_tx_idxs) in estimated_locations {
+            memory_used += LOCATION_SIZE + estimated_tx_idxs.len() * ENTRY_SIZE;
+            // Estimates count as the last written locations so the first
+            // incarnation clears the ones it doesn't actually write to.
+            for tx_idx in estimated_tx_idxs.iter() {
+                last_locations[*tx_idx].write.push(location_hash);
+            }
             data.insert(
                 location_hash,
                 estimated_tx_idxs
@@ -66,11 +162,89 @@ impl MvMemory {
         }
         Self {
             data,
-            last_locations: (0..block_size).map(|_| Mutex::default()).collect(),
+            last_locations: last_locations.into_iter().map(Mutex::new).collect(),
             lazy_addresses: Mutex::new(lazy_addresses),
+            memory_used: AtomicUsize::new(memory_used),
+            peak_memory_used: AtomicUsize::new(memory_used),
+            max_memory: None,
+            location_stats: None,
         }
     }
 
+    // Add more estimated locations on top of the initial ones before
+    // execution, like from user hints.
+    pub(crate) fn with_estimated_locations(
+        mut self,
+        estimated_locations: impl IntoIterator<Item = (MemoryLocationHash, Vec<TxIdx>)>,
+    ) -> Self {
+        let block_size = self.last_locations.len();
+        let mut added_memory = 0;
+        for (location_hash, estimated_tx_idxs) in estimated_locations {
+            let mut written_transactions = self.data.entry(location_hash).or_insert_with(|| {
+                added_memory += LOCATION_SIZE;
+                BTreeMap::new()
+            });
+            for tx_idx in estimated_tx_idxs {
+                if tx_idx < block_size && !written_transactions.contains_key(&tx_idx) {
+                    written_transactions.insert(tx_idx, MemoryEntry::Estimate);
+                    added_memory += ENTRY_SIZE;
+                    self.last_locations[tx_idx]
+                        .get_mut()
+                        .unwrap()
+                        .write
+                        .push(location_hash);
+                }
+            }
+        }
+        let memory_used = self.memory_used.get_mut();
+        *memory_used += added_memory;
+        *self.peak_memory_used.get_mut() = *memory_used;
+        self
+    }
+
+    // Cap the approximate memory used by the data and read sets.
+    pub(crate) fn with_max_memory(mut self, max_memory: usize) -> Self {
+        self.max_memory = Some(max_memory);
+        self
+    }
+
+    // Count the writes and conflicts of each memory location.
+    pub(crate) fn with_location_stats(mut self) -> Self {
+        self.location_stats = Some(DashMap::default());
+        self
+    }
+
+    // The highest approximate number of bytes used by the data and read
+    // sets so far.
+    pub(crate) fn peak_memory_used(&self) -> usize {
+        self.peak_memory_used.load(Ordering::Relaxed)
+    }
+
+    // Record a conflicting read of a memory location, like of an estimate
+    // or one that failed validation.
+    pub(crate) fn record_conflict(&self, location: &MemoryLocationHash) {
+        if let Some(location_stats) = &self.location_stats {
+            location_stats.entry(*location).or_default().conflicts += 1;
+        }
+    }
+
+    // The contention stats of the memory locations that were written to or
+    // conflicted on, [None] without [MvMemory::with_location_stats].
+    pub(crate) fn location_stats(&self) -> Option<Vec<(MemoryLocationHash, LocationStats)>> {
+        self.location_stats.as_ref().map(|location_stats| {
+            location_stats
+                .iter()
+                .map(|entry| (*entry.key(), *entry.value()))
+                .collect()
+        })
+    }
+
+    // Whether the approximate memory used exceeds the cap.
+    pub(crate) fn is_over_budget(&self) -> bool {
+        self.max_memory
+            .is_some_and(|max_memory| self.memory_used.load(Ordering::Relaxed) > max_memory)
+    }
+
     // Apply a new pair of read & write sets to the multi-version data structure.
     // Return whether a write occurred to a memory location not written to by
     // the previous incarnation of the same transaction. This determines whether
@@ -85,21 +259,43 @@ impl MvMemory {
         // Update the multi-version as fast as possible for higher transactions to
         // read from.
         let new_locations: Vec<MemoryLocationHash> = write_set.iter().map(|(l, _)| *l).collect();
+        let mut added_memory = read_set.len() * READ_SIZE;
         for (location, value) in write_set {
-            self.data.entry(location).or_default().insert(
+            let mut written_transactions = self.data.entry(location).or_insert_with(|| {
+                added_memory += LOCATION_SIZE;
+                BTreeMap::new()
+            });
+            let prev_entry = written_transactions.insert(
                 tx_version.tx_idx,
                 MemoryEntry::Data(tx_version.tx_incarnation, value),
             );
+            if prev_entry.is_none() {
+                added_memory += ENTRY_SIZE;
+            }
+            if let Some(location_stats) = &self.location_stats {
+                location_stats.entry(location).or_default().writes += 1;
+            }
         }
         // TODO: Faster "difference" function when there are many locations
         let mut last_locations = index_mutex!(self.last_locations, tx_version.tx_idx);
+        let mut removed_memory = last_locations.read.len() * READ_SIZE;
         for prev_location in last_locations.write.iter() {
             if !new_locations.contains(prev_location) {
                 if let Some(mut written_transactions) = self.data.get_mut(prev_location) {
-                    written_transactions.remove(&tx_version.tx_idx);
+                    if written_transactions.remove(&tx_version.tx_idx).is_some() {
+                        removed_memory += ENTRY_SIZE;
+                    }
                 }
             }
         }
+        let memory_used =
+            self.memory_used.fetch_add(added_memory, Ordering::Relaxed) + added_memory;
+        self.memory_used
+            .fetch_sub(removed_memory, Ordering::Relaxed);
+        self.peak_memory_used.fetch_max(
+            memory_used.saturating_sub(removed_memory),
+            Ordering::Relaxed,
+        );
 
         // Update lazy addresses
         if !new_lazy_addresses.is_empty() {
@@ -137,40 +333,54 @@ impl MvMemory {
     // can be aborted at most once).
     pub(crate) fn validate_read_locations(&self, tx_idx: TxIdx) -> bool {
         for (location, prior_origins) in index_mutex!(self.last_locations, tx_idx).read.iter() {
-            if let Some(written_transactions) = self.read_location(location) {
-                let mut iter = written_transactions.range(..tx_idx);
-                for prior_origin in prior_origins {
-                    if let ReadOrigin::MvMemory(prior_version) = prior_origin {
-                        // Found something: Must match version.
-                        if let Some((closest_idx, MemoryEntry::Data(tx_incarnation, ..))) =
-                            iter.next_back()
+            if !self.validate_read_location(tx_idx, location, prior_origins) {
+                self.record_conflict(location);
+                return false;
+            }
+        }
+        true
+    }
+
+    // Check that re-reading a memory location still yields the same read
+    // origins.
+    fn validate_read_location(
+        &self,
+        tx_idx: TxIdx,
+        location: &MemoryLocationHash,
+        prior_origins: &[ReadOrigin],
+    ) -> bool {
+        if let Some(written_transactions) = self.read_location(location) {
+            let mut iter = written_transactions.range(..tx_idx);
+            for prior_origin in prior_origins {
+                if let ReadOrigin::MvMemory(prior_version) = prior_origin {
+                    // Found something: Must match version.
+                    if let Some((closest_idx, MemoryEntry::Data(tx_incarnation, ..))) =
+                        iter.next_back()
+                    {
+                        if closest_idx != &prior_version.tx_idx
+                            || &prior_version.tx_incarnation != tx_incarnation
                         {
-                            if closest_idx != &prior_version.tx_idx
-                                || &prior_version.tx_incarnation != tx_incarnation
-                            {
-                                return false;
-                            }
-                        }
-                        // The previously read value is now cleared
-                        // or marked with ESTIMATE.
-                        else {
                             return false;
                         }
                     }
-                    // Read from storage but there is now something
-                    // in between!
-                    else if iter.next_back().is_some() {
+                    // The previously read value is now cleared
+                    // or marked with ESTIMATE.
+                    else {
                         return false;
                     }
                 }
+                // Read from storage but there is now something
+                // in between!
+                else if iter.next_back().is_some() {
+                    return false;
+                }
             }
-            // Read from multi-version data but now it's cleared.
-            else if prior_origins.len() != 1 || prior_origins.last() != Some(&ReadOrigin::Storage)
-            {
-                return false;
-            }
+            true
+        }
+        // Read from multi-version data but now it's cleared.
+        else {
+            prior_origins.len() == 1 && prior_origins.last() == Some(&ReadOrigin::Storage)
         }
-        true
     }
 
     // Replace the write set of the aborted version in the shared memory data
@@ -184,6 +394,29 @@ impl MvMemory {
         }
     }
 
+    // The lower transactions that each transaction's last recorded read set
+    // read from, sorted without duplicates.
+    pub(crate) fn dependencies(&self) -> Vec<SmallVec<[TxIdx; 4]>> {
+        self.last_locations
+            .iter()
+            .map(|last_locations| {
+                let last_locations = last_locations.lock().unwrap();
+                let mut dependencies: SmallVec<[TxIdx; 4]> = last_locations
+                    .read
+                    .values()
+                    .flatten()
+                    .filter_map(|origin| match origin {
+                        ReadOrigin::MvMemory(tx_version) => Some(tx_version.tx_idx),
+                        ReadOrigin::Storage => None,
+                    })
+                    .collect();
+                dependencies.sort_unstable();
+                dependencies.dedup();
+                dependencies
+            })
+            .collect()
+    }
+
     pub(crate) fn read_location(
         &self,
         location: &MemoryLocationHash,
@@ -1,105 +1,3228 @@
 use std::{
+    any::Any,
+    collections::{hash_map::Entry, HashMap},
     fmt::Debug,
+    future::Future,
+    iter,
+    mem::{self, size_of},
     num::NonZeroUsize,
-    sync::{Mutex, OnceLock},
+    ops::Range,
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, OnceLock,
+    },
     thread,
+    time::{Duration, Instant},
+};
+
+use ahash::{AHashMap, AHashSet};
+use alloy_consensus::TxType;
+use alloy_primitives::{Address, Log, B256, U256};
+use alloy_rpc_types::{Block, BlockTransactions, Header};
+use dashmap::DashMap;
+use defer_drop::DeferDrop;
+use futures::channel::oneshot;
+use revm::{
+    db::CacheDB,
+    precompile::{Precompile, PrecompileWithAddress},
+    primitives::{
+        calc_excess_blob_gas, BlockEnv, Bytes, EVMError, ExecutionResult, InvalidTransaction,
+        SpecId::{self, CANCUN, SPURIOUS_DRAGON},
+        TransactTo, TxEnv, MAX_BLOB_GAS_PER_BLOCK,
+    },
+    DatabaseCommit, Evm,
 };
+use smallvec::SmallVec;
+use thiserror::Error;
+
+use crate::{
+    block::{BlockInput, BlockParts, ConsensusBlock},
+    chain::{IrregularStateChange, PevmChain},
+    compat::{get_block_env, get_tx_env, TransactionParsingError},
+    mv_memory::{LocationStats, MvMemory},
+    scheduler::{
+        ConcurrencyTuner, ExecutionReport, RetryPolicy, ScheduleEvent, Scheduler, SchedulingPolicy,
+        TaskEvent, ThreadPinning,
+    },
+    storage::{CommittedStorage, OverlayStorage, StateOverrides, StorageWrapper},
+    vm::{
+        build_evm, build_inspected_evm, receipt_with_bloom_mut, with_tx_type, BytecodeCache,
+        EvmStateTransitions, ExecutionError, ExternalFactory, Inspection, InspectorFactory,
+        PevmTxExecutionResult, TxEnvs, Vm, VmExecutionResult, NO_INSPECTION,
+    },
+    AccountBasic, BuildIdentityHasher, EvmAccount, EvmCode, MemoryEntry, MemoryLocation,
+    MemoryLocationHash, MemoryValue, NewLazyAddresses, ReadError, Storage, StorageError, Task,
+    TxIdx, TxVersion,
+};
+
+/// An error from executing a specific transaction.
+#[derive(Debug, Clone, PartialEq, Error)]
+#[error("transaction {tx_idx} (incarnation {tx_incarnation}) failed: {error}")]
+pub struct TxExecutionError {
+    /// The index of the transaction in the block.
+    pub tx_idx: usize,
+    /// The incarnation of the transaction that failed, which is
+    /// always zero for sequential execution.
+    pub tx_incarnation: usize,
+    /// The error from the underlying EVM executor.
+    pub error: ExecutionError,
+}
+
+/// Errors when executing a block with PEVM.
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum PevmError<C: PevmChain> {
+    /// Cannot derive the chain spec from the block header.
+    #[error("cannot derive the chain spec from the block header: {0:?}")]
+    BlockSpecError(C::BlockSpecError),
+    /// Block header lacks information for execution.
+    #[error("block header lacks information for execution")]
+    MissingHeaderData,
+    /// The provided ommer headers don't match the block's ommers or lack
+    /// information for rewards.
+    #[error("ommer headers don't match the block's ommers")]
+    MissingOmmerData,
+    /// An ommer isn't from the six blocks before the including block, so
+    /// it can't be rewarded.
+    #[error("ommer {ommer_idx} isn't from the six blocks before the block")]
+    InvalidOmmer {
+        /// The index of the ommer in the block.
+        ommer_idx: usize,
+    },
+    /// Transactions lack information for execution.
+    #[error("transactions lack information for execution")]
+    MissingTransactionData,
+    /// The blob gas used by the block's transactions doesn't match the
+    /// header or exceeds the per-block limit (EIP-4844).
+    #[error("invalid blob gas used: {computed} executed, {header} in header")]
+    InvalidBlobGasUsed {
+        /// The blob gas used declared in the block header.
+        header: u128,
+        /// The total blob gas used by the executed transactions.
+        computed: u128,
+    },
+    /// The excess blob gas of the header doesn't follow from the parent
+    /// header given with [PevmOptions::with_parent_header] (EIP-4844).
+    #[error("invalid excess blob gas: {computed} from parent, {header} in header")]
+    InvalidExcessBlobGas {
+        /// The excess blob gas declared in the block header.
+        header: u128,
+        /// The excess blob gas computed from the parent header.
+        computed: u128,
+    },
+    /// The block isn't a valid RLP encoding of a block.
+    #[error("invalid block encoding: {0}")]
+    InvalidBlockEncoding(alloy_rlp::Error),
+    /// The sender of a transaction can't be recovered from its signature.
+    #[error("invalid signature of transaction {tx_idx}")]
+    InvalidSignature {
+        /// The index of the transaction in the block.
+        tx_idx: usize,
+    },
+    /// Invalid input transaction.
+    #[error("invalid transaction: {0:?}")]
+    InvalidTransaction(TransactionParsingError<C>),
+    /// Storage error.
+    #[error("storage error: {0}")]
+    StorageError(StorageError),
+    /// EVM execution error of a transaction.
+    #[error(transparent)]
+    ExecutionError(TxExecutionError),
+    /// A replayed schedule diverged from the recorded one, as the task of
+    /// the event at this index wasn't ready when its turn came.
+    #[error("replayed schedule diverged at event {event_idx}")]
+    ScheduleDiverged {
+        /// The index of the first event that couldn't be replayed.
+        event_idx: usize,
+    },
+    /// The multi-version memory outgrew [PevmStrategy::memory_budget]
+    /// without falling back to sequential execution.
+    #[error("multi-version memory exceeded its budget of {max_bytes} bytes")]
+    MemoryBudgetExceeded {
+        /// The budget that was exceeded.
+        max_bytes: usize,
+    },
+    /// The range of transactions to execute is out of the block's bounds,
+    /// or doesn't start right after the prior results.
+    #[error("invalid transaction range {start}..{end} of {block_size} transactions")]
+    InvalidTransactionRange {
+        /// The start of the range.
+        start: usize,
+        /// The end of the range.
+        end: usize,
+        /// The number of transactions of the block.
+        block_size: usize,
+    },
+    /// A transaction of a simulated bundle reverted without being allowed
+    /// to via [BundleOptions::reverting_tx_idxs].
+    #[error("bundle transaction {tx_idx} reverted")]
+    BundleTxReverted {
+        /// The index of the transaction in the bundle.
+        tx_idx: usize,
+    },
+    /// The transaction to estimate the gas of fails even with its gas
+    /// limit as the cap, like when it reverts.
+    #[error("gas estimation failed at the gas cap")]
+    GasEstimationFailed {
+        /// The output of the failed execution, like the revert payload.
+        output: Bytes,
+    },
+    /// The background execution of [Pevm::execute_async] panicked, with
+    /// the panic message.
+    #[error("the background execution panicked: {0}")]
+    ExecutionPanicked(String),
+    /// Impractical errors that should be unreachable.
+    /// The library has bugs if this is yielded.
+    #[error("unreachable error")]
+    UnreachableError,
+}
+
+/// Errors when executing a range of blocks via
+/// [Pevm::execute_range_of_blocks], with the number of the failing block.
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum BlockRangeError<C: PevmChain, E> {
+    /// The block can't be fetched.
+    #[error("cannot fetch block {block_number}: {error:?}")]
+    Fetch {
+        /// The number of the block.
+        block_number: u64,
+        /// The error of the fetcher.
+        error: E,
+    },
+    /// The block failed to execute.
+    #[error("cannot execute block {block_number}: {error}")]
+    Execution {
+        /// The number of the block.
+        block_number: u64,
+        /// The execution error.
+        error: PevmError<C>,
+    },
+    /// The result of the block can't be committed.
+    #[error("cannot commit block {block_number}: {error:?}")]
+    Commit {
+        /// The number of the block.
+        block_number: u64,
+        /// The error of the committer.
+        error: E,
+    },
+}
+
+/// Execution result of a list of transactions
+pub type PevmResult<C> = Result<Vec<PevmTxExecutionResult>, PevmError<C>>;
+
+/// Execution result of a block
+#[derive(Debug, Clone, PartialEq)]
+pub struct PevmBlockExecutionResult {
+    /// Irregular state transitions applied before the block's transactions,
+    /// like the DAO hardfork's refund.
+    pub pre_block_state: EvmStateTransitions,
+    /// Execution results of the block's transactions
+    pub tx_results: Vec<PevmTxExecutionResult>,
+    /// State transitions applied outside of transactions after they
+    /// are all executed, like withdrawals.
+    pub post_block_state: EvmStateTransitions,
+}
+
+/// Execution result of an Alloy block
+pub type PevmBlockResult<C> = Result<PevmBlockExecutionResult, PevmError<C>>;
+
+/// Why a parallel execution fell back to sequential execution.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FallbackReason {
+    /// Reading the sender or recipient account before executing the
+    /// transaction failed.
+    ReadError(ReadError),
+    /// The transaction was blocked on too many incarnations with
+    /// [RetryPolicy::BoundedThenSequential].
+    RetriesExhausted,
+    /// Recording the transaction's execution exceeded
+    /// [PevmStrategy::memory_budget].
+    MemoryBudgetExceeded,
+}
+
+/// A fallback from parallel to sequential execution, which erases the
+/// parallel speedup of the block.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SequentialFallback {
+    /// The index of the transaction that triggered the fallback.
+    pub tx_idx: usize,
+    /// Why the execution fell back.
+    pub reason: FallbackReason,
+}
+
+enum AbortReason {
+    FallbackToSequential(SequentialFallback),
+    ExecutionError(TxExecutionError),
+}
+
+/// How to handle transactions that fail to execute, which are either
+/// invalid or executed on incomplete state in parallel.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutionMode {
+    /// Sync canonical blocks that are known to be valid. Failing transactions
+    /// are retried until their lower transactions have been executed, which
+    /// can deadlock on invalid blocks.
+    #[default]
+    Sync,
+    /// Build a new block. Transactions that are invalid on the final state
+    /// of their lower transactions are skipped, and their indices are
+    /// available via [Pevm::skipped_tx_idxs] after execution. Balances and
+    /// nonces of raw transfers are fully evaluated during execution instead
+    /// of lazily after it, so candidate transactions are checked on their
+    /// actual state. The beneficiary's rewards stay lazy and are only
+    /// evaluated for transactions that read the beneficiary.
+    Build,
+    /// Validate an untrusted block. Failing transactions are retried a bounded
+    /// number of times, and the block errors out if any transaction is
+    /// invalid on the final state of its lower transactions.
+    Validate,
+}
+
+impl ExecutionMode {
+    // Whether to lazily update the balances and nonces of raw transfers,
+    // which defers their validity checks to after execution.
+    pub(crate) fn lazy_updates(&self) -> bool {
+        *self != Self::Build
+    }
+}
+
+/// Hooks that [Pevm] invokes on the transactions of a block once their
+/// results are final, in block order, like for live indexing, balance
+/// tracking or custom accounting. They are never invoked on speculative
+/// incarnations, and only on successful executions of whole blocks (like
+/// via [Pevm::execute] or [Pevm::execute_revm_parallel]) right before
+/// their results are returned. Simulations, ranges and transactions
+/// skipped in [ExecutionMode::Build] aren't hooked.
+pub trait ExecutionHook: Debug + Send + Sync {
+    /// Invoked before [ExecutionHook::after_tx] with the transaction at
+    /// [tx_idx].
+    fn before_tx(&self, _tx_idx: usize, _tx: &TxEnv) {}
+
+    /// Invoked with the final result of the transaction at [tx_idx].
+    fn after_tx(&self, _tx_idx: usize, _tx_result: &PevmTxExecutionResult) {}
+
+    /// Invoked periodically from the workers of parallel executions with
+    /// the numbers of executed and validated transactions out of the
+    /// [total] of the block (or chunk), and once with all [total] done when
+    /// the parallel execution completes. Transactions that can't have read
+    /// stale values skip validation, so [validated] may lag until the end.
+    fn on_progress(&self, _executed: usize, _validated: usize, _total: usize) {}
+}
+
+/// Strategies to tune parallel execution with, which don't change the
+/// execution results.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PevmStrategy {
+    /// The order to first execute transactions in.
+    pub scheduling: SchedulingPolicy,
+    /// How to retry transactions that read from lower transactions that are
+    /// yet to (re-)execute.
+    pub retry: RetryPolicy,
+    /// Tune the number of active workers during execution, starting from
+    /// the given concurrency level and parking workers when most executions
+    /// abort, to not hand-tune the level per block or machine. The chosen
+    /// level is available via [Pevm::concurrency_level] after execution.
+    pub adaptive_concurrency: bool,
+    /// Record every scheduling decision, available via [Pevm::schedule]
+    /// after execution to replay via [Pevm::replay_revm_parallel]. This
+    /// serializes the workers on a lock so is only meant for debugging.
+    pub record_schedule: bool,
+    /// Record which lower transactions each transaction read from in its
+    /// final incarnation, available via [Pevm::dependencies] after
+    /// execution to analyze conflict patterns.
+    pub record_dependencies: bool,
+    /// Record the scheduling statistics of each transaction, available via
+    /// [Pevm::report] after execution to tune strategies with.
+    pub record_report: bool,
+    /// Record the timed tasks of each worker, available via
+    /// [Pevm::take_events] after execution to analyze the timeline of the
+    /// block. Every task takes a lock so this is only meant for profiling.
+    pub record_events: bool,
+    /// Record the writes and conflicts of each memory location, available
+    /// via [Pevm::hot_locations] after execution to find the contracts
+    /// that serialize the block.
+    pub record_hot_locations: bool,
+    /// Cap the memory of the multi-version data and read sets, which grows
+    /// with the block for very large blocks. [None] for no cap.
+    pub memory_budget: Option<MemoryBudget>,
+    /// Pin worker threads to CPU cores to reduce the variance of execution
+    /// times, notably on many-core aarch64 machines.
+    pub thread_pinning: ThreadPinning,
+    /// Execute blocks larger than this many transactions in consecutive
+    /// chunks, each on the committed state of the previous ones, to bound
+    /// the scheduler and multi-version data of very large blocks. This
+    /// trades the parallelism across chunks for memory, and [None] executes
+    /// whole blocks. Blocks executed with hints, with inspectors or while
+    /// recording a schedule aren't chunked, and [Pevm::dependencies] only
+    /// includes dependencies within each chunk.
+    pub chunk_size: Option<NonZeroUsize>,
+}
+
+/// The indices of the lower transactions that a transaction read from,
+/// sorted without duplicates.
+pub type TxDependencies = SmallVec<[usize; 4]>;
+
+/// A cap on the approximate memory used by parallel execution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryBudget {
+    /// The maximum number of bytes, approximated from the number of
+    /// recorded locations, writes and reads.
+    pub max_bytes: usize,
+    /// Re-execute the block sequentially when the budget is exceeded,
+    /// instead of erroring with [PevmError::MemoryBudgetExceeded].
+    pub fallback_to_sequential: bool,
+}
+
+/// Hints of pre-known conflicts between the transactions of a block, for
+/// integrators with knowledge beyond what [PevmChain::build_mv_memory]
+/// estimates, like of sequencer fee vaults. Wrong hints only cost some
+/// parallelism and never change the execution results.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ExecutionHints {
+    /// Locations and the transactions that likely write to them, which
+    /// higher transactions wait for before reading the locations.
+    pub hot_locations: Vec<(HintedLocation, Vec<usize>)>,
+    /// The lower transactions that each transaction likely depends on, by
+    /// transaction index like [Pevm::dependencies]. Transactions wait for
+    /// their highest dependency before first executing.
+    pub dependencies: Vec<TxDependencies>,
+}
+
+/// The contention on a memory location in a parallel execution, from
+/// [Pevm::hot_locations].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HotLocation {
+    /// The location, [None] when it can't be mapped back from its hash,
+    /// like when only aborted incarnations touched it.
+    pub location: Option<HintedLocation>,
+    /// The number of incarnations that wrote to the location.
+    pub writes: usize,
+    /// The number of reads of the location that failed validation or
+    /// waited for a lower transaction to re-execute.
+    pub conflicts: usize,
+}
+
+/// The approximate memory used by a parallel execution, from
+/// [Pevm::memory_usage], for admission control across concurrent [Pevm]s.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryUsage {
+    /// The peak bytes of the multi-version data and read sets, like
+    /// accounted for [PevmStrategy::memory_budget].
+    pub peak_mv_memory: usize,
+    /// The bytes of the execution results returned.
+    pub results: usize,
+}
+
+impl MemoryUsage {
+    /// The approximate peak bytes of the execution, as the results are
+    /// built before the multi-version data is dropped.
+    pub fn peak_total(&self) -> usize {
+        self.peak_mv_memory + self.results
+    }
+}
+
+/// Options to simulate a bundle of transactions with [Pevm::simulate_bundle].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BundleOptions {
+    /// The spec to execute the bundle with.
+    pub spec_id: SpecId,
+    /// The indices of the transactions that are allowed to revert, as the
+    /// bundle fails when any other transaction reverts.
+    pub reverting_tx_idxs: Vec<usize>,
+    /// The maximum number of threads to execute the bundle with.
+    pub concurrency_level: NonZeroUsize,
+    /// Overrides of the block's environment to simulate the bundle in.
+    pub block_overrides: BlockOverrides,
+    /// Overrides of the state to simulate the bundle on, without changing
+    /// the storage.
+    pub state_overrides: StateOverrides,
+}
+
+/// The result of a bundle simulated with [Pevm::simulate_bundle].
+#[derive(Debug, Clone, PartialEq)]
+pub struct BundleSimulation {
+    /// Execution results of the bundle's transactions.
+    pub tx_results: Vec<PevmTxExecutionResult>,
+    /// The increase of the beneficiary's balance over the bundle, from both
+    /// the priority fees and direct transfers, to score the bundle.
+    pub coinbase_diff: U256,
+    /// The gas used by all transactions of the bundle.
+    pub gas_used: u64,
+}
+
+/// Overrides of a block's environment for simulations, following the
+/// `blockOverrides` of `eth_call` and `eth_simulateV1`. Unset fields keep
+/// the block's values.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct BlockOverrides {
+    /// Override the block number.
+    pub number: Option<U256>,
+    /// Override the block timestamp.
+    pub timestamp: Option<U256>,
+    /// Override the base fee per gas.
+    pub basefee: Option<U256>,
+    /// Override the block gas limit.
+    pub gas_limit: Option<U256>,
+    /// Override the beneficiary.
+    pub coinbase: Option<Address>,
+    /// Override the previous RANDAO value.
+    pub prevrandao: Option<B256>,
+}
+
+impl BlockOverrides {
+    /// Apply the overrides to a block environment.
+    pub fn apply(&self, block_env: &mut BlockEnv) {
+        if let Some(number) = self.number {
+            block_env.number = number;
+        }
+        if let Some(timestamp) = self.timestamp {
+            block_env.timestamp = timestamp;
+        }
+        if let Some(basefee) = self.basefee {
+            block_env.basefee = basefee;
+        }
+        if let Some(gas_limit) = self.gas_limit {
+            block_env.gas_limit = gas_limit;
+        }
+        if let Some(coinbase) = self.coinbase {
+            block_env.coinbase = coinbase;
+        }
+        if let Some(prevrandao) = self.prevrandao {
+            block_env.prevrandao = Some(prevrandao);
+        }
+    }
+
+    // Apply the overrides to an Alloy block header, like [Self::apply].
+    fn apply_to_header(&self, header: &mut Header) {
+        if let Some(number) = self.number {
+            header.number = Some(number.saturating_to());
+        }
+        if let Some(timestamp) = self.timestamp {
+            header.timestamp = timestamp.saturating_to();
+        }
+        if let Some(basefee) = self.basefee {
+            header.base_fee_per_gas = Some(basefee.saturating_to());
+        }
+        if let Some(gas_limit) = self.gas_limit {
+            header.gas_limit = gas_limit.saturating_to();
+        }
+        if let Some(coinbase) = self.coinbase {
+            header.miner = coinbase;
+        }
+        if let Some(prevrandao) = self.prevrandao {
+            header.mix_hash = Some(prevrandao);
+        }
+    }
+}
+
+/// A block of calls to simulate with [Pevm::simulate_blocks].
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SimulatedBlock {
+    /// Overrides of the block's environment, which otherwise follows from
+    /// the previous block with the next number and a timestamp twelve
+    /// seconds later.
+    pub block_overrides: BlockOverrides,
+    /// The calls to execute in order.
+    pub calls: Vec<TxEnv>,
+}
+
+/// Options to execute an Alloy block with via [Pevm::execute_with], built
+/// from [PevmOptions::default] with the `with_*` methods. Unset options
+/// keep the defaults of [Pevm::execute] and the configuration of the [Pevm].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PevmOptions {
+    concurrency_level: NonZeroUsize,
+    force_sequential: bool,
+    mode: Option<ExecutionMode>,
+    strategy: Option<PevmStrategy>,
+    ommers: Option<Vec<Header>>,
+    parent_header: Option<Header>,
+    block_overrides: BlockOverrides,
+    state_overrides: StateOverrides,
+}
+
+impl Default for PevmOptions {
+    fn default() -> Self {
+        Self {
+            concurrency_level: thread::available_parallelism().unwrap_or(NonZeroUsize::MIN),
+            force_sequential: false,
+            mode: None,
+            strategy: None,
+            ommers: None,
+            parent_header: None,
+            block_overrides: BlockOverrides::default(),
+            state_overrides: StateOverrides::default(),
+        }
+    }
+}
+
+impl PevmOptions {
+    /// Execute with at most [concurrency_level] threads, defaulting to the
+    /// available parallelism.
+    pub fn with_concurrency_level(mut self, concurrency_level: NonZeroUsize) -> Self {
+        self.concurrency_level = concurrency_level;
+        self
+    }
+
+    /// Execute the block sequentially regardless of its size.
+    pub fn with_force_sequential(mut self, force_sequential: bool) -> Self {
+        self.force_sequential = force_sequential;
+        self
+    }
+
+    /// Execute in [mode] instead of the [Pevm]'s mode.
+    pub fn with_mode(mut self, mode: ExecutionMode) -> Self {
+        self.mode = Some(mode);
+        self
+    }
+
+    /// Execute with [strategy] instead of the [Pevm]'s strategy.
+    pub fn with_strategy(mut self, strategy: PevmStrategy) -> Self {
+        self.strategy = Some(strategy);
+        self
+    }
+
+    /// Credit the block and ommer rewards with the block's ommer headers,
+    /// like [Pevm::execute_with_ommers].
+    pub fn with_ommers(mut self, ommers: Vec<Header>) -> Self {
+        self.ommers = Some(ommers);
+        self
+    }
+
+    /// Validate the excess blob gas of Cancun blocks against their
+    /// [parent_header], which is otherwise unknown to the execution.
+    pub fn with_parent_header(mut self, parent_header: Header) -> Self {
+        self.parent_header = Some(parent_header);
+        self
+    }
+
+    /// Override the block's header, like [Pevm::execute_with_overrides].
+    pub fn with_block_overrides(mut self, block_overrides: BlockOverrides) -> Self {
+        self.block_overrides = block_overrides;
+        self
+    }
+
+    /// Execute on top of overrides of the state without changing the
+    /// storage, like [Pevm::execute_with_overrides].
+    pub fn with_state_overrides(mut self, state_overrides: StateOverrides) -> Self {
+        self.state_overrides = state_overrides;
+        self
+    }
+}
+
+/// A memory location to hint at in [ExecutionHints].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HintedLocation {
+    /// An account's balance and nonce.
+    Account(Address),
+    /// An account's code.
+    Code(Address),
+    /// A storage slot of an account.
+    Storage(Address, U256),
+}
+
+impl From<HintedLocation> for MemoryLocation {
+    fn from(location: HintedLocation) -> Self {
+        match location {
+            HintedLocation::Account(address) => Self::Basic(address),
+            HintedLocation::Code(address) => Self::CodeHash(address),
+            HintedLocation::Storage(address, slot) => Self::Storage(address, slot),
+        }
+    }
+}
+
+/// The PEVM engine for executing blocks.
+// TODO: Reuse more (de)allocations between runs.
+#[derive(Debug, Default)]
+pub struct Pevm {
+    mode: ExecutionMode,
+    strategy: PevmStrategy,
+    skipped_tx_idxs: Vec<usize>,
+    concurrency_level: Option<NonZeroUsize>,
+    schedule: Option<Vec<ScheduleEvent>>,
+    dependencies: Option<Vec<TxDependencies>>,
+    report: Option<ExecutionReport>,
+    events: Option<Vec<TaskEvent>>,
+    hot_locations: Option<Vec<HotLocation>>,
+    memory_usage: Option<MemoryUsage>,
+    fallback: Option<SequentialFallback>,
+    bytecode_cache: BytecodeCache,
+    incremental_block: Option<IncrementalBlock>,
+    reused_tx_count: usize,
+    hooks: Vec<Arc<dyn ExecutionHook>>,
+    precompiles: Vec<PrecompileWithAddress>,
+    #[cfg(feature = "rayon")]
+    thread_pool: Option<Arc<rayon::ThreadPool>>,
+}
+
+// The last block executed incrementally, to reuse the results of its
+// unchanged prefix next time.
+#[derive(Debug)]
+struct IncrementalBlock {
+    spec_id: SpecId,
+    block_env: BlockEnv,
+    txs: Vec<TxEnv>,
+    tx_results: Vec<PevmTxExecutionResult>,
+    skipped_tx_idxs: Vec<usize>,
+}
+
+impl Pevm {
+    /// Construct a [Pevm] that executes blocks in [mode].
+    pub fn new(mode: ExecutionMode) -> Self {
+        Self {
+            mode,
+            strategy: PevmStrategy::default(),
+            skipped_tx_idxs: Vec::new(),
+            concurrency_level: None,
+            schedule: None,
+            dependencies: None,
+            report: None,
+            events: None,
+            hot_locations: None,
+            memory_usage: None,
+            fallback: None,
+            bytecode_cache: BytecodeCache::default(),
+            incremental_block: None,
+            reused_tx_count: 0,
+            hooks: Vec::new(),
+            precompiles: Vec::new(),
+            #[cfg(feature = "rayon")]
+            thread_pool: None,
+        }
+    }
+
+    /// Set the strategy to execute blocks in parallel with.
+    pub fn with_strategy(mut self, strategy: PevmStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Add a hook to invoke on the final results of every block executed
+    /// from now on, after the hooks already added.
+    pub fn with_hook(mut self, hook: Arc<dyn ExecutionHook>) -> Self {
+        self.hooks.push(hook);
+        self
+    }
+
+    /// Add a precompile at [address] on top of the chain's, overriding the
+    /// chain's precompile at the same address, like the cheatcodes of
+    /// simulation platforms. Transactions that call it are never lazily
+    /// updated.
+    pub fn with_precompile(mut self, address: Address, precompile: Precompile) -> Self {
+        self.set_precompile(address, precompile);
+        self
+    }
+
+    /// Add or replace the precompile at [address] like
+    /// [Pevm::with_precompile], for the next executions of this [Pevm].
+    pub fn set_precompile(&mut self, address: Address, precompile: Precompile) {
+        self.precompiles
+            .retain(|precompile| precompile.0 != address);
+        self.precompiles
+            .push(PrecompileWithAddress(address, precompile));
+    }
+
+    /// Remove the precompiles added via [Pevm::with_precompile].
+    pub fn clear_precompiles(&mut self) {
+        self.precompiles.clear();
+    }
+
+    /// Run parallel executions on a shared thread pool instead of spawning
+    /// worker threads per execution, to not oversubscribe the cores of
+    /// applications that already have a pool. The concurrency level is
+    /// capped at the pool's number of threads, and pool threads are never
+    /// pinned by [PevmStrategy::thread_pinning].
+    #[cfg(feature = "rayon")]
+    pub fn with_thread_pool(mut self, thread_pool: Arc<rayon::ThreadPool>) -> Self {
+        self.thread_pool = Some(thread_pool);
+        self
+    }
+
+    /// The indices of the transactions that the last execution skipped,
+    /// which can only be non-empty in [ExecutionMode::Build].
+    pub fn skipped_tx_idxs(&self) -> &[usize] {
+        &self.skipped_tx_idxs
+    }
+
+    /// The concurrency level that adaptive tuning settled on in the last
+    /// parallel execution, [None] without [PevmStrategy::adaptive_concurrency].
+    pub fn concurrency_level(&self) -> Option<NonZeroUsize> {
+        self.concurrency_level
+    }
+
+    /// The scheduling decisions of the last parallel execution, [None]
+    /// without [PevmStrategy::record_schedule] unless replaying.
+    pub fn schedule(&self) -> Option<&[ScheduleEvent]> {
+        self.schedule.as_deref()
+    }
+
+    /// The realized dependency graph of the last parallel execution, as the
+    /// dependencies of each transaction, [None] without
+    /// [PevmStrategy::record_dependencies] or when it fell back to sequential
+    /// execution.
+    pub fn dependencies(&self) -> Option<&[TxDependencies]> {
+        self.dependencies.as_deref()
+    }
+
+    /// The scheduling statistics of the last parallel execution, [None]
+    /// without [PevmStrategy::record_report] or when it fell back to
+    /// sequential execution.
+    pub fn report(&self) -> Option<&ExecutionReport> {
+        self.report.as_ref()
+    }
+
+    /// Take the timed tasks of the last parallel execution, sorted by their
+    /// start times, [None] without [PevmStrategy::record_events] or when
+    /// already taken. They export to CSV via [TaskEvent::to_csv_row] or to
+    /// JSON via [serde] for benchmark tooling.
+    pub fn take_events(&mut self) -> Option<Vec<TaskEvent>> {
+        self.events.take()
+    }
+
+    /// The [n] hottest memory locations of the last parallel execution, by
+    /// conflicts then writes, [None] without
+    /// [PevmStrategy::record_hot_locations] or when it fell back to
+    /// sequential execution.
+    pub fn hot_locations(&self, n: usize) -> Option<&[HotLocation]> {
+        self.hot_locations
+            .as_deref()
+            .map(|hot_locations| &hot_locations[..n.min(hot_locations.len())])
+    }
+
+    /// The approximate memory used by the last parallel execution, [None]
+    /// when it fell back to sequential execution.
+    pub fn memory_usage(&self) -> Option<MemoryUsage> {
+        self.memory_usage
+    }
+
+    /// Why the last parallel execution fell back to sequential execution,
+    /// [None] if it didn't.
+    pub fn fallback(&self) -> Option<&SequentialFallback> {
+        self.fallback.as_ref()
+    }
+
+    /// The number of transactions whose results the last incremental
+    /// execution reused from the one before it.
+    pub fn reused_tx_count(&self) -> usize {
+        self.reused_tx_count
+    }
+
+    /// Execute an Alloy block, which is becoming the "standard" format in Rust.
+    /// TODO: Better error handling.
+    pub fn execute<S: Storage + Send + Sync, C: PevmChain + Send + Sync>(
+        &mut self,
+        storage: &S,
+        chain: &C,
+        block: Block,
+        concurrency_level: NonZeroUsize,
+        force_sequential: bool,
+    ) -> PevmBlockResult<C> {
+        self.execute_with_ommers(
+            storage,
+            chain,
+            block,
+            None,
+            concurrency_level,
+            force_sequential,
+        )
+    }
+
+    /// Execute an Alloy block like [Pevm::execute] with [options], which
+    /// gathers the arguments of the other block execution methods to extend
+    /// without breaking callers. The mode and strategy of the options only
+    /// apply to this execution.
+    pub fn execute_with<S: Storage + Send + Sync, C: PevmChain + Send + Sync>(
+        &mut self,
+        storage: &S,
+        chain: &C,
+        mut block: Block,
+        options: PevmOptions,
+    ) -> PevmBlockResult<C> {
+        options.block_overrides.apply_to_header(&mut block.header);
+        // Pre-Cancun parents have neither, which count as zero.
+        if let (Some(parent_header), Some(excess_blob_gas)) =
+            (&options.parent_header, block.header.excess_blob_gas)
+        {
+            let computed = calc_excess_blob_gas(
+                parent_header.excess_blob_gas.unwrap_or_default() as u64,
+                parent_header.blob_gas_used.unwrap_or_default() as u64,
+            ) as u128;
+            if excess_blob_gas != computed {
+                return Err(PevmError::InvalidExcessBlobGas {
+                    header: excess_blob_gas,
+                    computed,
+                });
+            }
+        }
+        let prev_mode = options.mode.map(|mode| mem::replace(&mut self.mode, mode));
+        let prev_strategy = options
+            .strategy
+            .map(|strategy| mem::replace(&mut self.strategy, strategy));
+        let ommers = options.ommers.as_deref();
+        let result = if options.state_overrides.is_empty() {
+            self.execute_with_ommers(
+                storage,
+                chain,
+                block,
+                ommers,
+                options.concurrency_level,
+                options.force_sequential,
+            )
+        } else {
+            self.execute_with_ommers(
+                &OverlayStorage::new(storage, options.state_overrides),
+                chain,
+                block,
+                ommers,
+                options.concurrency_level,
+                options.force_sequential,
+            )
+        };
+        if let Some(mode) = prev_mode {
+            self.mode = mode;
+        }
+        if let Some(strategy) = prev_strategy {
+            self.strategy = strategy;
+        }
+        result
+    }
+
+    /// Execute a block of the consensus RLP format like [Pevm::execute_block],
+    /// like to replay era files or blocks from devp2p without an RPC node.
+    /// The senders of the transactions are recovered from their signatures,
+    /// and as the format lacks the block's total difficulty, it must be
+    /// provided for the chain to derive the spec with.
+    pub fn execute_encoded<S: Storage + Send + Sync, C: PevmChain + Send + Sync>(
+        &mut self,
+        storage: &S,
+        chain: &C,
+        block_rlp: &[u8],
+        total_difficulty: U256,
+        concurrency_level: NonZeroUsize,
+        force_sequential: bool,
+    ) -> PevmBlockResult<C> {
+        let block = ConsensusBlock::decode(block_rlp, total_difficulty)
+            .map_err(PevmError::InvalidBlockEncoding)?;
+        self.execute_block(storage, chain, block, concurrency_level, force_sequential)
+    }
+
+    /// Execute an Alloy block like [Pevm::execute] on a background thread,
+    /// or on the thread pool set via [Pevm::with_thread_pool], to await it
+    /// from async runtimes without blocking their threads. The future is
+    /// cancel-safe: dropping it lets the background execution finish and
+    /// discards its result, leaving this [Pevm] with its configuration but
+    /// without its caches and the results of previous executions, which is
+    /// also the state after a panicking execution.
+    pub fn execute_async<S, C>(
+        &mut self,
+        storage: Arc<S>,
+        chain: Arc<C>,
+        block: Block,
+        concurrency_level: NonZeroUsize,
+        force_sequential: bool,
+    ) -> impl Future<Output = PevmBlockResult<C>> + Send + '_
+    where
+        S: Storage + Send + Sync + 'static,
+        C: PevmChain + Send + Sync + 'static,
+        PevmError<C>: Send,
+    {
+        let fresh_pevm = self.with_same_config();
+        let mut pevm = mem::replace(self, fresh_pevm);
+        let (sender, receiver) = oneshot::channel();
+        let job = move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                pevm.execute(
+                    storage.as_ref(),
+                    chain.as_ref(),
+                    block,
+                    concurrency_level,
+                    force_sequential,
+                )
+            }));
+            // A panicking execution may leave the engine inconsistent, so
+            // it is dropped in favour of the fresh one.
+            let outcome = match result {
+                Ok(result) => (Some(pevm), result),
+                Err(payload) => (
+                    None,
+                    Err(PevmError::ExecutionPanicked(panic_message(payload))),
+                ),
+            };
+            // The receiver is gone when the future was dropped.
+            let _ = sender.send(outcome);
+        };
+        #[cfg(feature = "rayon")]
+        match &self.thread_pool {
+            Some(thread_pool) => thread_pool.spawn(job),
+            None => drop(thread::spawn(job)),
+        }
+        #[cfg(not(feature = "rayon"))]
+        thread::spawn(job);
+        async move {
+            let Ok((pevm, result)) = receiver.await else {
+                return Err(PevmError::ExecutionPanicked(
+                    "the background execution was dropped".to_string(),
+                ));
+            };
+            if let Some(pevm) = pevm {
+                *self = pevm;
+            }
+            result
+        }
+    }
+
+    // A fresh [Pevm] without caches or results, of the same configuration.
+    fn with_same_config(&self) -> Self {
+        let mut pevm = Self::new(self.mode).with_strategy(self.strategy.clone());
+        pevm.hooks.clone_from(&self.hooks);
+        pevm.precompiles.clone_from(&self.precompiles);
+        #[cfg(feature = "rayon")]
+        let pevm = match &self.thread_pool {
+            Some(thread_pool) => pevm.with_thread_pool(thread_pool.clone()),
+            None => pevm,
+        };
+        pevm
+    }
+
+    // Take over the results of the last execution of [other], like the
+    // skipped transactions and the report, as if this [Pevm] executed it.
+    fn take_execution_state(&mut self, other: &mut Self) {
+        self.skipped_tx_idxs = mem::take(&mut other.skipped_tx_idxs);
+        self.concurrency_level = other.concurrency_level.take();
+        self.schedule = other.schedule.take();
+        self.dependencies = other.dependencies.take();
+        self.report = other.report.take();
+        self.events = other.events.take();
+        self.hot_locations = other.hot_locations.take();
+        self.memory_usage = other.memory_usage.take();
+        self.fallback = other.fallback.take();
+    }
+
+    /// Execute an Alloy block like [Pevm::execute], additionally crediting the
+    /// static block and ommer rewards when the block's ommer headers are
+    /// provided. The RPC block format only includes the ommers' hashes, so
+    /// callers need to fetch their headers separately (like via
+    /// `eth_getUncleByBlockHashAndIndex`).
+    pub fn execute_with_ommers<S: Storage + Send + Sync, C: PevmChain + Send + Sync>(
+        &mut self,
+        storage: &S,
+        chain: &C,
+        block: Block,
+        ommers: Option<&[Header]>,
+        concurrency_level: NonZeroUsize,
+        force_sequential: bool,
+    ) -> PevmBlockResult<C> {
+        if let Some(ommers) = ommers {
+            if ommers.len() != block.uncles.len()
+                || ommers.iter().any(|ommer| ommer.number.is_none())
+            {
+                return Err(PevmError::MissingOmmerData);
+            }
+        }
+        let mut parts = block.into_parts(chain)?;
+        parts.ommers = ommers.map(<[Header]>::to_vec);
+        self.execute_parts(storage, chain, parts, concurrency_level, force_sequential)
+    }
+
+    /// Execute a block of any [BlockInput] representation, like an Alloy
+    /// block via [Pevm::execute] or a [ConsensusBlock] of a node's database
+    /// without converting it to the RPC format. The block and ommer rewards
+    /// are credited when the block has its ommer headers.
+    pub fn execute_block<S: Storage + Send + Sync, C: PevmChain + Send + Sync, B: BlockInput>(
+        &mut self,
+        storage: &S,
+        chain: &C,
+        mut block: B,
+        concurrency_level: NonZeroUsize,
+        force_sequential: bool,
+    ) -> PevmBlockResult<C> {
+        block.recover_senders(chain, concurrency_level)?;
+        let parts = block.into_parts(chain)?;
+        self.execute_parts(storage, chain, parts, concurrency_level, force_sequential)
+    }
+
+    fn execute_parts<S: Storage + Send + Sync, C: PevmChain + Send + Sync>(
+        &mut self,
+        storage: &S,
+        chain: &C,
+        parts: BlockParts,
+        concurrency_level: NonZeroUsize,
+        force_sequential: bool,
+    ) -> PevmBlockResult<C> {
+        #[cfg(feature = "metrics")]
+        let started_at = Instant::now();
+        let BlockParts {
+            header,
+            tx_types,
+            txs: tx_envs,
+            ommers,
+            withdrawals,
+        } = parts;
+        #[cfg(feature = "tracing")]
+        let _span =
+            tracing::info_span!("execute_block", number = header.number, txs = tx_envs.len())
+                .entered();
+        let spec_id = chain
+            .get_block_spec(&header)
+            .map_err(PevmError::BlockSpecError)?;
+        let Some(block_env) = get_block_env(&header) else {
+            return Err(PevmError::MissingHeaderData);
+        };
+        // Rewarded ommers must be from the six blocks before the block.
+        if let Some(ommers) = &ommers {
+            let number = header.number.ok_or(PevmError::MissingHeaderData)?;
+            for (ommer_idx, ommer) in ommers.iter().enumerate() {
+                let ommer_number = ommer.number.ok_or(PevmError::MissingOmmerData)?;
+                if ommer_number >= number || number - ommer_number > 6 {
+                    return Err(PevmError::InvalidOmmer { ommer_idx });
+                }
+            }
+        }
+        // Cancun blocks must declare their blob gas for validation. Only the
+        // presence of the excess blob gas is checked here, as it follows from
+        // the parent header given with [PevmOptions::with_parent_header].
+        let header_blob_gas_used = if spec_id.is_enabled_in(CANCUN) {
+            match (header.blob_gas_used, header.excess_blob_gas) {
+                (Some(blob_gas_used), Some(_)) => Some(blob_gas_used),
+                _ => return Err(PevmError::MissingHeaderData),
+            }
+        } else {
+            None
+        };
+
+        let pre_block_state =
+            apply_state_changes(storage, &[], chain.get_pre_block_state_changes(&header))?;
+
+        // TODO: Continue to fine tune this condition.
+        let sequential = force_sequential
+            || tx_envs.len() < concurrency_level.into()
+            || header.gas_used < 4_000_000;
+        let hooked_txs = (!self.hooks.is_empty()).then(|| tx_envs.clone());
+        let mut tx_results = if pre_block_state.is_empty() {
+            self.execute_txs(
+                storage,
+                chain,
+                spec_id,
+                block_env,
+                tx_envs,
+                concurrency_level,
+                sequential,
+            )
+        } else {
+            // Transactions must execute on top of the irregular state changes.
+            self.execute_txs(
+                &PreBlockStorage {
+                    storage,
+                    state: &pre_block_state,
+                },
+                chain,
+                spec_id,
+                block_env,
+                tx_envs,
+                concurrency_level,
+                sequential,
+            )
+        }?;
+
+        retag_receipts(&mut tx_results, tx_types, &self.skipped_tx_idxs);
+
+        // Skipped transactions in [ExecutionMode::Build] don't count towards
+        // the header's blob gas, which is only a target when building.
+        if let Some(header_blob_gas_used) =
+            header_blob_gas_used.filter(|_| self.mode != ExecutionMode::Build)
+        {
+            let blob_gas_used: u128 = tx_results
+                .iter()
+                .map(|tx_result| tx_result.blob_gas_used as u128)
+                .sum();
+            if blob_gas_used != header_blob_gas_used
+                || blob_gas_used > MAX_BLOB_GAS_PER_BLOCK as u128
+            {
+                return Err(PevmError::InvalidBlobGasUsed {
+                    header: header_blob_gas_used,
+                    computed: blob_gas_used,
+                });
+            }
+        }
+
+        let mut post_block_changes = match ommers {
+            Some(ommers) => chain.get_block_rewards(spec_id, &header, &ommers),
+            None => Vec::new(),
+        };
+        post_block_changes.extend(chain.get_post_block_state_changes(&header));
+        if let Some(withdrawals) = &withdrawals {
+            post_block_changes.extend(withdrawals.iter().map(|withdrawal| {
+                IrregularStateChange::BalanceIncrement(withdrawal.address, withdrawal.amount_wei())
+            }));
+        }
+        let prior_states: Vec<&EvmStateTransitions> = iter::once(&pre_block_state)
+            .chain(tx_results.iter().map(|tx_result| &tx_result.state))
+            .collect();
+        let post_block_state = apply_state_changes(storage, &prior_states, post_block_changes)?;
+
+        if let Some(txs) = hooked_txs {
+            self.run_hooks(&txs, &self.skipped_tx_idxs, &tx_results);
+        }
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_block(tx_results.len(), started_at.elapsed());
+        Ok(PevmBlockExecutionResult {
+            pre_block_state,
+            tx_results,
+            post_block_state,
+        })
+    }
+
+    /// Execute an Alloy block like [Pevm::execute] with overrides of its
+    /// header and state for simulations, like for `eth_call`'s block and
+    /// state overrides, without changing the storage.
+    #[allow(clippy::too_many_arguments)]
+    pub fn execute_with_overrides<S: Storage + Send + Sync, C: PevmChain + Send + Sync>(
+        &mut self,
+        storage: &S,
+        chain: &C,
+        mut block: Block,
+        block_overrides: &BlockOverrides,
+        state_overrides: StateOverrides,
+        concurrency_level: NonZeroUsize,
+        force_sequential: bool,
+    ) -> PevmBlockResult<C> {
+        block_overrides.apply_to_header(&mut block.header);
+        self.execute(
+            &OverlayStorage::new(storage, state_overrides),
+            chain,
+            block,
+            concurrency_level,
+            force_sequential,
+        )
+    }
+
+    /// Execute a range of an Alloy block's transactions on top of the results
+    /// of the transactions before it, like to trace a single transaction or
+    /// to stream pre-confirmations. The prior results must be from executing
+    /// the block's earlier transactions on the same storage, like via a
+    /// previous range, with one result per earlier transaction except for
+    /// the ascending [prior_skipped_tx_idxs] that [ExecutionMode::Build]
+    /// skipped. Only the range's results are returned, with their cumulative
+    /// gas continuing from the prior results, and the indices of skipped
+    /// transactions are of the whole block.
+    #[allow(clippy::too_many_arguments)]
+    pub fn execute_range<S: Storage + Send + Sync, C: PevmChain + Send + Sync>(
+        &mut self,
+        storage: &S,
+        chain: &C,
+        block: Block,
+        range: Range<usize>,
+        prior_results: &[PevmTxExecutionResult],
+        prior_skipped_tx_idxs: &[usize],
+        concurrency_level: NonZeroUsize,
+    ) -> PevmResult<C> {
+        let spec_id = chain
+            .get_block_spec(&block.header)
+            .map_err(PevmError::BlockSpecError)?;
+        let Some(block_env) = get_block_env(&block.header) else {
+            return Err(PevmError::MissingHeaderData);
+        };
+        let BlockTransactions::Full(txs) = block.transactions else {
+            return Err(PevmError::MissingTransactionData);
+        };
+        // Transactions skipped in [ExecutionMode::Build] have no results.
+        let has_prior_results = prior_skipped_tx_idxs
+            .windows(2)
+            .all(|tx_idxs| tx_idxs[0] < tx_idxs[1])
+            && prior_skipped_tx_idxs
+                .last()
+                .map_or(true, |tx_idx| *tx_idx < range.start)
+            && prior_results.len() + prior_skipped_tx_idxs.len() == range.start;
+        if range.start > range.end || range.end > txs.len() || !has_prior_results {
+            return Err(PevmError::InvalidTransactionRange {
+                start: range.start,
+                end: range.end,
+                block_size: txs.len(),
+            });
+        }
+        let tx_types: Vec<_> = txs[range.clone()]
+            .iter()
+            .map(|tx| TxType::try_from(tx.transaction_type.unwrap_or_default()).ok())
+            .collect();
+        let tx_envs = txs
+            .into_iter()
+            .skip(range.start)
+            .take(range.len())
+            .map(|tx| get_tx_env(chain, tx))
+            .collect::<Result<Vec<TxEnv>, TransactionParsingError<_>>>()
+            .map_err(PevmError::InvalidTransaction)?;
+
+        // The range executes on top of the irregular state changes and the
+        // prior transactions.
+        let committed_storage = commit_prior_results(storage, chain, &block.header, prior_results)?;
+        let sequential = tx_envs.len() < concurrency_level.into();
+        let mut tx_results = self.execute_txs(
+            &committed_storage,
+            chain,
+            spec_id,
+            block_env,
+            tx_envs,
+            concurrency_level,
+            sequential,
+        )?;
+        retag_receipts(&mut tx_results, tx_types, &self.skipped_tx_idxs);
+
+        let prior_gas_used = prior_results
+            .last()
+            .map(|tx_result| tx_result.receipt.cumulative_gas_used())
+            .unwrap_or_default();
+        for tx_result in tx_results.iter_mut() {
+            receipt_with_bloom_mut(&mut tx_result.receipt)
+                .receipt
+                .cumulative_gas_used += prior_gas_used;
+        }
+        for tx_idx in self.skipped_tx_idxs.iter_mut() {
+            *tx_idx += range.start;
+        }
+        Ok(tx_results)
+    }
+
+    /// Execute the transaction at [tx_idx] of an Alloy block on top of the
+    /// state after the block's earlier transactions, which execute in
+    /// parallel like via [Pevm::execute_range], to serve `eth_call` at a
+    /// historical position or `trace_transaction`. [tx_override] replaces
+    /// the transaction at [tx_idx], or appends one to the block when
+    /// [tx_idx] is the block's size. The result's cumulative gas continues
+    /// from the earlier transactions.
+    pub fn execute_tx_at<S: Storage + Send + Sync, C: PevmChain + Send + Sync>(
+        &mut self,
+        storage: &S,
+        chain: &C,
+        block: Block,
+        tx_idx: usize,
+        tx_override: Option<TxEnv>,
+        concurrency_level: NonZeroUsize,
+    ) -> Result<PevmTxExecutionResult, PevmError<C>> {
+        let spec_id = chain
+            .get_block_spec(&block.header)
+            .map_err(PevmError::BlockSpecError)?;
+        let Some(block_env) = get_block_env(&block.header) else {
+            return Err(PevmError::MissingHeaderData);
+        };
+        let BlockTransactions::Full(txs) = &block.transactions else {
+            return Err(PevmError::MissingTransactionData);
+        };
+        let block_size = txs.len();
+        if tx_idx > block_size || (tx_idx == block_size && tx_override.is_none()) {
+            return Err(PevmError::InvalidTransactionRange {
+                start: tx_idx,
+                end: tx_idx + 1,
+                block_size,
+            });
+        }
+        let (tx_type, tx_env) = match tx_override {
+            Some(tx_env) => (None, tx_env),
+            None => {
+                let tx = txs[tx_idx].clone();
+                (
+                    TxType::try_from(tx.transaction_type.unwrap_or_default()).ok(),
+                    get_tx_env(chain, tx).map_err(PevmError::InvalidTransaction)?,
+                )
+            }
+        };
+        let header = block.header.clone();
+
+        let prior_results = self.execute_range(
+            storage,
+            chain,
+            block,
+            0..tx_idx,
+            &[],
+            &[],
+            concurrency_level,
+        )?;
+        let committed_storage = commit_prior_results(storage, chain, &header, &prior_results)?;
+        let mut tx_results = execute_revm_sequential_in_mode(
+            &committed_storage,
+            chain,
+            spec_id,
+            block_env,
+            vec![tx_env],
+            None,
+            &self.precompiles,
+            NO_INSPECTION,
+            &(),
+        )
+        .map_err(|err| match err {
+            PevmError::ExecutionError(err) => {
+                PevmError::ExecutionError(TxExecutionError { tx_idx, ..err })
+            }
+            err => err,
+        })?;
+        retag_receipts(&mut tx_results, vec![tx_type], &[]);
+
+        let mut tx_result = tx_results.pop().ok_or(PevmError::UnreachableError)?;
+        receipt_with_bloom_mut(&mut tx_result.receipt)
+            .receipt
+            .cumulative_gas_used += prior_results
+            .last()
+            .map(|tx_result| tx_result.receipt.cumulative_gas_used())
+            .unwrap_or_default();
+        Ok(tx_result)
+    }
+
+    /// Execute consecutive Alloy blocks like [Pevm::execute], speculatively
+    /// executing each next block on the state before the current one while
+    /// the current one executes, to hide storage latency during sync. Once
+    /// the current block finishes, the next block's result is kept if the
+    /// storage values it read are unchanged by the current block, and it is
+    /// re-executed on the new state otherwise. Each block executes with up to
+    /// [concurrency_level] threads, so up to twice as many are busy while
+    /// speculating. Returns the results up to the first failing block, and
+    /// accessors like [Pevm::skipped_tx_idxs] and [Pevm::report] refer to
+    /// the last executed block, whether it was speculated or not.
+    pub fn execute_pipelined<S: Storage + Send + Sync, C: PevmChain + Send + Sync>(
+        &mut self,
+        storage: &S,
+        chain: &C,
+        blocks: Vec<Block>,
+        concurrency_level: NonZeroUsize,
+    ) -> Vec<PevmBlockResult<C>> {
+        let mut committed_storage = CommittedStorage::new(storage, &[]);
+        // The speculative engine runs no hooks, which only run once its
+        // result is accepted to not see discarded speculations.
+        let mut speculative_pevm = self.with_same_config();
+        speculative_pevm.hooks.clear();
+        let mut block_results = Vec::with_capacity(blocks.len());
+        // The result of the current block when it was speculated correctly.
+        let mut speculated_result = None;
+        let mut blocks = blocks.into_iter().peekable();
+        while let Some(block) = blocks.next() {
+            let block_hash = block.header.number.zip(block.header.hash);
+            let (block_result, speculation) = match speculated_result.take() {
+                Some(block_result) => (Ok(block_result), None),
+                None => thread::scope(|scope| {
+                    let speculation = blocks.peek().cloned().map(|mut next_block| {
+                        let hooked = !self.hooks.is_empty();
+                        let (speculative_pevm, committed_storage) =
+                            (&mut speculative_pevm, &committed_storage);
+                        scope.spawn(move || {
+                            let speculative_storage = SpeculativeStorage {
+                                storage: committed_storage,
+                                reads: SpeculativeReads::default(),
+                            };
+                            let parts = next_block
+                                .recover_senders(chain, concurrency_level)
+                                .and_then(|()| next_block.into_parts(chain));
+                            let Ok(parts) = parts else {
+                                return (None, speculative_storage.reads, None);
+                            };
+                            let hooked_txs = hooked.then(|| parts.txs.clone());
+                            let block_result = speculative_pevm
+                                .execute_parts(
+                                    &speculative_storage,
+                                    chain,
+                                    parts,
+                                    concurrency_level,
+                                    false,
+                                )
+                                .ok();
+                            (block_result, speculative_storage.reads, hooked_txs)
+                        })
+                    });
+                    let block_result =
+                        self.execute(&committed_storage, chain, block, concurrency_level, false);
+                    (
+                        block_result,
+                        speculation.map(|handle| handle.join().unwrap()),
+                    )
+                }),
+            };
+            let block_result = match block_result {
+                Ok(block_result) => block_result,
+                Err(err) => {
+                    block_results.push(Err(err));
+                    break;
+                }
+            };
+            committed_storage.commit_block(&block_result);
+            if let Some((block_number, block_hash)) = block_hash {
+                committed_storage.commit_block_hash(block_number, block_hash);
+            }
+            if let Some((Some(next_block_result), reads, hooked_txs)) = speculation {
+                if reads.hold_after(&committed_storage, &block_result) {
+                    if let Some(txs) = hooked_txs {
+                        self.run_hooks(
+                            &txs,
+                            &speculative_pevm.skipped_tx_idxs,
+                            &next_block_result.tx_results,
+                        );
+                    }
+                    self.take_execution_state(&mut speculative_pevm);
+                    speculated_result = Some(next_block_result);
+                }
+            }
+            block_results.push(Ok(block_result));
+        }
+        block_results
+    }
+
+    /// Execute the consecutive Alloy blocks of [block_numbers] like
+    /// [Pevm::execute], like for sync loops. Each block is fetched via
+    /// [fetch_block], executes on top of the changes and hashes of the
+    /// previous blocks of the range layered over the storage, then its
+    /// result is passed to [commit] in order, like to persist it in a
+    /// node's database. Caches like the bytecode cache are reused between
+    /// blocks. It stops at the first block that fails to fetch, execute or
+    /// commit.
+    pub fn execute_range_of_blocks<S: Storage + Send + Sync, C: PevmChain + Send + Sync, E>(
+        &mut self,
+        storage: &S,
+        chain: &C,
+        block_numbers: Range<u64>,
+        mut fetch_block: impl FnMut(u64) -> Result<Block, E>,
+        mut commit: impl FnMut(&Header, PevmBlockExecutionResult) -> Result<(), E>,
+        concurrency_level: NonZeroUsize,
+    ) -> Result<(), BlockRangeError<C, E>> {
+        let mut committed_storage = CommittedStorage::new(storage, &[]);
+        for block_number in block_numbers {
+            let block = fetch_block(block_number).map_err(|error| BlockRangeError::Fetch {
+                block_number,
+                error,
+            })?;
+            let header = block.header.clone();
+            let block_result = self
+                .execute(&committed_storage, chain, block, concurrency_level, false)
+                .map_err(|error| BlockRangeError::Execution {
+                    block_number,
+                    error,
+                })?;
+            committed_storage.commit_block(&block_result);
+            if let Some(hash) = header.hash {
+                committed_storage.commit_block_hash(block_number, hash);
+            }
+            commit(&header, block_result).map_err(|error| BlockRangeError::Commit {
+                block_number,
+                error,
+            })?;
+        }
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn execute_txs<S: Storage + Send + Sync, C: PevmChain + Send + Sync>(
+        &mut self,
+        storage: &S,
+        chain: &C,
+        spec_id: SpecId,
+        block_env: BlockEnv,
+        tx_envs: Vec<TxEnv>,
+        concurrency_level: NonZeroUsize,
+        sequential: bool,
+    ) -> PevmResult<C> {
+        if sequential {
+            self.skipped_tx_idxs.clear();
+            self.concurrency_level = None;
+            self.schedule = None;
+            self.dependencies = None;
+            self.report = None;
+            self.events = None;
+            self.hot_locations = None;
+            self.memory_usage = None;
+            self.fallback = None;
+            execute_revm_sequential_in_mode(
+                storage,
+                chain,
+                spec_id,
+                block_env,
+                tx_envs,
+                (self.mode == ExecutionMode::Build).then_some(&mut self.skipped_tx_idxs),
+                &self.precompiles,
+                NO_INSPECTION,
+                &(),
+            )
+        } else {
+            self.execute_revm_parallel_unhooked(
+                storage,
+                chain,
+                spec_id,
+                block_env,
+                tx_envs,
+                concurrency_level,
+            )
+        }
+    }
+
+    /// Execute an REVM block.
+    // Ideally everyone would go through the [Alloy] interface. This one is currently
+    // useful for testing, and for users that are heavily tied to Revm like Reth.
+    pub fn execute_revm_parallel<S: Storage + Send + Sync, C: PevmChain + Send + Sync>(
+        &mut self,
+        storage: &S,
+        chain: &C,
+        spec_id: SpecId,
+        block_env: BlockEnv,
+        txs: Vec<TxEnv>,
+        concurrency_level: NonZeroUsize,
+    ) -> PevmResult<C> {
+        let hooked_txs = (!self.hooks.is_empty()).then(|| txs.clone());
+        let tx_results = self.execute_revm_parallel_unhooked(
+            storage,
+            chain,
+            spec_id,
+            block_env,
+            txs,
+            concurrency_level,
+        )?;
+        if let Some(txs) = hooked_txs {
+            self.run_hooks(&txs, &self.skipped_tx_idxs, &tx_results);
+        }
+        Ok(tx_results)
+    }
+
+    fn execute_revm_parallel_unhooked<S: Storage + Send + Sync, C: PevmChain + Send + Sync>(
+        &mut self,
+        storage: &S,
+        chain: &C,
+        spec_id: SpecId,
+        block_env: BlockEnv,
+        txs: Vec<TxEnv>,
+        concurrency_level: NonZeroUsize,
+    ) -> PevmResult<C> {
+        match self.strategy.chunk_size {
+            Some(chunk_size) if txs.len() > chunk_size.get() && !self.strategy.record_schedule => {
+                self.run_revm_parallel_chunked(
+                    storage,
+                    chain,
+                    spec_id,
+                    block_env,
+                    txs,
+                    concurrency_level,
+                    chunk_size,
+                    |_, _| {},
+                )
+            }
+            _ => self.run_revm_parallel(
+                storage,
+                chain,
+                spec_id,
+                block_env,
+                txs,
+                concurrency_level,
+                None,
+                None,
+                NO_INSPECTION,
+                &(),
+            ),
+        }
+    }
+
+    // Invoke the hooks on the final results of [txs] in block order.
+    fn run_hooks(
+        &self,
+        txs: &[TxEnv],
+        skipped_tx_idxs: &[usize],
+        tx_results: &[PevmTxExecutionResult],
+    ) {
+        let tx_idxs =
+            (0..txs.len()).filter(|tx_idx| skipped_tx_idxs.binary_search(tx_idx).is_err());
+        for (tx_idx, tx_result) in tx_idxs.zip(tx_results) {
+            for hook in &self.hooks {
+                hook.before_tx(tx_idx, &txs[tx_idx]);
+                hook.after_tx(tx_idx, tx_result);
+            }
+        }
+    }
+
+    /// Simulate an ordered bundle of transactions on top of a state with the
+    /// parallel engine, like for searchers and builders to score MEV
+    /// bundles by their coinbase payments. Transactions skipped in
+    /// [ExecutionMode::Build] are available via [Pevm::skipped_tx_idxs] and
+    /// have no results.
+    pub fn simulate_bundle<S: Storage + Send + Sync, C: PevmChain + Send + Sync>(
+        &mut self,
+        storage: &S,
+        chain: &C,
+        block_env: BlockEnv,
+        txs: Vec<TxEnv>,
+        options: &BundleOptions,
+    ) -> Result<BundleSimulation, PevmError<C>> {
+        let mut block_env = block_env;
+        options.block_overrides.apply(&mut block_env);
+        let storage = OverlayStorage::new(storage, options.state_overrides.clone());
+        let coinbase = block_env.coinbase;
+        let coinbase_balance = storage
+            .basic(&coinbase)
+            .map_err(|err| PevmError::StorageError(StorageError::new(err)))?
+            .map_or(U256::ZERO, |account| account.balance);
+        let bundle_size = txs.len();
+        let tx_results = self.execute_revm_parallel_unhooked(
+            &storage,
+            chain,
+            options.spec_id,
+            block_env,
+            txs,
+            options.concurrency_level,
+        )?;
+
+        let tx_idxs =
+            (0..bundle_size).filter(|tx_idx| self.skipped_tx_idxs.binary_search(tx_idx).is_err());
+        for (tx_idx, tx_result) in tx_idxs.zip(&tx_results) {
+            if !tx_result.receipt.status() && !options.reverting_tx_idxs.contains(&tx_idx) {
+                return Err(PevmError::BundleTxReverted { tx_idx });
+            }
+        }
+        let final_coinbase_balance = tx_results
+            .iter()
+            .rev()
+            .find_map(|tx_result| tx_result.state.get(&coinbase))
+            .map_or(coinbase_balance, |account| {
+                account
+                    .as_ref()
+                    .map_or(U256::ZERO, |account| account.balance)
+            });
+        Ok(BundleSimulation {
+            coinbase_diff: final_coinbase_balance.saturating_sub(coinbase_balance),
+            gas_used: tx_results.iter().map(|tx_result| tx_result.gas_used).sum(),
+            tx_results,
+        })
+    }
+
+    /// Simulate consecutive blocks of calls on top of the block of
+    /// [block_env], like for `eth_simulateV1` and `eth_callMany`. Each block
+    /// executes in parallel on the committed state of the previous ones,
+    /// reusing this [Pevm]'s caches between blocks. Errors are those of the
+    /// failing block, with transaction indices within that block.
+    pub fn simulate_blocks<S: Storage + Send + Sync, C: PevmChain + Send + Sync>(
+        &mut self,
+        storage: &S,
+        chain: &C,
+        spec_id: SpecId,
+        mut block_env: BlockEnv,
+        blocks: Vec<SimulatedBlock>,
+        concurrency_level: NonZeroUsize,
+    ) -> Result<Vec<Vec<PevmTxExecutionResult>>, PevmError<C>> {
+        let mut committed_storage = CommittedStorage::new(storage, &[]);
+        let mut block_results = Vec::with_capacity(blocks.len());
+        for block in blocks {
+            block_env.number += U256::from(1);
+            block_env.timestamp += U256::from(12);
+            block.block_overrides.apply(&mut block_env);
+            let tx_results = self.execute_revm_parallel_unhooked(
+                &committed_storage,
+                chain,
+                spec_id,
+                block_env.clone(),
+                block.calls,
+                concurrency_level,
+            )?;
+            for tx_result in tx_results.iter() {
+                committed_storage.commit(&tx_result.state);
+            }
+            block_results.push(tx_results);
+        }
+        Ok(block_results)
+    }
+
+    /// Estimate the lowest gas limit that a transaction succeeds with on top
+    /// of a state, like for `eth_estimateGas`, by the standard binary search
+    /// up to the transaction's gas limit as the cap. All iterations reuse
+    /// the same EVM, with the accounts, storage slots and analyzed bytecode
+    /// read by the previous ones, instead of re-reading storage every time.
+    pub fn estimate_gas<S: Storage, C: PevmChain>(
+        &self,
+        storage: &S,
+        chain: &C,
+        spec_id: SpecId,
+        block_env: BlockEnv,
+        tx: TxEnv,
+    ) -> Result<u64, PevmError<C>> {
+        let mut db = CacheDB::new(StorageWrapper(storage));
+        let mut evm = build_evm(
+            &mut db,
+            chain,
+            spec_id,
+            block_env,
+            true,
+            &self.precompiles,
+            (),
+        );
+        let gas_cap = tx.gas_limit;
+        *evm.tx_mut() = tx;
+        let result = evm
+            .transact()
+            .map_err(|err| {
+                PevmError::ExecutionError(TxExecutionError {
+                    tx_idx: 0,
+                    tx_incarnation: 0,
+                    error: err.map_db_err(|err| ReadError::StorageError(StorageError::new(err))),
+                })
+            })?
+            .result;
+        let ExecutionResult::Success {
+            gas_used,
+            gas_refunded,
+            ..
+        } = result
+        else {
+            return Err(PevmError::GasEstimationFailed {
+                output: result.output().cloned().unwrap_or_default(),
+            });
+        };
+
+        // Whether the transaction succeeds with a gas limit. Lower limits
+        // can fail validation, like being below the intrinsic gas.
+        let mut succeeds = |gas_limit: u64| -> Result<bool, PevmError<C>> {
+            evm.tx_mut().gas_limit = gas_limit;
+            match evm.transact() {
+                Ok(result_and_state) => Ok(result_and_state.result.is_success()),
+                Err(EVMError::Database(err)) => {
+                    Err(PevmError::StorageError(StorageError::new(err)))
+                }
+                Err(_) => Ok(false),
+            }
+        };
+        // The transaction needs more than the gas it used after refunds, and
+        // often just what it spent before refunds plus a call stipend, as the
+        // 63/64 rule (EIP-150) makes nested calls need more than they spend.
+        let mut lo = gas_used.saturating_sub(1);
+        let mut hi = gas_cap;
+        let optimistic = ((gas_used + gas_refunded + 2_300) * 64 / 63).min(hi);
+        if optimistic > lo && optimistic < hi {
+            if succeeds(optimistic)? {
+                hi = optimistic;
+            } else {
+                lo = optimistic;
+            }
+        }
+        while lo + 1 < hi {
+            let mid = lo + (hi - lo) / 2;
+            if succeeds(mid)? {
+                hi = mid;
+            } else {
+                lo = mid;
+            }
+        }
+        Ok(hi)
+    }
+
+    /// Execute an REVM block like [Pevm::execute_revm_parallel], passing each
+    /// transaction's index and final result to [on_tx_result] in block order
+    /// as soon as it is final, for RPC servers and pre-confirmations to not
+    /// wait for the whole block. Results are final at the end of each chunk
+    /// of [PevmStrategy::chunk_size] transactions, or of the whole block
+    /// without chunking. Send them to a channel from [on_tx_result] to
+    /// consume them on another thread.
+    pub fn execute_streaming<S: Storage + Send + Sync, C: PevmChain + Send + Sync>(
+        &mut self,
+        storage: &S,
+        chain: &C,
+        spec_id: SpecId,
+        block_env: BlockEnv,
+        txs: Vec<TxEnv>,
+        concurrency_level: NonZeroUsize,
+        on_tx_result: impl FnMut(usize, &PevmTxExecutionResult),
+    ) -> PevmResult<C> {
+        let chunk_size = match self.strategy.chunk_size {
+            Some(chunk_size) if !self.strategy.record_schedule => chunk_size,
+            _ => NonZeroUsize::new(txs.len()).unwrap_or(NonZeroUsize::MIN),
+        };
+        self.run_revm_parallel_chunked(
+            storage,
+            chain,
+            spec_id,
+            block_env,
+            txs,
+            concurrency_level,
+            chunk_size,
+            on_tx_result,
+        )
+    }
+
+    /// Execute an REVM block like [Pevm::execute_revm_parallel], with hints
+    /// of pre-known conflicts between its transactions to reduce aborts.
+    #[allow(clippy::too_many_arguments)]
+    pub fn execute_revm_parallel_with_hints<
+        S: Storage + Send + Sync,
+        C: PevmChain + Send + Sync,
+    >(
+        &mut self,
+        storage: &S,
+        chain: &C,
+        spec_id: SpecId,
+        block_env: BlockEnv,
+        txs: Vec<TxEnv>,
+        hints: &ExecutionHints,
+        concurrency_level: NonZeroUsize,
+    ) -> PevmResult<C> {
+        self.run_revm_parallel(
+            storage,
+            chain,
+            spec_id,
+            block_env,
+            txs,
+            concurrency_level,
+            Some(hints),
+            None,
+            NO_INSPECTION,
+            &(),
+        )
+    }
+
+    /// Execute an REVM block like [Pevm::execute_revm_parallel], attaching a
+    /// new inspector from [inspector_factory] to every execution, including
+    /// aborted incarnations whose outputs are discarded. Return the outputs
+    /// of the final executions in the order of the transaction results.
+    #[allow(clippy::too_many_arguments)]
+    pub fn execute_revm_parallel_with_inspector<
+        S: Storage + Send + Sync,
+        C: PevmChain + Send + Sync,
+        F: InspectorFactory,
+    >(
+        &mut self,
+        storage: &S,
+        chain: &C,
+        spec_id: SpecId,
+        block_env: BlockEnv,
+        txs: Vec<TxEnv>,
+        inspector_factory: &F,
+        concurrency_level: NonZeroUsize,
+    ) -> Result<(Vec<PevmTxExecutionResult>, Vec<F::Output>), PevmError<C>> {
+        let inspection = Inspection::new(inspector_factory, txs.len());
+        let tx_results = self.run_revm_parallel(
+            storage,
+            chain,
+            spec_id,
+            block_env,
+            txs,
+            concurrency_level,
+            None,
+            None,
+            Some(&inspection),
+            &(),
+        )?;
+        // Skipped transactions may have succeeded in previous incarnations.
+        let outputs = inspection
+            .outputs
+            .into_iter()
+            .enumerate()
+            .filter(|(tx_idx, _)| self.skipped_tx_idxs.binary_search(tx_idx).is_err())
+            .map(|(_, output)| output.into_inner().unwrap().unwrap())
+            .collect();
+        Ok((tx_results, outputs))
+    }
+
+    /// Execute an REVM block like [Pevm::execute_revm_parallel], with a new
+    /// external context from [external_factory] for every execution, for
+    /// the handler hooks of [crate::ExternalContext] to read side-channel
+    /// data from.
+    #[allow(clippy::too_many_arguments)]
+    pub fn execute_revm_parallel_with_external<
+        S: Storage + Send + Sync,
+        C: PevmChain + Send + Sync,
+        X: ExternalFactory,
+    >(
+        &mut self,
+        storage: &S,
+        chain: &C,
+        spec_id: SpecId,
+        block_env: BlockEnv,
+        txs: Vec<TxEnv>,
+        external_factory: &X,
+        concurrency_level: NonZeroUsize,
+    ) -> PevmResult<C> {
+        self.run_revm_parallel(
+            storage,
+            chain,
+            spec_id,
+            block_env,
+            txs,
+            concurrency_level,
+            None,
+            None,
+            NO_INSPECTION,
+            external_factory,
+        )
+    }
+
+    /// Execute an REVM block like [Pevm::execute_revm_parallel], reusing the
+    /// results of the transactions before the first one that changed since
+    /// the last incremental execution, to only execute the changed suffix.
+    /// This suits block builders that try different suffixes on the same
+    /// prefix. The storage must be the same as in the last incremental
+    /// execution, and the number of reused transactions is available via
+    /// [Pevm::reused_tx_count].
+    pub fn execute_revm_parallel_incremental<
+        S: Storage + Send + Sync,
+        C: PevmChain + Send + Sync,
+    >(
+        &mut self,
+        storage: &S,
+        chain: &C,
+        spec_id: SpecId,
+        block_env: BlockEnv,
+        txs: Vec<TxEnv>,
+        concurrency_level: NonZeroUsize,
+    ) -> PevmResult<C> {
+        let (prefix_len, mut tx_results, mut skipped_tx_idxs) = match self.incremental_block.take()
+        {
+            Some(last) if last.spec_id == spec_id && last.block_env == block_env => {
+                let prefix_len = iter::zip(&last.txs, &txs)
+                    .take_while(|(last_tx, tx)| last_tx == tx)
+                    .count();
+                let mut skipped_tx_idxs = last.skipped_tx_idxs;
+                skipped_tx_idxs.retain(|tx_idx| *tx_idx < prefix_len);
+                let mut tx_results = last.tx_results;
+                tx_results.truncate(prefix_len - skipped_tx_idxs.len());
+                (prefix_len, tx_results, skipped_tx_idxs)
+            }
+            _ => (0, Vec::new(), Vec::new()),
+        };
+        self.reused_tx_count = prefix_len;
+
+        let result = self
+            .execute_revm_parallel_unhooked(
+                &CommittedStorage::new(storage, &tx_results),
+                chain,
+                spec_id,
+                block_env.clone(),
+                txs[prefix_len..].to_vec(),
+                concurrency_level,
+            )
+            .map(|suffix_results| {
+                let prefix_gas_used = tx_results
+                    .last()
+                    .map(|tx_result| tx_result.receipt.cumulative_gas_used())
+                    .unwrap_or_default();
+                for mut tx_result in suffix_results {
+                    receipt_with_bloom_mut(&mut tx_result.receipt)
+                        .receipt
+                        .cumulative_gas_used += prefix_gas_used;
+                    tx_results.push(tx_result);
+                }
+                skipped_tx_idxs.extend(
+                    self.skipped_tx_idxs
+                        .iter()
+                        .map(|tx_idx| tx_idx + prefix_len),
+                );
+            });
+        // The hooks see the whole block, including the reused prefix.
+        if result.is_ok() {
+            self.run_hooks(&txs, &skipped_tx_idxs, &tx_results);
+        }
+        // Keep the prefix for the next execution even if the suffix fails.
+        self.incremental_block = Some(IncrementalBlock {
+            spec_id,
+            block_env,
+            txs: if result.is_ok() {
+                txs
+            } else {
+                txs[..prefix_len].to_vec()
+            },
+            tx_results: tx_results.clone(),
+            skipped_tx_idxs: skipped_tx_idxs.clone(),
+        });
+        self.skipped_tx_idxs = skipped_tx_idxs;
+        result.map(|()| tx_results)
+    }
+
+    /// Replay a schedule recorded with [PevmStrategy::record_schedule] on
+    /// the same block, running the recorded tasks in order on the current
+    /// thread to reproduce race conditions deterministically. The replay's
+    /// own schedule is available via [Pevm::schedule] to compare against.
+    pub fn replay_revm_parallel<S: Storage + Send + Sync, C: PevmChain + Send + Sync>(
+        &mut self,
+        storage: &S,
+        chain: &C,
+        spec_id: SpecId,
+        block_env: BlockEnv,
+        txs: Vec<TxEnv>,
+        schedule: &[ScheduleEvent],
+    ) -> PevmResult<C> {
+        self.run_revm_parallel(
+            storage,
+            chain,
+            spec_id,
+            block_env,
+            txs,
+            NonZeroUsize::MIN,
+            None,
+            Some(schedule),
+            NO_INSPECTION,
+            &(),
+        )
+    }
+
+    // Execute the block in chunks of [chunk_size] transactions, layering the
+    // results of each chunk for the next ones to execute on, and passing the
+    // results of each chunk to [on_tx_result] as soon as it is done.
+    #[allow(clippy::too_many_arguments)]
+    fn run_revm_parallel_chunked<S: Storage + Send + Sync, C: PevmChain + Send + Sync>(
+        &mut self,
+        storage: &S,
+        chain: &C,
+        spec_id: SpecId,
+        block_env: BlockEnv,
+        mut txs: Vec<TxEnv>,
+        concurrency_level: NonZeroUsize,
+        chunk_size: NonZeroUsize,
+        mut on_tx_result: impl FnMut(usize, &PevmTxExecutionResult),
+    ) -> PevmResult<C> {
+        let mut committed_storage = CommittedStorage::new(storage, &[]);
+        let mut tx_results = Vec::with_capacity(txs.len());
+        let mut skipped_tx_idxs = Vec::new();
+        let mut dependencies = self.strategy.record_dependencies.then(Vec::new);
+        let mut report = self.strategy.record_report.then(ExecutionReport::default);
+        let mut events = self.strategy.record_events.then(Vec::new);
+        let mut hot_locations = self.strategy.record_hot_locations.then(Vec::new);
+        let mut memory_usage = Some(MemoryUsage::default());
+        let started_at = Instant::now();
+        let mut fallback = None;
+        let mut chunk_start = 0;
+        while !txs.is_empty() {
+            let remaining_txs = txs.split_off(chunk_size.get().min(txs.len()));
+            let chunk_len = txs.len();
+            let chunk_start_ns = started_at.elapsed().as_nanos() as u64;
+            let chunk_results = self
+                .run_revm_parallel(
+                    &committed_storage,
+                    chain,
+                    spec_id,
+                    block_env.clone(),
+                    mem::replace(&mut txs, remaining_txs),
+                    concurrency_level,
+                    None,
+                    None,
+                    NO_INSPECTION,
+                    &(),
+                )
+                .map_err(|err| match err {
+                    PevmError::ExecutionError(mut err) => {
+                        err.tx_idx += chunk_start;
+                        PevmError::ExecutionError(err)
+                    }
+                    err => err,
+                })?;
+            let prev_gas_used = tx_results
+                .last()
+                .map(|tx_result: &PevmTxExecutionResult| tx_result.receipt.cumulative_gas_used())
+                .unwrap_or_default();
+            let chunk_tx_idxs =
+                (0..chunk_len).filter(|tx_idx| self.skipped_tx_idxs.binary_search(tx_idx).is_err());
+            for (tx_idx, mut tx_result) in chunk_tx_idxs.zip(chunk_results) {
+                committed_storage.commit(&tx_result.state);
+                receipt_with_bloom_mut(&mut tx_result.receipt)
+                    .receipt
+                    .cumulative_gas_used += prev_gas_used;
+                on_tx_result(chunk_start + tx_idx, &tx_result);
+                tx_results.push(tx_result);
+            }
+            skipped_tx_idxs.extend(
+                self.skipped_tx_idxs
+                    .iter()
+                    .map(|tx_idx| tx_idx + chunk_start),
+            );
+            // Chunks that fell back to sequential execution have no dependencies.
+            dependencies = dependencies.zip(self.dependencies.take()).map(
+                |(mut dependencies, chunk_dependencies)| {
+                    dependencies.extend(chunk_dependencies.into_iter().map(|tx_dependencies| {
+                        tx_dependencies
+                            .into_iter()
+                            .map(|tx_idx| tx_idx + chunk_start)
+                            .collect()
+                    }));
+                    dependencies
+                },
+            );
+            // Chunks execute one after another, so their critical paths
+            // chain up.
+            report = report
+                .zip(self.report.take())
+                .map(|(mut report, chunk_report)| {
+                    report.txs.extend(chunk_report.txs);
+                    report.critical_path.extend(
+                        chunk_report
+                            .critical_path
+                            .into_iter()
+                            .map(|tx_idx| tx_idx + chunk_start),
+                    );
+                    report.critical_path_gas += chunk_report.critical_path_gas;
+                    report.critical_path_time += chunk_report.critical_path_time;
+                    report
+                });
+            // Chunk events are relative to their chunk's execution.
+            events = events
+                .zip(self.events.take())
+                .map(|(mut events, chunk_events)| {
+                    events.extend(chunk_events.into_iter().map(|mut event| {
+                        event.tx_idx += chunk_start;
+                        event.start_ns += chunk_start_ns;
+                        event.end_ns += chunk_start_ns;
+                        event
+                    }));
+                    events
+                });
+            hot_locations = hot_locations.zip(self.hot_locations.take()).map(
+                |(mut hot_locations, chunk_hot_locations)| {
+                    merge_hot_locations(&mut hot_locations, chunk_hot_locations);
+                    hot_locations
+                },
+            );
+            // Each chunk's multi-version data is dropped before the next
+            // one, while the results add up.
+            memory_usage = memory_usage.zip(self.memory_usage.take()).map(
+                |(memory_usage, chunk_memory_usage)| MemoryUsage {
+                    peak_mv_memory: memory_usage
+                        .peak_mv_memory
+                        .max(chunk_memory_usage.peak_mv_memory),
+                    results: memory_usage.results + chunk_memory_usage.results,
+                },
+            );
+            if fallback.is_none() {
+                fallback = self.fallback.take().map(|mut fallback| {
+                    fallback.tx_idx += chunk_start;
+                    fallback
+                });
+            }
+            chunk_start += chunk_len;
+        }
+        self.skipped_tx_idxs = skipped_tx_idxs;
+        self.dependencies = dependencies;
+        self.report = report;
+        self.events = events;
+        self.hot_locations = hot_locations;
+        self.memory_usage = memory_usage;
+        self.fallback = fallback;
+        Ok(tx_results)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn run_revm_parallel<
+        S: Storage + Send + Sync,
+        C: PevmChain + Send + Sync,
+        F: InspectorFactory,
+        X: ExternalFactory,
+    >(
+        &mut self,
+        storage: &S,
+        chain: &C,
+        spec_id: SpecId,
+        block_env: BlockEnv,
+        txs: Vec<TxEnv>,
+        concurrency_level: NonZeroUsize,
+        hints: Option<&ExecutionHints>,
+        replay: Option<&[ScheduleEvent]>,
+        inspection: Option<&Inspection<F>>,
+        externals: &X,
+    ) -> PevmResult<C> {
+        self.skipped_tx_idxs.clear();
+        self.concurrency_level = None;
+        self.schedule = None;
+        self.dependencies = None;
+        self.report = None;
+        self.events = None;
+        self.hot_locations = None;
+        self.memory_usage = None;
+        self.fallback = None;
+        if txs.is_empty() {
+            return Ok(Vec::new());
+        }
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(
+            "execute_parallel",
+            txs = txs.len(),
+            concurrency_level = concurrency_level.get()
+        )
+        .entered();
+
+        prefetch(storage, &block_env, &txs);
+
+        // Preprocess locations
+        let block_size = txs.len();
+        let hasher = ahash::RandomState::new();
+        // Initialize the remaining core components
+        // TODO: Provide more explicit garbage collecting configs for users over random background
+        // threads like this. For instance, to have a dedicated thread (pool) for cleanup.
+        let mut mv_memory = chain.build_mv_memory(&hasher, &block_env, &txs);
+        if let Some(memory_budget) = self.strategy.memory_budget {
+            mv_memory = mv_memory.with_max_memory(memory_budget.max_bytes);
+        }
+        if self.strategy.record_hot_locations {
+            mv_memory = mv_memory.with_location_stats();
+        }
+        let mut scheduler = Scheduler::new(
+            &txs,
+            self.strategy.scheduling,
+            self.strategy.record_schedule || replay.is_some(),
+            self.strategy.record_report,
+            self.strategy.record_events,
+        );
+        if let Some(hints) = hints {
+            mv_memory = mv_memory.with_estimated_locations(hints.hot_locations.iter().map(
+                |(location, tx_idxs)| {
+                    (
+                        hasher.hash_one(MemoryLocation::from(*location)),
+                        tx_idxs.clone(),
+                    )
+                },
+            ));
+            scheduler = scheduler.with_dependencies(&hints.dependencies);
+        }
+        let num_system_txs = txs
+            .iter()
+            .take_while(|tx| chain.is_top_of_block_system_tx(tx))
+            .count();
+        let mv_memory = DeferDrop::new(mv_memory);
+        let scheduler = DeferDrop::new(scheduler);
+        let txs = TxEnvs::new(txs);
+        let vm = Vm::new(
+            &hasher,
+            storage,
+            &mv_memory,
+            chain,
+            &block_env,
+            &txs,
+            spec_id,
+            self.mode,
+            self.strategy.retry,
+            &self.bytecode_cache,
+            &self.precompiles,
+            externals,
+        );
+
+        let mut abort_reason = OnceLock::new();
+        let execution_results: Vec<_> = (0..block_size).map(|_| Mutex::new(None)).collect();
+
+        let tuner = (self.strategy.adaptive_concurrency && replay.is_none())
+            .then(|| ConcurrencyTuner::new(concurrency_level));
+        let progress =
+            (!self.hooks.is_empty()).then(|| ProgressReporter::new(&self.hooks, block_size));
+
+        if let Some(schedule) = replay {
+            let replayed = replay_schedule(
+                &mv_memory,
+                &vm,
+                &scheduler,
+                &abort_reason,
+                &execution_results,
+                schedule,
+            );
+            if let Err(event_idx) = replayed {
+                self.schedule = scheduler.take_schedule();
+                return Err(PevmError::ScheduleDiverged { event_idx });
+            }
+        } else {
+            let (mv_memory, vm, scheduler, abort_reason, execution_results, tuner, progress) = (
+                &mv_memory,
+                &vm,
+                &scheduler,
+                &abort_reason,
+                &execution_results,
+                tuner.as_ref(),
+                progress.as_ref(),
+            );
+            execute_top_of_block(
+                mv_memory,
+                vm,
+                scheduler,
+                abort_reason,
+                execution_results,
+                inspection,
+                num_system_txs,
+            );
+            #[cfg(feature = "tracing")]
+            let parent_span = &tracing::Span::current();
+            let run_worker = move |worker_idx: usize| {
+                #[cfg(feature = "tracing")]
+                let _span =
+                    tracing::debug_span!(parent: parent_span, "worker", worker_idx).entered();
+                if tuner.is_some_and(|tuner| !tuner.wait_for_turn(worker_idx, scheduler)) {
+                    return;
+                }
+                let mut task = scheduler.next_task();
+                while let Some(current_task) = task {
+                    scheduler.record_task(&current_task);
+                    let timer = scheduler.start_task_timer(&current_task, worker_idx);
+                    task = run_task(
+                        mv_memory,
+                        vm,
+                        scheduler,
+                        abort_reason,
+                        execution_results,
+                        inspection,
+                        current_task,
+                    );
+                    if let Some(timer) = timer {
+                        scheduler.finish_task_timer(timer);
+                    }
+                    if let Some(progress) = progress {
+                        progress.maybe_report(scheduler);
+                    }
+
+                    // Invalid transactions in [ExecutionMode::Build] & [ExecutionMode::Validate]
+                    // don't abort, as they may become valid when their lower transactions
+                    // are re-executed.
+                    if abort_reason.get().is_some() {
+                        break;
+                    }
+
+                    if task.is_none() {
+                        // Park between tasks while above the tuned level.
+                        if tuner.is_some_and(|tuner| !tuner.wait_for_turn(worker_idx, scheduler)) {
+                            break;
+                        }
+                        task = scheduler.next_task();
+                    }
+                }
+            };
+            #[cfg(feature = "rayon")]
+            if let Some(thread_pool) = &self.thread_pool {
+                run_workers_on_pool(thread_pool, concurrency_level, run_worker);
+            } else {
+                spawn_workers(concurrency_level, self.strategy.thread_pinning, run_worker);
+            }
+            #[cfg(not(feature = "rayon"))]
+            spawn_workers(concurrency_level, self.strategy.thread_pinning, run_worker);
+        }
+        self.schedule = scheduler.take_schedule();
+        self.events = scheduler.take_events();
+        self.concurrency_level = tuner.map(|tuner| tuner.level());
+        #[cfg(feature = "metrics")]
+        scheduler.record_metrics();
+        drop(vm);
+        let txs = DeferDrop::new(txs.into_inner());
+
+        if let Some(abort_reason) = abort_reason.take() {
+            match abort_reason {
+                AbortReason::FallbackToSequential(fallback) => {
+                    if let (FallbackReason::MemoryBudgetExceeded, Some(memory_budget)) =
+                        (&fallback.reason, self.strategy.memory_budget)
+                    {
+                        if !memory_budget.fallback_to_sequential {
+                            return Err(PevmError::MemoryBudgetExceeded {
+                                max_bytes: memory_budget.max_bytes,
+                            });
+                        }
+                    }
+                    #[cfg(feature = "metrics")]
+                    crate::metrics::record_fallback(&fallback.reason);
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!(
+                        tx_idx = fallback.tx_idx,
+                        reason = ?fallback.reason,
+                        "falling back to sequential execution"
+                    );
+                    self.fallback = Some(fallback);
+                    return execute_revm_sequential_in_mode(
+                        storage,
+                        chain,
+                        spec_id,
+                        block_env,
+                        DeferDrop::into_inner(txs),
+                        (self.mode == ExecutionMode::Build).then_some(&mut self.skipped_tx_idxs),
+                        &self.precompiles,
+                        inspection,
+                        externals,
+                    );
+                }
+                AbortReason::ExecutionError(err) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!(
+                        tx_idx = err.tx_idx,
+                        tx_incarnation = err.tx_incarnation,
+                        error = ?err.error,
+                        "transaction failed to execute"
+                    );
+                    return Err(PevmError::ExecutionError(err));
+                }
+            }
+        }
+        if self.strategy.record_dependencies {
+            self.dependencies = Some(mv_memory.dependencies());
+        }
+        self.report = scheduler.take_report();
+
+        let mut fully_evaluated_results = Vec::with_capacity(block_size);
+        let mut cumulative_gas_used: u128 = 0;
+        for (tx_idx, mutex) in execution_results.into_iter().enumerate() {
+            match mutex.into_inner().unwrap().unwrap() {
+                Ok(mut execution_result) => {
+                    if let Some(report) = &mut self.report {
+                        report.txs[tx_idx].gas_used = execution_result.gas_used;
+                    }
+                    let receipt =
+                        &mut receipt_with_bloom_mut(&mut execution_result.receipt).receipt;
+                    cumulative_gas_used += receipt.cumulative_gas_used;
+                    receipt.cumulative_gas_used = cumulative_gas_used;
+                    fully_evaluated_results.push(execution_result);
+                }
+                // Only transactions that are invalid on the final state remain here.
+                Err(err) => {
+                    if self.mode == ExecutionMode::Build {
+                        self.skipped_tx_idxs.push(tx_idx);
+                    } else {
+                        return Err(PevmError::ExecutionError(err));
+                    }
+                }
+            }
+        }
+
+        if let Some(report) = &mut self.report {
+            match &self.dependencies {
+                Some(dependencies) => report.compute_critical_path(dependencies),
+                None => report.compute_critical_path(&mv_memory.dependencies()),
+            }
+        }
+
+        // We fully evaluate (the balance and nonce of) the beneficiary account
+        // and raw transfer recipients that may have been atomically updated.
+        for address in mv_memory.consume_lazy_addresses() {
+            let location_hash = hasher.hash_one(MemoryLocation::Basic(address));
+            if let Some(write_history) = mv_memory.consume_location(&location_hash) {
+                let mut balance = U256::ZERO;
+                let mut nonce = 0;
+                // Read from storage if the first multi-version entry is not an absolute value.
+                if !matches!(
+                    write_history.first_key_value(),
+                    Some((_, MemoryEntry::Data(_, MemoryValue::Basic(_))))
+                ) {
+                    match storage.basic(&address) {
+                        Ok(Some(account)) => {
+                            balance = account.balance;
+                            nonce = account.nonce;
+                        }
+                        Ok(None) => {}
+                        Err(err) => return Err(PevmError::StorageError(StorageError::new(err))),
+                    }
+                }
+                // Accounts that take implicit writes like the beneficiary account can be contract!
+                let mut code_hash = match storage.code_hash(&address) {
+                    Ok(code_hash) => code_hash,
+                    Err(err) => return Err(PevmError::StorageError(StorageError::new(err))),
+                };
+                let mut code = if let Some(code_hash) = &code_hash {
+                    match storage.code_by_hash(code_hash) {
+                        Ok(code) => code,
+                        Err(err) => return Err(PevmError::StorageError(StorageError::new(err))),
+                    }
+                } else {
+                    None
+                };
+
+                // TODO: Assert that the evaluated nonce matches the tx's.
+                for (tx_idx, memory_entry) in write_history {
+                    match memory_entry {
+                        MemoryEntry::Data(_, MemoryValue::Basic(info)) => {
+                            if let Some(info) = info {
+                                balance = info.balance;
+                                nonce = info.nonce;
+                            } else {
+                                // The account was self-destructed in this transaction,
+                                // which is already recorded in its state. Later lazy
+                                // updates start from an empty account.
+                                balance = U256::ZERO;
+                                nonce = 0;
+                                code_hash = None;
+                                code = None;
+                                continue;
+                            }
+                        }
+                        MemoryEntry::Data(_, MemoryValue::LazyRecipient(addition)) => {
+                            balance += addition;
+                        }
+                        MemoryEntry::Data(tx_incarnation, MemoryValue::LazySender(addition)) => {
+                            // We must re-do extra sender balance checks as we mock
+                            // the max value in [Vm] during execution. Ideally we
+                            // can turn off these redundant checks in revm.
+                            // Senders are never lazy in [ExecutionMode::Build], which
+                            // skips these invalid transactions instead.
+                            // TODO: Guard against overflows & underflows
+                            // Ideally we would share these calculations with revm
+                            // (using their utility functions).
+                            let tx = &unsafe { txs.get_unchecked(tx_idx) };
+                            let mut max_fee = U256::from(tx.gas_limit) * tx.gas_price + tx.value;
+                            if let Some(blob_fee) = tx.max_fee_per_blob_gas {
+                                max_fee +=
+                                    U256::from(tx.get_total_blob_gas()) * U256::from(blob_fee);
+                            }
+                            if balance < max_fee {
+                                return Err(PevmError::ExecutionError(TxExecutionError {
+                                    tx_idx,
+                                    tx_incarnation,
+                                    error: EVMError::Transaction(
+                                        InvalidTransaction::LackOfFundForMaxFee {
+                                            fee: Box::new(max_fee),
+                                            balance: Box::new(balance),
+                                        },
+                                    ),
+                                }));
+                            }
+                            balance -= addition;
+                            // End of overflow TODO
+
+                            nonce += 1;
+                        }
+                        // TODO: Better error handling
+                        _ => unreachable!(),
+                    }
+
+                    // Skipped transactions only write zero rewards to the
+                    // beneficiary, but shift the indices of the higher
+                    // transactions' results.
+                    if self.skipped_tx_idxs.binary_search(&tx_idx).is_ok() {
+                        continue;
+                    }
+                    let result_idx = tx_idx - self.skipped_tx_idxs.partition_point(|i| *i < tx_idx);
+                    // SAFETY: The multi-version data structure should not leak an index over block size.
+                    let tx_result =
+                        unsafe { fully_evaluated_results.get_unchecked_mut(result_idx) };
+                    let account = tx_result.state.entry(address).or_default();
+                    // TODO: Deduplicate this logic with [PevmTxExecutionResult::from_revm]
+                    if spec_id.is_enabled_in(SPURIOUS_DRAGON)
+                        && code_hash.is_none()
+                        && nonce == 0
+                        && balance == U256::ZERO
+                    {
+                        *account = None;
+                    } else if let Some(account) = account {
+                        // Explicit write: only overwrite the account info in case there are storage changes
+                        // TODO: Can code be changed mid-block?
+                        account.balance = balance;
+                        account.nonce = nonce;
+                    } else {
+                        // Implicit write: e.g. gas payments to the beneficiary account,
+                        // which doesn't have explicit writes in [tx_result.state]
+                        *account = Some(EvmAccount {
+                            balance,
+                            nonce,
+                            code_hash,
+                            code: code.clone(),
+                            storage: AHashMap::default(),
+                        });
+                    }
+                }
+            }
+        }
+
+        if let Some(location_stats) = mv_memory.location_stats() {
+            self.hot_locations = Some(rank_hot_locations(
+                &hasher,
+                location_stats,
+                &txs,
+                &fully_evaluated_results,
+            ));
+        }
+        for hook in self.hooks.iter() {
+            hook.on_progress(block_size, block_size, block_size);
+        }
+        self.memory_usage = Some(MemoryUsage {
+            peak_mv_memory: mv_memory.peak_memory_used(),
+            results: approximate_results_size(&fully_evaluated_results),
+        });
+
+        Ok(fully_evaluated_results)
+    }
+}
+
+/// Execute an Alloy block with the default [Pevm].
+pub fn execute<S: Storage + Send + Sync, C: PevmChain + Send + Sync>(
+    storage: &S,
+    chain: &C,
+    block: Block,
+    concurrency_level: NonZeroUsize,
+    force_sequential: bool,
+) -> PevmBlockResult<C> {
+    Pevm::default().execute(storage, chain, block, concurrency_level, force_sequential)
+}
+
+/// Execute an Alloy block and credit its ommer rewards with the default [Pevm],
+/// like [Pevm::execute_with_ommers].
+pub fn execute_with_ommers<S: Storage + Send + Sync, C: PevmChain + Send + Sync>(
+    storage: &S,
+    chain: &C,
+    block: Block,
+    ommers: Option<&[Header]>,
+    concurrency_level: NonZeroUsize,
+    force_sequential: bool,
+) -> PevmBlockResult<C> {
+    Pevm::default().execute_with_ommers(
+        storage,
+        chain,
+        block,
+        ommers,
+        concurrency_level,
+        force_sequential,
+    )
+}
+
+// Apply irregular state changes on top of some prior state transitions
+// (ordered from oldest to latest) and the underlying storage. These are
+// balance updates that happen outside of the EVM, so they don't touch
+// storage or code.
+fn apply_state_changes<S: Storage, C: PevmChain>(
+    storage: &S,
+    prior_states: &[&EvmStateTransitions],
+    changes: Vec<IrregularStateChange>,
+) -> Result<EvmStateTransitions, PevmError<C>> {
+    let mut state = EvmStateTransitions::default();
+    for change in changes {
+        match change {
+            IrregularStateChange::BalanceIncrement(address, amount) => {
+                // Zero increments (like zero-amount withdrawals) must not
+                // touch (and create) empty accounts.
+                if amount.is_zero() {
+                    continue;
+                }
+                load_account(storage, prior_states, &mut state, address)?
+                    .get_or_insert_with(EvmAccount::default)
+                    .balance += amount;
+            }
+            IrregularStateChange::BalanceTransfer { from, to } => {
+                // Don't mark non-existent accounts for removal by loading them.
+                if !state.contains_key(&from) {
+                    match read_latest_account(storage, prior_states, &from)
+                        .map_err(|err| PevmError::StorageError(StorageError::new(err)))?
+                    {
+                        Some(account) => state.insert(from, Some(account)),
+                        None => continue,
+                    };
+                }
+                let amount = match state.get_mut(&from) {
+                    Some(Some(account)) => std::mem::take(&mut account.balance),
+                    _ => continue,
+                };
+                load_account(storage, prior_states, &mut state, to)?
+                    .get_or_insert_with(EvmAccount::default)
+                    .balance += amount;
+            }
+        }
+    }
+    Ok(state)
+}
+
+// The message of a caught panic, which is usually a string.
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => payload.downcast_ref::<&str>().map_or_else(
+            || "unknown panic".to_string(),
+            |message| message.to_string(),
+        ),
+    }
+}
+
+// Layer the irregular pre-block state changes of a block and the results
+// of its prior transactions on top of the storage, to execute the block's
+// later transactions.
+fn commit_prior_results<'a, S: Storage, C: PevmChain>(
+    storage: &'a S,
+    chain: &C,
+    header: &Header,
+    prior_results: &[PevmTxExecutionResult],
+) -> Result<CommittedStorage<'a, S>, PevmError<C>> {
+    let mut committed_storage = CommittedStorage::new(storage, &[]);
+    committed_storage.commit(&apply_state_changes(
+        storage,
+        &[],
+        chain.get_pre_block_state_changes(header),
+    )?);
+    for prior_result in prior_results {
+        committed_storage.commit(&prior_result.state);
+    }
+    Ok(committed_storage)
+}
+
+fn load_account<'a, S: Storage, C: PevmChain>(
+    storage: &S,
+    prior_states: &[&EvmStateTransitions],
+    state: &'a mut EvmStateTransitions,
+    address: Address,
+) -> Result<&'a mut Option<EvmAccount>, PevmError<C>> {
+    Ok(match state.entry(address) {
+        Entry::Occupied(entry) => entry.into_mut(),
+        Entry::Vacant(entry) => entry.insert(
+            read_latest_account(storage, prior_states, &address)
+                .map_err(|err| PevmError::StorageError(StorageError::new(err)))?,
+        ),
+    })
+}
+
+// Read an account's info from the latest state transitions that touched it,
+// falling back to storage for untouched accounts. The returned account has no
+// storage entries as irregular state changes only update account info.
+fn read_latest_account<S: Storage>(
+    storage: &S,
+    prior_states: &[&EvmStateTransitions],
+    address: &Address,
+) -> Result<Option<EvmAccount>, S::Error> {
+    for state in prior_states.iter().rev() {
+        if let Some(account) = state.get(address) {
+            return Ok(account.clone().map(|account| EvmAccount {
+                storage: AHashMap::default(),
+                ..account
+            }));
+        }
+    }
+    let Some(basic) = storage.basic(address)? else {
+        return Ok(None);
+    };
+    let code_hash = storage.code_hash(address)?;
+    let code = match &code_hash {
+        Some(code_hash) => storage.code_by_hash(code_hash)?,
+        None => None,
+    };
+    Ok(Some(EvmAccount {
+        balance: basic.balance,
+        nonce: basic.nonce,
+        code_hash,
+        code,
+        storage: AHashMap::default(),
+    }))
+}
+
+// Re-tag the receipts with the actual transaction types, as they can only
+// be inferred from the transaction environments.
+fn retag_receipts(
+    tx_results: &mut [PevmTxExecutionResult],
+    tx_types: Vec<Option<TxType>>,
+    skipped_tx_idxs: &[usize],
+) {
+    let tx_types = tx_types
+        .into_iter()
+        .enumerate()
+        .filter(|(tx_idx, _)| skipped_tx_idxs.binary_search(tx_idx).is_err());
+    for (tx_result, (_, tx_type)) in tx_results.iter_mut().zip(tx_types) {
+        if let Some(tx_type) = tx_type.filter(|tx_type| *tx_type != tx_result.receipt.tx_type()) {
+            let receipt = receipt_with_bloom_mut(&mut tx_result.receipt).clone();
+            tx_result.receipt = with_tx_type(receipt, tx_type);
+        }
+    }
+}
+
+// The storage values that a speculative execution read.
+#[derive(Default)]
+struct SpeculativeReads {
+    basic: DashMap<Address, Option<AccountBasic>>,
+    code_hashes: DashMap<Address, Option<B256>>,
+    has_storage: DashMap<Address, bool>,
+    storage: DashMap<(Address, U256), U256>,
+    block_hashes: DashMap<u64, B256>,
+}
+
+impl SpeculativeReads {
+    // Whether the reads still hold on the storage after a block, only
+    // re-reading the accounts that the block changed and the block hashes,
+    // like of the block itself. Storage errors invalidate the reads to
+    // re-execute and surface them.
+    fn hold_after<S: Storage>(&self, storage: &S, block_result: &PevmBlockExecutionResult) -> bool {
+        let changed_addresses: AHashSet<&Address> = iter::once(&block_result.pre_block_state)
+            .chain(
+                block_result
+                    .tx_results
+                    .iter()
+                    .map(|tx_result| &tx_result.state),
+            )
+            .chain(iter::once(&block_result.post_block_state))
+            .flat_map(|state| state.keys())
+            .collect();
+        self.basic.iter().all(|read| {
+            !changed_addresses.contains(read.key())
+                || storage
+                    .basic(read.key())
+                    .is_ok_and(|basic| &basic == read.value())
+        }) && self.code_hashes.iter().all(|read| {
+            !changed_addresses.contains(read.key())
+                || storage
+                    .code_hash(read.key())
+                    .is_ok_and(|code_hash| &code_hash == read.value())
+        }) && self.has_storage.iter().all(|read| {
+            !changed_addresses.contains(read.key())
+                || storage
+                    .has_storage(read.key())
+                    .is_ok_and(|has_storage| &has_storage == read.value())
+        }) && self.storage.iter().all(|read| {
+            let (address, index) = read.key();
+            !changed_addresses.contains(address)
+                || storage
+                    .storage(address, index)
+                    .is_ok_and(|value| &value == read.value())
+        }) && self.block_hashes.iter().all(|read| {
+            storage
+                .block_hash(read.key())
+                .is_ok_and(|block_hash| &block_hash == read.value())
+        })
+    }
+}
+
+// A storage that records the values read by a speculative execution, to
+// validate them once the state it speculated on is final.
+struct SpeculativeStorage<'a, S: Storage> {
+    storage: &'a S,
+    reads: SpeculativeReads,
+}
+
+impl<'a, S: Storage> Storage for SpeculativeStorage<'a, S> {
+    type Error = S::Error;
+
+    fn basic(&self, address: &Address) -> Result<Option<AccountBasic>, Self::Error> {
+        let basic = self.storage.basic(address)?;
+        self.reads.basic.insert(*address, basic.clone());
+        Ok(basic)
+    }
+
+    fn code_hash(&self, address: &Address) -> Result<Option<B256>, Self::Error> {
+        let code_hash = self.storage.code_hash(address)?;
+        self.reads.code_hashes.insert(*address, code_hash);
+        Ok(code_hash)
+    }
+
+    // Codes are immutable by their hashes.
+    fn code_by_hash(&self, code_hash: &B256) -> Result<Option<EvmCode>, Self::Error> {
+        self.storage.code_by_hash(code_hash)
+    }
+
+    fn has_storage(&self, address: &Address) -> Result<bool, Self::Error> {
+        let has_storage = self.storage.has_storage(address)?;
+        self.reads.has_storage.insert(*address, has_storage);
+        Ok(has_storage)
+    }
+
+    fn storage(&self, address: &Address, index: &U256) -> Result<U256, Self::Error> {
+        let value = self.storage.storage(address, index)?;
+        self.reads.storage.insert((*address, *index), value);
+        Ok(value)
+    }
 
-use ahash::AHashMap;
-use alloy_primitives::U256;
-use alloy_rpc_types::{Block, BlockTransactions};
-use defer_drop::DeferDrop;
-use revm::{
-    db::CacheDB,
-    primitives::{
-        BlockEnv,
-        SpecId::{self, SPURIOUS_DRAGON},
-        TxEnv,
-    },
-    DatabaseCommit,
-};
+    fn block_hash(&self, number: &u64) -> Result<B256, Self::Error> {
+        let block_hash = self.storage.block_hash(number)?;
+        self.reads.block_hashes.insert(*number, block_hash);
+        Ok(block_hash)
+    }
 
-use crate::{
-    chain::PevmChain,
-    compat::{get_block_env, get_tx_env, TransactionParsingError},
-    mv_memory::MvMemory,
-    scheduler::Scheduler,
-    storage::StorageWrapper,
-    vm::{build_evm, ExecutionError, PevmTxExecutionResult, Vm, VmExecutionResult},
-    EvmAccount, MemoryEntry, MemoryLocation, MemoryValue, Storage, Task, TxVersion,
-};
+    fn basic_many(&self, addresses: &[Address]) -> Result<Vec<Option<AccountBasic>>, Self::Error> {
+        let basics = self.storage.basic_many(addresses)?;
+        for (address, basic) in addresses.iter().zip(&basics) {
+            self.reads.basic.insert(*address, basic.clone());
+        }
+        Ok(basics)
+    }
 
-/// Errors when executing a block with PEVM.
-#[derive(Debug, Clone, PartialEq)]
-pub enum PevmError<C: PevmChain> {
-    /// Cannot derive the chain spec from the block header.
-    BlockSpecError(C::BlockSpecError),
-    /// Block header lacks information for execution.
-    MissingHeaderData,
-    /// Transactions lack information for execution.
-    MissingTransactionData,
-    /// Invalid input transaction.
-    InvalidTransaction(TransactionParsingError<C>),
-    /// Storage error.
-    // TODO: More concrete types than just an arbitrary string.
-    StorageError(String),
-    /// EVM execution error.
-    // TODO: More concrete types than just an arbitrary string.
-    ExecutionError(String),
-    /// Impractical errors that should be unreachable.
-    /// The library has bugs if this is yielded.
-    UnreachableError,
+    fn code_by_hash_many(&self, code_hashes: &[B256]) -> Result<Vec<Option<EvmCode>>, Self::Error> {
+        self.storage.code_by_hash_many(code_hashes)
+    }
+
+    fn storage_many(&self, slots: &[(Address, U256)]) -> Result<Vec<U256>, Self::Error> {
+        let values = self.storage.storage_many(slots)?;
+        for (slot, value) in slots.iter().zip(&values) {
+            self.reads.storage.insert(*slot, *value);
+        }
+        Ok(values)
+    }
 }
 
-/// Execution result of a block
-pub type PevmResult<C> = Result<Vec<PevmTxExecutionResult>, PevmError<C>>;
+// A storage that serves the pre-block irregular state changes on top
+// of the underlying storage for the block's transactions to read.
+struct PreBlockStorage<'a, S: Storage> {
+    storage: &'a S,
+    state: &'a EvmStateTransitions,
+}
 
-enum AbortReason {
-    FallbackToSequential,
-    ExecutionError(ExecutionError),
+impl<'a, S: Storage> Storage for PreBlockStorage<'a, S> {
+    type Error = S::Error;
+
+    fn basic(&self, address: &Address) -> Result<Option<AccountBasic>, Self::Error> {
+        match self.state.get(address) {
+            Some(account) => Ok(account.as_ref().map(|account| AccountBasic {
+                balance: account.balance,
+                nonce: account.nonce,
+            })),
+            None => self.storage.basic(address),
+        }
+    }
+
+    fn code_hash(&self, address: &Address) -> Result<Option<B256>, Self::Error> {
+        match self.state.get(address) {
+            Some(account) => Ok(account.as_ref().and_then(|account| account.code_hash)),
+            None => self.storage.code_hash(address),
+        }
+    }
+
+    fn code_by_hash(&self, code_hash: &B256) -> Result<Option<EvmCode>, Self::Error> {
+        self.storage.code_by_hash(code_hash)
+    }
+
+    fn has_storage(&self, address: &Address) -> Result<bool, Self::Error> {
+        match self.state.get(address) {
+            Some(None) => Ok(false),
+            _ => self.storage.has_storage(address),
+        }
+    }
+
+    fn storage(&self, address: &Address, index: &U256) -> Result<U256, Self::Error> {
+        match self.state.get(address) {
+            Some(None) => Ok(U256::ZERO),
+            _ => self.storage.storage(address, index),
+        }
+    }
+
+    fn block_hash(&self, number: &u64) -> Result<B256, Self::Error> {
+        self.storage.block_hash(number)
+    }
+
+    fn basic_many(&self, addresses: &[Address]) -> Result<Vec<Option<AccountBasic>>, Self::Error> {
+        // Only batch the accounts that the pre-block changes don't cover.
+        let missed_addresses: Vec<_> = addresses
+            .iter()
+            .filter(|address| !self.state.contains_key(address))
+            .copied()
+            .collect();
+        let mut missed_basics = self.storage.basic_many(&missed_addresses)?.into_iter();
+        addresses
+            .iter()
+            .map(|address| match self.state.get(address) {
+                Some(_) => self.basic(address),
+                None => Ok(missed_basics.next().unwrap()),
+            })
+            .collect()
+    }
+
+    fn code_by_hash_many(&self, code_hashes: &[B256]) -> Result<Vec<Option<EvmCode>>, Self::Error> {
+        self.storage.code_by_hash_many(code_hashes)
+    }
+
+    fn storage_many(&self, slots: &[(Address, U256)]) -> Result<Vec<U256>, Self::Error> {
+        // Only batch the slots of accounts that the pre-block changes didn't clear.
+        let missed_slots: Vec<_> = slots
+            .iter()
+            .filter(|(address, _)| !matches!(self.state.get(address), Some(None)))
+            .copied()
+            .collect();
+        let mut missed_values = self.storage.storage_many(&missed_slots)?.into_iter();
+        Ok(slots
+            .iter()
+            .map(|(address, _)| match self.state.get(address) {
+                Some(None) => U256::ZERO,
+                _ => missed_values.next().unwrap(),
+            })
+            .collect())
+    }
 }
 
-// TODO: Add a [Pevm] struct for long-lasting use to minimize
-// (de)allocations between runs.
+// Approximate the bytes of execution results, from their touched accounts,
+// storage slots and logs.
+fn approximate_results_size(tx_results: &[PevmTxExecutionResult]) -> usize {
+    tx_results
+        .iter()
+        .map(|tx_result| {
+            let state_size: usize = tx_result
+                .state
+                .values()
+                .map(|account| {
+                    size_of::<(Address, Option<EvmAccount>)>()
+                        + account.as_ref().map_or(0, |account| {
+                            account.storage.len() * size_of::<(U256, U256)>()
+                        })
+                })
+                .sum();
+            let logs_size: usize = tx_result
+                .receipt
+                .logs()
+                .iter()
+                .map(|log| size_of::<Log>() + log.data.data.len())
+                .sum();
+            size_of::<PevmTxExecutionResult>() + state_size + logs_size
+        })
+        .sum()
+}
 
-/// Execute an Alloy block, which is becoming the "standard" format in Rust.
-/// TODO: Better error handling.
-pub fn execute<S: Storage + Send + Sync, C: PevmChain + Send + Sync>(
-    storage: &S,
-    chain: &C,
-    block: Block,
-    concurrency_level: NonZeroUsize,
-    force_sequential: bool,
-) -> PevmResult<C> {
-    let spec_id = chain
-        .get_block_spec(&block.header)
-        .map_err(PevmError::BlockSpecError)?;
-    let Some(block_env) = get_block_env(&block.header) else {
-        return Err(PevmError::MissingHeaderData);
-    };
-    let tx_envs = match block.transactions {
-        BlockTransactions::Full(txs) => txs
-            .into_iter()
-            .map(|tx| get_tx_env(chain, tx))
-            .collect::<Result<Vec<TxEnv>, TransactionParsingError<_>>>()
-            .map_err(PevmError::InvalidTransaction)?,
-        _ => return Err(PevmError::MissingTransactionData),
+// How often workers report progress to the hooks.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+// Throttles the progress reports of the workers to the hooks, so only one
+// worker reports every [PROGRESS_INTERVAL].
+struct ProgressReporter<'a> {
+    hooks: &'a [Arc<dyn ExecutionHook>],
+    total: usize,
+    started_at: Instant,
+    next_report_ns: AtomicU64,
+}
+
+impl<'a> ProgressReporter<'a> {
+    fn new(hooks: &'a [Arc<dyn ExecutionHook>], total: usize) -> Self {
+        Self {
+            hooks,
+            total,
+            started_at: Instant::now(),
+            next_report_ns: AtomicU64::new(PROGRESS_INTERVAL.as_nanos() as u64),
+        }
+    }
+
+    fn maybe_report(&self, scheduler: &Scheduler) {
+        let now_ns = self.started_at.elapsed().as_nanos() as u64;
+        let next_report_ns = self.next_report_ns.load(Ordering::Relaxed);
+        if now_ns >= next_report_ns
+            && self
+                .next_report_ns
+                .compare_exchange(
+                    next_report_ns,
+                    now_ns + PROGRESS_INTERVAL.as_nanos() as u64,
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                )
+                .is_ok()
+        {
+            let (executed, validated) = scheduler.progress();
+            for hook in self.hooks {
+                hook.on_progress(executed, validated, self.total);
+            }
+        }
+    }
+}
+
+// Map the contention stats of memory locations back to the accounts and
+// slots that the transactions and their results touched, hottest first.
+fn rank_hot_locations(
+    hasher: &ahash::RandomState,
+    location_stats: Vec<(MemoryLocationHash, LocationStats)>,
+    txs: &[TxEnv],
+    tx_results: &[PevmTxExecutionResult],
+) -> Vec<HotLocation> {
+    let mut locations: HashMap<MemoryLocationHash, HintedLocation, BuildIdentityHasher> =
+        HashMap::default();
+    let mut add_location = |location: HintedLocation| {
+        locations.insert(hasher.hash_one(MemoryLocation::from(location)), location);
     };
-    // TODO: Continue to fine tune this condition.
-    if force_sequential
-        || tx_envs.len() < concurrency_level.into()
-        || block.header.gas_used < 4_000_000
-    {
-        execute_revm_sequential(storage, chain, spec_id, block_env, tx_envs)
-    } else {
-        execute_revm_parallel(
-            storage,
-            chain,
-            spec_id,
-            block_env,
-            tx_envs,
-            concurrency_level,
-        )
+    for tx in txs {
+        add_location(HintedLocation::Account(tx.caller));
+        if let TransactTo::Call(to) = tx.transact_to {
+            add_location(HintedLocation::Account(to));
+        }
+    }
+    for tx_result in tx_results {
+        for (address, account) in tx_result.state.iter() {
+            add_location(HintedLocation::Account(*address));
+            add_location(HintedLocation::Code(*address));
+            for slot in account.iter().flat_map(|account| account.storage.keys()) {
+                add_location(HintedLocation::Storage(*address, *slot));
+            }
+        }
+    }
+    let mut hot_locations: Vec<HotLocation> = location_stats
+        .into_iter()
+        .map(|(location_hash, stats)| HotLocation {
+            location: locations.get(&location_hash).copied(),
+            writes: stats.writes,
+            conflicts: stats.conflicts,
+        })
+        .collect();
+    sort_hot_locations(&mut hot_locations);
+    hot_locations
+}
+
+// Add the hot locations of a chunk to those of the previous chunks, which
+// were hashed differently so only mapped locations can be summed up.
+fn merge_hot_locations(
+    hot_locations: &mut Vec<HotLocation>,
+    chunk_hot_locations: Vec<HotLocation>,
+) {
+    let mut location_idxs: AHashMap<HintedLocation, usize> = hot_locations
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, hot_location)| Some((hot_location.location?, idx)))
+        .collect();
+    for chunk_hot_location in chunk_hot_locations {
+        match chunk_hot_location
+            .location
+            .and_then(|location| location_idxs.get(&location).copied())
+        {
+            Some(idx) => {
+                hot_locations[idx].writes += chunk_hot_location.writes;
+                hot_locations[idx].conflicts += chunk_hot_location.conflicts;
+            }
+            None => {
+                if let Some(location) = chunk_hot_location.location {
+                    location_idxs.insert(location, hot_locations.len());
+                }
+                hot_locations.push(chunk_hot_location);
+            }
+        }
+    }
+    sort_hot_locations(hot_locations);
+}
+
+fn sort_hot_locations(hot_locations: &mut [HotLocation]) {
+    hot_locations.sort_by(|a, b| (b.conflicts, b.writes).cmp(&(a.conflicts, a.writes)));
+}
+
+// Run the workers on their own scoped threads, pinned to cores by the
+// thread pinning strategy.
+fn spawn_workers(
+    concurrency_level: NonZeroUsize,
+    thread_pinning: ThreadPinning,
+    run_worker: impl Fn(usize) + Copy + Send,
+) {
+    let core_ids = thread_pinning.core_ids();
+    // TODO: Better thread handling
+    thread::scope(|scope| {
+        let core_ids = core_ids.as_ref();
+        for worker_idx in 0..concurrency_level.into() {
+            scope.spawn(move || {
+                if let Some(core_ids) = core_ids {
+                    // Pinning is best-effort, like when the core is outside
+                    // of this process's allowed set.
+                    core_affinity::set_for_current(core_ids[worker_idx % core_ids.len()]);
+                }
+                run_worker(worker_idx);
+            });
+        }
+    });
+}
+
+// Run the workers on an external thread pool, which owns the pinning of its
+// threads.
+#[cfg(feature = "rayon")]
+fn run_workers_on_pool(
+    thread_pool: &rayon::ThreadPool,
+    concurrency_level: NonZeroUsize,
+    run_worker: impl Fn(usize) + Copy + Send,
+) {
+    // Workers spin until the block is done, so workers beyond the pool's
+    // threads would only start after then.
+    let concurrency_level = concurrency_level
+        .get()
+        .min(thread_pool.current_num_threads());
+    thread_pool.scope(|scope| {
+        for worker_idx in 0..concurrency_level {
+            scope.spawn(move |_| run_worker(worker_idx));
+        }
+    });
+}
+
+// Read the accounts & storage slots that are known before execution, from
+// the beneficiary, senders, recipients and access lists, in batches. Storage
+// backends that batch lookups (like [RpcStorage] concurrently over RPC) can
+// warm their caches so workers rarely block on IO mid-execution. Errors are ignored
+// here as they surface again on the actual reads.
+fn prefetch<S: Storage>(storage: &S, block_env: &BlockEnv, txs: &[TxEnv]) {
+    let mut addresses = Vec::with_capacity(txs.len() * 2 + 1);
+    let mut slots = Vec::new();
+    addresses.push(block_env.coinbase);
+    for tx in txs {
+        addresses.push(tx.caller);
+        if let TransactTo::Call(to) = tx.transact_to {
+            addresses.push(to);
+        }
+        for item in &tx.access_list {
+            addresses.push(item.address);
+            slots.extend(
+                item.storage_keys
+                    .iter()
+                    .map(|key| (item.address, U256::from_be_bytes(key.0))),
+            );
+        }
+    }
+    addresses.sort_unstable();
+    addresses.dedup();
+    slots.sort_unstable();
+    slots.dedup();
+    let _ = storage.basic_many(&addresses);
+    if !slots.is_empty() {
+        let _ = storage.storage_many(&slots);
     }
 }
 
@@ -113,33 +3236,20 @@ pub fn execute_revm_sequential<S: Storage, C: PevmChain>(
     block_env: BlockEnv,
     txs: Vec<TxEnv>,
 ) -> PevmResult<C> {
-    let mut db = CacheDB::new(StorageWrapper(storage));
-    let mut evm = build_evm(&mut db, chain, spec_id, block_env, true);
-    let mut results = Vec::with_capacity(txs.len());
-    let mut cumulative_gas_used: u128 = 0;
-    for tx in txs {
-        *evm.tx_mut() = tx;
-        match evm.transact() {
-            Ok(result_and_state) => {
-                evm.db_mut().commit(result_and_state.state.clone());
-
-                let mut execution_result =
-                    PevmTxExecutionResult::from_revm(spec_id, result_and_state);
-
-                cumulative_gas_used += execution_result.receipt.cumulative_gas_used;
-                execution_result.receipt.cumulative_gas_used = cumulative_gas_used;
-
-                results.push(execution_result);
-            }
-            Err(err) => return Err(PevmError::ExecutionError(err.to_string())),
-        }
-    }
-    Ok(results)
+    execute_revm_sequential_in_mode(
+        storage,
+        chain,
+        spec_id,
+        block_env,
+        txs,
+        None,
+        &[],
+        NO_INSPECTION,
+        &(),
+    )
 }
 
-/// Execute an REVM block.
-// Ideally everyone would go through the [Alloy] interface. This one is currently
-// useful for testing, and for users that are heavily tied to Revm like Reth.
+/// Execute an REVM block with the default [Pevm], like [Pevm::execute_revm_parallel].
 pub fn execute_revm_parallel<S: Storage + Send + Sync, C: PevmChain + Send + Sync>(
     storage: &S,
     chain: &C,
@@ -148,228 +3258,213 @@ pub fn execute_revm_parallel<S: Storage + Send + Sync, C: PevmChain + Send + Syn
     txs: Vec<TxEnv>,
     concurrency_level: NonZeroUsize,
 ) -> PevmResult<C> {
-    if txs.is_empty() {
-        return Ok(Vec::new());
-    }
-
-    // Preprocess locations
-    let block_size = txs.len();
-    let hasher = ahash::RandomState::new();
-    // Initialize the remaining core components
-    // TODO: Provide more explicit garbage collecting configs for users over random background
-    // threads like this. For instance, to have a dedicated thread (pool) for cleanup.
-    let mv_memory = DeferDrop::new(chain.build_mv_memory(&hasher, &block_env, &txs));
-    let txs = DeferDrop::new(txs);
-    let vm = Vm::new(
-        &hasher, storage, &mv_memory, chain, &block_env, &txs, spec_id,
-    );
-    let scheduler = DeferDrop::new(Scheduler::new(block_size));
-
-    let mut abort_reason = OnceLock::new();
-    let execution_results: Vec<_> = (0..block_size).map(|_| Mutex::new(None)).collect();
-
-    // TODO: Better thread handling
-    thread::scope(|scope| {
-        for _ in 0..concurrency_level.into() {
-            scope.spawn(|| {
-                let mut task = scheduler.next_task();
-                while task.is_some() {
-                    task = match task.unwrap() {
-                        Task::Execution(tx_version) => try_execute(
-                            &mv_memory,
-                            &vm,
-                            &scheduler,
-                            &abort_reason,
-                            &execution_results,
-                            tx_version,
-                        ),
-                        Task::Validation(tx_version) => {
-                            try_validate(&mv_memory, &scheduler, &tx_version)
-                        }
-                    };
-
-                    // TODO: Have different functions or an enum for the caller to choose
-                    // the handling behaviour when a transaction's EVM execution fails.
-                    // Parallel block builders would like to exclude such transaction,
-                    // verifiers may want to exit early to save CPU cycles, while testers
-                    // may want to collect all execution results. We are exiting early as
-                    // the default behaviour for now.
-                    if abort_reason.get().is_some() {
-                        break;
-                    }
-
-                    if task.is_none() {
-                        task = scheduler.next_task();
-                    }
-                }
-            });
-        }
-    });
+    Pevm::default().execute_revm_parallel(
+        storage,
+        chain,
+        spec_id,
+        block_env,
+        txs,
+        concurrency_level,
+    )
+}
 
-    if let Some(abort_reason) = abort_reason.take() {
-        match abort_reason {
-            AbortReason::FallbackToSequential => {
-                return execute_revm_sequential(
-                    storage,
-                    chain,
-                    spec_id,
-                    block_env,
-                    DeferDrop::into_inner(txs),
-                )
-            }
-            AbortReason::ExecutionError(err) => {
-                return Err(PevmError::ExecutionError(format!("{err:?}")))
-            }
-        }
+// Execute REVM transactions sequentially, skipping invalid transactions
+// and recording their indices in [skipped_tx_idxs] if provided instead
+// of erroring out.
+#[allow(clippy::too_many_arguments)]
+fn execute_revm_sequential_in_mode<
+    S: Storage,
+    C: PevmChain,
+    F: InspectorFactory,
+    X: ExternalFactory,
+>(
+    storage: &S,
+    chain: &C,
+    spec_id: SpecId,
+    block_env: BlockEnv,
+    txs: Vec<TxEnv>,
+    skipped_tx_idxs: Option<&mut Vec<usize>>,
+    precompiles: &[PrecompileWithAddress],
+    inspection: Option<&Inspection<F>>,
+    externals: &X,
+) -> PevmResult<C> {
+    let mut db = CacheDB::new(StorageWrapper(storage));
+    match inspection {
+        None => execute_txs_sequentially(
+            &mut build_evm(
+                &mut db,
+                chain,
+                spec_id,
+                block_env,
+                true,
+                precompiles,
+                externals.external(0),
+            ),
+            spec_id,
+            txs,
+            skipped_tx_idxs,
+            // Swap in the external context of the next transaction.
+            |tx_idx, external| *external = externals.external(tx_idx + 1),
+        ),
+        Some(inspection) => execute_txs_sequentially(
+            &mut build_inspected_evm(
+                &mut db,
+                chain,
+                spec_id,
+                block_env,
+                true,
+                precompiles,
+                inspection
+                    .factory
+                    .inspector::<&mut CacheDB<StorageWrapper<S>>>(0),
+            ),
+            spec_id,
+            txs,
+            skipped_tx_idxs,
+            |tx_idx, inspector| {
+                // Swap in the inspector of the next transaction.
+                let inspector = mem::replace(
+                    inspector,
+                    inspection
+                        .factory
+                        .inspector::<&mut CacheDB<StorageWrapper<S>>>(tx_idx + 1),
+                );
+                *index_mutex!(inspection.outputs, tx_idx) = Some(
+                    inspection
+                        .factory
+                        .finish::<&mut CacheDB<StorageWrapper<S>>>(inspector),
+                );
+            },
+        ),
     }
+}
 
-    let mut fully_evaluated_results = Vec::with_capacity(block_size);
+// Execute transactions in order on the same EVM, calling [on_executed] with
+// the EVM's external context after each executed or skipped transaction.
+fn execute_txs_sequentially<EXT, S: Storage, C: PevmChain>(
+    evm: &mut Evm<'_, EXT, &mut CacheDB<StorageWrapper<S>>>,
+    spec_id: SpecId,
+    txs: Vec<TxEnv>,
+    mut skipped_tx_idxs: Option<&mut Vec<usize>>,
+    mut on_executed: impl FnMut(usize, &mut EXT),
+) -> PevmResult<C> {
+    let mut results = Vec::with_capacity(txs.len());
     let mut cumulative_gas_used: u128 = 0;
-    for mutex in execution_results {
-        let mut execution_result = mutex.into_inner().unwrap().unwrap();
-        cumulative_gas_used += execution_result.receipt.cumulative_gas_used;
-        execution_result.receipt.cumulative_gas_used = cumulative_gas_used;
-        fully_evaluated_results.push(execution_result);
-    }
-
-    // We fully evaluate (the balance and nonce of) the beneficiary account
-    // and raw transfer recipients that may have been atomically updated.
-    for address in mv_memory.consume_lazy_addresses() {
-        let location_hash = hasher.hash_one(MemoryLocation::Basic(address));
-        if let Some(write_history) = mv_memory.consume_location(&location_hash) {
-            let mut balance = U256::ZERO;
-            let mut nonce = 0;
-            // Read from storage if the first multi-version entry is not an absolute value.
-            if !matches!(
-                write_history.first_key_value(),
-                Some((_, MemoryEntry::Data(_, MemoryValue::Basic(_))))
-            ) {
-                if let Ok(Some(account)) = storage.basic(&address) {
-                    balance = account.balance;
-                    nonce = account.nonce;
-                }
-            }
-            // Accounts that take implicit writes like the beneficiary account can be contract!
-            let code_hash = match storage.code_hash(&address) {
-                Ok(code_hash) => code_hash,
-                Err(err) => return Err(PevmError::StorageError(err.to_string())),
-            };
-            let code = if let Some(code_hash) = &code_hash {
-                match storage.code_by_hash(code_hash) {
-                    Ok(code) => code,
-                    Err(err) => return Err(PevmError::StorageError(err.to_string())),
-                }
-            } else {
-                None
-            };
+    for (tx_idx, tx) in txs.into_iter().enumerate() {
+        *evm.tx_mut() = tx;
+        match evm.transact() {
+            Ok(result_and_state) => {
+                evm.db_mut().commit(result_and_state.state.clone());
 
-            // TODO: Assert that the evaluated nonce matches the tx's.
-            for (tx_idx, memory_entry) in write_history {
-                match memory_entry {
-                    MemoryEntry::Data(_, MemoryValue::Basic(info)) => {
-                        if let Some(info) = info {
-                            balance = info.balance;
-                            nonce = info.nonce;
-                        }
-                        // TODO: Assert that there must be no self-destructed
-                        // accounts here.
-                    }
-                    MemoryEntry::Data(_, MemoryValue::LazyRecipient(addition)) => {
-                        balance += addition;
-                    }
-                    MemoryEntry::Data(_, MemoryValue::LazySender(addition)) => {
-                        // We must re-do extra sender balance checks as we mock
-                        // the max value in [Vm] during execution. Ideally we
-                        // can turn off these redundant checks in revm.
-                        // TODO: Guard against overflows & underflows
-                        // Ideally we would share these calculations with revm
-                        // (using their utility functions).
-                        let tx = &unsafe { txs.get_unchecked(tx_idx) };
-                        let mut max_fee = U256::from(tx.gas_limit) * tx.gas_price + tx.value;
-                        if let Some(blob_fee) = tx.max_fee_per_blob_gas {
-                            max_fee += U256::from(tx.get_total_blob_gas()) * U256::from(blob_fee);
-                        }
-                        if balance < max_fee {
-                            return Err(PevmError::ExecutionError(
-                                "Transaction(LackOfFundForMaxFee)".to_string(),
-                            ));
-                        }
-                        balance -= addition;
-                        // End of overflow TODO
+                let mut execution_result = PevmTxExecutionResult::from_revm(
+                    spec_id,
+                    evm.block(),
+                    evm.tx(),
+                    result_and_state,
+                );
 
-                        nonce += 1;
-                    }
-                    // TODO: Better error handling
-                    _ => unreachable!(),
-                }
+                let receipt = &mut receipt_with_bloom_mut(&mut execution_result.receipt).receipt;
+                cumulative_gas_used += receipt.cumulative_gas_used;
+                receipt.cumulative_gas_used = cumulative_gas_used;
 
-                // SAFETY: The multi-version data structure should not leak an index over block size.
-                let tx_result = unsafe { fully_evaluated_results.get_unchecked_mut(tx_idx) };
-                let account = tx_result.state.entry(address).or_default();
-                // TODO: Deduplicate this logic with [PevmTxExecutionResult::from_revm]
-                if spec_id.is_enabled_in(SPURIOUS_DRAGON)
-                    && code_hash.is_none()
-                    && nonce == 0
-                    && balance == U256::ZERO
-                {
-                    *account = None;
-                } else if let Some(account) = account {
-                    // Explicit write: only overwrite the account info in case there are storage changes
-                    // TODO: Can code be changed mid-block?
-                    account.balance = balance;
-                    account.nonce = nonce;
-                } else {
-                    // Implicit write: e.g. gas payments to the beneficiary account,
-                    // which doesn't have explicit writes in [tx_result.state]
-                    *account = Some(EvmAccount {
-                        balance,
-                        nonce,
-                        code_hash,
-                        code: code.clone(),
-                        storage: AHashMap::default(),
-                    });
-                }
+                results.push(execution_result);
+                on_executed(tx_idx, &mut evm.context.external);
+            }
+            Err(EVMError::Transaction(_)) if skipped_tx_idxs.is_some() => {
+                skipped_tx_idxs.as_mut().unwrap().push(tx_idx);
+                on_executed(tx_idx, &mut evm.context.external);
+            }
+            Err(err) => {
+                return Err(PevmError::ExecutionError(TxExecutionError {
+                    tx_idx,
+                    tx_incarnation: 0,
+                    error: err.map_db_err(|err| ReadError::StorageError(StorageError::new(err))),
+                }))
             }
         }
     }
-
-    Ok(fully_evaluated_results)
+    Ok(results)
 }
 
-fn try_execute<S: Storage, C: PevmChain>(
+fn try_execute<S: Storage, C: PevmChain, F: InspectorFactory, X: ExternalFactory>(
     mv_memory: &MvMemory,
-    vm: &Vm<S, C>,
+    vm: &Vm<S, C, X>,
     scheduler: &Scheduler,
     abort_reason: &OnceLock<AbortReason>,
-    execution_results: &[Mutex<Option<PevmTxExecutionResult>>],
+    execution_results: &[Mutex<Option<Result<PevmTxExecutionResult, TxExecutionError>>>],
+    inspection: Option<&Inspection<F>>,
     tx_version: TxVersion,
 ) -> Option<Task> {
+    // Count the immediate retries along with the previous incarnations
+    // to bound optimistic retries.
+    let mut attempt = tx_version.tx_incarnation;
+    // Retries from [RetryPolicy::Immediate] don't count as attempts, as they
+    // don't wait for the blocking transaction to change what they read.
+    let mut immediate_retries = 0;
     loop {
-        return match vm.execute(tx_version.tx_idx) {
+        return match vm.execute(tx_version.tx_idx, attempt, inspection) {
             VmExecutionResult::Retry => {
                 if abort_reason.get().is_none() {
                     continue;
                 }
                 None
             }
-            VmExecutionResult::FallbackToSequential => {
-                scheduler.abort();
-                abort_reason.get_or_init(|| AbortReason::FallbackToSequential);
+            VmExecutionResult::FallbackToSequential(err) => {
+                fall_back_to_sequential(
+                    scheduler,
+                    abort_reason,
+                    tx_version.tx_idx,
+                    FallbackReason::ReadError(err),
+                );
                 None
             }
             VmExecutionResult::ReadError { blocking_tx_idx } => {
+                match vm.retry_policy() {
+                    RetryPolicy::Immediate { max_retries }
+                        if immediate_retries < max_retries && abort_reason.get().is_none() =>
+                    {
+                        immediate_retries += 1;
+                        continue;
+                    }
+                    RetryPolicy::WaitInPlace
+                        if scheduler.wait_for_execution(blocking_tx_idx)
+                            && abort_reason.get().is_none() =>
+                    {
+                        attempt += 1;
+                        continue;
+                    }
+                    RetryPolicy::BoundedThenSequential { max_retries }
+                        if attempt >= max_retries =>
+                    {
+                        fall_back_to_sequential(
+                            scheduler,
+                            abort_reason,
+                            tx_version.tx_idx,
+                            FallbackReason::RetriesExhausted,
+                        );
+                        return None;
+                    }
+                    _ => {}
+                }
                 if !scheduler.add_dependency(tx_version.tx_idx, blocking_tx_idx)
                     && abort_reason.get().is_none()
                 {
                     // Retry the execution immediately if the blocking transaction was
                     // re-executed by the time we can add it as a dependency.
+                    attempt += 1;
                     continue;
                 }
                 None
             }
-            VmExecutionResult::ExecutionError(err) => {
+            VmExecutionResult::ExecutionError(error) => {
                 scheduler.abort();
-                abort_reason.get_or_init(|| AbortReason::ExecutionError(err));
+                abort_reason.get_or_init(|| {
+                    AbortReason::ExecutionError(TxExecutionError {
+                        tx_idx: tx_version.tx_idx,
+                        tx_incarnation: tx_version.tx_incarnation,
+                        error,
+                    })
+                });
                 None
             }
             VmExecutionResult::Ok {
@@ -377,15 +3472,201 @@ fn try_execute<S: Storage, C: PevmChain>(
                 read_set,
                 write_set,
                 lazy_addresses,
-                next_validation_idx,
             } => {
-                *index_mutex!(execution_results, tx_version.tx_idx) = Some(execution_result);
+                *index_mutex!(execution_results, tx_version.tx_idx) = Some(Ok(execution_result));
+                scheduler.report_lazy(tx_version.tx_idx, !lazy_addresses.is_empty());
                 let wrote_new_location =
                     mv_memory.record(&tx_version, read_set, write_set, lazy_addresses);
+                if mv_memory.is_over_budget() {
+                    fall_back_to_sequential(
+                        scheduler,
+                        abort_reason,
+                        tx_version.tx_idx,
+                        FallbackReason::MemoryBudgetExceeded,
+                    );
+                    return None;
+                }
+                // Preprocessing estimates the locations that each transaction
+                // writes to for higher transactions to wait on. A write to an
+                // unestimated location breaks this dependency chain, so higher
+                // transactions may have read stale values and must be validated.
+                // Lower writes are all estimated otherwise, so the reads of this
+                // and lower transactions are already safe.
+                let next_validation_idx = wrote_new_location.then_some(tx_version.tx_idx + 1);
                 scheduler.finish_execution(tx_version, wrote_new_location, next_validation_idx)
             }
+            VmExecutionResult::InvalidTransaction {
+                error,
+                read_set,
+                write_set,
+            } => {
+                *index_mutex!(execution_results, tx_version.tx_idx) = Some(Err(TxExecutionError {
+                    tx_idx: tx_version.tx_idx,
+                    tx_incarnation: tx_version.tx_incarnation,
+                    error,
+                }));
+                // Record the read set so this transaction is re-executed if
+                // what it read changes.
+                let wrote_new_location =
+                    mv_memory.record(&tx_version, read_set, write_set, NewLazyAddresses::new());
+                if mv_memory.is_over_budget() {
+                    fall_back_to_sequential(
+                        scheduler,
+                        abort_reason,
+                        tx_version.tx_idx,
+                        FallbackReason::MemoryBudgetExceeded,
+                    );
+                    return None;
+                }
+                scheduler.finish_execution(tx_version, wrote_new_location, Some(tx_version.tx_idx))
+            }
+        };
+    }
+}
+
+// Abort the parallel execution to re-execute the block sequentially.
+fn fall_back_to_sequential(
+    scheduler: &Scheduler,
+    abort_reason: &OnceLock<AbortReason>,
+    tx_idx: TxIdx,
+    reason: FallbackReason,
+) {
+    scheduler.abort();
+    abort_reason
+        .get_or_init(|| AbortReason::FallbackToSequential(SequentialFallback { tx_idx, reason }));
+}
+
+fn run_task<S: Storage, C: PevmChain, F: InspectorFactory, X: ExternalFactory>(
+    mv_memory: &MvMemory,
+    vm: &Vm<S, C, X>,
+    scheduler: &Scheduler,
+    abort_reason: &OnceLock<AbortReason>,
+    execution_results: &[Mutex<Option<Result<PevmTxExecutionResult, TxExecutionError>>>],
+    inspection: Option<&Inspection<F>>,
+    task: Task,
+) -> Option<Task> {
+    #[cfg(feature = "tracing")]
+    let _span = match &task {
+        Task::Execution(tx_version) => tracing::trace_span!(
+            "execution",
+            tx_idx = tx_version.tx_idx,
+            tx_incarnation = tx_version.tx_incarnation
+        ),
+        Task::Validation(tx_version) => tracing::trace_span!(
+            "validation",
+            tx_idx = tx_version.tx_idx,
+            tx_incarnation = tx_version.tx_incarnation
+        ),
+    }
+    .entered();
+    match task {
+        Task::Execution(tx_version) => try_execute(
+            mv_memory,
+            vm,
+            scheduler,
+            abort_reason,
+            execution_results,
+            inspection,
+            tx_version,
+        ),
+        Task::Validation(tx_version) => try_validate(mv_memory, scheduler, &tx_version),
+    }
+}
+
+// Execute the first [num_system_txs] transactions in order on the current
+// thread before the workers start, as the top-of-block system transactions
+// would otherwise serialize the rest of the block behind them. Their writes
+// are then in [MvMemory] for the workers to read.
+fn execute_top_of_block<S: Storage, C: PevmChain, F: InspectorFactory, X: ExternalFactory>(
+    mv_memory: &MvMemory,
+    vm: &Vm<S, C, X>,
+    scheduler: &Scheduler,
+    abort_reason: &OnceLock<AbortReason>,
+    execution_results: &[Mutex<Option<Result<PevmTxExecutionResult, TxExecutionError>>>],
+    inspection: Option<&Inspection<F>>,
+    num_system_txs: usize,
+) {
+    for tx_idx in 0..num_system_txs {
+        let mut task = Some(Task::Execution(TxVersion {
+            tx_idx,
+            tx_incarnation: 0,
+        }))
+        .filter(|task| scheduler.take_task(task));
+        while let Some(current_task) = task {
+            scheduler.record_task(&current_task);
+            let timer = scheduler.start_task_timer(&current_task, 0);
+            task = run_task(
+                mv_memory,
+                vm,
+                scheduler,
+                abort_reason,
+                execution_results,
+                inspection,
+                current_task,
+            );
+            if let Some(timer) = timer {
+                scheduler.finish_task_timer(timer);
+            }
+            if abort_reason.get().is_some() {
+                return;
+            }
+        }
+    }
+}
+
+// Run the tasks of a recorded schedule in order on the current thread, then
+// finish whatever is left like when the recording was cut short. Return the
+// index of the first event whose task can't be taken as the replay diverged.
+fn replay_schedule<S: Storage, C: PevmChain, X: ExternalFactory>(
+    mv_memory: &MvMemory,
+    vm: &Vm<S, C, X>,
+    scheduler: &Scheduler,
+    abort_reason: &OnceLock<AbortReason>,
+    execution_results: &[Mutex<Option<Result<PevmTxExecutionResult, TxExecutionError>>>],
+    schedule: &[ScheduleEvent],
+) -> Result<(), usize> {
+    let run = |task: Task| {
+        scheduler.record_task(&task);
+        // Replays run on a single worker.
+        let timer = scheduler.start_task_timer(&task, 0);
+        let next_task = run_task(
+            mv_memory,
+            vm,
+            scheduler,
+            abort_reason,
+            execution_results,
+            NO_INSPECTION,
+            task,
+        );
+        if let Some(timer) = timer {
+            scheduler.finish_task_timer(timer);
+        }
+        next_task
+    };
+    // The tasks returned by finished tasks, which the scheduler has already
+    // assigned to the worker that finished them.
+    let mut assigned_tasks = Vec::new();
+    for (event_idx, event) in schedule.iter().enumerate() {
+        let Some(task) = event.task() else {
+            continue;
         };
+        if let Some(i) = assigned_tasks.iter().position(|assigned| assigned == &task) {
+            assigned_tasks.swap_remove(i);
+        } else if !scheduler.take_task(&task) {
+            return Err(event_idx);
+        }
+        assigned_tasks.extend(run(task));
+        if abort_reason.get().is_some() {
+            return Ok(());
+        }
+    }
+    while let Some(task) = assigned_tasks.pop().or_else(|| scheduler.next_task()) {
+        assigned_tasks.extend(run(task));
+        if abort_reason.get().is_some() {
+            break;
+        }
     }
+    Ok(())
 }
 
 fn try_validate(
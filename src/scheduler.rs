@@ -1,13 +1,388 @@
 use std::{
     cmp::min,
+    fs,
+    num::NonZeroUsize,
     sync::{
         atomic::{AtomicBool, AtomicUsize, Ordering},
-        Mutex,
+        Condvar, Mutex,
     },
     thread,
+    time::{Duration, Instant},
 };
 
-use crate::{IncarnationStatus, Task, TxIdx, TxStatus, TxVersion};
+use ahash::AHashMap;
+use core_affinity::CoreId;
+use revm::primitives::{Address, TxEnv};
+use serde::{Deserialize, Serialize};
+
+use crate::{IncarnationStatus, Task, TxDependencies, TxIdx, TxStatus, TxVersion};
+
+/// The order that the scheduler first executes transactions in. Transactions
+/// are still committed, validated and re-executed by their block order.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SchedulingPolicy {
+    /// Execute transactions in their block order.
+    #[default]
+    InOrder,
+    /// Execute transactions with higher gas limits first, as they likely run
+    /// the longest and would otherwise be discovered late to then delay or
+    /// abort their higher transactions. Ties keep their block order.
+    GasWeighted,
+}
+
+impl SchedulingPolicy {
+    // The transactions to execute at each position, [None] for the block order.
+    fn execution_order(&self, txs: &[TxEnv]) -> Option<Vec<TxIdx>> {
+        match self {
+            Self::InOrder => None,
+            Self::GasWeighted => {
+                let mut order: Vec<TxIdx> = (0..txs.len()).collect();
+                order.sort_by_key(|tx_idx| std::cmp::Reverse(txs[*tx_idx].gas_limit));
+                Some(order)
+            }
+        }
+    }
+}
+
+/// How to retry a transaction that reads from a lower transaction that is
+/// yet to (re-)execute, or whose sender's nonce or balance is ahead of the
+/// lower transactions executed so far.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RetryPolicy {
+    /// Re-execute after the blocking transaction, which is the previous
+    /// transaction when the sender's nonce or balance is ahead.
+    #[default]
+    WaitForDependency,
+    /// Like [RetryPolicy::WaitForDependency], but re-execute after the
+    /// closest lower transaction of the same sender when its nonce is ahead,
+    /// instead of after every transaction in between.
+    WaitForSender,
+    /// Re-execute immediately up to [max_retries] times per execution task,
+    /// which suits short dependencies that are likely done by then, before
+    /// waiting for the blocking transaction.
+    Immediate {
+        /// The maximum number of immediate retries per execution task.
+        max_retries: usize,
+    },
+    /// Wait for the blocking transaction, but fall back to sequential
+    /// execution once a transaction is blocked on its [max_retries]-th
+    /// incarnation, as such blocks are too contended to gain from
+    /// parallelism.
+    BoundedThenSequential {
+        /// The maximum number of incarnations to block on.
+        max_retries: usize,
+    },
+    /// Sleep until the blocking transaction finishes its ongoing execution
+    /// and re-execute right after, instead of aborting to retry after it.
+    /// This avoids wasted executions on highly serial blocks.
+    /// Transactions blocked on a transaction that isn't executing wait for
+    /// it like [RetryPolicy::WaitForDependency].
+    WaitInPlace,
+}
+
+impl RetryPolicy {
+    // The closest lower transaction of the same sender for each transaction
+    // to wait for, [None] when waiting for the previous transaction instead.
+    pub(crate) fn sender_dependencies(
+        &self,
+        senders: impl Iterator<Item = Address>,
+    ) -> Option<Vec<Option<TxIdx>>> {
+        match self {
+            Self::WaitForSender => {
+                let mut last_sender_txs = AHashMap::new();
+                Some(
+                    senders
+                        .enumerate()
+                        .map(|(tx_idx, sender)| last_sender_txs.insert(sender, tx_idx))
+                        .collect(),
+                )
+            }
+            _ => None,
+        }
+    }
+}
+
+/// How to pin worker threads to CPU cores, which reduces the variance of
+/// execution times from the OS migrating workers between cores.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ThreadPinning {
+    /// Let the OS schedule workers on any core.
+    #[default]
+    Unpinned,
+    /// Pin each worker to a logical core, in order.
+    LogicalCores,
+    /// Pin each worker to a physical core, skipping SMT siblings so no two
+    /// workers share one while there are enough physical cores. Siblings
+    /// are only detected on Linux, behaving like
+    /// [ThreadPinning::LogicalCores] elsewhere.
+    PhysicalCores,
+}
+
+impl ThreadPinning {
+    // The cores to pin workers to, round-robin by their indices. [None]
+    // when unpinned or when the cores cannot be listed.
+    pub(crate) fn core_ids(&self) -> Option<Vec<CoreId>> {
+        if *self == Self::Unpinned {
+            return None;
+        }
+        let core_ids = core_affinity::get_core_ids().filter(|core_ids| !core_ids.is_empty())?;
+        if *self == Self::PhysicalCores {
+            let physical_core_ids: Vec<_> = core_ids
+                .iter()
+                .filter(|core_id| is_first_smt_sibling(core_id.id))
+                .copied()
+                .collect();
+            if !physical_core_ids.is_empty() {
+                return Some(physical_core_ids);
+            }
+        }
+        Some(core_ids)
+    }
+}
+
+// Whether a logical core is the first of its SMT siblings, defaulting to
+// true when the topology is unknown.
+fn is_first_smt_sibling(cpu: usize) -> bool {
+    fs::read_to_string(format!(
+        "/sys/devices/system/cpu/cpu{cpu}/topology/thread_siblings_list"
+    ))
+    .ok()
+    .and_then(|siblings| {
+        siblings
+            .trim()
+            .split([',', '-'])
+            .next()?
+            .parse::<usize>()
+            .ok()
+    })
+    .map_or(true, |first_sibling| first_sibling == cpu)
+}
+
+/// Why an incarnation was aborted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AbortCause {
+    /// The incarnation read an estimated write of a lower transaction, and
+    /// waits for it to be re-executed.
+    Dependency {
+        /// The lower transaction that the incarnation waits for.
+        blocking_tx_idx: usize,
+    },
+    /// The incarnation failed validation as what it read has changed.
+    Validation,
+}
+
+/// A scheduling decision of a parallel execution, recorded with
+/// [crate::PevmStrategy::record_schedule] to replay the same interleaving
+/// of tasks single-threaded via [crate::Pevm::replay_revm_parallel].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScheduleEvent {
+    /// Started executing an incarnation of a transaction.
+    Execute {
+        /// The index of the transaction in the block.
+        tx_idx: usize,
+        /// The incarnation of the transaction, counting from 0.
+        tx_incarnation: usize,
+    },
+    /// Started validating an incarnation of a transaction.
+    Validate {
+        /// The index of the transaction in the block.
+        tx_idx: usize,
+        /// The incarnation of the transaction, counting from 0.
+        tx_incarnation: usize,
+    },
+    /// Aborted an incarnation of a transaction to re-execute it.
+    Abort {
+        /// The index of the transaction in the block.
+        tx_idx: usize,
+        /// The incarnation of the transaction, counting from 0.
+        tx_incarnation: usize,
+        /// Why the incarnation was aborted.
+        cause: AbortCause,
+    },
+}
+
+impl ScheduleEvent {
+    // The task that this event started, [None] for aborts.
+    pub(crate) fn task(&self) -> Option<Task> {
+        match *self {
+            Self::Execute {
+                tx_idx,
+                tx_incarnation,
+            } => Some(Task::Execution(TxVersion {
+                tx_idx,
+                tx_incarnation,
+            })),
+            Self::Validate {
+                tx_idx,
+                tx_incarnation,
+            } => Some(Task::Validation(TxVersion {
+                tx_idx,
+                tx_incarnation,
+            })),
+            Self::Abort { .. } => None,
+        }
+    }
+}
+
+/// The scheduling statistics of a parallel execution, recorded with
+/// [crate::PevmStrategy::record_report] and available via
+/// [crate::Pevm::report] after execution.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ExecutionReport {
+    /// The statistics of each transaction, by transaction index in the
+    /// block including the transactions skipped in
+    /// [crate::ExecutionMode::Build].
+    pub txs: Vec<TxReport>,
+    /// The chain of transactions with the most gas through the realized
+    /// dependency graph, in block order. Its transactions can't execute in
+    /// parallel however many workers there are.
+    pub critical_path: Vec<usize>,
+    /// The gas used by the transactions of [ExecutionReport::critical_path].
+    pub critical_path_gas: u64,
+    /// The longest time to execute a chain of transactions through the
+    /// realized dependency graph, by their final incarnations.
+    pub critical_path_time: Duration,
+}
+
+impl ExecutionReport {
+    /// Get the maximum speedup over sequential execution with unlimited
+    /// workers, as the block's gas over [ExecutionReport::critical_path_gas],
+    /// [None] for blocks without gas.
+    pub fn theoretical_speedup(&self) -> Option<f64> {
+        let gas_used: u64 = self.txs.iter().map(|tx_report| tx_report.gas_used).sum();
+        (self.critical_path_gas > 0).then(|| gas_used as f64 / self.critical_path_gas as f64)
+    }
+
+    // Compute the critical paths from the dependencies of each transaction,
+    // which are all lower transactions.
+    pub(crate) fn compute_critical_path(&mut self, dependencies: &[TxDependencies]) {
+        // The gas and the previous transaction of the heaviest chain ending
+        // at each transaction, and the time of the longest one.
+        let mut gas_chains: Vec<(u64, Option<TxIdx>)> = Vec::with_capacity(self.txs.len());
+        let mut time_chains: Vec<Duration> = Vec::with_capacity(self.txs.len());
+        for (tx_idx, tx_report) in self.txs.iter().enumerate() {
+            let tx_dependencies = dependencies
+                .get(tx_idx)
+                .map(|tx_dependencies| tx_dependencies.as_slice())
+                .unwrap_or_default();
+            let heaviest_dependency = tx_dependencies
+                .iter()
+                .copied()
+                .max_by_key(|dependency| gas_chains[*dependency].0);
+            gas_chains.push((
+                tx_report.gas_used
+                    + heaviest_dependency.map_or(0, |dependency| gas_chains[dependency].0),
+                heaviest_dependency,
+            ));
+            time_chains.push(
+                tx_report.last_execution_time
+                    + tx_dependencies
+                        .iter()
+                        .map(|dependency| time_chains[*dependency])
+                        .max()
+                        .unwrap_or_default(),
+            );
+        }
+        self.critical_path.clear();
+        let mut tx_idx = (0..gas_chains.len()).max_by_key(|tx_idx| gas_chains[*tx_idx].0);
+        self.critical_path_gas = tx_idx.map_or(0, |tx_idx| gas_chains[tx_idx].0);
+        while let Some(current_idx) = tx_idx {
+            self.critical_path.push(current_idx);
+            tx_idx = gas_chains[current_idx].1;
+        }
+        self.critical_path.reverse();
+        self.critical_path_time = time_chains.into_iter().max().unwrap_or_default();
+    }
+}
+
+/// The scheduling statistics of a transaction in a parallel execution.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TxReport {
+    /// The number of incarnations executed, which is one more than the
+    /// number of aborts.
+    pub incarnations: usize,
+    /// Why each aborted incarnation was aborted, in order.
+    pub aborts: Vec<AbortCause>,
+    /// The total time spent executing all incarnations.
+    pub execution_time: Duration,
+    /// The time spent executing the final incarnation.
+    pub last_execution_time: Duration,
+    /// The total time spent validating all incarnations.
+    pub validation_time: Duration,
+    /// The index of the worker that executed the final incarnation.
+    pub worker_idx: usize,
+    /// Whether the final incarnation lazily updated the balances of its
+    /// sender and recipient, as for raw transfers.
+    pub lazy: bool,
+    /// The gas used by the final incarnation, zero for the transactions
+    /// skipped in [crate::ExecutionMode::Build].
+    pub gas_used: u64,
+}
+
+/// The kind of a task run by a worker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskKind {
+    /// Executing an incarnation of a transaction.
+    Execution,
+    /// Validating an incarnation of a transaction.
+    Validation,
+}
+
+/// A task run by a worker in a parallel execution, recorded with
+/// [crate::PevmStrategy::record_events] and available via
+/// [crate::Pevm::take_events] after execution, to compute abort ratios or
+/// critical paths from the timeline of the block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskEvent {
+    /// The index of the transaction in the block.
+    pub tx_idx: usize,
+    /// The incarnation of the transaction, counting from 0.
+    pub tx_incarnation: usize,
+    /// Whether the task executed or validated the incarnation.
+    pub kind: TaskKind,
+    /// The index of the worker that ran the task.
+    pub worker_idx: usize,
+    /// When the task started, in nanoseconds since the execution started.
+    pub start_ns: u64,
+    /// When the task ended, in nanoseconds since the execution started.
+    pub end_ns: u64,
+}
+
+impl TaskEvent {
+    /// The header of the CSV rows of [TaskEvent::to_csv_row].
+    pub const CSV_HEADER: &'static str = "tx_idx,tx_incarnation,kind,worker_idx,start_ns,end_ns";
+
+    /// Format the event as a CSV row matching [TaskEvent::CSV_HEADER],
+    /// without a trailing newline.
+    pub fn to_csv_row(&self) -> String {
+        let kind = match self.kind {
+            TaskKind::Execution => "execution",
+            TaskKind::Validation => "validation",
+        };
+        format!(
+            "{},{},{kind},{},{},{}",
+            self.tx_idx, self.tx_incarnation, self.worker_idx, self.start_ns, self.end_ns
+        )
+    }
+}
+
+// A task being timed for the execution report or the recorded events.
+pub(crate) struct TaskTimer {
+    tx_idx: TxIdx,
+    tx_incarnation: usize,
+    is_execution: bool,
+    worker_idx: usize,
+    started_at: Instant,
+}
+
+// The execution order of a non-default [SchedulingPolicy].
+struct ExecutionOrder {
+    // The transaction to execute at each position.
+    tx_idxs: Vec<TxIdx>,
+    // The position of each transaction.
+    positions: Vec<usize>,
+}
 
 // The Pevm collaborative scheduler coordinates execution & validation
 // tasks among work threads.
@@ -45,15 +420,43 @@ pub(crate) struct Scheduler {
     // The list of dependent transactions to resume when the
     // key transaction is re-executed.
     transactions_dependents: Vec<Mutex<Vec<TxIdx>>>,
-    // The next transaction to try and execute.
+    // The order to execute transactions in, [None] for the block order.
+    execution_order: Option<ExecutionOrder>,
+    // The position in the execution order of the next transaction to try
+    // and execute.
     execution_idx: AtomicUsize,
     // The next transaction to try and validate.
     validation_idx: AtomicUsize,
-    // We won't validate until we find the first transaction that
-    // reads or writes outside of its preprocessed dependencies.
+    // We won't validate until we find the first transaction that writes
+    // outside of its preprocessed dependencies, as only higher transactions
+    // may have read stale values since.
     min_validation_idx: AtomicUsize,
     // The number of validated transactions
     num_validated: AtomicUsize,
+    // The number of transactions whose latest incarnation has executed,
+    // for progress reports.
+    num_executed: AtomicUsize,
+    // The recorded scheduling decisions, if recording.
+    schedule: Option<Mutex<Vec<ScheduleEvent>>>,
+    // The statistics of each transaction, if reporting.
+    tx_reports: Option<Vec<Mutex<TxReport>>>,
+    // The timed tasks, if recording events.
+    events: Option<Mutex<Vec<TaskEvent>>>,
+    // When the scheduler was created, which event times are relative to.
+    created_at: Instant,
+    // The number of started executions and of aborted ones, to tune the
+    // concurrency level with.
+    num_executions: AtomicUsize,
+    num_aborts: AtomicUsize,
+    // The number of aborts from failing validations, for metrics.
+    #[cfg(feature = "metrics")]
+    num_validation_aborts: AtomicUsize,
+    // The workers sleeping on executing transactions with
+    // [RetryPolicy::WaitInPlace], woken up whenever an execution finishes
+    // or aborts.
+    num_waiters: AtomicUsize,
+    waiters_lock: Mutex<()>,
+    execution_ended: Condvar,
     // True if the scheduler has been aborted, likely due to fatal exeuction
     // errors.
     aborted: AtomicBool,
@@ -62,9 +465,24 @@ pub(crate) struct Scheduler {
 // TODO: Better error handling.
 // Like returning errors instead of panicking on [unreachable]s.
 impl Scheduler {
-    pub(crate) fn new(block_size: usize) -> Self {
+    pub(crate) fn new(
+        txs: &[TxEnv],
+        policy: SchedulingPolicy,
+        record_schedule: bool,
+        record_report: bool,
+        record_events: bool,
+    ) -> Self {
+        let block_size = txs.len();
+        let execution_order = policy.execution_order(txs).map(|tx_idxs| {
+            let mut positions = vec![0; block_size];
+            for (position, tx_idx) in tx_idxs.iter().enumerate() {
+                positions[*tx_idx] = position;
+            }
+            ExecutionOrder { tx_idxs, positions }
+        });
         Self {
             block_size,
+            execution_order,
             execution_idx: AtomicUsize::new(0),
             transactions_status: (0..block_size)
                 .map(|_| {
@@ -80,12 +498,258 @@ impl Scheduler {
             validation_idx: AtomicUsize::new(block_size),
             min_validation_idx: AtomicUsize::new(block_size),
             num_validated: AtomicUsize::new(0),
+            num_executed: AtomicUsize::new(0),
+            schedule: record_schedule.then(Mutex::default),
+            tx_reports: record_report.then(|| (0..block_size).map(|_| Mutex::default()).collect()),
+            events: record_events.then(Mutex::default),
+            created_at: Instant::now(),
+            num_executions: AtomicUsize::new(0),
+            num_aborts: AtomicUsize::new(0),
+            #[cfg(feature = "metrics")]
+            num_validation_aborts: AtomicUsize::new(0),
+            num_waiters: AtomicUsize::new(0),
+            waiters_lock: Mutex::new(()),
+            execution_ended: Condvar::new(),
             aborted: AtomicBool::new(false),
         }
     }
 
     pub(crate) fn abort(&self) {
         self.aborted.store(true, Ordering::Release);
+        self.wake_waiters();
+    }
+
+    // Wake up the workers waiting on executing transactions. This must not
+    // be called while holding a transaction status lock, which waiters take
+    // while holding [waiters_lock].
+    fn wake_waiters(&self) {
+        if self.num_waiters.load(Ordering::SeqCst) > 0 {
+            let _guard = self.waiters_lock.lock().unwrap();
+            self.execution_ended.notify_all();
+        }
+    }
+
+    // Sleep until [tx_idx] isn't executing anymore, and return whether it
+    // has been executed for the caller to retry right away. Waiting chains
+    // only go down the block, and always end at a transaction that is making
+    // progress on another worker, so they never deadlock.
+    pub(crate) fn wait_for_execution(&self, tx_idx: TxIdx) -> bool {
+        self.num_waiters.fetch_add(1, Ordering::SeqCst);
+        let mut guard = self.waiters_lock.lock().unwrap();
+        let executed = loop {
+            let tx = index_mutex!(self.transactions_status, tx_idx);
+            if self.aborted.load(Ordering::Acquire) {
+                break false;
+            }
+            match tx.status {
+                IncarnationStatus::Executing => {}
+                IncarnationStatus::Executed | IncarnationStatus::Validated => break true,
+                _ => break false,
+            }
+            drop(tx);
+            guard = self.execution_ended.wait(guard).unwrap();
+        };
+        drop(guard);
+        self.num_waiters.fetch_sub(1, Ordering::SeqCst);
+        executed
+    }
+
+    fn record(&self, event: ScheduleEvent) {
+        #[cfg(feature = "tracing")]
+        if let ScheduleEvent::Abort {
+            tx_idx,
+            tx_incarnation,
+            cause,
+        } = event
+        {
+            tracing::debug!(tx_idx, tx_incarnation, ?cause, "aborted incarnation");
+        }
+        if let (Some(tx_reports), ScheduleEvent::Abort { tx_idx, cause, .. }) =
+            (&self.tx_reports, event)
+        {
+            index_mutex!(tx_reports, tx_idx).aborts.push(cause);
+        }
+        if let Some(schedule) = &self.schedule {
+            schedule.lock().unwrap().push(event);
+        }
+    }
+
+    // Record the start of a task if recording.
+    pub(crate) fn record_task(&self, task: &Task) {
+        if self.schedule.is_some() {
+            self.record(match task {
+                Task::Execution(tx_version) => ScheduleEvent::Execute {
+                    tx_idx: tx_version.tx_idx,
+                    tx_incarnation: tx_version.tx_incarnation,
+                },
+                Task::Validation(tx_version) => ScheduleEvent::Validate {
+                    tx_idx: tx_version.tx_idx,
+                    tx_incarnation: tx_version.tx_incarnation,
+                },
+            });
+        }
+    }
+
+    // Start timing a task for the execution report if reporting, or for the
+    // recorded events.
+    pub(crate) fn start_task_timer(&self, task: &Task, worker_idx: usize) -> Option<TaskTimer> {
+        if self.tx_reports.is_none() && self.events.is_none() {
+            return None;
+        }
+        let (tx_version, is_execution) = match task {
+            Task::Execution(tx_version) => (tx_version, true),
+            Task::Validation(tx_version) => (tx_version, false),
+        };
+        Some(TaskTimer {
+            tx_idx: tx_version.tx_idx,
+            tx_incarnation: tx_version.tx_incarnation,
+            is_execution,
+            worker_idx,
+            started_at: Instant::now(),
+        })
+    }
+
+    // Record a timed task in the execution report and the events.
+    pub(crate) fn finish_task_timer(&self, timer: TaskTimer) {
+        let ended_at = Instant::now();
+        if let Some(tx_reports) = &self.tx_reports {
+            let elapsed = ended_at - timer.started_at;
+            let mut tx_report = index_mutex!(tx_reports, timer.tx_idx);
+            if timer.is_execution {
+                tx_report.incarnations += 1;
+                tx_report.execution_time += elapsed;
+                tx_report.last_execution_time = elapsed;
+                tx_report.worker_idx = timer.worker_idx;
+            } else {
+                tx_report.validation_time += elapsed;
+            }
+        }
+        if let Some(events) = &self.events {
+            let since_created = |instant: Instant| (instant - self.created_at).as_nanos() as u64;
+            events.lock().unwrap().push(TaskEvent {
+                tx_idx: timer.tx_idx,
+                tx_incarnation: timer.tx_incarnation,
+                kind: if timer.is_execution {
+                    TaskKind::Execution
+                } else {
+                    TaskKind::Validation
+                },
+                worker_idx: timer.worker_idx,
+                start_ns: since_created(timer.started_at),
+                end_ns: since_created(ended_at),
+            });
+        }
+    }
+
+    // Record whether the latest execution of a transaction was lazily
+    // updated, if reporting.
+    pub(crate) fn report_lazy(&self, tx_idx: TxIdx, lazy: bool) {
+        if let Some(tx_reports) = &self.tx_reports {
+            index_mutex!(tx_reports, tx_idx).lazy = lazy;
+        }
+    }
+
+    // Take the recorded statistics of each transaction.
+    pub(crate) fn take_report(&self) -> Option<ExecutionReport> {
+        self.tx_reports.as_ref().map(|tx_reports| ExecutionReport {
+            txs: tx_reports
+                .iter()
+                .map(|tx_report| std::mem::take(&mut *tx_report.lock().unwrap()))
+                .collect(),
+            ..ExecutionReport::default()
+        })
+    }
+
+    // Take the recorded events, sorted by their start times.
+    pub(crate) fn take_events(&self) -> Option<Vec<TaskEvent>> {
+        self.events.as_ref().map(|events| {
+            let mut events = std::mem::take(&mut *events.lock().unwrap());
+            events.sort_by_key(|event| event.start_ns);
+            events
+        })
+    }
+
+    // Take the recorded scheduling decisions.
+    pub(crate) fn take_schedule(&self) -> Option<Vec<ScheduleEvent>> {
+        self.schedule
+            .as_ref()
+            .map(|schedule| std::mem::take(&mut *schedule.lock().unwrap()))
+    }
+
+    // Record the numbers of executions and aborts so far to the metrics.
+    #[cfg(feature = "metrics")]
+    pub(crate) fn record_metrics(&self) {
+        crate::metrics::record_scheduling(
+            self.num_executions.load(Ordering::Relaxed),
+            self.num_aborts.load(Ordering::Relaxed),
+            self.num_validation_aborts.load(Ordering::Relaxed),
+        );
+    }
+
+    // The numbers of executed and of validated transactions so far.
+    pub(crate) fn progress(&self) -> (usize, usize) {
+        (
+            self.num_executed.load(Ordering::Relaxed),
+            self.num_validated.load(Ordering::Relaxed),
+        )
+    }
+
+    // Take a specific task like [next_task] would have returned it, for
+    // replaying recorded schedules. Return [false] if the task's incarnation
+    // isn't ready for it, as the replay has diverged.
+    pub(crate) fn take_task(&self, task: &Task) -> bool {
+        match task {
+            Task::Execution(tx_version) => {
+                let mut tx = index_mutex!(self.transactions_status, tx_version.tx_idx);
+                if tx.status != IncarnationStatus::ReadyToExecute
+                    || tx.incarnation != tx_version.tx_incarnation
+                {
+                    return false;
+                }
+                tx.status = IncarnationStatus::Executing;
+                self.num_executions.fetch_add(1, Ordering::Relaxed);
+                true
+            }
+            Task::Validation(tx_version) => {
+                let tx = index_mutex!(self.transactions_status, tx_version.tx_idx);
+                tx.incarnation == tx_version.tx_incarnation
+                    && matches!(
+                        tx.status,
+                        IncarnationStatus::Executed | IncarnationStatus::Validated
+                    )
+            }
+        }
+    }
+
+    // Whether all transactions have been executed and validated, or the
+    // scheduler has been aborted.
+    pub(crate) fn is_done(&self) -> bool {
+        self.aborted.load(Ordering::Acquire)
+            || self.execution_idx.load(Ordering::Acquire) >= self.block_size
+                && self.validation_idx.load(Ordering::Acquire) >= self.block_size
+                && self.num_validated.load(Ordering::Acquire)
+                    >= self.block_size - self.min_validation_idx.load(Ordering::Acquire)
+    }
+
+    // The number of execution and validation tasks yet to be picked.
+    fn num_pending_tasks(&self) -> usize {
+        let remaining = |idx: usize| self.block_size - min(idx, self.block_size);
+        remaining(self.execution_idx.load(Ordering::Acquire))
+            + remaining(self.validation_idx.load(Ordering::Acquire))
+    }
+
+    fn tx_idx_at(&self, position: usize) -> TxIdx {
+        match &self.execution_order {
+            Some(order) if position < self.block_size => order.tx_idxs[position],
+            _ => position,
+        }
+    }
+
+    fn position_of(&self, tx_idx: TxIdx) -> usize {
+        match &self.execution_order {
+            Some(order) => order.positions[tx_idx],
+            None => tx_idx,
+        }
     }
 
     fn try_execute(&self, tx_idx: TxIdx) -> Option<TxVersion> {
@@ -93,6 +757,7 @@ impl Scheduler {
             let mut tx = index_mutex!(self.transactions_status, tx_idx);
             if tx.status == IncarnationStatus::ReadyToExecute {
                 tx.status = IncarnationStatus::Executing;
+                self.num_executions.fetch_add(1, Ordering::Relaxed);
                 return Some(TxVersion {
                     tx_idx,
                     tx_incarnation: tx.incarnation,
@@ -124,6 +789,7 @@ impl Scheduler {
                     // "Steal" execution job while holding the lock
                     if tx.status == IncarnationStatus::ReadyToExecute {
                         tx.status = IncarnationStatus::Executing;
+                        self.num_executions.fetch_add(1, Ordering::Relaxed);
                         return Some(Task::Execution(TxVersion {
                             tx_idx,
                             tx_incarnation: tx.incarnation,
@@ -153,7 +819,7 @@ impl Scheduler {
 
             // Prioritize execution task
             if let Some(tx_version) =
-                self.try_execute(self.execution_idx.fetch_add(1, Ordering::Release))
+                self.try_execute(self.tx_idx_at(self.execution_idx.fetch_add(1, Ordering::Release)))
             {
                 return Some(Task::Execution(tx_version));
             }
@@ -161,6 +827,31 @@ impl Scheduler {
         None
     }
 
+    // Block transactions on the lower transactions that they likely depend
+    // on before execution, to first execute them once those are executed
+    // instead of executing and aborting them. Dependencies on higher or
+    // equal transactions are ignored.
+    pub(crate) fn with_dependencies(mut self, dependencies: &[TxDependencies]) -> Self {
+        for (tx_idx, blocking_tx_idxs) in dependencies.iter().enumerate().take(self.block_size) {
+            // The highest dependency likely executes last.
+            let Some(blocking_tx_idx) = blocking_tx_idxs
+                .iter()
+                .copied()
+                .filter(|blocking_tx_idx| *blocking_tx_idx < tx_idx)
+                .max()
+            else {
+                continue;
+            };
+            self.transactions_status[tx_idx].get_mut().unwrap().status =
+                IncarnationStatus::Aborting;
+            self.transactions_dependents[blocking_tx_idx]
+                .get_mut()
+                .unwrap()
+                .push(tx_idx);
+        }
+        self
+    }
+
     // Add [tx_idx] as a dependent of [blocking_tx_idx] so [tx_idx] is
     // re-executed when the next [blocking_tx_idx] incarnation is executed.
     // Return [false] if we encounter a race condition when [blocking_tx_idx]
@@ -179,13 +870,23 @@ impl Scheduler {
         let mut tx = index_mutex!(self.transactions_status, tx_idx);
         if tx.status == IncarnationStatus::Executing {
             tx.status = IncarnationStatus::Aborting;
+            let tx_incarnation = tx.incarnation;
             drop(tx);
+            self.num_aborts.fetch_add(1, Ordering::Relaxed);
+            self.record(ScheduleEvent::Abort {
+                tx_idx,
+                tx_incarnation,
+                cause: AbortCause::Dependency { blocking_tx_idx },
+            });
 
             let mut blocking_dependents =
                 index_mutex!(self.transactions_dependents, blocking_tx_idx);
             blocking_dependents.push(tx_idx);
             drop(blocking_dependents);
+            drop(blocking_tx);
 
+            // Workers may be waiting on this aborted execution.
+            self.wake_waiters();
             return true;
         }
 
@@ -213,22 +914,26 @@ impl Scheduler {
             debug_assert_eq!(tx.incarnation, tx_version.tx_incarnation);
             tx.status = IncarnationStatus::Executed;
             drop(tx);
+            self.num_executed.fetch_add(1, Ordering::Relaxed);
+            self.wake_waiters();
 
             // Resume dependent transactions
             let mut dependents = index_mutex!(self.transactions_dependents, tx_version.tx_idx);
-            let mut min_dependent_idx = None;
+            let mut min_dependent_position = None;
             for tx_idx in dependents.iter() {
                 self.set_ready_status(*tx_idx);
-                min_dependent_idx = match min_dependent_idx {
-                    None => Some(*tx_idx),
-                    Some(min_index) => Some(min(*tx_idx, min_index)),
+                let position = self.position_of(*tx_idx);
+                min_dependent_position = match min_dependent_position {
+                    None => Some(position),
+                    Some(min_position) => Some(min(position, min_position)),
                 }
             }
             dependents.clear();
             drop(dependents);
 
-            if let Some(min_idx) = min_dependent_idx {
-                self.execution_idx.fetch_min(min_idx, Ordering::Release);
+            if let Some(min_position) = min_dependent_position {
+                self.execution_idx
+                    .fetch_min(min_position, Ordering::Release);
             }
 
             // Decide where to validate from next
@@ -283,6 +988,15 @@ impl Scheduler {
         );
         if aborting {
             tx.status = IncarnationStatus::Aborting;
+            self.num_executed.fetch_sub(1, Ordering::Relaxed);
+            self.num_aborts.fetch_add(1, Ordering::Relaxed);
+            #[cfg(feature = "metrics")]
+            self.num_validation_aborts.fetch_add(1, Ordering::Relaxed);
+            self.record(ScheduleEvent::Abort {
+                tx_idx: tx_version.tx_idx,
+                tx_incarnation: tx.incarnation,
+                cause: AbortCause::Validation,
+            });
         }
         aborting
     }
@@ -295,7 +1009,7 @@ impl Scheduler {
             self.set_ready_status(tx_version.tx_idx);
             self.validation_idx
                 .fetch_min(tx_version.tx_idx + 1, Ordering::Release);
-            if self.execution_idx.load(Ordering::Acquire) > tx_version.tx_idx {
+            if self.execution_idx.load(Ordering::Acquire) > self.position_of(tx_version.tx_idx) {
                 return self.try_execute(tx_version.tx_idx).map(Task::Execution);
             }
         } else {
@@ -308,3 +1022,74 @@ impl Scheduler {
         None
     }
 }
+
+// The number of executions between two concurrency tunings.
+const TUNING_WINDOW: usize = 64;
+
+// How long parked workers sleep before checking whether they can resume.
+const PARK_TIMEOUT: Duration = Duration::from_micros(50);
+
+// Tunes the number of active workers to the observed contention, with an
+// additive increase when few executions abort and there are more pending
+// tasks than active workers, and a multiplicative decrease when most
+// executions abort. Workers above the current level park between tasks.
+pub(crate) struct ConcurrencyTuner {
+    max_level: usize,
+    level: AtomicUsize,
+    // The numbers of executions and aborts at the last tuning.
+    last_counts: Mutex<(usize, usize)>,
+}
+
+impl ConcurrencyTuner {
+    pub(crate) fn new(max_level: NonZeroUsize) -> Self {
+        Self {
+            max_level: max_level.get(),
+            level: AtomicUsize::new(max_level.get()),
+            last_counts: Mutex::new((0, 0)),
+        }
+    }
+
+    pub(crate) fn level(&self) -> NonZeroUsize {
+        NonZeroUsize::new(self.level.load(Ordering::Acquire)).unwrap_or(NonZeroUsize::MIN)
+    }
+
+    fn tune(&self, scheduler: &Scheduler) {
+        let num_executions = scheduler.num_executions.load(Ordering::Relaxed);
+        let num_aborts = scheduler.num_aborts.load(Ordering::Relaxed);
+        let mut last_counts = self.last_counts.lock().unwrap();
+        let window = num_executions - last_counts.0;
+        if window < TUNING_WINDOW {
+            return;
+        }
+        let abort_rate = (num_aborts - last_counts.1) as f64 / window as f64;
+        *last_counts = (num_executions, num_aborts);
+        drop(last_counts);
+
+        let level = self.level.load(Ordering::Acquire);
+        if abort_rate > 0.5 {
+            self.level.store((level / 2).max(1), Ordering::Release);
+        } else if abort_rate < 0.1
+            && level < self.max_level
+            && scheduler.num_pending_tasks() > level
+        {
+            self.level.store(level + 1, Ordering::Release);
+        }
+    }
+
+    // Wait until the worker is active to pick its next task, returning
+    // [false] if the scheduler is done in the meantime. The first worker is
+    // always active and tunes the level for the rest.
+    pub(crate) fn wait_for_turn(&self, worker_idx: usize, scheduler: &Scheduler) -> bool {
+        if worker_idx == 0 {
+            self.tune(scheduler);
+            return true;
+        }
+        while worker_idx >= self.level.load(Ordering::Acquire) {
+            if scheduler.is_done() {
+                return false;
+            }
+            thread::park_timeout(PARK_TIMEOUT);
+        }
+        true
+    }
+}
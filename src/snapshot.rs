@@ -0,0 +1,398 @@
+//! A versioned single-file snapshot of a block and the pre-block state to
+//! execute it, zstd-compressed bincode for fast loading in benchmarks and
+//! tests. Ranges of consecutive blocks share a single file and state.
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use ahash::{AHashMap, AHashSet};
+use alloy_primitives::{Address, B256, U256};
+use alloy_rpc_types::Block;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{Bytecodes, EvmAccount, EvmCode, InMemoryStorage, PevmBlockExecutionResult};
+
+// The magic bytes that start a snapshot file.
+const MAGIC: &[u8; 8] = b"PEVMSNAP";
+// The magic bytes that start a range snapshot file.
+const RANGE_MAGIC: &[u8; 8] = b"PEVMRNGE";
+
+/// The current version of the snapshot format.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+// The zstd compression level, favoring decompression speed and ratio over
+// compression speed as snapshots are written once and read many times.
+const COMPRESSION_LEVEL: i32 = 19;
+
+/// Errors when reading or writing a [BlockSnapshot].
+#[derive(Debug, Error)]
+pub enum SnapshotError {
+    /// Cannot read or write the file.
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+    /// The file is not a snapshot.
+    #[error("not a block snapshot")]
+    InvalidMagic,
+    /// The snapshot is of an unsupported version.
+    #[error("unsupported snapshot version {0}")]
+    UnsupportedVersion(u32),
+    /// Cannot encode or decode the snapshot.
+    #[error("bincode error: {0}")]
+    Bincode(#[from] bincode::Error),
+    /// Cannot encode or decode the block, or a legacy JSON file.
+    #[error("json error: {0}")]
+    Json(#[from] serde_json::Error),
+    /// The blocks of a range snapshot aren't consecutive.
+    #[error("expected block {expected} but got block {found}")]
+    NonConsecutiveBlocks {
+        /// The expected next block number.
+        expected: u64,
+        /// The number of the block.
+        found: u64,
+    },
+}
+
+/// A block with the pre-block state to execute it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockSnapshot {
+    /// The block to execute.
+    pub block: Block,
+    /// The pre-block accounts read by the block, without their codes.
+    pub accounts: AHashMap<Address, EvmAccount>,
+    /// The codes of the accounts by their hashes.
+    pub bytecodes: Bytecodes,
+    /// The block hashes read by the block.
+    pub block_hashes: AHashMap<u64, B256>,
+}
+
+// The encoded layout of a snapshot. Alloy blocks and our accounts rely on
+// self-describing serde features that bincode doesn't support, so the block
+// is nested as JSON and accounts are flattened. Entries are sorted for
+// deterministic files.
+#[derive(Serialize, Deserialize)]
+struct EncodedSnapshot {
+    block: Vec<u8>,
+    accounts: Vec<EncodedAccount>,
+    bytecodes: Vec<(B256, EvmCode)>,
+    block_hashes: Vec<(u64, B256)>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct EncodedAccount {
+    address: Address,
+    balance: U256,
+    nonce: u64,
+    code_hash: Option<B256>,
+    storage: Vec<(U256, U256)>,
+}
+
+// The encoded layout of a range snapshot, like [EncodedSnapshot].
+#[derive(Serialize, Deserialize)]
+struct EncodedRangeSnapshot {
+    blocks: Vec<Vec<u8>>,
+    accounts: Vec<EncodedAccount>,
+    bytecodes: Vec<(B256, EvmCode)>,
+    block_hashes: Vec<(u64, B256)>,
+}
+
+fn encode_accounts(accounts: &AHashMap<Address, EvmAccount>) -> Vec<EncodedAccount> {
+    let mut accounts: Vec<_> = accounts
+        .iter()
+        .map(|(address, account)| {
+            let mut storage: Vec<_> = account.storage.clone().into_iter().collect();
+            storage.sort_unstable();
+            EncodedAccount {
+                address: *address,
+                balance: account.balance,
+                nonce: account.nonce,
+                code_hash: account.code_hash,
+                storage,
+            }
+        })
+        .collect();
+    accounts.sort_unstable_by_key(|account| account.address);
+    accounts
+}
+
+fn decode_accounts(accounts: Vec<EncodedAccount>) -> AHashMap<Address, EvmAccount> {
+    accounts
+        .into_iter()
+        .map(|account| {
+            (
+                account.address,
+                EvmAccount {
+                    balance: account.balance,
+                    nonce: account.nonce,
+                    code_hash: account.code_hash,
+                    code: None,
+                    storage: account.storage.into_iter().collect(),
+                },
+            )
+        })
+        .collect()
+}
+
+fn encode_bytecodes(bytecodes: &Bytecodes) -> Vec<(B256, EvmCode)> {
+    let mut bytecodes: Vec<_> = bytecodes.clone().into_iter().collect();
+    bytecodes.sort_unstable_by_key(|(code_hash, _)| *code_hash);
+    bytecodes
+}
+
+fn encode_block_hashes(block_hashes: &AHashMap<u64, B256>) -> Vec<(u64, B256)> {
+    let mut block_hashes: Vec<_> = block_hashes.clone().into_iter().collect();
+    block_hashes.sort_unstable();
+    block_hashes
+}
+
+// Write an encoded snapshot after its magic bytes and version.
+fn write_encoded<T: Serialize>(
+    path: impl AsRef<Path>,
+    magic: &[u8; 8],
+    encoded: &T,
+) -> Result<(), SnapshotError> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    writer.write_all(magic)?;
+    writer.write_all(&SNAPSHOT_VERSION.to_le_bytes())?;
+    let mut encoder = zstd::Encoder::new(writer, COMPRESSION_LEVEL)?;
+    bincode::serialize_into(&mut encoder, encoded)?;
+    encoder.finish()?.flush()?;
+    Ok(())
+}
+
+// Read an encoded snapshot, checking its magic bytes and version.
+fn read_encoded<T: for<'de> Deserialize<'de>>(
+    path: impl AsRef<Path>,
+    expected_magic: &[u8; 8],
+) -> Result<T, SnapshotError> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut magic = [0; 8];
+    reader.read_exact(&mut magic)?;
+    if &magic != expected_magic {
+        return Err(SnapshotError::InvalidMagic);
+    }
+    let mut version = [0; 4];
+    reader.read_exact(&mut version)?;
+    let version = u32::from_le_bytes(version);
+    if version != SNAPSHOT_VERSION {
+        return Err(SnapshotError::UnsupportedVersion(version));
+    }
+    Ok(bincode::deserialize_from(zstd::Decoder::new(reader)?)?)
+}
+
+impl BlockSnapshot {
+    /// Get an in-memory storage of the snapshot's pre-block state.
+    pub fn storage(&self) -> InMemoryStorage<'_> {
+        InMemoryStorage::new(
+            self.accounts.clone(),
+            Some(&self.bytecodes),
+            self.block_hashes.clone(),
+        )
+    }
+
+    /// Write the snapshot to a file.
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<(), SnapshotError> {
+        let encoded = EncodedSnapshot {
+            block: serde_json::to_vec(&self.block)?,
+            accounts: encode_accounts(&self.accounts),
+            bytecodes: encode_bytecodes(&self.bytecodes),
+            block_hashes: encode_block_hashes(&self.block_hashes),
+        };
+        write_encoded(path, MAGIC, &encoded)
+    }
+
+    /// Read a snapshot from a file.
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, SnapshotError> {
+        let encoded: EncodedSnapshot = read_encoded(path, MAGIC)?;
+        Ok(Self {
+            block: serde_json::from_slice(&encoded.block)?,
+            accounts: decode_accounts(encoded.accounts),
+            bytecodes: encoded.bytecodes.into_iter().collect(),
+            block_hashes: encoded.block_hashes.into_iter().collect(),
+        })
+    }
+
+    /// Convert a block directory of the legacy layout, with `block.json`,
+    /// `pre_state.json` and an optional `block_hashes.json`, reading codes
+    /// from the shared bytecodes of all blocks. Only the codes of the
+    /// block's accounts are kept.
+    pub fn from_legacy_dir(
+        dir: impl AsRef<Path>,
+        bytecodes: &Bytecodes,
+    ) -> Result<Self, SnapshotError> {
+        let dir = dir.as_ref();
+        let block: Block =
+            serde_json::from_reader(BufReader::new(File::open(dir.join("block.json"))?))?;
+        let mut accounts: AHashMap<Address, EvmAccount> =
+            serde_json::from_reader::<_, HashMap<Address, EvmAccount>>(BufReader::new(
+                File::open(dir.join("pre_state.json"))?,
+            ))?
+            .into_iter()
+            .collect();
+        let block_hashes = match File::open(dir.join("block_hashes.json")) {
+            Ok(file) => serde_json::from_reader::<_, HashMap<u64, B256>>(BufReader::new(file))?
+                .into_iter()
+                .collect(),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => AHashMap::default(),
+            Err(err) => return Err(err.into()),
+        };
+        let mut snapshot_bytecodes = Bytecodes::default();
+        for account in accounts.values_mut() {
+            if let Some(code_hash) = account.code_hash {
+                let code = account
+                    .code
+                    .take()
+                    .or_else(|| bytecodes.get(&code_hash).cloned());
+                if let Some(code) = code {
+                    snapshot_bytecodes.insert(code_hash, code);
+                }
+            }
+        }
+        Ok(Self {
+            block,
+            accounts,
+            bytecodes: snapshot_bytecodes,
+            block_hashes,
+        })
+    }
+}
+
+/// A range of consecutive blocks with the state before the first block that
+/// they read, to execute in order via [crate::Pevm::execute_range_of_blocks].
+/// The blocks share their codes and the pre-block state of each block is
+/// only kept where no lower block of the range wrote to, so a range is much
+/// smaller than the snapshots of its blocks.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BlockRangeSnapshot {
+    /// The consecutive blocks to execute.
+    pub blocks: Vec<Block>,
+    /// The accounts before the first block read by the blocks, without
+    /// their codes.
+    pub accounts: AHashMap<Address, EvmAccount>,
+    /// The codes of the accounts by their hashes.
+    pub bytecodes: Bytecodes,
+    /// The block hashes read by the blocks.
+    pub block_hashes: AHashMap<u64, B256>,
+}
+
+impl BlockRangeSnapshot {
+    /// Merge the snapshots of consecutive blocks with the results of
+    /// executing each block, which tell which pre-block values of the
+    /// higher blocks come from the lower blocks instead of the state before
+    /// the range.
+    pub fn from_block_snapshots(
+        snapshots: impl IntoIterator<Item = (BlockSnapshot, PevmBlockExecutionResult)>,
+    ) -> Result<Self, SnapshotError> {
+        let mut range = Self::default();
+        // The accounts and storage slots written by the blocks so far, and
+        // the accounts whose whole storage was cleared by self-destructs.
+        let mut written_accounts = AHashSet::new();
+        let mut written_slots = AHashSet::new();
+        let mut destructed_accounts = AHashSet::new();
+        for (snapshot, block_result) in snapshots {
+            let block_number = snapshot.block.header.number.unwrap_or_default();
+            if let Some(last_block) = range.blocks.last() {
+                let expected = last_block.header.number.unwrap_or_default() + 1;
+                if block_number != expected {
+                    return Err(SnapshotError::NonConsecutiveBlocks {
+                        expected,
+                        found: block_number,
+                    });
+                }
+            }
+            for (address, account) in snapshot.accounts {
+                if !written_accounts.contains(&address) {
+                    range.accounts.entry(address).or_insert_with(|| EvmAccount {
+                        balance: account.balance,
+                        nonce: account.nonce,
+                        code_hash: account.code_hash,
+                        code: None,
+                        storage: AHashMap::default(),
+                    });
+                }
+                // Accounts created by lower blocks had no storage before the
+                // range.
+                let Some(range_account) = range.accounts.get_mut(&address) else {
+                    continue;
+                };
+                if destructed_accounts.contains(&address) {
+                    continue;
+                }
+                for (slot, value) in account.storage {
+                    if !written_slots.contains(&(address, slot)) {
+                        range_account.storage.entry(slot).or_insert(value);
+                    }
+                }
+            }
+            range.bytecodes.extend(snapshot.bytecodes);
+            range.block_hashes.extend(snapshot.block_hashes);
+            range.blocks.push(snapshot.block);
+
+            for (address, account) in block_result
+                .pre_block_state
+                .iter()
+                .chain(
+                    block_result
+                        .tx_results
+                        .iter()
+                        .flat_map(|tx| tx.state.iter()),
+                )
+                .chain(block_result.post_block_state.iter())
+            {
+                written_accounts.insert(*address);
+                match account {
+                    Some(account) => {
+                        written_slots.extend(account.storage.keys().map(|slot| (*address, *slot)))
+                    }
+                    None => {
+                        destructed_accounts.insert(*address);
+                    }
+                }
+            }
+        }
+        Ok(range)
+    }
+
+    /// Get an in-memory storage of the state before the range.
+    pub fn storage(&self) -> InMemoryStorage<'_> {
+        InMemoryStorage::new(
+            self.accounts.clone(),
+            Some(&self.bytecodes),
+            self.block_hashes.clone(),
+        )
+    }
+
+    /// Write the range snapshot to a file.
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<(), SnapshotError> {
+        let encoded = EncodedRangeSnapshot {
+            blocks: self
+                .blocks
+                .iter()
+                .map(serde_json::to_vec)
+                .collect::<Result<_, _>>()?,
+            accounts: encode_accounts(&self.accounts),
+            bytecodes: encode_bytecodes(&self.bytecodes),
+            block_hashes: encode_block_hashes(&self.block_hashes),
+        };
+        write_encoded(path, RANGE_MAGIC, &encoded)
+    }
+
+    /// Read a range snapshot from a file.
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, SnapshotError> {
+        let encoded: EncodedRangeSnapshot = read_encoded(path, RANGE_MAGIC)?;
+        Ok(Self {
+            blocks: encoded
+                .blocks
+                .iter()
+                .map(|block| serde_json::from_slice(block))
+                .collect::<Result<_, _>>()?,
+            accounts: decode_accounts(encoded.accounts),
+            bytecodes: encoded.bytecodes.into_iter().collect(),
+            block_hashes: encoded.block_hashes.into_iter().collect(),
+        })
+    }
+}
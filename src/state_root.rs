@@ -0,0 +1,598 @@
+//! Post-block state root computation from Merkle proofs of the pre-block
+//! state, so blocks can be validated end to end.
+
+use std::{iter, mem};
+
+use ahash::AHashMap;
+use alloy_primitives::{keccak256, Address, Bytes, B256, U256};
+use alloy_rlp::{Encodable, Header, EMPTY_STRING_CODE};
+use alloy_trie::EMPTY_ROOT_HASH;
+use revm::primitives::KECCAK_EMPTY;
+use thiserror::Error;
+
+use crate::{EvmAccount, EvmStateTransitions, PevmBlockExecutionResult, Storage, StorageError};
+
+/// The Merkle proof of an account and some of its storage slots, like the
+/// one returned by `eth_getProof`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AccountProof {
+    /// The root of the account's storage trie.
+    pub storage_root: B256,
+    /// The RLP-encoded trie nodes from the state root to the account.
+    pub account_proof: Vec<Bytes>,
+    /// The RLP-encoded trie nodes from the storage root to each requested
+    /// slot, in the requested order.
+    pub storage_proofs: Vec<Vec<Bytes>>,
+}
+
+/// A [Storage] that provides Merkle proofs of its state.
+pub trait ProofStorage: Storage {
+    /// Get the state root of the storage.
+    fn state_root(&self) -> Result<B256, Self::Error>;
+
+    /// Get the proof of an account and some of its storage slots. Proofs of
+    /// non-existent accounts and slots must include the nodes up to where
+    /// their paths diverge from the trie.
+    fn proof(&self, address: &Address, slots: &[U256]) -> Result<AccountProof, Self::Error>;
+}
+
+/// Errors when computing a state root.
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum StateRootError {
+    /// Cannot read proofs from storage.
+    #[error("storage error: {0}")]
+    StorageError(StorageError),
+    /// A trie node needed for the update is not in the proofs. This happens
+    /// when a deletion collapses a branch into a sibling that no proof has
+    /// revealed.
+    #[error("missing trie node {0}")]
+    MissingTrieNode(B256),
+    /// A proof has an invalid trie node.
+    #[error("invalid trie node: {0}")]
+    InvalidTrieNode(alloy_rlp::Error),
+}
+
+/// Compute the post-block state root of a block's execution result on top
+/// of the storage it was executed against.
+pub fn compute_state_root<S: ProofStorage>(
+    storage: &S,
+    result: &PevmBlockExecutionResult,
+) -> Result<B256, StateRootError> {
+    let storage_error = |err: S::Error| StateRootError::StorageError(StorageError::new(err));
+    let merged_accounts = merge_state_transitions(
+        iter::once(&result.pre_block_state)
+            .chain(result.tx_results.iter().map(|tx_result| &tx_result.state))
+            .chain(iter::once(&result.post_block_state)),
+    );
+
+    let mut account_trie = SparseTrie::new(storage.state_root().map_err(storage_error)?);
+    for (address, merged_account) in merged_accounts {
+        let slots: Vec<U256> = match &merged_account.account {
+            Some(account) if !merged_account.storage_cleared => {
+                account.storage.keys().copied().collect()
+            }
+            _ => Vec::new(),
+        };
+        let proof = storage.proof(&address, &slots).map_err(storage_error)?;
+        account_trie.reveal(&proof.account_proof);
+
+        let account_key = keccak256(address);
+        let Some(account) = merged_account.account else {
+            account_trie.remove(&account_key)?;
+            continue;
+        };
+
+        let mut storage_trie = if merged_account.storage_cleared {
+            SparseTrie::new(EMPTY_ROOT_HASH)
+        } else {
+            let mut storage_trie = SparseTrie::new(proof.storage_root);
+            for storage_proof in proof.storage_proofs.iter() {
+                storage_trie.reveal(storage_proof);
+            }
+            storage_trie
+        };
+        for (slot, value) in account.storage.iter() {
+            if value.is_zero() {
+                storage_trie.remove(&slot_key(slot))?;
+            } else {
+                storage_trie.insert(&slot_key(slot), alloy_rlp::encode(value))?;
+            }
+        }
+        account_trie.insert(&account_key, encode_account(&account, storage_trie.root()))?;
+    }
+    Ok(account_trie.root())
+}
+
+// The state transitions of an account merged over a block.
+struct MergedAccount {
+    account: Option<EvmAccount>,
+    // Whether the account's pre-block storage has been cleared, like when it
+    // is self-destructed then revived in the block.
+    storage_cleared: bool,
+}
+
+// Merge state transitions from the oldest to the latest.
+fn merge_state_transitions<'a>(
+    states: impl Iterator<Item = &'a EvmStateTransitions>,
+) -> AHashMap<Address, MergedAccount> {
+    let mut merged_accounts: AHashMap<Address, MergedAccount> = AHashMap::new();
+    for state in states {
+        for (address, account) in state {
+            let Some(merged_account) = merged_accounts.get_mut(address) else {
+                merged_accounts.insert(
+                    *address,
+                    MergedAccount {
+                        account: account.clone(),
+                        storage_cleared: account.is_none(),
+                    },
+                );
+                continue;
+            };
+            match (&mut merged_account.account, account) {
+                (_, None) => {
+                    merged_account.account = None;
+                    merged_account.storage_cleared = true;
+                }
+                (Some(merged), Some(account)) => {
+                    merged.balance = account.balance;
+                    merged.nonce = account.nonce;
+                    merged.code_hash = account.code_hash;
+                    merged
+                        .storage
+                        .extend(account.storage.iter().map(|(slot, value)| (*slot, *value)));
+                }
+                (None, Some(account)) => merged_account.account = Some(account.clone()),
+            }
+        }
+    }
+    merged_accounts
+}
+
+pub(crate) fn slot_key(slot: &U256) -> B256 {
+    keccak256(slot.to_be_bytes::<32>())
+}
+
+pub(crate) fn encode_account(account: &EvmAccount, storage_root: B256) -> Vec<u8> {
+    let code_hash = account.code_hash.unwrap_or(KECCAK_EMPTY);
+    let payload_length = account.nonce.length()
+        + account.balance.length()
+        + storage_root.length()
+        + code_hash.length();
+    let mut out = Vec::with_capacity(payload_length + 2);
+    Header {
+        list: true,
+        payload_length,
+    }
+    .encode(&mut out);
+    account.nonce.encode(&mut out);
+    account.balance.encode(&mut out);
+    storage_root.encode(&mut out);
+    code_hash.encode(&mut out);
+    out
+}
+
+// A trie node. All keys are 32-byte hashes (64 nibbles) in the state and
+// storage tries, so branches never hold values.
+enum TrieNode {
+    Empty,
+    // A subtrie that hasn't been revealed by any proof.
+    Hash(B256),
+    // The remaining key nibbles and the value.
+    Leaf(Vec<u8>, Vec<u8>),
+    Extension(Vec<u8>, Box<TrieNode>),
+    Branch(Box<[TrieNode; 16]>),
+}
+
+// A Merkle Patricia trie with subtries only revealed by proofs, for
+// updating the root of a large trie without having all of its nodes.
+// TODO: Cache the hashes of unchanged subtries.
+pub(crate) struct SparseTrie {
+    root: TrieNode,
+    // The revealed RLP-encoded nodes by their hashes.
+    nodes: AHashMap<B256, Bytes>,
+}
+
+impl Default for SparseTrie {
+    fn default() -> Self {
+        Self::new(EMPTY_ROOT_HASH)
+    }
+}
+
+impl SparseTrie {
+    fn new(root: B256) -> Self {
+        Self {
+            root: if root == EMPTY_ROOT_HASH {
+                TrieNode::Empty
+            } else {
+                TrieNode::Hash(root)
+            },
+            nodes: AHashMap::new(),
+        }
+    }
+
+    fn reveal(&mut self, proof: &[Bytes]) {
+        for node in proof {
+            self.nodes.insert(keccak256(node), node.clone());
+        }
+    }
+
+    pub(crate) fn root(&self) -> B256 {
+        match &self.root {
+            TrieNode::Empty => EMPTY_ROOT_HASH,
+            TrieNode::Hash(hash) => *hash,
+            root => keccak256(encode_node(root)),
+        }
+    }
+
+    fn insert(&mut self, key: &B256, value: Vec<u8>) -> Result<(), StateRootError> {
+        let root = mem::replace(&mut self.root, TrieNode::Empty);
+        self.root = self.insert_at(root, &to_nibbles(key), value)?;
+        Ok(())
+    }
+
+    fn remove(&mut self, key: &B256) -> Result<(), StateRootError> {
+        let root = mem::replace(&mut self.root, TrieNode::Empty);
+        self.root = self.remove_at(root, &to_nibbles(key))?;
+        Ok(())
+    }
+
+    // Get the RLP-encoded nodes from the root to a key, which only
+    // includes revealed nodes. We also include the children of the branches
+    // along the path so deleting the key can collapse them.
+    pub(crate) fn proof(&self, key: &B256) -> Vec<Bytes> {
+        let nibbles = to_nibbles(key);
+        let mut path = nibbles.as_slice();
+        let mut node = &self.root;
+        let mut proof = Vec::new();
+        loop {
+            match node {
+                TrieNode::Empty | TrieNode::Hash(_) => break,
+                TrieNode::Leaf(..) => {
+                    proof.push(encode_node(node).into());
+                    break;
+                }
+                TrieNode::Extension(key, child) => {
+                    proof.push(encode_node(node).into());
+                    if !path.starts_with(key) {
+                        break;
+                    }
+                    path = &path[key.len()..];
+                    node = child;
+                }
+                TrieNode::Branch(children) => {
+                    proof.push(encode_node(node).into());
+                    proof.extend(
+                        children
+                            .iter()
+                            .enumerate()
+                            .filter(|(idx, child)| {
+                                *idx != path[0] as usize
+                                    && !matches!(child, TrieNode::Empty | TrieNode::Hash(_))
+                            })
+                            .map(|(_, child)| encode_node(child).into()),
+                    );
+                    node = &children[path[0] as usize];
+                    path = &path[1..];
+                }
+            }
+        }
+        proof
+    }
+
+    fn resolve(&self, node: TrieNode) -> Result<TrieNode, StateRootError> {
+        match node {
+            TrieNode::Hash(hash) => {
+                let encoded = self
+                    .nodes
+                    .get(&hash)
+                    .ok_or(StateRootError::MissingTrieNode(hash))?;
+                decode_node(encoded).map_err(StateRootError::InvalidTrieNode)
+            }
+            node => Ok(node),
+        }
+    }
+
+    fn insert_at(
+        &self,
+        node: TrieNode,
+        path: &[u8],
+        value: Vec<u8>,
+    ) -> Result<TrieNode, StateRootError> {
+        Ok(match self.resolve(node)? {
+            TrieNode::Empty => TrieNode::Leaf(path.to_vec(), value),
+            TrieNode::Leaf(key, prev_value) => {
+                if key == path {
+                    return Ok(TrieNode::Leaf(key, value));
+                }
+                // Keys have the same length so they must diverge before the end.
+                let common = common_prefix_length(&key, path);
+                let mut children = empty_children();
+                children[key[common] as usize] =
+                    TrieNode::Leaf(key[common + 1..].to_vec(), prev_value);
+                children[path[common] as usize] =
+                    TrieNode::Leaf(path[common + 1..].to_vec(), value);
+                with_extension(path[..common].to_vec(), TrieNode::Branch(children))
+            }
+            TrieNode::Extension(key, child) => {
+                let common = common_prefix_length(&key, path);
+                if common == key.len() {
+                    let child = self.insert_at(*child, &path[common..], value)?;
+                    return Ok(TrieNode::Extension(key, Box::new(child)));
+                }
+                let mut children = empty_children();
+                children[key[common] as usize] = with_extension(key[common + 1..].to_vec(), *child);
+                children[path[common] as usize] =
+                    TrieNode::Leaf(path[common + 1..].to_vec(), value);
+                with_extension(path[..common].to_vec(), TrieNode::Branch(children))
+            }
+            TrieNode::Branch(mut children) => {
+                let idx = path[0] as usize;
+                let child = mem::replace(&mut children[idx], TrieNode::Empty);
+                children[idx] = self.insert_at(child, &path[1..], value)?;
+                TrieNode::Branch(children)
+            }
+            TrieNode::Hash(_) => unreachable!("Resolved nodes are never hashes"),
+        })
+    }
+
+    fn remove_at(&self, node: TrieNode, path: &[u8]) -> Result<TrieNode, StateRootError> {
+        Ok(match self.resolve(node)? {
+            TrieNode::Empty => TrieNode::Empty,
+            TrieNode::Leaf(key, value) => {
+                if key == path {
+                    TrieNode::Empty
+                } else {
+                    TrieNode::Leaf(key, value)
+                }
+            }
+            TrieNode::Extension(key, child) => {
+                if !path.starts_with(&key) {
+                    return Ok(TrieNode::Extension(key, child));
+                }
+                let child = self.remove_at(*child, &path[key.len()..])?;
+                self.join(key, child)?
+            }
+            TrieNode::Branch(mut children) => {
+                let idx = path[0] as usize;
+                let child = mem::replace(&mut children[idx], TrieNode::Empty);
+                children[idx] = self.remove_at(child, &path[1..])?;
+                let mut remaining = children
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, child)| !matches!(child, TrieNode::Empty))
+                    .map(|(idx, _)| idx);
+                match (remaining.next(), remaining.next()) {
+                    (None, _) => TrieNode::Empty,
+                    // Collapse a branch with only one child left.
+                    (Some(only_idx), None) => {
+                        let only_child = mem::replace(&mut children[only_idx], TrieNode::Empty);
+                        self.join(vec![only_idx as u8], only_child)?
+                    }
+                    _ => TrieNode::Branch(children),
+                }
+            }
+            TrieNode::Hash(_) => unreachable!("Resolved nodes are never hashes"),
+        })
+    }
+
+    // Prepend a path to a node, merging it into the node's own path if any.
+    fn join(&self, prefix: Vec<u8>, node: TrieNode) -> Result<TrieNode, StateRootError> {
+        Ok(match self.resolve(node)? {
+            TrieNode::Empty => TrieNode::Empty,
+            TrieNode::Leaf(key, value) => TrieNode::Leaf([prefix, key].concat(), value),
+            TrieNode::Extension(key, child) => TrieNode::Extension([prefix, key].concat(), child),
+            branch => TrieNode::Extension(prefix, Box::new(branch)),
+        })
+    }
+}
+
+// Verify the Merkle proof of a key against a trie root, returning the proven
+// value, or [None] if the proof shows that the key is not in the trie. Nodes
+// are only followed by their hashes from the root, so a proof that doesn't
+// match the root misses a node.
+pub(crate) fn verify_proof(
+    root: B256,
+    key: &B256,
+    proof: &[Bytes],
+) -> Result<Option<Vec<u8>>, StateRootError> {
+    let mut trie = SparseTrie::new(root);
+    trie.reveal(proof);
+    let nibbles = to_nibbles(key);
+    let mut path = nibbles.as_slice();
+    let mut node = mem::replace(&mut trie.root, TrieNode::Empty);
+    loop {
+        match trie.resolve(node)? {
+            TrieNode::Empty => return Ok(None),
+            TrieNode::Leaf(key, value) => return Ok((key == path).then_some(value)),
+            TrieNode::Extension(key, child) => {
+                if !path.starts_with(&key) {
+                    return Ok(None);
+                }
+                path = &path[key.len()..];
+                node = *child;
+            }
+            TrieNode::Branch(mut children) => {
+                node = mem::replace(&mut children[path[0] as usize], TrieNode::Empty);
+                path = &path[1..];
+            }
+            TrieNode::Hash(_) => unreachable!("Resolved nodes are never hashes"),
+        }
+    }
+}
+
+// Build a fully revealed trie from all of its entries.
+pub(crate) fn build_trie(entries: impl IntoIterator<Item = (B256, Vec<u8>)>) -> SparseTrie {
+    let mut trie = SparseTrie::default();
+    for (key, value) in entries {
+        // A trie built from scratch has no unrevealed nodes to miss.
+        trie.insert(&key, value).unwrap();
+    }
+    trie
+}
+
+fn empty_children() -> Box<[TrieNode; 16]> {
+    Box::new(std::array::from_fn(|_| TrieNode::Empty))
+}
+
+fn with_extension(prefix: Vec<u8>, node: TrieNode) -> TrieNode {
+    if prefix.is_empty() {
+        node
+    } else {
+        TrieNode::Extension(prefix, Box::new(node))
+    }
+}
+
+fn common_prefix_length(a: &[u8], b: &[u8]) -> usize {
+    a.iter().zip(b).take_while(|(a, b)| a == b).count()
+}
+
+fn to_nibbles(key: &B256) -> Vec<u8> {
+    key.iter()
+        .flat_map(|byte| [byte >> 4, byte & 0x0f])
+        .collect()
+}
+
+// Hex-prefix encoding of a path and whether it is of a leaf.
+fn encode_path(nibbles: &[u8], is_leaf: bool) -> Vec<u8> {
+    let flag = if is_leaf { 0x20 } else { 0x00 };
+    let mut encoded = Vec::with_capacity(nibbles.len() / 2 + 1);
+    let rest = if nibbles.len() % 2 == 1 {
+        encoded.push(flag | 0x10 | nibbles[0]);
+        &nibbles[1..]
+    } else {
+        encoded.push(flag);
+        nibbles
+    };
+    encoded.extend(rest.chunks_exact(2).map(|pair| (pair[0] << 4) | pair[1]));
+    encoded
+}
+
+fn decode_path(encoded: &[u8]) -> alloy_rlp::Result<(Vec<u8>, bool)> {
+    let first = *encoded.first().ok_or(alloy_rlp::Error::InputTooShort)?;
+    let flag = first >> 4;
+    if flag > 3 {
+        return Err(alloy_rlp::Error::Custom("invalid hex-prefix path"));
+    }
+    let mut nibbles = Vec::with_capacity(encoded.len() * 2);
+    if flag & 1 == 1 {
+        nibbles.push(first & 0x0f);
+    }
+    for byte in &encoded[1..] {
+        nibbles.push(byte >> 4);
+        nibbles.push(byte & 0x0f);
+    }
+    Ok((nibbles, flag & 2 == 2))
+}
+
+fn encode_bytes(bytes: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(bytes.len() + 1);
+    bytes.encode(&mut out);
+    out
+}
+
+fn encode_list(items: &[Vec<u8>]) -> Vec<u8> {
+    let payload_length = items.iter().map(Vec::len).sum();
+    let mut out = Vec::with_capacity(payload_length + 3);
+    Header {
+        list: true,
+        payload_length,
+    }
+    .encode(&mut out);
+    for item in items {
+        out.extend_from_slice(item);
+    }
+    out
+}
+
+// Encode a node as referenced by its parent: inlined if its encoding is
+// shorter than 32 bytes, else by its hash.
+fn encode_child(node: &TrieNode) -> Vec<u8> {
+    match node {
+        TrieNode::Empty => vec![EMPTY_STRING_CODE],
+        TrieNode::Hash(hash) => encode_bytes(hash.as_slice()),
+        node => {
+            let encoded = encode_node(node);
+            if encoded.len() < 32 {
+                encoded
+            } else {
+                encode_bytes(keccak256(&encoded).as_slice())
+            }
+        }
+    }
+}
+
+fn encode_node(node: &TrieNode) -> Vec<u8> {
+    match node {
+        TrieNode::Empty => vec![EMPTY_STRING_CODE],
+        TrieNode::Hash(hash) => encode_bytes(hash.as_slice()),
+        TrieNode::Leaf(key, value) => {
+            encode_list(&[encode_bytes(&encode_path(key, true)), encode_bytes(value)])
+        }
+        TrieNode::Extension(key, child) => {
+            encode_list(&[encode_bytes(&encode_path(key, false)), encode_child(child)])
+        }
+        TrieNode::Branch(children) => {
+            let mut items: Vec<Vec<u8>> = children.iter().map(encode_child).collect();
+            // The empty branch value
+            items.push(vec![EMPTY_STRING_CODE]);
+            encode_list(&items)
+        }
+    }
+}
+
+// Split the next RLP item, including its header, off a buffer.
+fn split_item<'a>(buf: &mut &'a [u8]) -> alloy_rlp::Result<&'a [u8]> {
+    let mut payload = *buf;
+    let header = Header::decode(&mut payload)?;
+    let length = buf.len() - payload.len() + header.payload_length;
+    if buf.len() < length {
+        return Err(alloy_rlp::Error::InputTooShort);
+    }
+    let (item, rest) = buf.split_at(length);
+    *buf = rest;
+    Ok(item)
+}
+
+fn decode_node(mut buf: &[u8]) -> alloy_rlp::Result<TrieNode> {
+    let mut payload = Header::decode_bytes(&mut buf, true)?;
+    let mut items = Vec::with_capacity(17);
+    while !payload.is_empty() {
+        items.push(split_item(&mut payload)?);
+    }
+    match items.len() {
+        17 => {
+            let mut children = empty_children();
+            for (child, item) in children.iter_mut().zip(items) {
+                *child = decode_child(item)?;
+            }
+            Ok(TrieNode::Branch(children))
+        }
+        2 => {
+            let (key, is_leaf) = decode_path(Header::decode_bytes(&mut items[0], false)?)?;
+            if is_leaf {
+                let value = Header::decode_bytes(&mut items[1], false)?;
+                Ok(TrieNode::Leaf(key, value.to_vec()))
+            } else {
+                Ok(TrieNode::Extension(key, Box::new(decode_child(items[1])?)))
+            }
+        }
+        _ => Err(alloy_rlp::Error::Custom("invalid trie node")),
+    }
+}
+
+fn decode_child(mut item: &[u8]) -> alloy_rlp::Result<TrieNode> {
+    if item == [EMPTY_STRING_CODE] {
+        return Ok(TrieNode::Empty);
+    }
+    let header = Header::decode(&mut &item[..])?;
+    if header.list {
+        // Inlined nodes
+        decode_node(item)
+    } else {
+        let hash = Header::decode_bytes(&mut item, false)?;
+        if hash.len() != 32 {
+            return Err(alloy_rlp::Error::UnexpectedLength);
+        }
+        Ok(TrieNode::Hash(B256::from_slice(hash)))
+    }
+}
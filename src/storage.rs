@@ -1,8 +1,15 @@
-use std::{fmt::Display, sync::Arc};
+use std::{
+    any::Any,
+    fmt::{self, Display},
+    future::Future,
+    sync::Arc,
+};
 
 use ahash::AHashMap;
 use alloy_primitives::{Address, Bytes, B256, U256};
 use bitvec::vec::BitVec;
+#[cfg(feature = "eof")]
+use revm::primitives::{Eof, EOF_MAGIC_BYTES};
 use revm::{
     interpreter::analysis::to_analysed,
     primitives::{Account, AccountInfo, Bytecode, JumpTable, KECCAK_EMPTY},
@@ -63,8 +70,9 @@ impl Default for AccountBasic {
     }
 }
 
-/// EVM Code, currently mapping to REVM's [ByteCode::LegacyAnalyzed].
-// TODO: Support raw legacy & EOF
+/// EVM Code, currently mapping to REVM's [ByteCode::LegacyAnalyzed], or to
+/// [ByteCode::Eof] for raw EOF containers with the `eof` feature.
+// TODO: Support raw legacy
 #[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
 pub struct EvmCode {
     /// Bytecode with 32 zero bytes padding
@@ -77,6 +85,18 @@ pub struct EvmCode {
 
 impl From<EvmCode> for Bytecode {
     fn from(code: EvmCode) -> Self {
+        // EOF containers are stored raw, with the magic prefix that no
+        // legacy contract can start with since EIP-3541. Codes with the
+        // prefix that are not valid containers are analysed again as legacy.
+        #[cfg(feature = "eof")]
+        if code.bytecode.starts_with(&EOF_MAGIC_BYTES) {
+            return match Eof::decode(code.bytecode.clone()) {
+                Ok(eof) => Bytecode::Eof(eof.into()),
+                Err(_) => to_analysed(Bytecode::LegacyRaw(
+                    code.bytecode.slice(..code.original_len),
+                )),
+            };
+        }
         // TODO: Better error handling.
         // A common trap would be converting a default [EvmCode] into
         // a [Bytecode]. On failure we should fallback to legacy and
@@ -96,9 +116,54 @@ impl From<Bytecode> for EvmCode {
                 original_len: code.original_len,
                 jump_table: code.jump_table.0,
             },
-            Bytecode::Eof(_) => unimplemented!("TODO: Support EOF"),
+            #[cfg(feature = "eof")]
+            Bytecode::Eof(eof) => EvmCode {
+                bytecode: eof.raw.clone(),
+                original_len: eof.raw.len(),
+                jump_table: Arc::default(),
+            },
+            #[cfg(not(feature = "eof"))]
+            Bytecode::Eof(_) => unimplemented!("EOF requires the eof feature"),
+        }
+    }
+}
+
+/// An error from a [Storage] backend. It keeps the backend's own error so
+/// callers can tell transient IO failures to retry from permanent ones.
+#[derive(Debug, Clone)]
+pub struct StorageError {
+    message: String,
+    error: Arc<dyn Any + Send + Sync>,
+}
+
+impl StorageError {
+    /// Wrap an error of a storage backend.
+    pub fn new<E: Display + Send + Sync + 'static>(error: E) -> Self {
+        Self {
+            message: error.to_string(),
+            error: Arc::new(error),
         }
     }
+
+    /// Get the backend's own error if it is of type [E].
+    pub fn downcast_ref<E: 'static>(&self) -> Option<&E> {
+        self.error.downcast_ref::<E>()
+    }
+}
+
+impl Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for StorageError {}
+
+// Backend error types may not be comparable, so we compare their messages.
+impl PartialEq for StorageError {
+    fn eq(&self, other: &Self) -> bool {
+        self.message == other.message
+    }
 }
 
 /// Mapping between code hashes and [EvmCode] values
@@ -110,7 +175,7 @@ pub type Bytecodes = AHashMap<B256, EvmCode>;
 /// TODO: Better API for third-party integration.
 pub trait Storage {
     /// Errors when querying data from storage.
-    type Error: Display;
+    type Error: Display + Send + Sync + 'static;
 
     /// Get basic account information.
     fn basic(&self, address: &Address) -> Result<Option<AccountBasic>, Self::Error>;
@@ -129,20 +194,129 @@ pub trait Storage {
 
     /// Get block hash by block number.
     fn block_hash(&self, number: &u64) -> Result<B256, Self::Error>;
+
+    /// Get basic account information of many accounts at once. Override
+    /// this for backends that can batch lookups, like over RPC.
+    fn basic_many(&self, addresses: &[Address]) -> Result<Vec<Option<AccountBasic>>, Self::Error> {
+        addresses
+            .iter()
+            .map(|address| self.basic(address))
+            .collect()
+    }
+
+    /// Get account codes of many code hashes at once.
+    fn code_by_hash_many(&self, code_hashes: &[B256]) -> Result<Vec<Option<EvmCode>>, Self::Error> {
+        code_hashes
+            .iter()
+            .map(|code_hash| self.code_by_hash(code_hash))
+            .collect()
+    }
+
+    /// Get storage values of many (address, index) slots at once.
+    fn storage_many(&self, slots: &[(Address, U256)]) -> Result<Vec<U256>, Self::Error> {
+        slots
+            .iter()
+            .map(|(address, index)| self.storage(address, index))
+            .collect()
+    }
 }
 
-// We can use any REVM database as storage provider. Convenient for
-// testing blocks fetched from RPC via REVM's [CachedDB]. Otherwise, use
-// our [Storage] types to avoid redundant conversions.
-// TODO: Do something equivalent to [CachedDB] ourselves and remove this.
-impl<D: DatabaseRef> Storage for D
+// Borrowed storages, like to layer an [OverlayStorage] on a storage that
+// callers keep using.
+impl<S: Storage + ?Sized> Storage for &S {
+    type Error = S::Error;
+
+    fn basic(&self, address: &Address) -> Result<Option<AccountBasic>, Self::Error> {
+        (**self).basic(address)
+    }
+
+    fn code_hash(&self, address: &Address) -> Result<Option<B256>, Self::Error> {
+        (**self).code_hash(address)
+    }
+
+    fn code_by_hash(&self, code_hash: &B256) -> Result<Option<EvmCode>, Self::Error> {
+        (**self).code_by_hash(code_hash)
+    }
+
+    fn has_storage(&self, address: &Address) -> Result<bool, Self::Error> {
+        (**self).has_storage(address)
+    }
+
+    fn storage(&self, address: &Address, index: &U256) -> Result<U256, Self::Error> {
+        (**self).storage(address, index)
+    }
+
+    fn block_hash(&self, number: &u64) -> Result<B256, Self::Error> {
+        (**self).block_hash(number)
+    }
+
+    fn basic_many(&self, addresses: &[Address]) -> Result<Vec<Option<AccountBasic>>, Self::Error> {
+        (**self).basic_many(addresses)
+    }
+
+    fn code_by_hash_many(&self, code_hashes: &[B256]) -> Result<Vec<Option<EvmCode>>, Self::Error> {
+        (**self).code_by_hash_many(code_hashes)
+    }
+
+    fn storage_many(&self, slots: &[(Address, U256)]) -> Result<Vec<U256>, Self::Error> {
+        (**self).storage_many(slots)
+    }
+}
+
+/// An asynchronous version of [Storage] for network-backed state, like via
+/// RPC. Execution threads are synchronous, so use [AsyncStorageBridge] to
+/// drive these futures on a dedicated IO runtime.
+pub trait AsyncStorage {
+    /// Errors when querying data from storage.
+    type Error: Display + Send + Sync + 'static;
+
+    /// Get basic account information.
+    fn basic(
+        &self,
+        address: &Address,
+    ) -> impl Future<Output = Result<Option<AccountBasic>, Self::Error>>;
+
+    /// Get the code of an account.
+    fn code_hash(
+        &self,
+        address: &Address,
+    ) -> impl Future<Output = Result<Option<B256>, Self::Error>>;
+
+    /// Get account code by its hash.
+    fn code_by_hash(
+        &self,
+        code_hash: &B256,
+    ) -> impl Future<Output = Result<Option<EvmCode>, Self::Error>>;
+
+    /// Get if the account already has storage (to support EIP-7610).
+    fn has_storage(&self, address: &Address) -> impl Future<Output = Result<bool, Self::Error>>;
+
+    /// Get storage value of address at index.
+    fn storage(
+        &self,
+        address: &Address,
+        index: &U256,
+    ) -> impl Future<Output = Result<U256, Self::Error>>;
+
+    /// Get block hash by block number.
+    fn block_hash(&self, number: &u64) -> impl Future<Output = Result<B256, Self::Error>>;
+}
+
+/// An adapter to use any REVM database as a [Storage], like an existing
+/// [revm::db::CacheDB] or a node's state provider, the reverse of
+/// [StorageWrapper]. Prefer our [Storage] types when possible to avoid
+/// redundant conversions.
+#[derive(Debug, Clone)]
+pub struct DatabaseAsStorage<D: DatabaseRef>(pub D);
+
+impl<D: DatabaseRef> Storage for DatabaseAsStorage<D>
 where
-    D::Error: Display,
+    D::Error: Display + Send + Sync + 'static,
 {
     type Error = D::Error;
 
     fn basic(&self, address: &Address) -> Result<Option<AccountBasic>, Self::Error> {
-        self.basic_ref(*address).map(|a| {
+        self.0.basic_ref(*address).map(|a| {
             a.map(|info| AccountBasic {
                 balance: info.balance,
                 nonce: info.nonce,
@@ -151,13 +325,13 @@ where
     }
 
     fn code_hash(&self, address: &Address) -> Result<Option<B256>, Self::Error> {
-        self.basic_ref(*address).map(|info| {
+        self.0.basic_ref(*address).map(|info| {
             info.and_then(|info| (!info.is_empty_code_hash()).then_some(info.code_hash))
         })
     }
 
     fn code_by_hash(&self, code_hash: &B256) -> Result<Option<EvmCode>, Self::Error> {
-        self.code_by_hash_ref(*code_hash).map(|bytecode| {
+        self.0.code_by_hash_ref(*code_hash).map(|bytecode| {
             if bytecode.is_empty() {
                 None
             } else {
@@ -167,15 +341,15 @@ where
     }
 
     fn has_storage(&self, address: &Address) -> Result<bool, Self::Error> {
-        self.has_storage_ref(*address)
+        self.0.has_storage_ref(*address)
     }
 
     fn storage(&self, address: &Address, index: &U256) -> Result<U256, Self::Error> {
-        self.storage_ref(*address, *index)
+        self.0.storage_ref(*address, *index)
     }
 
     fn block_hash(&self, number: &u64) -> Result<B256, Self::Error> {
-        self.block_hash_ref(*number)
+        self.0.block_hash_ref(*number)
     }
 }
 
@@ -225,7 +399,23 @@ impl<'a, S: Storage> DatabaseRef for StorageWrapper<'a, S> {
     }
 }
 
+mod async_bridge;
+pub use async_bridge::AsyncStorageBridge;
+mod cached;
+pub use cached::{CachedStorage, LruTier};
+mod committed;
+pub use committed::CommittedStorage;
 mod in_memory;
 pub use in_memory::InMemoryStorage;
+mod instrumented;
+pub use instrumented::{InstrumentedStorage, MethodMetrics, StorageMetrics, LATENCY_BUCKETS};
+mod mmap;
+pub use mmap::MmapStorage;
+mod overlay;
+pub use overlay::{AccountOverride, OverlayStorage, StateOverrides};
 mod rpc;
 pub use rpc::RpcStorage;
+mod tiered;
+pub use tiered::{MemoryTier, StorageTier, TieredStorage};
+mod witness;
+pub use witness::{ExecutionWitness, WitnessStorage};
@@ -0,0 +1,102 @@
+use std::sync::Arc;
+
+use alloy_primitives::{Address, B256, U256};
+use futures::future::try_join_all;
+use tokio::runtime::{Builder, Handle, Runtime};
+
+use super::{AsyncStorage, EvmCode};
+use crate::{AccountBasic, Storage};
+
+/// A [Storage] that blocks on the futures of an [AsyncStorage] with a
+/// dedicated IO runtime. Execution worker threads only wait for their own
+/// reads while the runtime keeps driving the IO of all pending requests.
+/// Note that [Storage] methods must not be called from within an async
+/// context, like on the runtime's own threads.
+#[derive(Debug, Clone)]
+pub struct AsyncStorageBridge<S> {
+    storage: S,
+    handle: Handle,
+    // Keep the owned runtime alive as long as its handle.
+    _runtime: Option<Arc<Runtime>>,
+}
+
+impl<S> AsyncStorageBridge<S> {
+    /// Bridge an async storage with a new multi-threaded IO runtime.
+    pub fn new(storage: S) -> std::io::Result<Self> {
+        let runtime = Builder::new_multi_thread()
+            .thread_name("pevm-io")
+            .enable_all()
+            .build()?;
+        Ok(Self {
+            storage,
+            handle: runtime.handle().clone(),
+            _runtime: Some(Arc::new(runtime)),
+        })
+    }
+
+    /// Bridge an async storage with the handle of an existing runtime.
+    pub fn with_handle(storage: S, handle: Handle) -> Self {
+        Self {
+            storage,
+            handle,
+            _runtime: None,
+        }
+    }
+
+    /// Get the underlying async storage.
+    pub fn inner(&self) -> &S {
+        &self.storage
+    }
+}
+
+impl<S: AsyncStorage> Storage for AsyncStorageBridge<S> {
+    type Error = S::Error;
+
+    fn basic(&self, address: &Address) -> Result<Option<AccountBasic>, Self::Error> {
+        self.handle.block_on(self.storage.basic(address))
+    }
+
+    fn code_hash(&self, address: &Address) -> Result<Option<B256>, Self::Error> {
+        self.handle.block_on(self.storage.code_hash(address))
+    }
+
+    fn code_by_hash(&self, code_hash: &B256) -> Result<Option<EvmCode>, Self::Error> {
+        self.handle.block_on(self.storage.code_by_hash(code_hash))
+    }
+
+    fn has_storage(&self, address: &Address) -> Result<bool, Self::Error> {
+        self.handle.block_on(self.storage.has_storage(address))
+    }
+
+    fn storage(&self, address: &Address, index: &U256) -> Result<U256, Self::Error> {
+        self.handle.block_on(self.storage.storage(address, index))
+    }
+
+    fn block_hash(&self, number: &u64) -> Result<B256, Self::Error> {
+        self.handle.block_on(self.storage.block_hash(number))
+    }
+
+    // Drive the lookups of a batch concurrently.
+
+    fn basic_many(&self, addresses: &[Address]) -> Result<Vec<Option<AccountBasic>>, Self::Error> {
+        self.handle.block_on(try_join_all(
+            addresses.iter().map(|address| self.storage.basic(address)),
+        ))
+    }
+
+    fn code_by_hash_many(&self, code_hashes: &[B256]) -> Result<Vec<Option<EvmCode>>, Self::Error> {
+        self.handle.block_on(try_join_all(
+            code_hashes
+                .iter()
+                .map(|code_hash| self.storage.code_by_hash(code_hash)),
+        ))
+    }
+
+    fn storage_many(&self, slots: &[(Address, U256)]) -> Result<Vec<U256>, Self::Error> {
+        self.handle.block_on(try_join_all(
+            slots
+                .iter()
+                .map(|(address, index)| self.storage.storage(address, index)),
+        ))
+    }
+}
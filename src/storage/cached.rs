@@ -0,0 +1,103 @@
+use std::{hash::Hash, num::NonZeroUsize, sync::Mutex};
+
+use alloy_primitives::{Address, B256, U256};
+use lru::LruCache;
+
+use super::{EvmCode, StorageTier, TieredStorage};
+use crate::AccountBasic;
+
+/// A [Storage] decorator with bounded LRU caches, for long-running
+/// processes that can't cache all state they have read.
+pub type CachedStorage<S> = TieredStorage<LruTier, S>;
+
+impl<S> CachedStorage<S> {
+    /// Wrap a storage with LRU caches of a capacity each, for account
+    /// basics, code hashes, bytecodes, storage slots, and block hashes.
+    pub fn with_capacity(storage: S, capacity: NonZeroUsize) -> Self {
+        TieredStorage::new(LruTier::new(capacity), storage)
+    }
+}
+
+/// A [StorageTier] of bounded LRU caches.
+// TODO: Shard the caches if the locks get contended.
+#[derive(Debug)]
+pub struct LruTier {
+    basics: Mutex<LruCache<Address, Option<AccountBasic>>>,
+    code_hashes: Mutex<LruCache<Address, Option<B256>>>,
+    bytecodes: Mutex<LruCache<B256, Option<EvmCode>>>,
+    has_storages: Mutex<LruCache<Address, bool>>,
+    storage: Mutex<LruCache<(Address, U256), U256>>,
+    block_hashes: Mutex<LruCache<u64, B256>>,
+}
+
+impl LruTier {
+    /// Construct a new [LruTier] with a capacity for each cache.
+    pub fn new(capacity: NonZeroUsize) -> Self {
+        Self {
+            basics: Mutex::new(LruCache::new(capacity)),
+            code_hashes: Mutex::new(LruCache::new(capacity)),
+            bytecodes: Mutex::new(LruCache::new(capacity)),
+            has_storages: Mutex::new(LruCache::new(capacity)),
+            storage: Mutex::new(LruCache::new(capacity)),
+            block_hashes: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+}
+
+fn get<K: Hash + Eq, V: Clone>(cache: &Mutex<LruCache<K, V>>, key: &K) -> Option<V> {
+    cache.lock().unwrap().get(key).cloned()
+}
+
+fn put<K: Hash + Eq, V>(cache: &Mutex<LruCache<K, V>>, key: K, value: V) {
+    cache.lock().unwrap().put(key, value);
+}
+
+impl StorageTier for LruTier {
+    fn cached_basic(&self, address: &Address) -> Option<Option<AccountBasic>> {
+        get(&self.basics, address)
+    }
+
+    fn cached_code_hash(&self, address: &Address) -> Option<Option<B256>> {
+        get(&self.code_hashes, address)
+    }
+
+    fn cached_code_by_hash(&self, code_hash: &B256) -> Option<Option<EvmCode>> {
+        get(&self.bytecodes, code_hash)
+    }
+
+    fn cached_has_storage(&self, address: &Address) -> Option<bool> {
+        get(&self.has_storages, address)
+    }
+
+    fn cached_storage(&self, address: &Address, index: &U256) -> Option<U256> {
+        get(&self.storage, &(*address, *index))
+    }
+
+    fn cached_block_hash(&self, number: &u64) -> Option<B256> {
+        get(&self.block_hashes, number)
+    }
+
+    fn fill_basic(&self, address: Address, basic: Option<AccountBasic>) {
+        put(&self.basics, address, basic);
+    }
+
+    fn fill_code_hash(&self, address: Address, code_hash: Option<B256>) {
+        put(&self.code_hashes, address, code_hash);
+    }
+
+    fn fill_code_by_hash(&self, code_hash: B256, code: Option<EvmCode>) {
+        put(&self.bytecodes, code_hash, code);
+    }
+
+    fn fill_has_storage(&self, address: Address, has_storage: bool) {
+        put(&self.has_storages, address, has_storage);
+    }
+
+    fn fill_storage(&self, address: Address, index: U256, value: U256) {
+        put(&self.storage, (address, index), value);
+    }
+
+    fn fill_block_hash(&self, number: u64, block_hash: B256) {
+        put(&self.block_hashes, number, block_hash);
+    }
+}
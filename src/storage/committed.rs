@@ -0,0 +1,174 @@
+use ahash::AHashMap;
+use alloy_primitives::{Address, B256, U256};
+
+use super::{Bytecodes, EvmCode};
+use crate::{
+    AccountBasic, EvmAccount, EvmStateTransitions, PevmBlockExecutionResult, PevmTxExecutionResult,
+    Storage,
+};
+
+// An account changed by committed transactions.
+#[derive(Debug, Clone)]
+struct CommittedAccount {
+    // [None] for self-destructed accounts.
+    account: Option<EvmAccount>,
+    // Whether the account was self-destructed before its latest state, so
+    // the underlying storage's slots are gone.
+    storage_cleared: bool,
+}
+
+/// A storage that layers the state transitions of committed transactions
+/// on top of another storage, to execute later transactions of the same
+/// block without re-executing the earlier ones.
+#[derive(Debug, Clone)]
+pub struct CommittedStorage<'a, S> {
+    storage: &'a S,
+    accounts: AHashMap<Address, CommittedAccount>,
+    bytecodes: Bytecodes,
+    block_hashes: AHashMap<u64, B256>,
+}
+
+impl<'a, S> CommittedStorage<'a, S> {
+    /// Construct a new [CommittedStorage] with the state transitions of
+    /// these execution results, in order.
+    pub fn new(storage: &'a S, tx_results: &[PevmTxExecutionResult]) -> Self {
+        let mut committed_storage = Self {
+            storage,
+            accounts: AHashMap::default(),
+            bytecodes: Bytecodes::default(),
+            block_hashes: AHashMap::default(),
+        };
+        for tx_result in tx_results {
+            committed_storage.commit(&tx_result.state);
+        }
+        committed_storage
+    }
+
+    /// Layer the state transitions of one more transaction.
+    pub fn commit(&mut self, state: &EvmStateTransitions) {
+        for (address, account) in state {
+            let Some(account) = account else {
+                self.accounts.insert(
+                    *address,
+                    CommittedAccount {
+                        account: None,
+                        storage_cleared: true,
+                    },
+                );
+                continue;
+            };
+            if let (Some(code_hash), Some(code)) = (account.code_hash, &account.code) {
+                self.bytecodes
+                    .entry(code_hash)
+                    .or_insert_with(|| code.clone());
+            }
+            let committed_account =
+                self.accounts
+                    .entry(*address)
+                    .or_insert_with(|| CommittedAccount {
+                        account: None,
+                        storage_cleared: false,
+                    });
+            match &mut committed_account.account {
+                Some(committed) => {
+                    committed.balance = account.balance;
+                    committed.nonce = account.nonce;
+                    committed.code_hash = account.code_hash;
+                    committed.code.clone_from(&account.code);
+                    // Transitions only include the changed slots.
+                    committed
+                        .storage
+                        .extend(account.storage.iter().map(|(slot, value)| (*slot, *value)));
+                }
+                None => committed_account.account = Some(account.clone()),
+            }
+        }
+    }
+
+    /// Layer the state transitions of a whole block.
+    pub fn commit_block(&mut self, block_result: &PevmBlockExecutionResult) {
+        self.commit(&block_result.pre_block_state);
+        for tx_result in &block_result.tx_results {
+            self.commit(&tx_result.state);
+        }
+        self.commit(&block_result.post_block_state);
+    }
+
+    /// Layer the hash of a committed block, for later blocks to read.
+    pub fn commit_block_hash(&mut self, number: u64, hash: B256) {
+        self.block_hashes.insert(number, hash);
+    }
+
+    /// Get the underlying storage.
+    pub fn inner(&self) -> &S {
+        self.storage
+    }
+}
+
+impl<'a, S: Storage> Storage for CommittedStorage<'a, S> {
+    type Error = S::Error;
+
+    fn basic(&self, address: &Address) -> Result<Option<AccountBasic>, Self::Error> {
+        match self.accounts.get(address) {
+            Some(committed) => Ok(committed.account.as_ref().map(|account| AccountBasic {
+                balance: account.balance,
+                nonce: account.nonce,
+            })),
+            None => self.storage.basic(address),
+        }
+    }
+
+    fn code_hash(&self, address: &Address) -> Result<Option<B256>, Self::Error> {
+        match self.accounts.get(address) {
+            Some(committed) => Ok(committed
+                .account
+                .as_ref()
+                .and_then(|account| account.code_hash)),
+            None => self.storage.code_hash(address),
+        }
+    }
+
+    fn code_by_hash(&self, code_hash: &B256) -> Result<Option<EvmCode>, Self::Error> {
+        match self.bytecodes.get(code_hash) {
+            Some(code) => Ok(Some(code.clone())),
+            None => self.storage.code_by_hash(code_hash),
+        }
+    }
+
+    fn has_storage(&self, address: &Address) -> Result<bool, Self::Error> {
+        let Some(committed) = self.accounts.get(address) else {
+            return self.storage.has_storage(address);
+        };
+        let Some(account) = &committed.account else {
+            return Ok(false);
+        };
+        if account.storage.values().any(|value| !value.is_zero()) {
+            return Ok(true);
+        }
+        if committed.storage_cleared {
+            return Ok(false);
+        }
+        self.storage.has_storage(address)
+    }
+
+    fn storage(&self, address: &Address, index: &U256) -> Result<U256, Self::Error> {
+        let Some(committed) = self.accounts.get(address) else {
+            return self.storage.storage(address, index);
+        };
+        let Some(account) = &committed.account else {
+            return Ok(U256::ZERO);
+        };
+        match account.storage.get(index) {
+            Some(value) => Ok(*value),
+            None if committed.storage_cleared => Ok(U256::ZERO),
+            None => self.storage.storage(address, index),
+        }
+    }
+
+    fn block_hash(&self, number: &u64) -> Result<B256, Self::Error> {
+        match self.block_hashes.get(number) {
+            Some(hash) => Ok(*hash),
+            None => self.storage.block_hash(number),
+        }
+    }
+}
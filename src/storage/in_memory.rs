@@ -3,8 +3,11 @@ use std::{collections::HashMap, fmt::Debug};
 use ahash::AHashMap;
 use alloy_primitives::{keccak256, Address, B256, U256};
 
-use super::{Bytecodes, EvmCode};
-use crate::{AccountBasic, BuildAddressHasher, EvmAccount, Storage};
+use super::{Bytecodes, EvmCode, StorageTier};
+use crate::{
+    AccountBasic, BuildAddressHasher, EvmAccount, EvmStateTransitions, PevmBlockExecutionResult,
+    PevmTxExecutionResult, Storage,
+};
 
 type Accounts = HashMap<Address, EvmAccount, BuildAddressHasher>;
 
@@ -13,6 +16,8 @@ type Accounts = HashMap<Address, EvmAccount, BuildAddressHasher>;
 pub struct InMemoryStorage<'a> {
     accounts: Accounts,
     bytecodes: Option<&'a Bytecodes>,
+    // Codes deployed by applied results, as the shared bytecodes are borrowed.
+    new_bytecodes: Bytecodes,
     block_hashes: AHashMap<u64, B256>,
 }
 
@@ -26,9 +31,55 @@ impl<'a> InMemoryStorage<'a> {
         InMemoryStorage {
             accounts: accounts.into_iter().collect(),
             bytecodes,
+            new_bytecodes: Bytecodes::default(),
             block_hashes: block_hashes.into_iter().collect(),
         }
     }
+
+    /// Apply the state transitions of executed transactions in order, to
+    /// advance the storage for the next block in multi-block replays.
+    pub fn apply(&mut self, results: &[PevmTxExecutionResult]) {
+        for result in results {
+            self.apply_state(&result.state);
+        }
+    }
+
+    /// Apply all state transitions of an executed block, including the
+    /// irregular ones before and after its transactions.
+    pub fn apply_block(&mut self, result: &PevmBlockExecutionResult) {
+        self.apply_state(&result.pre_block_state);
+        self.apply(&result.tx_results);
+        self.apply_state(&result.post_block_state);
+    }
+
+    fn apply_state(&mut self, state: &EvmStateTransitions) {
+        for (address, account) in state {
+            let Some(account) = account else {
+                self.accounts.remove(address);
+                continue;
+            };
+            if let (Some(code_hash), Some(code)) = (account.code_hash, &account.code) {
+                if self.code(&code_hash).is_none() {
+                    self.new_bytecodes.insert(code_hash, code.clone());
+                }
+            }
+            let stored_account = self.accounts.entry(*address).or_default();
+            stored_account.balance = account.balance;
+            stored_account.nonce = account.nonce;
+            stored_account.code_hash = account.code_hash;
+            // Transitions only include the changed slots.
+            stored_account
+                .storage
+                .extend(account.storage.iter().map(|(slot, value)| (*slot, *value)));
+            stored_account.storage.retain(|_, value| !value.is_zero());
+        }
+    }
+
+    fn code(&self, code_hash: &B256) -> Option<&EvmCode> {
+        self.bytecodes
+            .and_then(|bytecodes| bytecodes.get(code_hash))
+            .or_else(|| self.new_bytecodes.get(code_hash))
+    }
 }
 
 impl<'a> Storage for InMemoryStorage<'a> {
@@ -50,10 +101,7 @@ impl<'a> Storage for InMemoryStorage<'a> {
     }
 
     fn code_by_hash(&self, code_hash: &B256) -> Result<Option<EvmCode>, Self::Error> {
-        Ok(match self.bytecodes {
-            Some(bytecodes) => bytecodes.get(code_hash).cloned(),
-            None => None,
-        })
+        Ok(self.code(code_hash).cloned())
     }
 
     fn has_storage(&self, address: &Address) -> Result<bool, Self::Error> {
@@ -81,3 +129,94 @@ impl<'a> Storage for InMemoryStorage<'a> {
             .unwrap_or_else(|| keccak256(number.to_string().as_bytes())))
     }
 }
+
+// An in-memory snapshot as a read-only tier that misses on accounts, code
+// and slots it doesn't have, like caches persisted from [RpcStorage].
+impl<'a> StorageTier for InMemoryStorage<'a> {
+    fn cached_basic(&self, address: &Address) -> Option<Option<AccountBasic>> {
+        self.accounts.get(address).map(|account| {
+            Some(AccountBasic {
+                balance: account.balance,
+                nonce: account.nonce,
+            })
+        })
+    }
+
+    fn cached_code_hash(&self, address: &Address) -> Option<Option<B256>> {
+        self.accounts.get(address).map(|account| account.code_hash)
+    }
+
+    fn cached_code_by_hash(&self, code_hash: &B256) -> Option<Option<EvmCode>> {
+        self.code(code_hash).map(|code| Some(code.clone()))
+    }
+
+    fn cached_has_storage(&self, address: &Address) -> Option<bool> {
+        // Snapshots may only include some of an account's slots, so only
+        // accounts with storage are conclusive.
+        self.accounts
+            .get(address)
+            .filter(|account| !account.storage.is_empty())
+            .map(|_| true)
+    }
+
+    fn cached_storage(&self, address: &Address, index: &U256) -> Option<U256> {
+        self.accounts
+            .get(address)
+            .and_then(|account| account.storage.get(index))
+            .copied()
+    }
+
+    fn cached_block_hash(&self, number: &u64) -> Option<B256> {
+        self.block_hashes.get(number).copied()
+    }
+}
+
+// Rebuilding the tries on every call is slow but fine for testing.
+#[cfg(feature = "state-root")]
+impl<'a> crate::ProofStorage for InMemoryStorage<'a> {
+    fn state_root(&self) -> Result<B256, Self::Error> {
+        Ok(self.account_trie().root())
+    }
+
+    fn proof(&self, address: &Address, slots: &[U256]) -> Result<crate::AccountProof, Self::Error> {
+        let Some(account) = self.accounts.get(address) else {
+            return Ok(crate::AccountProof {
+                storage_root: alloy_trie::EMPTY_ROOT_HASH,
+                account_proof: self.account_trie().proof(&keccak256(address)),
+                storage_proofs: vec![Vec::new(); slots.len()],
+            });
+        };
+        let storage_trie = storage_trie(account);
+        Ok(crate::AccountProof {
+            storage_root: storage_trie.root(),
+            account_proof: self.account_trie().proof(&keccak256(address)),
+            storage_proofs: slots
+                .iter()
+                .map(|slot| storage_trie.proof(&crate::state_root::slot_key(slot)))
+                .collect(),
+        })
+    }
+}
+
+#[cfg(feature = "state-root")]
+impl<'a> InMemoryStorage<'a> {
+    fn account_trie(&self) -> crate::state_root::SparseTrie {
+        crate::state_root::build_trie(self.accounts.iter().map(|(address, account)| {
+            (
+                keccak256(address),
+                crate::state_root::encode_account(account, storage_trie(account).root()),
+            )
+        }))
+    }
+}
+
+#[cfg(feature = "state-root")]
+fn storage_trie(account: &EvmAccount) -> crate::state_root::SparseTrie {
+    crate::state_root::build_trie(
+        account
+            .storage
+            .iter()
+            .filter(|(_, value)| !value.is_zero())
+            .map(|(slot, value)| (crate::state_root::slot_key(slot), alloy_rlp::encode(value))),
+    )
+}
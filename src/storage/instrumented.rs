@@ -0,0 +1,391 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use alloy_primitives::{Address, B256, U256};
+
+use super::{EvmCode, StorageTier};
+use crate::{AccountBasic, Storage};
+
+/// The number of latency histogram buckets. Bucket `i` counts calls that
+/// took under `2^i` microseconds, the last bucket counts all slower calls.
+pub const LATENCY_BUCKETS: usize = 24;
+
+/// Metrics of a storage method, snapshotted from an [InstrumentedStorage].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MethodMetrics {
+    /// The number of calls, a batched lookup counting as one.
+    pub calls: u64,
+    /// The number of calls that errored.
+    pub errors: u64,
+    /// The number of lookups found in a wrapped [StorageTier].
+    pub hits: u64,
+    /// The number of lookups missed in a wrapped [StorageTier].
+    pub misses: u64,
+    /// The total time spent in calls.
+    pub total_latency: Duration,
+    /// The histogram of call latencies, see [LATENCY_BUCKETS].
+    pub latency_histogram: [u64; LATENCY_BUCKETS],
+}
+
+impl MethodMetrics {
+    /// Get the ratio of tier lookups that hit, [None] without lookups.
+    pub fn hit_ratio(&self) -> Option<f64> {
+        let lookups = self.hits + self.misses;
+        (lookups > 0).then(|| self.hits as f64 / lookups as f64)
+    }
+
+    /// Get the mean latency of calls, [None] without calls.
+    pub fn mean_latency(&self) -> Option<Duration> {
+        (self.calls > 0).then(|| {
+            Duration::from_nanos((self.total_latency.as_nanos() / self.calls as u128) as u64)
+        })
+    }
+}
+
+/// Metrics of all storage methods, snapshotted from an [InstrumentedStorage].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct StorageMetrics {
+    /// Metrics of [Storage::basic].
+    pub basic: MethodMetrics,
+    /// Metrics of [Storage::code_hash].
+    pub code_hash: MethodMetrics,
+    /// Metrics of [Storage::code_by_hash].
+    pub code_by_hash: MethodMetrics,
+    /// Metrics of [Storage::has_storage].
+    pub has_storage: MethodMetrics,
+    /// Metrics of [Storage::storage].
+    pub storage: MethodMetrics,
+    /// Metrics of [Storage::block_hash].
+    pub block_hash: MethodMetrics,
+    /// Metrics of [Storage::basic_many].
+    pub basic_many: MethodMetrics,
+    /// Metrics of [Storage::code_by_hash_many].
+    pub code_by_hash_many: MethodMetrics,
+    /// Metrics of [Storage::storage_many].
+    pub storage_many: MethodMetrics,
+}
+
+impl StorageMetrics {
+    /// Get the total time spent in all storage calls, to compare against
+    /// the block's execution time.
+    pub fn total_latency(&self) -> Duration {
+        [
+            &self.basic,
+            &self.code_hash,
+            &self.code_by_hash,
+            &self.has_storage,
+            &self.storage,
+            &self.block_hash,
+            &self.basic_many,
+            &self.code_by_hash_many,
+            &self.storage_many,
+        ]
+        .into_iter()
+        .map(|metrics| metrics.total_latency)
+        .sum()
+    }
+}
+
+// The live counters of a method, recorded concurrently by execution threads.
+#[derive(Debug, Default)]
+struct MethodRecorder {
+    // The method's label in the exported metrics.
+    #[cfg(feature = "metrics")]
+    method: &'static str,
+    calls: AtomicU64,
+    errors: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    total_latency_ns: AtomicU64,
+    latency_histogram: [AtomicU64; LATENCY_BUCKETS],
+}
+
+impl MethodRecorder {
+    fn new(_method: &'static str) -> Self {
+        Self {
+            #[cfg(feature = "metrics")]
+            method: _method,
+            ..Self::default()
+        }
+    }
+
+    fn record_latency(&self, started_at: Instant) {
+        let latency = started_at.elapsed();
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_storage_latency(self.method, latency);
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        self.total_latency_ns
+            .fetch_add(latency.as_nanos() as u64, Ordering::Relaxed);
+        let micros = latency.as_micros() as u64;
+        let bucket = (u64::BITS - micros.leading_zeros()) as usize;
+        self.latency_histogram[bucket.min(LATENCY_BUCKETS - 1)].fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn record<T, E>(&self, started_at: Instant, result: Result<T, E>) -> Result<T, E> {
+        self.record_latency(started_at);
+        if result.is_err() {
+            self.errors.fetch_add(1, Ordering::Relaxed);
+        }
+        result
+    }
+
+    fn record_lookup<T>(&self, started_at: Instant, cached: Option<T>) -> Option<T> {
+        self.record_latency(started_at);
+        if cached.is_some() {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+        }
+        cached
+    }
+
+    fn snapshot(&self) -> MethodMetrics {
+        MethodMetrics {
+            calls: self.calls.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            total_latency: Duration::from_nanos(self.total_latency_ns.load(Ordering::Relaxed)),
+            latency_histogram: std::array::from_fn(|i| {
+                self.latency_histogram[i].load(Ordering::Relaxed)
+            }),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Recorders {
+    basic: MethodRecorder,
+    code_hash: MethodRecorder,
+    code_by_hash: MethodRecorder,
+    has_storage: MethodRecorder,
+    storage: MethodRecorder,
+    block_hash: MethodRecorder,
+    basic_many: MethodRecorder,
+    code_by_hash_many: MethodRecorder,
+    storage_many: MethodRecorder,
+}
+
+impl Default for Recorders {
+    fn default() -> Self {
+        Self {
+            basic: MethodRecorder::new("basic"),
+            code_hash: MethodRecorder::new("code_hash"),
+            code_by_hash: MethodRecorder::new("code_by_hash"),
+            has_storage: MethodRecorder::new("has_storage"),
+            storage: MethodRecorder::new("storage"),
+            block_hash: MethodRecorder::new("block_hash"),
+            basic_many: MethodRecorder::new("basic_many"),
+            code_by_hash_many: MethodRecorder::new("code_by_hash_many"),
+            storage_many: MethodRecorder::new("storage_many"),
+        }
+    }
+}
+
+/// A [Storage] decorator that records per-method call counts, errors and
+/// latency histograms, to tell whether a slow block was EVM-bound or
+/// IO-bound. It is also a [StorageTier] when wrapping one, recording the
+/// hits and misses of a [crate::TieredStorage]'s cache. Clones share the
+/// same metrics.
+#[derive(Debug, Clone)]
+pub struct InstrumentedStorage<S> {
+    storage: S,
+    recorders: Arc<Recorders>,
+}
+
+impl<S> InstrumentedStorage<S> {
+    /// Construct a new [InstrumentedStorage]
+    pub fn new(storage: S) -> Self {
+        Self {
+            storage,
+            recorders: Arc::default(),
+        }
+    }
+
+    /// Get the underlying storage.
+    pub fn inner(&self) -> &S {
+        &self.storage
+    }
+
+    /// Get a snapshot of the metrics recorded so far.
+    pub fn metrics(&self) -> StorageMetrics {
+        let recorders = &self.recorders;
+        StorageMetrics {
+            basic: recorders.basic.snapshot(),
+            code_hash: recorders.code_hash.snapshot(),
+            code_by_hash: recorders.code_by_hash.snapshot(),
+            has_storage: recorders.has_storage.snapshot(),
+            storage: recorders.storage.snapshot(),
+            block_hash: recorders.block_hash.snapshot(),
+            basic_many: recorders.basic_many.snapshot(),
+            code_by_hash_many: recorders.code_by_hash_many.snapshot(),
+            storage_many: recorders.storage_many.snapshot(),
+        }
+    }
+
+    /// Reset all metrics, like between blocks. Calls recorded concurrently
+    /// may be partially reset.
+    pub fn reset_metrics(&self) {
+        let recorders = &self.recorders;
+        for recorder in [
+            &recorders.basic,
+            &recorders.code_hash,
+            &recorders.code_by_hash,
+            &recorders.has_storage,
+            &recorders.storage,
+            &recorders.block_hash,
+            &recorders.basic_many,
+            &recorders.code_by_hash_many,
+            &recorders.storage_many,
+        ] {
+            recorder.calls.store(0, Ordering::Relaxed);
+            recorder.errors.store(0, Ordering::Relaxed);
+            recorder.hits.store(0, Ordering::Relaxed);
+            recorder.misses.store(0, Ordering::Relaxed);
+            recorder.total_latency_ns.store(0, Ordering::Relaxed);
+            for bucket in &recorder.latency_histogram {
+                bucket.store(0, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+impl<S: Storage> Storage for InstrumentedStorage<S> {
+    type Error = S::Error;
+
+    fn basic(&self, address: &Address) -> Result<Option<AccountBasic>, Self::Error> {
+        let started_at = Instant::now();
+        self.recorders
+            .basic
+            .record(started_at, self.storage.basic(address))
+    }
+
+    fn code_hash(&self, address: &Address) -> Result<Option<B256>, Self::Error> {
+        let started_at = Instant::now();
+        self.recorders
+            .code_hash
+            .record(started_at, self.storage.code_hash(address))
+    }
+
+    fn code_by_hash(&self, code_hash: &B256) -> Result<Option<EvmCode>, Self::Error> {
+        let started_at = Instant::now();
+        self.recorders
+            .code_by_hash
+            .record(started_at, self.storage.code_by_hash(code_hash))
+    }
+
+    fn has_storage(&self, address: &Address) -> Result<bool, Self::Error> {
+        let started_at = Instant::now();
+        self.recorders
+            .has_storage
+            .record(started_at, self.storage.has_storage(address))
+    }
+
+    fn storage(&self, address: &Address, index: &U256) -> Result<U256, Self::Error> {
+        let started_at = Instant::now();
+        self.recorders
+            .storage
+            .record(started_at, self.storage.storage(address, index))
+    }
+
+    fn block_hash(&self, number: &u64) -> Result<B256, Self::Error> {
+        let started_at = Instant::now();
+        self.recorders
+            .block_hash
+            .record(started_at, self.storage.block_hash(number))
+    }
+
+    fn basic_many(&self, addresses: &[Address]) -> Result<Vec<Option<AccountBasic>>, Self::Error> {
+        let started_at = Instant::now();
+        self.recorders
+            .basic_many
+            .record(started_at, self.storage.basic_many(addresses))
+    }
+
+    fn code_by_hash_many(&self, code_hashes: &[B256]) -> Result<Vec<Option<EvmCode>>, Self::Error> {
+        let started_at = Instant::now();
+        self.recorders
+            .code_by_hash_many
+            .record(started_at, self.storage.code_by_hash_many(code_hashes))
+    }
+
+    fn storage_many(&self, slots: &[(Address, U256)]) -> Result<Vec<U256>, Self::Error> {
+        let started_at = Instant::now();
+        self.recorders
+            .storage_many
+            .record(started_at, self.storage.storage_many(slots))
+    }
+}
+
+impl<S: StorageTier> StorageTier for InstrumentedStorage<S> {
+    fn cached_basic(&self, address: &Address) -> Option<Option<AccountBasic>> {
+        let started_at = Instant::now();
+        self.recorders
+            .basic
+            .record_lookup(started_at, self.storage.cached_basic(address))
+    }
+
+    fn cached_code_hash(&self, address: &Address) -> Option<Option<B256>> {
+        let started_at = Instant::now();
+        self.recorders
+            .code_hash
+            .record_lookup(started_at, self.storage.cached_code_hash(address))
+    }
+
+    fn cached_code_by_hash(&self, code_hash: &B256) -> Option<Option<EvmCode>> {
+        let started_at = Instant::now();
+        self.recorders
+            .code_by_hash
+            .record_lookup(started_at, self.storage.cached_code_by_hash(code_hash))
+    }
+
+    fn cached_has_storage(&self, address: &Address) -> Option<bool> {
+        let started_at = Instant::now();
+        self.recorders
+            .has_storage
+            .record_lookup(started_at, self.storage.cached_has_storage(address))
+    }
+
+    fn cached_storage(&self, address: &Address, index: &U256) -> Option<U256> {
+        let started_at = Instant::now();
+        self.recorders
+            .storage
+            .record_lookup(started_at, self.storage.cached_storage(address, index))
+    }
+
+    fn cached_block_hash(&self, number: &u64) -> Option<B256> {
+        let started_at = Instant::now();
+        self.recorders
+            .block_hash
+            .record_lookup(started_at, self.storage.cached_block_hash(number))
+    }
+
+    fn fill_basic(&self, address: Address, basic: Option<AccountBasic>) {
+        self.storage.fill_basic(address, basic);
+    }
+
+    fn fill_code_hash(&self, address: Address, code_hash: Option<B256>) {
+        self.storage.fill_code_hash(address, code_hash);
+    }
+
+    fn fill_code_by_hash(&self, code_hash: B256, code: Option<EvmCode>) {
+        self.storage.fill_code_by_hash(code_hash, code);
+    }
+
+    fn fill_has_storage(&self, address: Address, has_storage: bool) {
+        self.storage.fill_has_storage(address, has_storage);
+    }
+
+    fn fill_storage(&self, address: Address, index: U256, value: U256) {
+        self.storage.fill_storage(address, index, value);
+    }
+
+    fn fill_block_hash(&self, number: u64, block_hash: B256) {
+        self.storage.fill_block_hash(number, block_hash);
+    }
+}
@@ -0,0 +1,363 @@
+use std::{
+    cmp::Ordering,
+    convert::Infallible,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    sync::Arc,
+};
+
+use alloy_primitives::{keccak256, Address, Bytes, B256, U256};
+use bitvec::vec::BitVec;
+use memmap2::Mmap;
+
+use super::{Bytecodes, EvmCode};
+use crate::{AccountBasic, EvmAccount, Storage};
+
+// The layout of a snapshot file, with all integers in little endian except
+// for 256-bit values, which are big endian:
+//   - Header: magic, version, then the numbers of accounts, slots, block
+//     hashes and bytecodes as u64.
+//   - Accounts sorted by address: address, balance, nonce, code hash (zero
+//     for no code), then the start and number of their slots.
+//   - Slots sorted by index per account: index, value.
+//   - Block hashes sorted by number: number, hash.
+//   - Bytecodes sorted by hash: hash, then the offset & length of the code,
+//     its original length, then the offset & bit length of its jump table
+//     in the data section.
+//   - The data section of codes and jump tables.
+const MAGIC: &[u8; 8] = b"PEVMMMAP";
+const VERSION: u32 = 1;
+const HEADER_SIZE: usize = 8 + 4 + 8 * 4;
+const ACCOUNT_SIZE: usize = 20 + 32 + 8 + 32 + 8 + 8;
+const SLOT_SIZE: usize = 32 + 32;
+const BLOCK_HASH_SIZE: usize = 8 + 32;
+const BYTECODE_SIZE: usize = 32 + 8 * 5;
+
+/// A read-only storage that memory-maps a flat snapshot file of sorted
+/// accounts, slots, block hashes and bytecodes, for near-zero startup
+/// instead of deserializing the whole state, like for benchmarks and
+/// stateless execution workers. Lookups are binary searches in the mapped
+/// sections, which the OS pages in on demand. Clones share the same map.
+#[derive(Debug, Clone)]
+pub struct MmapStorage {
+    mmap: Arc<Mmap>,
+    num_accounts: usize,
+    num_slots: usize,
+    num_block_hashes: usize,
+    num_bytecodes: usize,
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap())
+}
+
+fn read_b256(bytes: &[u8], offset: usize) -> B256 {
+    B256::from_slice(&bytes[offset..offset + 32])
+}
+
+fn read_u256(bytes: &[u8], offset: usize) -> U256 {
+    U256::from_be_slice(&bytes[offset..offset + 32])
+}
+
+impl MmapStorage {
+    /// Write a snapshot file of some state, to be opened as [MmapStorage].
+    /// The codes of accounts are read from their own code when set, or
+    /// from [bytecodes] otherwise.
+    pub fn write_snapshot(
+        path: impl AsRef<Path>,
+        accounts: impl IntoIterator<Item = (Address, EvmAccount)>,
+        bytecodes: &Bytecodes,
+        block_hashes: impl IntoIterator<Item = (u64, B256)>,
+    ) -> io::Result<()> {
+        let mut accounts: Vec<_> = accounts.into_iter().collect();
+        accounts.sort_unstable_by_key(|(address, _)| *address);
+        let mut block_hashes: Vec<_> = block_hashes.into_iter().collect();
+        block_hashes.sort_unstable();
+        let mut all_bytecodes = bytecodes.clone();
+        for (_, account) in accounts.iter_mut() {
+            if let (Some(code_hash), Some(code)) = (account.code_hash, account.code.take()) {
+                all_bytecodes.insert(code_hash, code);
+            }
+        }
+        let mut all_bytecodes: Vec<_> = all_bytecodes.into_iter().collect();
+        all_bytecodes.sort_unstable_by_key(|(code_hash, _)| *code_hash);
+        let num_slots: usize = accounts
+            .iter()
+            .map(|(_, account)| account.storage.len())
+            .sum();
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        for count in [
+            accounts.len(),
+            num_slots,
+            block_hashes.len(),
+            all_bytecodes.len(),
+        ] {
+            writer.write_all(&(count as u64).to_le_bytes())?;
+        }
+
+        let mut slots_start = 0;
+        for (address, account) in &accounts {
+            writer.write_all(address.as_slice())?;
+            writer.write_all(&account.balance.to_be_bytes::<32>())?;
+            writer.write_all(&account.nonce.to_le_bytes())?;
+            writer.write_all(account.code_hash.unwrap_or_default().as_slice())?;
+            writer.write_all(&(slots_start as u64).to_le_bytes())?;
+            writer.write_all(&(account.storage.len() as u64).to_le_bytes())?;
+            slots_start += account.storage.len();
+        }
+        for (_, account) in &accounts {
+            let mut slots: Vec<_> = account.storage.iter().collect();
+            slots.sort_unstable();
+            for (index, value) in slots {
+                writer.write_all(&index.to_be_bytes::<32>())?;
+                writer.write_all(&value.to_be_bytes::<32>())?;
+            }
+        }
+        for (number, block_hash) in &block_hashes {
+            writer.write_all(&number.to_le_bytes())?;
+            writer.write_all(block_hash.as_slice())?;
+        }
+        let mut data_offset = 0;
+        for (code_hash, code) in &all_bytecodes {
+            let jump_table = code.jump_table.as_raw_slice();
+            writer.write_all(code_hash.as_slice())?;
+            for value in [
+                data_offset,
+                code.bytecode.len(),
+                code.original_len,
+                data_offset + code.bytecode.len(),
+                code.jump_table.len(),
+            ] {
+                writer.write_all(&(value as u64).to_le_bytes())?;
+            }
+            data_offset += code.bytecode.len() + jump_table.len();
+        }
+        for (_, code) in &all_bytecodes {
+            writer.write_all(&code.bytecode)?;
+            writer.write_all(code.jump_table.as_raw_slice())?;
+        }
+        writer.flush()
+    }
+
+    /// Open a snapshot file written by [MmapStorage::write_snapshot].
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified while it is mapped, which is undefined
+    /// behaviour like for any memory-mapped file.
+    pub unsafe fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mmap = Mmap::map(&File::open(path)?)?;
+        if mmap.len() < HEADER_SIZE || &mmap[..8] != MAGIC {
+            return Err(invalid_data("not an mmap storage snapshot"));
+        }
+        if mmap[8..12] != VERSION.to_le_bytes() {
+            return Err(invalid_data("unsupported mmap storage snapshot version"));
+        }
+        let storage = Self {
+            num_accounts: read_u64(&mmap, 12) as usize,
+            num_slots: read_u64(&mmap, 20) as usize,
+            num_block_hashes: read_u64(&mmap, 28) as usize,
+            num_bytecodes: read_u64(&mmap, 36) as usize,
+            mmap: Arc::new(mmap),
+        };
+        // Validate all offsets once so lookups can index without checks.
+        // The section counts are untrusted, so their sizes may overflow.
+        let data_offset = [
+            (storage.num_accounts, ACCOUNT_SIZE),
+            (storage.num_slots, SLOT_SIZE),
+            (storage.num_block_hashes, BLOCK_HASH_SIZE),
+            (storage.num_bytecodes, BYTECODE_SIZE),
+        ]
+        .into_iter()
+        .try_fold(HEADER_SIZE, |offset, (count, size)| {
+            count
+                .checked_mul(size)
+                .and_then(|section_size| offset.checked_add(section_size))
+        })
+        .ok_or_else(|| invalid_data("invalid section sizes of mmap storage snapshot"))?;
+        if storage.mmap.len() < data_offset {
+            return Err(invalid_data("truncated mmap storage snapshot"));
+        }
+        for idx in 0..storage.num_accounts {
+            let account = storage.account_at(idx);
+            let slots_end = read_u64(account, 92).checked_add(read_u64(account, 100));
+            if slots_end
+                .filter(|end| *end <= storage.num_slots as u64)
+                .is_none()
+            {
+                return Err(invalid_data("invalid slots of an account"));
+            }
+        }
+        let data_len = (storage.mmap.len() - data_offset) as u64;
+        for idx in 0..storage.num_bytecodes {
+            let bytecode = storage.bytecode_at(idx);
+            let code_end = read_u64(bytecode, 32).checked_add(read_u64(bytecode, 40));
+            let jump_table_end =
+                read_u64(bytecode, 56).checked_add(read_u64(bytecode, 64).div_ceil(8));
+            if code_end.filter(|end| *end <= data_len).is_none()
+                || jump_table_end.filter(|end| *end <= data_len).is_none()
+            {
+                return Err(invalid_data("invalid bytecode"));
+            }
+        }
+        Ok(storage)
+    }
+
+    fn accounts_offset(&self) -> usize {
+        HEADER_SIZE
+    }
+
+    fn slots_offset(&self) -> usize {
+        self.accounts_offset() + self.num_accounts * ACCOUNT_SIZE
+    }
+
+    fn block_hashes_offset(&self) -> usize {
+        self.slots_offset() + self.num_slots * SLOT_SIZE
+    }
+
+    fn bytecodes_offset(&self) -> usize {
+        self.block_hashes_offset() + self.num_block_hashes * BLOCK_HASH_SIZE
+    }
+
+    fn data_offset(&self) -> usize {
+        self.bytecodes_offset() + self.num_bytecodes * BYTECODE_SIZE
+    }
+
+    fn account_at(&self, idx: usize) -> &[u8] {
+        let offset = self.accounts_offset() + idx * ACCOUNT_SIZE;
+        &self.mmap[offset..offset + ACCOUNT_SIZE]
+    }
+
+    fn slot_at(&self, idx: usize) -> &[u8] {
+        let offset = self.slots_offset() + idx * SLOT_SIZE;
+        &self.mmap[offset..offset + SLOT_SIZE]
+    }
+
+    fn block_hash_at(&self, idx: usize) -> &[u8] {
+        let offset = self.block_hashes_offset() + idx * BLOCK_HASH_SIZE;
+        &self.mmap[offset..offset + BLOCK_HASH_SIZE]
+    }
+
+    fn bytecode_at(&self, idx: usize) -> &[u8] {
+        let offset = self.bytecodes_offset() + idx * BYTECODE_SIZE;
+        &self.mmap[offset..offset + BYTECODE_SIZE]
+    }
+
+    // Binary search a section of sorted records by their keys.
+    fn search<'a, K: Ord>(
+        &'a self,
+        len: usize,
+        record_at: impl Fn(&'a Self, usize) -> &'a [u8],
+        key_of: impl Fn(&[u8]) -> K,
+        key: &K,
+    ) -> Option<&'a [u8]> {
+        let (mut low, mut high) = (0, len);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let record = record_at(self, mid);
+            match key_of(record).cmp(key) {
+                Ordering::Less => low = mid + 1,
+                Ordering::Greater => high = mid,
+                Ordering::Equal => return Some(record),
+            }
+        }
+        None
+    }
+
+    fn account(&self, address: &Address) -> Option<&[u8]> {
+        self.search(
+            self.num_accounts,
+            Self::account_at,
+            |record| Address::from_slice(&record[..20]),
+            address,
+        )
+    }
+}
+
+impl Storage for MmapStorage {
+    type Error = Infallible;
+
+    fn basic(&self, address: &Address) -> Result<Option<AccountBasic>, Self::Error> {
+        Ok(self.account(address).map(|account| AccountBasic {
+            balance: read_u256(account, 20),
+            nonce: read_u64(account, 52),
+        }))
+    }
+
+    fn code_hash(&self, address: &Address) -> Result<Option<B256>, Self::Error> {
+        Ok(self
+            .account(address)
+            .map(|account| read_b256(account, 60))
+            .filter(|code_hash| !code_hash.is_zero()))
+    }
+
+    fn code_by_hash(&self, code_hash: &B256) -> Result<Option<EvmCode>, Self::Error> {
+        Ok(self
+            .search(
+                self.num_bytecodes,
+                Self::bytecode_at,
+                |record| read_b256(record, 0),
+                code_hash,
+            )
+            .map(|record| {
+                let data = &self.mmap[self.data_offset()..];
+                let code_offset = read_u64(record, 32) as usize;
+                let code_len = read_u64(record, 40) as usize;
+                let jump_table_offset = read_u64(record, 56) as usize;
+                let jump_table_len = read_u64(record, 64) as usize;
+                let mut jump_table = BitVec::from_slice(
+                    &data[jump_table_offset..jump_table_offset + jump_table_len.div_ceil(8)],
+                );
+                jump_table.truncate(jump_table_len);
+                EvmCode {
+                    bytecode: Bytes::copy_from_slice(&data[code_offset..code_offset + code_len]),
+                    original_len: read_u64(record, 48) as usize,
+                    jump_table: Arc::new(jump_table),
+                }
+            }))
+    }
+
+    fn has_storage(&self, address: &Address) -> Result<bool, Self::Error> {
+        Ok(self
+            .account(address)
+            .is_some_and(|account| read_u64(account, 100) > 0))
+    }
+
+    fn storage(&self, address: &Address, index: &U256) -> Result<U256, Self::Error> {
+        let Some(account) = self.account(address) else {
+            return Ok(U256::ZERO);
+        };
+        let slots_start = read_u64(account, 92) as usize;
+        let num_slots = read_u64(account, 100) as usize;
+        Ok(self
+            .search(
+                num_slots,
+                |storage, idx| storage.slot_at(slots_start + idx),
+                |record| read_u256(record, 0),
+                index,
+            )
+            .map(|record| read_u256(record, 32))
+            .unwrap_or_default())
+    }
+
+    fn block_hash(&self, number: &u64) -> Result<B256, Self::Error> {
+        Ok(self
+            .search(
+                self.num_block_hashes,
+                Self::block_hash_at,
+                |record| read_u64(record, 0),
+                number,
+            )
+            .map(|record| read_b256(record, 8))
+            // Matching [InMemoryStorage] for missing block hashes.
+            .unwrap_or_else(|| keccak256(number.to_string().as_bytes())))
+    }
+}
@@ -0,0 +1,133 @@
+use ahash::AHashMap;
+use alloy_primitives::{Address, B256, U256};
+use revm::primitives::Bytecode;
+
+use super::{Bytecodes, EvmCode};
+use crate::{AccountBasic, Storage};
+
+/// Overrides of an account's state for simulations, following the
+/// `stateOverride` semantics of `eth_call`. Unset fields keep the values
+/// of the underlying storage.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AccountOverride {
+    /// Override the account's balance.
+    pub balance: Option<U256>,
+    /// Override the account's nonce.
+    pub nonce: Option<u64>,
+    /// Override the account's code. An empty code removes it.
+    pub code: Option<EvmCode>,
+    /// Replace the account's whole storage with these slots.
+    pub state: Option<AHashMap<U256, U256>>,
+    /// Patch these slots on top of the account's storage.
+    pub state_diff: AHashMap<U256, U256>,
+}
+
+/// Mapping between addresses and their state overrides
+pub type StateOverrides = AHashMap<Address, AccountOverride>;
+
+/// A storage that layers state overrides on top of another storage, like
+/// for `eth_call`-style simulations. Overridden accounts always exist.
+#[derive(Debug, Clone)]
+pub struct OverlayStorage<S> {
+    storage: S,
+    overrides: StateOverrides,
+    // The code hashes of overridden codes, [None] for removed codes.
+    code_hashes: AHashMap<Address, Option<B256>>,
+    bytecodes: Bytecodes,
+}
+
+impl<S> OverlayStorage<S> {
+    /// Construct a new [OverlayStorage]
+    pub fn new(storage: S, overrides: StateOverrides) -> Self {
+        let mut code_hashes = AHashMap::default();
+        let mut bytecodes = Bytecodes::default();
+        for (address, account) in &overrides {
+            if let Some(code) = &account.code {
+                let bytecode = Bytecode::from(code.clone());
+                let code_hash = (!bytecode.is_empty()).then(|| bytecode.hash_slow());
+                if let Some(code_hash) = code_hash {
+                    bytecodes.insert(code_hash, code.clone());
+                }
+                code_hashes.insert(*address, code_hash);
+            }
+        }
+        Self {
+            storage,
+            overrides,
+            code_hashes,
+            bytecodes,
+        }
+    }
+
+    /// Get the underlying storage.
+    pub fn inner(&self) -> &S {
+        &self.storage
+    }
+
+    /// Get the state overrides.
+    pub fn overrides(&self) -> &StateOverrides {
+        &self.overrides
+    }
+}
+
+impl<S: Storage> Storage for OverlayStorage<S> {
+    type Error = S::Error;
+
+    fn basic(&self, address: &Address) -> Result<Option<AccountBasic>, Self::Error> {
+        let Some(account) = self.overrides.get(address) else {
+            return self.storage.basic(address);
+        };
+        let mut basic = self.storage.basic(address)?.unwrap_or_default();
+        if let Some(balance) = account.balance {
+            basic.balance = balance;
+        }
+        if let Some(nonce) = account.nonce {
+            basic.nonce = nonce;
+        }
+        Ok(Some(basic))
+    }
+
+    fn code_hash(&self, address: &Address) -> Result<Option<B256>, Self::Error> {
+        match self.code_hashes.get(address) {
+            Some(code_hash) => Ok(*code_hash),
+            None => self.storage.code_hash(address),
+        }
+    }
+
+    fn code_by_hash(&self, code_hash: &B256) -> Result<Option<EvmCode>, Self::Error> {
+        match self.bytecodes.get(code_hash) {
+            Some(code) => Ok(Some(code.clone())),
+            None => self.storage.code_by_hash(code_hash),
+        }
+    }
+
+    fn has_storage(&self, address: &Address) -> Result<bool, Self::Error> {
+        let Some(account) = self.overrides.get(address) else {
+            return self.storage.has_storage(address);
+        };
+        if !account.state_diff.is_empty() {
+            return Ok(true);
+        }
+        match &account.state {
+            Some(state) => Ok(!state.is_empty()),
+            None => self.storage.has_storage(address),
+        }
+    }
+
+    fn storage(&self, address: &Address, index: &U256) -> Result<U256, Self::Error> {
+        let Some(account) = self.overrides.get(address) else {
+            return self.storage.storage(address, index);
+        };
+        if let Some(value) = account.state_diff.get(index) {
+            return Ok(*value);
+        }
+        match &account.state {
+            Some(state) => Ok(state.get(index).copied().unwrap_or_default()),
+            None => self.storage.storage(address, index),
+        }
+    }
+
+    fn block_hash(&self, number: &u64) -> Result<B256, Self::Error> {
+        self.storage.block_hash(number)
+    }
+}
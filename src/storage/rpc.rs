@@ -1,27 +1,66 @@
 // TODO: Put this behind an RPC flag to not pollute the core
 // library with RPC network & transport dependencies, etc.
 
-use std::{fmt::Debug, future::IntoFuture, sync::Mutex};
+use std::{
+    fmt::Debug,
+    fs::{self, File},
+    future::{Future, IntoFuture},
+    io::{self, BufReader, BufWriter},
+    num::NonZeroU32,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
 
 use ahash::AHashMap;
-use alloy_primitives::{Address, B256, U256};
+#[cfg(feature = "state-root")]
+use alloy_primitives::keccak256;
+use alloy_primitives::{Address, Bytes, B256, U256, U64};
 use alloy_provider::{Network, Provider, RootProvider};
+#[cfg(feature = "state-root")]
+use alloy_rlp::Decodable;
+use alloy_rpc_client::BatchRequest;
+#[cfg(feature = "state-root")]
+use alloy_rpc_types::EIP1186AccountProofResponse;
 use alloy_rpc_types::{BlockId, BlockNumberOrTag};
-use alloy_transport::TransportError;
+use alloy_transport::{RpcError, TransportError};
 use alloy_transport_http::Http;
+#[cfg(feature = "state-root")]
+use alloy_trie::EMPTY_ROOT_HASH;
+use futures::future::try_join_all;
 use reqwest::Client;
+#[cfg(feature = "state-root")]
+use revm::primitives::KECCAK_EMPTY;
 use revm::{
     precompile::{PrecompileSpecId, Precompiles},
     primitives::{Bytecode, SpecId},
 };
+use serde::{Deserialize, Serialize};
 use tokio::runtime::Runtime;
 
+#[cfg(feature = "state-root")]
+use crate::state_root::{encode_account, slot_key, verify_proof};
 use crate::{AccountBasic, EvmAccount, Storage};
 
-use super::EvmCode;
+use super::{AsyncStorage, EvmCode};
 
 type RpcProvider<N> = RootProvider<Http<Client>, N>;
 
+// The maximum number of calls in a JSON-RPC batch, within the limits of
+// common RPC providers.
+const MAX_BATCH_SIZE: usize = 300;
+
+// The cap of the exponential backoff between retries.
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(10);
+
+// The caches of an [RpcStorage] persisted on disk for a block id.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct DiskCache {
+    accounts: AHashMap<Address, EvmAccount>,
+    bytecodes: AHashMap<B256, EvmCode>,
+    block_hashes: AHashMap<u64, B256>,
+}
+
 /// A storage that fetches state data via RPC for execution.
 #[derive(Debug)]
 pub struct RpcStorage<N> {
@@ -37,6 +76,19 @@ pub struct RpcStorage<N> {
     cache_accounts: Mutex<AHashMap<Address, EvmAccount>>,
     cache_bytecodes: Mutex<AHashMap<B256, EvmCode>>,
     cache_block_hashes: Mutex<AHashMap<u64, B256>>,
+    // The file to persist the caches to, if backed by disk.
+    disk_cache_path: Option<PathBuf>,
+    // Retry transient failures, like when throttled by public RPCs, with
+    // an exponential backoff from [retry_backoff].
+    max_retries: u32,
+    retry_backoff: Duration,
+    // The minimum interval between requests to stay within a rate limit,
+    // and when the next request can be sent.
+    request_interval: Option<Duration>,
+    next_request_at: Mutex<Instant>,
+    // Verify fetched state with Merkle proofs against this trusted state root.
+    #[cfg(feature = "state-root")]
+    verified_state_root: Option<B256>,
     // TODO: Better async handling.
     runtime: Runtime,
 }
@@ -51,11 +103,89 @@ impl<N> RpcStorage<N> {
             cache_accounts: Mutex::default(),
             cache_bytecodes: Mutex::default(),
             cache_block_hashes: Mutex::default(),
+            disk_cache_path: None,
+            max_retries: 3,
+            retry_backoff: Duration::from_millis(200),
+            request_interval: None,
+            next_request_at: Mutex::new(Instant::now()),
+            #[cfg(feature = "state-root")]
+            verified_state_root: None,
             // TODO: Better error handling.
             runtime: Runtime::new().unwrap(),
         }
     }
 
+    /// Retry requests that fail transiently, like from network errors or
+    /// being throttled, up to [max_retries] times with an exponential backoff
+    /// starting from [backoff]. Defaults to 3 retries from 200ms.
+    pub fn with_retries(mut self, max_retries: u32, backoff: Duration) -> Self {
+        self.max_retries = max_retries;
+        self.retry_backoff = backoff;
+        self
+    }
+
+    /// Limit the number of calls sent per second, counting each call in a
+    /// batch. Unlimited by default.
+    pub fn with_rate_limit(mut self, calls_per_second: NonZeroU32) -> Self {
+        self.request_interval = Some(Duration::from_secs(1) / calls_per_second.get());
+        self
+    }
+
+    /// Fetch state via `eth_getProof` and verify its Merkle proofs against a
+    /// trusted state root of the block, like from a header validated by a
+    /// consensus client, so a malicious RPC endpoint cannot feed wrong state.
+    /// This costs larger responses and disables batched lookups.
+    #[cfg(feature = "state-root")]
+    pub fn with_proof_verification(mut self, state_root: B256) -> Self {
+        self.verified_state_root = Some(state_root);
+        self
+    }
+
+    /// Back the caches with a file in a directory, keyed by the block id,
+    /// so repeated replays of the same block don't re-fetch state via RPC.
+    /// Existing caches are loaded now, and persisted on
+    /// [RpcStorage::flush_disk_cache] or on drop. Only fixed block numbers
+    /// and hashes can be cached, not tags like `latest`.
+    pub fn with_disk_cache(mut self, dir: impl AsRef<Path>) -> io::Result<Self> {
+        let file_name = match self.block_id {
+            BlockId::Number(BlockNumberOrTag::Number(number)) => number.to_string(),
+            BlockId::Hash(hash) => hash.block_hash.to_string(),
+            BlockId::Number(tag) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("cannot cache the state of a moving block tag: {tag}"),
+                ))
+            }
+        };
+        fs::create_dir_all(&dir)?;
+        let path = dir.as_ref().join(format!("{file_name}.json"));
+        if path.exists() {
+            let cache: DiskCache = serde_json::from_reader(BufReader::new(File::open(&path)?))?;
+            self.cache_accounts = Mutex::new(cache.accounts);
+            self.cache_bytecodes = Mutex::new(cache.bytecodes);
+            self.cache_block_hashes = Mutex::new(cache.block_hashes);
+        }
+        self.disk_cache_path = Some(path);
+        Ok(self)
+    }
+
+    /// Persist the caches to disk if backed by disk.
+    pub fn flush_disk_cache(&self) -> io::Result<()> {
+        let Some(path) = &self.disk_cache_path else {
+            return Ok(());
+        };
+        let cache = DiskCache {
+            accounts: self.get_cache_accounts(),
+            bytecodes: self.get_cache_bytecodes(),
+            block_hashes: self.get_cache_block_hashes(),
+        };
+        // Write to a temporary file first to not leave a corrupted cache
+        // behind on failure.
+        let tmp_path = path.with_extension("json.tmp");
+        serde_json::to_writer(BufWriter::new(File::create(&tmp_path)?), &cache)?;
+        fs::rename(tmp_path, path)
+    }
+
     /// Get a snapshot of accounts
     pub fn get_cache_accounts(&self) -> AHashMap<Address, EvmAccount> {
         self.cache_accounts.lock().unwrap().clone()
@@ -70,75 +200,415 @@ impl<N> RpcStorage<N> {
     pub fn get_cache_block_hashes(&self) -> AHashMap<u64, B256> {
         self.cache_block_hashes.lock().unwrap().clone()
     }
+
+    // Cache a fetched account, returning [None] for non-existing accounts.
+    fn cache_account(
+        &self,
+        address: Address,
+        balance: U256,
+        nonce: u64,
+        code: Bytes,
+    ) -> Option<AccountBasic> {
+        // We need to distinguish new non-precompile accounts for gas calculation
+        // in early hard-forks (creating new accounts cost extra gas, etc.).
+        if !self
+            .precompiles
+            .addresses()
+            .any(|precompile_address| precompile_address == &address)
+            && balance.is_zero()
+            && nonce == 0
+            && code.is_empty()
+        {
+            return None;
+        }
+        let code = Bytecode::new_raw(code);
+        let code_hash = if code.is_empty() {
+            None
+        } else {
+            let code_hash = code.hash_slow();
+            self.cache_bytecodes
+                .lock()
+                .unwrap()
+                .insert(code_hash, code.into());
+            Some(code_hash)
+        };
+        self.cache_accounts.lock().unwrap().insert(
+            address,
+            EvmAccount {
+                balance,
+                nonce,
+                code_hash,
+                code: None,
+                storage: AHashMap::default(),
+            },
+        );
+        Some(AccountBasic { balance, nonce })
+    }
 }
 
-impl<N: Network> Storage for RpcStorage<N> {
+impl<N> Drop for RpcStorage<N> {
+    fn drop(&mut self) {
+        // Best effort, call [RpcStorage::flush_disk_cache] to handle errors.
+        let _ = self.flush_disk_cache();
+    }
+}
+
+// Whether an RPC error is worth retrying, like network failures and rate
+// limiting responses, as opposed to invalid requests.
+fn is_transient(err: &TransportError) -> bool {
+    match err {
+        RpcError::Transport(_) => true,
+        // HTTP 429 and the common rate limiting codes of providers.
+        RpcError::ErrorResp(payload) => {
+            matches!(payload.code, 429 | -32005 | -32016)
+                || payload.message.to_lowercase().contains("rate limit")
+        }
+        _ => false,
+    }
+}
+
+// Verify the proof of an account against a state root, returning its
+// verified storage root.
+#[cfg(feature = "state-root")]
+fn verify_account_proof(
+    state_root: B256,
+    address: &Address,
+    proof: &EIP1186AccountProofResponse,
+) -> Result<B256, TransportError> {
+    let invalid_proof = |reason: String| {
+        RpcError::local_usage_str(&format!("invalid proof of {address}: {reason}"))
+    };
+    let account = EvmAccount {
+        balance: proof.balance,
+        nonce: proof.nonce,
+        code_hash: (proof.code_hash != KECCAK_EMPTY && !proof.code_hash.is_zero())
+            .then_some(proof.code_hash),
+        code: None,
+        storage: AHashMap::default(),
+    };
+    match verify_proof(state_root, &keccak256(address), &proof.account_proof)
+        .map_err(|err| invalid_proof(err.to_string()))?
+    {
+        Some(encoded) if encoded == encode_account(&account, proof.storage_hash) => {
+            Ok(proof.storage_hash)
+        }
+        // Providers may return either zero or empty hashes for non-existent
+        // accounts.
+        None if account.balance.is_zero() && account.nonce == 0 && account.code_hash.is_none() => {
+            Ok(EMPTY_ROOT_HASH)
+        }
+        _ => Err(invalid_proof("mismatched account".to_string())),
+    }
+}
+
+impl<N: Network> RpcStorage<N> {
+    // Send a request of [calls] calls within the rate limit, retrying
+    // transient failures with exponential backoff.
+    async fn request<T, F: Future<Output = Result<T, TransportError>>>(
+        &self,
+        calls: usize,
+        request: impl Fn() -> F,
+    ) -> Result<T, TransportError> {
+        let mut retries = 0;
+        let mut backoff = self.retry_backoff;
+        loop {
+            self.throttle(calls).await;
+            match request().await {
+                Err(err) if retries < self.max_retries && is_transient(&err) => {
+                    retries += 1;
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(MAX_RETRY_BACKOFF);
+                }
+                result => return result,
+            }
+        }
+    }
+
+    // Wait for the next slot of the rate limit, if any.
+    async fn throttle(&self, calls: usize) {
+        let Some(request_interval) = self.request_interval else {
+            return;
+        };
+        let wait = {
+            let mut next_request_at = self.next_request_at.lock().unwrap();
+            let now = Instant::now();
+            let request_at = (*next_request_at).max(now);
+            *next_request_at = request_at + request_interval * calls as u32;
+            request_at - now
+        };
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    // Fetch an account with its proof and code, verified against a state root.
+    #[cfg(feature = "state-root")]
+    async fn verified_basic(
+        &self,
+        state_root: B256,
+        address: &Address,
+    ) -> Result<Option<AccountBasic>, TransportError> {
+        let (proof, code) = self
+            .request(2, move || async move {
+                let (res_proof, res_code) = tokio::join!(
+                    self.provider
+                        .get_proof(*address, Vec::new())
+                        .block_id(self.block_id)
+                        .into_future(),
+                    self.provider
+                        .get_code_at(*address)
+                        .block_id(self.block_id)
+                        .into_future()
+                );
+                Ok((res_proof?, res_code?))
+            })
+            .await?;
+        verify_account_proof(state_root, address, &proof)?;
+        let code_hash = if code.is_empty() {
+            KECCAK_EMPTY
+        } else {
+            keccak256(&code)
+        };
+        if code_hash != proof.code_hash && !(code.is_empty() && proof.code_hash.is_zero()) {
+            return Err(RpcError::local_usage_str(&format!(
+                "invalid proof of {address}: mismatched code"
+            )));
+        }
+        Ok(self.cache_account(*address, proof.balance, proof.nonce, code))
+    }
+
+    // Fetch a storage slot with its proof, verified against a state root.
+    #[cfg(feature = "state-root")]
+    async fn verified_storage(
+        &self,
+        state_root: B256,
+        address: &Address,
+        index: &U256,
+    ) -> Result<U256, TransportError> {
+        let proof = self
+            .request(1, move || {
+                self.provider
+                    .get_proof(*address, vec![B256::from(*index)])
+                    .block_id(self.block_id)
+                    .into_future()
+            })
+            .await?;
+        let storage_root = verify_account_proof(state_root, address, &proof)?;
+        let invalid_proof = |reason: String| {
+            RpcError::local_usage_str(&format!("invalid proof of {address} at {index}: {reason}"))
+        };
+        let storage_proof = proof
+            .storage_proof
+            .first()
+            .ok_or_else(|| invalid_proof("missing storage proof".to_string()))?;
+        let value = match verify_proof(storage_root, &slot_key(index), &storage_proof.proof)
+            .map_err(|err| invalid_proof(err.to_string()))?
+        {
+            Some(encoded) => U256::decode(&mut encoded.as_slice())
+                .map_err(|err| invalid_proof(err.to_string()))?,
+            None => U256::ZERO,
+        };
+        if value != storage_proof.value {
+            return Err(invalid_proof("mismatched value".to_string()));
+        }
+        Ok(value)
+    }
+
+    async fn unverified_storage(
+        &self,
+        address: &Address,
+        index: &U256,
+    ) -> Result<U256, TransportError> {
+        self.request(1, move || {
+            self.provider
+                .get_storage_at(*address, *index)
+                .block_id(self.block_id)
+                .into_future()
+        })
+        .await
+    }
+
+    // Fetch the uncached accounts of a burst of lookups in JSON-RPC batches,
+    // instead of three round trips per account.
+    async fn basic_batch(
+        &self,
+        addresses: &[Address],
+    ) -> Result<Vec<Option<AccountBasic>>, TransportError> {
+        let mut missed_addresses: Vec<_> = {
+            let cache_accounts = self.cache_accounts.lock().unwrap();
+            addresses
+                .iter()
+                .filter(|address| !cache_accounts.contains_key(*address))
+                .copied()
+                .collect()
+        };
+        missed_addresses.sort_unstable();
+        missed_addresses.dedup();
+        // Large bursts are split into batches that are sent concurrently, as
+        // RPC providers commonly limit the size of a batch.
+        let fetched_basics: AHashMap<_, _> = try_join_all(
+            missed_addresses
+                .chunks(MAX_BATCH_SIZE / 3)
+                .map(|addresses| self.send_basic_batch(addresses)),
+        )
+        .await?
+        .into_iter()
+        .flatten()
+        .collect();
+        let cache_accounts = self.cache_accounts.lock().unwrap();
+        Ok(addresses
+            .iter()
+            .map(|address| match fetched_basics.get(address) {
+                Some(basic) => basic.clone(),
+                None => cache_accounts.get(address).map(|account| AccountBasic {
+                    balance: account.balance,
+                    nonce: account.nonce,
+                }),
+            })
+            .collect())
+    }
+
+    // Fetch the uncached slots of a burst of lookups in JSON-RPC batches.
+    async fn storage_batch(&self, slots: &[(Address, U256)]) -> Result<Vec<U256>, TransportError> {
+        // Like [AsyncStorage::storage], only cache slots of non-empty accounts.
+        let addresses: Vec<_> = slots.iter().map(|(address, _)| *address).collect();
+        self.basic_batch(&addresses).await?;
+        let mut missed_slots: Vec<_> = {
+            let cache_accounts = self.cache_accounts.lock().unwrap();
+            slots
+                .iter()
+                .filter(|(address, index)| {
+                    !cache_accounts
+                        .get(address)
+                        .is_some_and(|account| account.storage.contains_key(index))
+                })
+                .copied()
+                .collect()
+        };
+        missed_slots.sort_unstable();
+        missed_slots.dedup();
+        let fetched_values: AHashMap<_, _> = try_join_all(
+            missed_slots
+                .chunks(MAX_BATCH_SIZE)
+                .map(|slots| self.send_storage_batch(slots)),
+        )
+        .await?
+        .into_iter()
+        .flatten()
+        .collect();
+        let cache_accounts = self.cache_accounts.lock().unwrap();
+        Ok(slots
+            .iter()
+            .map(|slot| match fetched_values.get(slot) {
+                Some(value) => *value,
+                None => cache_accounts
+                    .get(&slot.0)
+                    .and_then(|account| account.storage.get(&slot.1))
+                    .copied()
+                    .unwrap_or_default(),
+            })
+            .collect())
+    }
+
+    // Fetch accounts in a single JSON-RPC batch.
+    async fn send_basic_batch(
+        &self,
+        addresses: &[Address],
+    ) -> Result<Vec<(Address, Option<AccountBasic>)>, TransportError> {
+        self.request(addresses.len() * 3, move || async move {
+            let mut batch = BatchRequest::new(self.provider.client());
+            let mut waiters = Vec::with_capacity(addresses.len());
+            for address in addresses {
+                let params = (*address, self.block_id);
+                waiters.push((
+                    batch.add_call::<_, U256>("eth_getBalance", &params)?,
+                    batch.add_call::<_, U64>("eth_getTransactionCount", &params)?,
+                    batch.add_call::<_, Bytes>("eth_getCode", &params)?,
+                ));
+            }
+            batch.send().await?;
+            let mut basics = Vec::with_capacity(addresses.len());
+            for (address, (balance, nonce, code)) in addresses.iter().zip(waiters) {
+                let basic =
+                    self.cache_account(*address, balance.await?, nonce.await?.to(), code.await?);
+                basics.push((*address, basic));
+            }
+            Ok(basics)
+        })
+        .await
+    }
+
+    // Fetch storage slots in a single JSON-RPC batch.
+    async fn send_storage_batch(
+        &self,
+        slots: &[(Address, U256)],
+    ) -> Result<Vec<((Address, U256), U256)>, TransportError> {
+        let values = self
+            .request(slots.len(), move || async move {
+                let mut batch = BatchRequest::new(self.provider.client());
+                let mut waiters = Vec::with_capacity(slots.len());
+                for (address, index) in slots {
+                    waiters.push(batch.add_call::<_, U256>(
+                        "eth_getStorageAt",
+                        &(*address, *index, self.block_id),
+                    )?);
+                }
+                batch.send().await?;
+                let mut values = Vec::with_capacity(slots.len());
+                for (slot, waiter) in slots.iter().zip(waiters) {
+                    values.push((*slot, waiter.await?));
+                }
+                Ok(values)
+            })
+            .await?;
+        let mut cache_accounts = self.cache_accounts.lock().unwrap();
+        for ((address, index), value) in &values {
+            if let Some(account) = cache_accounts.get_mut(address) {
+                account.storage.insert(*index, *value);
+            }
+        }
+        Ok(values)
+    }
+}
+
+impl<N: Network> AsyncStorage for RpcStorage<N> {
     type Error = TransportError;
 
-    fn basic(&self, address: &Address) -> Result<Option<AccountBasic>, Self::Error> {
+    async fn basic(&self, address: &Address) -> Result<Option<AccountBasic>, Self::Error> {
         if let Some(account) = self.cache_accounts.lock().unwrap().get(address) {
             return Ok(Some(AccountBasic {
                 balance: account.balance,
                 nonce: account.nonce,
             }));
         }
-        self.runtime.block_on(async {
-            let (res_balance, res_nonce, res_code) = tokio::join!(
-                self.provider
-                    .get_balance(*address)
-                    .block_id(self.block_id)
-                    .into_future(),
-                self.provider
-                    .get_transaction_count(*address)
-                    .block_id(self.block_id)
-                    .into_future(),
-                self.provider
-                    .get_code_at(*address)
-                    .block_id(self.block_id)
-                    .into_future()
-            );
-            let balance = res_balance?;
-            let nonce = res_nonce?;
-            let code = res_code?;
-            // We need to distinguish new non-precompile accounts for gas calculation
-            // in early hard-forks (creating new accounts cost extra gas, etc.).
-            if !self
-                .precompiles
-                .addresses()
-                .any(|precompile_address| precompile_address == address)
-                && balance.is_zero()
-                && nonce == 0
-                && code.is_empty()
-            {
-                return Ok(None);
-            }
-            let code = Bytecode::new_raw(code);
-            let code_hash = if code.is_empty() {
-                None
-            } else {
-                let code_hash = code.hash_slow();
-                self.cache_bytecodes
-                    .lock()
-                    .unwrap()
-                    .insert(code_hash, code.into());
-                Some(code_hash)
-            };
-            self.cache_accounts.lock().unwrap().insert(
-                *address,
-                EvmAccount {
-                    balance,
-                    nonce,
-                    code_hash,
-                    code: None,
-                    storage: AHashMap::default(),
-                },
-            );
-            Ok(Some(AccountBasic { balance, nonce }))
-        })
+        #[cfg(feature = "state-root")]
+        if let Some(state_root) = self.verified_state_root {
+            return self.verified_basic(state_root, address).await;
+        }
+        let (balance, nonce, code) = self
+            .request(3, move || async move {
+                let (res_balance, res_nonce, res_code) = tokio::join!(
+                    self.provider
+                        .get_balance(*address)
+                        .block_id(self.block_id)
+                        .into_future(),
+                    self.provider
+                        .get_transaction_count(*address)
+                        .block_id(self.block_id)
+                        .into_future(),
+                    self.provider
+                        .get_code_at(*address)
+                        .block_id(self.block_id)
+                        .into_future()
+                );
+                Ok((res_balance?, res_nonce?, res_code?))
+            })
+            .await?;
+        Ok(self.cache_account(*address, balance, nonce, code))
     }
 
-    fn code_hash(&self, address: &Address) -> Result<Option<B256>, Self::Error> {
-        self.basic(address)?;
+    async fn code_hash(&self, address: &Address) -> Result<Option<B256>, Self::Error> {
+        AsyncStorage::basic(self, address).await?;
         Ok(self
             .cache_accounts
             .lock()
@@ -147,33 +617,34 @@ impl<N: Network> Storage for RpcStorage<N> {
             .and_then(|account| account.code_hash))
     }
 
-    fn code_by_hash(&self, code_hash: &B256) -> Result<Option<EvmCode>, Self::Error> {
+    async fn code_by_hash(&self, code_hash: &B256) -> Result<Option<EvmCode>, Self::Error> {
         Ok(self.cache_bytecodes.lock().unwrap().get(code_hash).cloned())
     }
 
-    fn has_storage(&self, _address: &Address) -> Result<bool, Self::Error> {
+    async fn has_storage(&self, _address: &Address) -> Result<bool, Self::Error> {
         // FIXME! Returning [false] should cover EIP-7610 for the time being.
         Ok(false)
     }
 
-    fn storage(&self, address: &Address, index: &U256) -> Result<U256, Self::Error> {
+    async fn storage(&self, address: &Address, index: &U256) -> Result<U256, Self::Error> {
         if let Some(account) = self.cache_accounts.lock().unwrap().get(address) {
             if let Some(value) = account.storage.get(index) {
                 return Ok(*value);
             }
         }
-        let value = self.runtime.block_on(
-            self.provider
-                .get_storage_at(*address, *index)
-                .block_id(self.block_id)
-                .into_future(),
-        )?;
+        #[cfg(feature = "state-root")]
+        let value = match self.verified_state_root {
+            Some(state_root) => self.verified_storage(state_root, address, index).await?,
+            None => self.unverified_storage(address, index).await?,
+        };
+        #[cfg(not(feature = "state-root"))]
+        let value = self.unverified_storage(address, index).await?;
 
         // We only cache if the pre-state account is non-empty. Else this
         // could be a false alarm that results in the default 0. Caching
         // that would make this account non-empty and may fail a tx that
         // deploys a contract here (EIP-7610).
-        self.basic(address)?;
+        AsyncStorage::basic(self, address).await?;
         if let Some(account) = self.cache_accounts.lock().unwrap().get_mut(address) {
             account.storage.insert(*index, value);
         }
@@ -181,18 +652,17 @@ impl<N: Network> Storage for RpcStorage<N> {
         Ok(value)
     }
 
-    fn block_hash(&self, number: &u64) -> Result<B256, Self::Error> {
+    async fn block_hash(&self, number: &u64) -> Result<B256, Self::Error> {
         if let Some(&block_hash) = self.cache_block_hashes.lock().unwrap().get(number) {
             return Ok(block_hash);
         }
 
         let block_hash = self
-            .runtime
-            .block_on(
+            .request(1, move || {
                 self.provider
                     .get_block_by_number(BlockNumberOrTag::Number(*number), false)
-                    .into_future(),
-            )
+            })
+            .await
             .map(|block| block.unwrap().header.hash.unwrap())?;
 
         self.cache_block_hashes
@@ -203,3 +673,61 @@ impl<N: Network> Storage for RpcStorage<N> {
         Ok(block_hash)
     }
 }
+
+impl<N: Network> Storage for RpcStorage<N> {
+    type Error = TransportError;
+
+    fn basic(&self, address: &Address) -> Result<Option<AccountBasic>, Self::Error> {
+        self.runtime.block_on(AsyncStorage::basic(self, address))
+    }
+
+    fn code_hash(&self, address: &Address) -> Result<Option<B256>, Self::Error> {
+        self.runtime
+            .block_on(AsyncStorage::code_hash(self, address))
+    }
+
+    fn code_by_hash(&self, code_hash: &B256) -> Result<Option<EvmCode>, Self::Error> {
+        self.runtime
+            .block_on(AsyncStorage::code_by_hash(self, code_hash))
+    }
+
+    fn has_storage(&self, address: &Address) -> Result<bool, Self::Error> {
+        self.runtime
+            .block_on(AsyncStorage::has_storage(self, address))
+    }
+
+    fn storage(&self, address: &Address, index: &U256) -> Result<U256, Self::Error> {
+        self.runtime
+            .block_on(AsyncStorage::storage(self, address, index))
+    }
+
+    fn block_hash(&self, number: &u64) -> Result<B256, Self::Error> {
+        self.runtime
+            .block_on(AsyncStorage::block_hash(self, number))
+    }
+
+    fn basic_many(&self, addresses: &[Address]) -> Result<Vec<Option<AccountBasic>>, Self::Error> {
+        // Proofs are fetched and verified per account.
+        #[cfg(feature = "state-root")]
+        if self.verified_state_root.is_some() {
+            return self.runtime.block_on(try_join_all(
+                addresses
+                    .iter()
+                    .map(|address| AsyncStorage::basic(self, address)),
+            ));
+        }
+        self.runtime.block_on(self.basic_batch(addresses))
+    }
+
+    fn storage_many(&self, slots: &[(Address, U256)]) -> Result<Vec<U256>, Self::Error> {
+        #[cfg(feature = "state-root")]
+        if self.verified_state_root.is_some() {
+            return self.runtime.block_on(try_join_all(
+                slots
+                    .iter()
+                    .map(|(address, index)| AsyncStorage::storage(self, address, index)),
+            ));
+        }
+        self.runtime.block_on(self.storage_batch(slots))
+    }
+}
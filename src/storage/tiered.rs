@@ -0,0 +1,274 @@
+use alloy_primitives::{Address, B256, U256};
+use dashmap::DashMap;
+
+use super::EvmCode;
+use crate::{AccountBasic, Storage};
+
+/// A cache-like storage tier in a [TieredStorage], which knows what it
+/// doesn't have so reads can fall through to the next tier.
+/// Each getter returns [None] on a miss.
+pub trait StorageTier {
+    /// Get basic account information.
+    fn cached_basic(&self, address: &Address) -> Option<Option<AccountBasic>>;
+
+    /// Get the code hash of an account.
+    fn cached_code_hash(&self, address: &Address) -> Option<Option<B256>>;
+
+    /// Get account code by its hash.
+    fn cached_code_by_hash(&self, code_hash: &B256) -> Option<Option<EvmCode>>;
+
+    /// Get if the account already has storage.
+    fn cached_has_storage(&self, address: &Address) -> Option<bool>;
+
+    /// Get storage value of address at index.
+    fn cached_storage(&self, address: &Address, index: &U256) -> Option<U256>;
+
+    /// Get block hash by block number.
+    fn cached_block_hash(&self, number: &u64) -> Option<B256>;
+
+    /// Fill basic account information read from a lower tier.
+    /// Read-only tiers can ignore all fills.
+    fn fill_basic(&self, _address: Address, _basic: Option<AccountBasic>) {}
+
+    /// Fill the code hash of an account read from a lower tier.
+    fn fill_code_hash(&self, _address: Address, _code_hash: Option<B256>) {}
+
+    /// Fill account code read from a lower tier.
+    fn fill_code_by_hash(&self, _code_hash: B256, _code: Option<EvmCode>) {}
+
+    /// Fill if the account has storage, read from a lower tier.
+    fn fill_has_storage(&self, _address: Address, _has_storage: bool) {}
+
+    /// Fill a storage value read from a lower tier.
+    fn fill_storage(&self, _address: Address, _index: U256, _value: U256) {}
+
+    /// Fill a block hash read from a lower tier.
+    fn fill_block_hash(&self, _number: u64, _block_hash: B256) {}
+}
+
+/// An unbounded in-memory [StorageTier] that keeps everything filled into
+/// it, like a hot cache for long-running replays.
+#[derive(Debug, Default, Clone)]
+pub struct MemoryTier {
+    basics: DashMap<Address, Option<AccountBasic>>,
+    code_hashes: DashMap<Address, Option<B256>>,
+    bytecodes: DashMap<B256, Option<EvmCode>>,
+    has_storages: DashMap<Address, bool>,
+    storage: DashMap<(Address, U256), U256>,
+    block_hashes: DashMap<u64, B256>,
+}
+
+impl StorageTier for MemoryTier {
+    fn cached_basic(&self, address: &Address) -> Option<Option<AccountBasic>> {
+        self.basics.get(address).map(|basic| basic.clone())
+    }
+
+    fn cached_code_hash(&self, address: &Address) -> Option<Option<B256>> {
+        self.code_hashes.get(address).map(|code_hash| *code_hash)
+    }
+
+    fn cached_code_by_hash(&self, code_hash: &B256) -> Option<Option<EvmCode>> {
+        self.bytecodes.get(code_hash).map(|code| code.clone())
+    }
+
+    fn cached_has_storage(&self, address: &Address) -> Option<bool> {
+        self.has_storages
+            .get(address)
+            .map(|has_storage| *has_storage)
+    }
+
+    fn cached_storage(&self, address: &Address, index: &U256) -> Option<U256> {
+        self.storage.get(&(*address, *index)).map(|value| *value)
+    }
+
+    fn cached_block_hash(&self, number: &u64) -> Option<B256> {
+        self.block_hashes.get(number).map(|block_hash| *block_hash)
+    }
+
+    fn fill_basic(&self, address: Address, basic: Option<AccountBasic>) {
+        self.basics.insert(address, basic);
+    }
+
+    fn fill_code_hash(&self, address: Address, code_hash: Option<B256>) {
+        self.code_hashes.insert(address, code_hash);
+    }
+
+    fn fill_code_by_hash(&self, code_hash: B256, code: Option<EvmCode>) {
+        self.bytecodes.insert(code_hash, code);
+    }
+
+    fn fill_has_storage(&self, address: Address, has_storage: bool) {
+        self.has_storages.insert(address, has_storage);
+    }
+
+    fn fill_storage(&self, address: Address, index: U256, value: U256) {
+        self.storage.insert((address, index), value);
+    }
+
+    fn fill_block_hash(&self, number: u64, block_hash: B256) {
+        self.block_hashes.insert(number, block_hash);
+    }
+}
+
+/// A storage where misses in the first tier fall through to the second
+/// storage then fill back. Tiers can be nested via the second storage, like
+/// a memory tier over a disk tier over RPC.
+#[derive(Debug, Clone)]
+pub struct TieredStorage<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<A, B> TieredStorage<A, B> {
+    /// Construct a new [TieredStorage]
+    pub fn new(first: A, second: B) -> Self {
+        Self { first, second }
+    }
+
+    /// Get the first tier.
+    pub fn first(&self) -> &A {
+        &self.first
+    }
+
+    /// Get the second storage.
+    pub fn second(&self) -> &B {
+        &self.second
+    }
+}
+
+impl<A: StorageTier, B: Storage> Storage for TieredStorage<A, B> {
+    type Error = B::Error;
+
+    fn basic(&self, address: &Address) -> Result<Option<AccountBasic>, Self::Error> {
+        if let Some(basic) = self.first.cached_basic(address) {
+            return Ok(basic);
+        }
+        let basic = self.second.basic(address)?;
+        self.first.fill_basic(*address, basic.clone());
+        Ok(basic)
+    }
+
+    fn code_hash(&self, address: &Address) -> Result<Option<B256>, Self::Error> {
+        if let Some(code_hash) = self.first.cached_code_hash(address) {
+            return Ok(code_hash);
+        }
+        let code_hash = self.second.code_hash(address)?;
+        self.first.fill_code_hash(*address, code_hash);
+        Ok(code_hash)
+    }
+
+    fn code_by_hash(&self, code_hash: &B256) -> Result<Option<EvmCode>, Self::Error> {
+        if let Some(code) = self.first.cached_code_by_hash(code_hash) {
+            return Ok(code);
+        }
+        let code = self.second.code_by_hash(code_hash)?;
+        self.first.fill_code_by_hash(*code_hash, code.clone());
+        Ok(code)
+    }
+
+    fn has_storage(&self, address: &Address) -> Result<bool, Self::Error> {
+        if let Some(has_storage) = self.first.cached_has_storage(address) {
+            return Ok(has_storage);
+        }
+        let has_storage = self.second.has_storage(address)?;
+        self.first.fill_has_storage(*address, has_storage);
+        Ok(has_storage)
+    }
+
+    fn storage(&self, address: &Address, index: &U256) -> Result<U256, Self::Error> {
+        if let Some(value) = self.first.cached_storage(address, index) {
+            return Ok(value);
+        }
+        let value = self.second.storage(address, index)?;
+        self.first.fill_storage(*address, *index, value);
+        Ok(value)
+    }
+
+    fn block_hash(&self, number: &u64) -> Result<B256, Self::Error> {
+        if let Some(block_hash) = self.first.cached_block_hash(number) {
+            return Ok(block_hash);
+        }
+        let block_hash = self.second.block_hash(number)?;
+        self.first.fill_block_hash(*number, block_hash);
+        Ok(block_hash)
+    }
+
+    // The batched lookups only forward the misses of the first tier to the
+    // second storage in one batch.
+
+    fn basic_many(&self, addresses: &[Address]) -> Result<Vec<Option<AccountBasic>>, Self::Error> {
+        let mut basics: Vec<_> = addresses
+            .iter()
+            .map(|address| self.first.cached_basic(address))
+            .collect();
+        let missed_addresses: Vec<_> = addresses
+            .iter()
+            .zip(&basics)
+            .filter(|(_, basic)| basic.is_none())
+            .map(|(address, _)| *address)
+            .collect();
+        if !missed_addresses.is_empty() {
+            let mut missed_basics = self.second.basic_many(&missed_addresses)?.into_iter();
+            for (address, basic) in addresses.iter().zip(basics.iter_mut()) {
+                if basic.is_none() {
+                    let missed_basic = missed_basics.next().unwrap();
+                    self.first.fill_basic(*address, missed_basic.clone());
+                    *basic = Some(missed_basic);
+                }
+            }
+        }
+        Ok(basics.into_iter().map(Option::unwrap).collect())
+    }
+
+    fn code_by_hash_many(&self, code_hashes: &[B256]) -> Result<Vec<Option<EvmCode>>, Self::Error> {
+        let mut codes: Vec<_> = code_hashes
+            .iter()
+            .map(|code_hash| self.first.cached_code_by_hash(code_hash))
+            .collect();
+        let missed_code_hashes: Vec<_> = code_hashes
+            .iter()
+            .zip(&codes)
+            .filter(|(_, code)| code.is_none())
+            .map(|(code_hash, _)| *code_hash)
+            .collect();
+        if !missed_code_hashes.is_empty() {
+            let mut missed_codes = self
+                .second
+                .code_by_hash_many(&missed_code_hashes)?
+                .into_iter();
+            for (code_hash, code) in code_hashes.iter().zip(codes.iter_mut()) {
+                if code.is_none() {
+                    let missed_code = missed_codes.next().unwrap();
+                    self.first
+                        .fill_code_by_hash(*code_hash, missed_code.clone());
+                    *code = Some(missed_code);
+                }
+            }
+        }
+        Ok(codes.into_iter().map(Option::unwrap).collect())
+    }
+
+    fn storage_many(&self, slots: &[(Address, U256)]) -> Result<Vec<U256>, Self::Error> {
+        let mut values: Vec<_> = slots
+            .iter()
+            .map(|(address, index)| self.first.cached_storage(address, index))
+            .collect();
+        let missed_slots: Vec<_> = slots
+            .iter()
+            .zip(&values)
+            .filter(|(_, value)| value.is_none())
+            .map(|(slot, _)| *slot)
+            .collect();
+        if !missed_slots.is_empty() {
+            let mut missed_values = self.second.storage_many(&missed_slots)?.into_iter();
+            for ((address, index), value) in slots.iter().zip(values.iter_mut()) {
+                if value.is_none() {
+                    let missed_value = missed_values.next().unwrap();
+                    self.first.fill_storage(*address, *index, missed_value);
+                    *value = Some(missed_value);
+                }
+            }
+        }
+        Ok(values.into_iter().map(Option::unwrap).collect())
+    }
+}
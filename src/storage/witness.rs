@@ -0,0 +1,141 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use alloy_primitives::{Address, B256, U256};
+use dashmap::DashSet;
+use serde::{Deserialize, Serialize};
+
+use super::EvmCode;
+use crate::{AccountBasic, Storage};
+
+/// The pre-block state that executions read, recorded by a
+/// [WitnessStorage] for stateless clients and provers to fetch exactly the
+/// proofs and preimages to re-execute the block with.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExecutionWitness {
+    /// The accounts whose basic info, code hash or storage emptiness was read.
+    pub accounts: BTreeSet<Address>,
+    /// The storage slots read, by account.
+    pub storage: BTreeMap<Address, BTreeSet<U256>>,
+    /// The hashes of the contract codes read.
+    pub code_hashes: BTreeSet<B256>,
+    /// The numbers of the blocks whose hashes were read.
+    pub block_hashes: BTreeSet<u64>,
+}
+
+/// A [Storage] decorator that records every location read from the wrapped
+/// storage into an [ExecutionWitness]. Parallel executions only read from
+/// storage what lower transactions haven't written, so this is the
+/// pre-block state of the block, plus what aborted incarnations read that
+/// their final incarnations didn't.
+#[derive(Debug)]
+pub struct WitnessStorage<S> {
+    storage: S,
+    accounts: DashSet<Address>,
+    slots: DashSet<(Address, U256)>,
+    code_hashes: DashSet<B256>,
+    block_hashes: DashSet<u64>,
+}
+
+impl<S> WitnessStorage<S> {
+    /// Construct a new [WitnessStorage]
+    pub fn new(storage: S) -> Self {
+        Self {
+            storage,
+            accounts: DashSet::new(),
+            slots: DashSet::new(),
+            code_hashes: DashSet::new(),
+            block_hashes: DashSet::new(),
+        }
+    }
+
+    /// Get the underlying storage.
+    pub fn inner(&self) -> &S {
+        &self.storage
+    }
+
+    /// Get the witness of the reads recorded so far.
+    pub fn witness(&self) -> ExecutionWitness {
+        let mut storage: BTreeMap<Address, BTreeSet<U256>> = BTreeMap::new();
+        for slot in self.slots.iter() {
+            let (address, index) = *slot;
+            storage.entry(address).or_default().insert(index);
+        }
+        ExecutionWitness {
+            accounts: self.accounts.iter().map(|address| *address).collect(),
+            storage,
+            code_hashes: self
+                .code_hashes
+                .iter()
+                .map(|code_hash| *code_hash)
+                .collect(),
+            block_hashes: self.block_hashes.iter().map(|number| *number).collect(),
+        }
+    }
+
+    /// Clear the recorded reads, like between blocks.
+    pub fn clear(&self) {
+        self.accounts.clear();
+        self.slots.clear();
+        self.code_hashes.clear();
+        self.block_hashes.clear();
+    }
+}
+
+impl<S: Storage> Storage for WitnessStorage<S> {
+    type Error = S::Error;
+
+    fn basic(&self, address: &Address) -> Result<Option<AccountBasic>, Self::Error> {
+        self.accounts.insert(*address);
+        self.storage.basic(address)
+    }
+
+    fn code_hash(&self, address: &Address) -> Result<Option<B256>, Self::Error> {
+        self.accounts.insert(*address);
+        let code_hash = self.storage.code_hash(address)?;
+        if let Some(code_hash) = code_hash {
+            self.code_hashes.insert(code_hash);
+        }
+        Ok(code_hash)
+    }
+
+    fn code_by_hash(&self, code_hash: &B256) -> Result<Option<EvmCode>, Self::Error> {
+        self.code_hashes.insert(*code_hash);
+        self.storage.code_by_hash(code_hash)
+    }
+
+    fn has_storage(&self, address: &Address) -> Result<bool, Self::Error> {
+        self.accounts.insert(*address);
+        self.storage.has_storage(address)
+    }
+
+    fn storage(&self, address: &Address, index: &U256) -> Result<U256, Self::Error> {
+        self.slots.insert((*address, *index));
+        self.storage.storage(address, index)
+    }
+
+    fn block_hash(&self, number: &u64) -> Result<B256, Self::Error> {
+        self.block_hashes.insert(*number);
+        self.storage.block_hash(number)
+    }
+
+    fn basic_many(&self, addresses: &[Address]) -> Result<Vec<Option<AccountBasic>>, Self::Error> {
+        for address in addresses {
+            self.accounts.insert(*address);
+        }
+        self.storage.basic_many(addresses)
+    }
+
+    fn code_by_hash_many(&self, code_hashes: &[B256]) -> Result<Vec<Option<EvmCode>>, Self::Error> {
+        for code_hash in code_hashes {
+            self.code_hashes.insert(*code_hash);
+        }
+        self.storage.code_by_hash_many(code_hashes)
+    }
+
+    fn storage_many(&self, slots: &[(Address, U256)]) -> Result<Vec<U256>, Self::Error> {
+        for slot in slots {
+            self.slots.insert(*slot);
+        }
+        self.storage.storage_many(slots)
+    }
+}
@@ -0,0 +1,175 @@
+//! Helpers to mock accounts and blocks and to check executions against
+//! sequential execution and block headers, for crates integrating pevm to
+//! write their own correctness tests.
+
+use std::{num::NonZeroUsize, thread};
+
+use alloy_primitives::{Address, Bloom, Bytes, B256, U160, U256};
+use alloy_rpc_types::{Block, Header};
+use revm::primitives::{BlockEnv, SpecId, TransactTo, TxEnv};
+
+use crate::{
+    chain::{PevmChain, PevmEthereum},
+    EvmAccount, InMemoryStorage, PevmResult, PevmTxExecutionResult, Storage,
+};
+
+/// The gas limit of raw transfers.
+pub const RAW_TRANSFER_GAS_LIMIT: u64 = 21_000;
+
+/// A header with the minimal fields to execute an Alloy block.
+pub static MOCK_BLOCK_HEADER: Header = Header {
+    // Minimal requirements for execution
+    number: Some(1),
+    timestamp: 1710338135,
+    mix_hash: Some(B256::ZERO),
+    excess_blob_gas: Some(0),
+    blob_gas_used: Some(0),
+    gas_limit: u128::MAX,
+    // Defaults
+    hash: None,
+    parent_hash: B256::ZERO,
+    uncles_hash: B256::ZERO,
+    miner: Address::ZERO,
+    state_root: B256::ZERO,
+    transactions_root: B256::ZERO,
+    receipts_root: B256::ZERO,
+    logs_bloom: Bloom::ZERO,
+    difficulty: U256::ZERO,
+    gas_used: 0,
+    total_difficulty: Some(U256::ZERO),
+    extra_data: Bytes::new(),
+    nonce: None,
+    base_fee_per_gas: None,
+    withdrawals_root: None,
+    parent_beacon_block_root: None,
+    requests_root: None,
+};
+
+/// Mock an account from an integer index that is used as the address.
+/// Useful for mock iterations.
+pub fn mock_account(idx: usize) -> (Address, EvmAccount) {
+    let address = Address::from(U160::from(idx));
+    let account = EvmAccount {
+        // Filling half full accounts to have enough tokens for tests without worrying about
+        // the corner case of balance not going beyond [U256::MAX].
+        balance: U256::MAX.div_ceil(U256::from(2)),
+        nonce: 1,
+        ..EvmAccount::default()
+    };
+    (address, account)
+}
+
+/// Mock a storage with the mock accounts of indices `0..num_accounts`, the
+/// first being the beneficiary account (`Address::ZERO`).
+pub fn mock_storage(num_accounts: usize) -> InMemoryStorage<'static> {
+    InMemoryStorage::new((0..num_accounts).map(mock_account), None, [])
+}
+
+/// Mock `block_size` raw transfers of the mock accounts `1..=block_size`,
+/// each sending some tokens to the next account, to execute on
+/// [mock_storage] of `block_size + 1` accounts.
+pub fn mock_raw_transfers(block_size: usize) -> Vec<TxEnv> {
+    (1..=block_size)
+        .map(|i| TxEnv {
+            caller: Address::from(U160::from(i)),
+            transact_to: TransactTo::Call(Address::from(U160::from(i % block_size + 1))),
+            value: U256::from(1),
+            gas_limit: RAW_TRANSFER_GAS_LIMIT,
+            gas_price: U256::from(1),
+            ..TxEnv::default()
+        })
+        .collect()
+}
+
+/// Assert that the sequential and parallel results of an execution match.
+pub fn assert_execution_result<C: PevmChain + PartialEq>(
+    sequential_result: &PevmResult<C>,
+    parallel_result: &PevmResult<C>,
+) {
+    assert_eq!(sequential_result, parallel_result);
+}
+
+/// Assert that the transaction results of an Alloy block match its header's
+/// receipts root, logs bloom and gas used.
+pub fn assert_block_header<C: PevmChain>(
+    chain: &C,
+    block: &Block,
+    tx_results: &[PevmTxExecutionResult],
+) {
+    let spec_id = chain.get_block_spec(&block.header).unwrap();
+
+    // We can only calculate the receipts root from Byzantium.
+    // Before EIP-658 (https://eips.ethereum.org/EIPS/eip-658), the
+    // receipt root is calculated with the post transaction state root,
+    // which we don't have here.
+    if block.header.number.unwrap() >= 4370000 {
+        assert_eq!(
+            block.header.receipts_root,
+            chain.calculate_receipt_root(spec_id, &block.transactions, tx_results)
+        );
+    }
+
+    assert_eq!(
+        block.header.logs_bloom,
+        tx_results
+            .iter()
+            .map(|tx| *tx.receipt.logs_bloom())
+            .fold(Bloom::default(), |acc, bloom| acc.bit_or(bloom))
+    );
+
+    assert_eq!(
+        block.header.gas_used,
+        tx_results
+            .iter()
+            .last()
+            .map(|result| result.receipt.cumulative_gas_used())
+            .unwrap_or_default()
+    );
+    assert_eq!(
+        block.header.gas_used,
+        tx_results
+            .iter()
+            .map(|result| result.gas_used as u128)
+            .sum::<u128>()
+    );
+}
+
+/// Execute an REVM block sequentially & with PEVM and assert that
+/// the execution results match.
+pub fn test_execute_revm<S: Storage + Clone + Send + Sync>(storage: S, txs: Vec<TxEnv>) {
+    let concurrency_level = thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+    assert_execution_result(
+        &crate::execute_revm_sequential(
+            &storage,
+            &PevmEthereum::mainnet(),
+            SpecId::LATEST,
+            BlockEnv::default(),
+            txs.clone(),
+        ),
+        &crate::execute_revm_parallel(
+            &storage,
+            &PevmEthereum::mainnet(),
+            SpecId::LATEST,
+            BlockEnv::default(),
+            txs,
+            concurrency_level,
+        ),
+    );
+}
+
+/// Execute an Alloy block sequentially & with PEVM and assert that
+/// the execution results match, and optionally the block header.
+pub fn test_execute_alloy<S: Storage + Send + Sync, C: PevmChain + Send + Sync + PartialEq>(
+    storage: &S,
+    chain: &C,
+    block: Block,
+    must_match_block_header: bool,
+) {
+    let concurrency_level = thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+    let sequential_result = crate::execute(storage, chain, block.clone(), concurrency_level, true);
+    let parallel_result = crate::execute(storage, chain, block.clone(), concurrency_level, false);
+    assert_execution_result(&sequential_result, &parallel_result);
+    if must_match_block_header {
+        assert_block_header(chain, &block, &sequential_result.unwrap().tx_results);
+    }
+}
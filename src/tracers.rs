@@ -0,0 +1,528 @@
+//! Built-in [InspectorFactory]s for the tracers that nodes serve via the
+//! `debug_trace*` and `trace_*` RPC methods, to trace blocks from parallel
+//! execution.
+
+use std::collections::BTreeMap;
+
+use alloy_primitives::{Address, Bytes, B256, U256};
+use alloy_rpc_types_trace::{
+    geth::{AccountState, CallFrame, PreStateFrame, PreStateMode},
+    parity::{
+        Action, CallAction, CallOutput, CallType, CreateAction, CreateOutput, SelfdestructAction,
+        TraceOutput, TransactionTrace,
+    },
+};
+use revm::{
+    interpreter::{
+        CallInputs, CallOutcome, CallScheme, CreateInputs, CreateOutcome, InstructionResult,
+        InterpreterResult,
+    },
+    primitives::{CreateScheme, EVMError, SpecId, KECCAK_EMPTY},
+    Database, EvmContext, Inspector,
+};
+
+use crate::InspectorFactory;
+
+/// An [InspectorFactory] for call frame traces compatible with Geth's
+/// `callTracer`, to serve `debug_traceBlockByNumber` with the tracer's
+/// output (from, to, value, input, output, error & nested calls) directly
+/// from a parallel execution.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CallTracer {
+    /// Only trace the top-level call of each transaction, like the tracer's
+    /// `onlyTopCall` option.
+    pub only_top_call: bool,
+}
+
+impl InspectorFactory for CallTracer {
+    type Inspector<DB: Database> = CallTracerInspector;
+    type Output = CallFrame;
+
+    fn inspector<DB: Database>(&self, _tx_idx: usize) -> CallTracerInspector {
+        CallTracerInspector {
+            only_top_call: self.only_top_call,
+            ..CallTracerInspector::default()
+        }
+    }
+
+    fn finish<DB: Database>(&self, inspector: CallTracerInspector) -> CallFrame {
+        inspector.root.unwrap_or_default()
+    }
+}
+
+/// The inspector that records the call frames of an execution for
+/// [CallTracer].
+#[derive(Debug, Default)]
+pub struct CallTracerInspector {
+    only_top_call: bool,
+    // The frames of the ongoing calls, from the top-level call.
+    stack: Vec<CallFrame>,
+    root: Option<CallFrame>,
+}
+
+impl CallTracerInspector {
+    fn start_frame(&mut self, frame: CallFrame) {
+        self.stack.push(frame);
+    }
+
+    fn end_frame(&mut self, result: &InterpreterResult, gas_used: u64, to: Option<Address>) {
+        let Some(mut frame) = self.stack.pop() else {
+            return;
+        };
+        frame.gas_used = U256::from(gas_used);
+        if to.is_some() {
+            frame.to = to;
+        }
+        if result.result.is_ok() || result.result.is_revert() {
+            frame.output = (!result.output.is_empty()).then(|| result.output.clone());
+        }
+        if result.result.is_revert() {
+            frame.error = Some(String::from("execution reverted"));
+            frame.revert_reason = decode_revert_reason(&result.output);
+        } else if !result.result.is_ok() {
+            frame.error = Some(error_message(result.result));
+        }
+        match self.stack.last_mut() {
+            Some(parent) if !self.only_top_call => parent.calls.push(frame),
+            Some(_) => {}
+            None => self.root = Some(frame),
+        }
+    }
+
+    // The gas used by the whole transaction for the top-level frame, after
+    // refunds, and by the frame otherwise.
+    fn gas_used<DB: Database>(&self, context: &EvmContext<DB>, result: &InterpreterResult) -> u64 {
+        if self.stack.len() > 1 {
+            return result.gas.spent();
+        }
+        let spent = context.env.tx.gas_limit - result.gas.remaining();
+        let max_refund_quotient = if context.spec_id().is_enabled_in(SpecId::LONDON) {
+            5
+        } else {
+            2
+        };
+        spent - (result.gas.refunded().max(0) as u64).min(spent / max_refund_quotient)
+    }
+
+    // The gas limit of the transaction for the top-level frame, and of the
+    // frame otherwise.
+    fn gas_limit<DB: Database>(&self, context: &EvmContext<DB>, gas_limit: u64) -> U256 {
+        U256::from(if self.stack.is_empty() {
+            context.env.tx.gas_limit
+        } else {
+            gas_limit
+        })
+    }
+}
+
+impl<DB: Database> Inspector<DB> for CallTracerInspector {
+    fn call(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        inputs: &mut CallInputs,
+    ) -> Option<CallOutcome> {
+        // Delegate calls execute the callee's code on behalf of the caller.
+        let from = if matches!(inputs.scheme, CallScheme::DelegateCall) {
+            inputs.target_address
+        } else {
+            inputs.caller
+        };
+        let value = match inputs.scheme {
+            CallScheme::DelegateCall | CallScheme::StaticCall => None,
+            _ => Some(inputs.call_value()),
+        };
+        self.start_frame(CallFrame {
+            from,
+            gas: self.gas_limit(context, inputs.gas_limit),
+            to: Some(inputs.bytecode_address),
+            input: inputs.input.clone(),
+            value,
+            typ: format!("{:?}", inputs.scheme).to_uppercase(),
+            ..CallFrame::default()
+        });
+        None
+    }
+
+    fn call_end(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        _inputs: &CallInputs,
+        outcome: CallOutcome,
+    ) -> CallOutcome {
+        let gas_used = self.gas_used(context, &outcome.result);
+        self.end_frame(&outcome.result, gas_used, None);
+        outcome
+    }
+
+    fn create(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        inputs: &mut CreateInputs,
+    ) -> Option<CreateOutcome> {
+        self.start_frame(CallFrame {
+            from: inputs.caller,
+            gas: self.gas_limit(context, inputs.gas_limit),
+            input: inputs.init_code.clone(),
+            value: Some(inputs.value),
+            typ: String::from(if matches!(inputs.scheme, CreateScheme::Create2 { .. }) {
+                "CREATE2"
+            } else {
+                "CREATE"
+            }),
+            ..CallFrame::default()
+        });
+        None
+    }
+
+    fn create_end(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        _inputs: &CreateInputs,
+        outcome: CreateOutcome,
+    ) -> CreateOutcome {
+        let gas_used = self.gas_used(context, &outcome.result);
+        self.end_frame(&outcome.result, gas_used, outcome.address);
+        outcome
+    }
+
+    fn selfdestruct(&mut self, contract: Address, target: Address, value: U256) {
+        if self.only_top_call {
+            return;
+        }
+        if let Some(parent) = self.stack.last_mut() {
+            parent.calls.push(CallFrame {
+                from: contract,
+                to: Some(target),
+                value: Some(value),
+                typ: String::from("SELFDESTRUCT"),
+                ..CallFrame::default()
+            });
+        }
+    }
+}
+
+/// An [InspectorFactory] for the pre-transaction states of every account
+/// and storage slot that each transaction read, in the shape of Geth's
+/// `prestateTracer` output, for stateless re-execution and debugging.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PrestateTracer;
+
+impl InspectorFactory for PrestateTracer {
+    type Inspector<DB: Database> = PrestateTracerInspector;
+    type Output = PreStateFrame;
+
+    fn inspector<DB: Database>(&self, _tx_idx: usize) -> PrestateTracerInspector {
+        PrestateTracerInspector::default()
+    }
+
+    fn finish<DB: Database>(&self, inspector: PrestateTracerInspector) -> PreStateFrame {
+        PreStateFrame::Default(PreStateMode(inspector.prestate))
+    }
+}
+
+/// The inspector that records the pre-transaction states read by an
+/// execution for [PrestateTracer].
+#[derive(Debug, Default)]
+pub struct PrestateTracerInspector {
+    // The depth of the ongoing call, to record the read states once the
+    // top-level call ends.
+    depth: usize,
+    prestate: BTreeMap<Address, AccountState>,
+}
+
+impl PrestateTracerInspector {
+    // Record the pre-transaction states of the accounts & storage slots
+    // that the transaction has loaded. The database still holds them as
+    // the transaction's changes are only committed after its execution.
+    fn record_prestate<DB: Database>(
+        &mut self,
+        context: &mut EvmContext<DB>,
+    ) -> Result<(), DB::Error> {
+        for (address, account) in context.inner.journaled_state.state.iter() {
+            // Accounts created by this transaction didn't exist before it.
+            let Some(info) = context.inner.db.basic(*address)? else {
+                continue;
+            };
+            let code = match info.code {
+                Some(code) => code.original_bytes(),
+                None if info.code_hash != KECCAK_EMPTY => context
+                    .inner
+                    .db
+                    .code_by_hash(info.code_hash)?
+                    .original_bytes(),
+                None => Bytes::new(),
+            };
+            self.prestate.insert(
+                *address,
+                AccountState {
+                    balance: Some(info.balance),
+                    code: (!code.is_empty()).then_some(code),
+                    nonce: (info.nonce > 0).then_some(info.nonce),
+                    storage: account
+                        .storage
+                        .iter()
+                        .map(|(slot, value)| {
+                            (B256::from(*slot), B256::from(value.original_value()))
+                        })
+                        .collect(),
+                },
+            );
+        }
+        Ok(())
+    }
+
+    fn end_frame<DB: Database>(&mut self, context: &mut EvmContext<DB>) {
+        self.depth -= 1;
+        if self.depth == 0 {
+            if let Err(err) = self.record_prestate(context) {
+                // Fail the execution to retry it like other read errors.
+                context.inner.error = Err(EVMError::Database(err));
+            }
+        }
+    }
+}
+
+impl<DB: Database> Inspector<DB> for PrestateTracerInspector {
+    fn call(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        _inputs: &mut CallInputs,
+    ) -> Option<CallOutcome> {
+        self.depth += 1;
+        None
+    }
+
+    fn call_end(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        _inputs: &CallInputs,
+        outcome: CallOutcome,
+    ) -> CallOutcome {
+        self.end_frame(context);
+        outcome
+    }
+
+    fn create(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        _inputs: &mut CreateInputs,
+    ) -> Option<CreateOutcome> {
+        self.depth += 1;
+        None
+    }
+
+    fn create_end(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        _inputs: &CreateInputs,
+        outcome: CreateOutcome,
+    ) -> CreateOutcome {
+        self.end_frame(context);
+        outcome
+    }
+}
+
+/// An [InspectorFactory] for the Parity-style traces of each transaction,
+/// the call, create & selfdestruct actions with their trace addresses, to
+/// serve `trace_block` and `trace_filter` directly from a parallel
+/// execution. The traces of a transaction are in execution order, each
+/// action before its subtraces.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ParityTracer;
+
+impl InspectorFactory for ParityTracer {
+    type Inspector<DB: Database> = ParityTracerInspector;
+    type Output = Vec<TransactionTrace>;
+
+    fn inspector<DB: Database>(&self, _tx_idx: usize) -> ParityTracerInspector {
+        ParityTracerInspector::default()
+    }
+
+    fn finish<DB: Database>(&self, inspector: ParityTracerInspector) -> Vec<TransactionTrace> {
+        inspector.traces
+    }
+}
+
+/// The inspector that records the actions of an execution for
+/// [ParityTracer].
+#[derive(Debug, Default)]
+pub struct ParityTracerInspector {
+    traces: Vec<TransactionTrace>,
+    // The indices in [traces] of the ongoing calls, from the top-level call.
+    stack: Vec<usize>,
+}
+
+impl ParityTracerInspector {
+    // Record a trace as the next subtrace of the ongoing call.
+    fn push_trace(&mut self, action: Action) -> usize {
+        let trace_address = match self.stack.last() {
+            Some(&parent_idx) => {
+                let parent = &mut self.traces[parent_idx];
+                let mut trace_address = parent.trace_address.clone();
+                trace_address.push(parent.subtraces);
+                parent.subtraces += 1;
+                trace_address
+            }
+            None => Vec::new(),
+        };
+        self.traces.push(TransactionTrace {
+            action,
+            error: None,
+            result: None,
+            subtraces: 0,
+            trace_address,
+        });
+        self.traces.len() - 1
+    }
+
+    fn end_frame(&mut self, result: &InterpreterResult, address: Option<Address>) {
+        let Some(trace_idx) = self.stack.pop() else {
+            return;
+        };
+        let trace = &mut self.traces[trace_idx];
+        if result.result.is_ok() {
+            let gas_used = result.gas.spent();
+            trace.result = Some(match address {
+                Some(address) => TraceOutput::Create(CreateOutput {
+                    gas_used,
+                    code: result.output.clone(),
+                    address,
+                }),
+                None => TraceOutput::Call(CallOutput {
+                    gas_used,
+                    output: result.output.clone(),
+                }),
+            });
+        } else {
+            trace.error = Some(parity_error_message(result.result));
+        }
+    }
+}
+
+impl<DB: Database> Inspector<DB> for ParityTracerInspector {
+    fn call(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        inputs: &mut CallInputs,
+    ) -> Option<CallOutcome> {
+        // Delegate calls execute the callee's code on behalf of the caller.
+        let (from, call_type) = match inputs.scheme {
+            CallScheme::Call => (inputs.caller, CallType::Call),
+            CallScheme::CallCode => (inputs.caller, CallType::CallCode),
+            CallScheme::DelegateCall => (inputs.target_address, CallType::DelegateCall),
+            CallScheme::StaticCall => (inputs.caller, CallType::StaticCall),
+        };
+        let value = match inputs.scheme {
+            CallScheme::DelegateCall | CallScheme::StaticCall => U256::ZERO,
+            _ => inputs.call_value(),
+        };
+        let trace_idx = self.push_trace(Action::Call(CallAction {
+            from,
+            call_type,
+            gas: inputs.gas_limit,
+            input: inputs.input.clone(),
+            to: inputs.bytecode_address,
+            value,
+        }));
+        self.stack.push(trace_idx);
+        None
+    }
+
+    fn call_end(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        _inputs: &CallInputs,
+        outcome: CallOutcome,
+    ) -> CallOutcome {
+        self.end_frame(&outcome.result, None);
+        outcome
+    }
+
+    fn create(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        inputs: &mut CreateInputs,
+    ) -> Option<CreateOutcome> {
+        let trace_idx = self.push_trace(Action::Create(CreateAction {
+            from: inputs.caller,
+            value: inputs.value,
+            gas: inputs.gas_limit,
+            init: inputs.init_code.clone(),
+        }));
+        self.stack.push(trace_idx);
+        None
+    }
+
+    fn create_end(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        _inputs: &CreateInputs,
+        outcome: CreateOutcome,
+    ) -> CreateOutcome {
+        // Failed creations have no address to report.
+        let address = outcome.address.unwrap_or_default();
+        self.end_frame(&outcome.result, Some(address));
+        outcome
+    }
+
+    fn selfdestruct(&mut self, contract: Address, target: Address, value: U256) {
+        self.push_trace(Action::Selfdestruct(SelfdestructAction {
+            address: contract,
+            refund_address: target,
+            balance: value,
+        }));
+    }
+}
+
+// Parity's error messages for the common halts.
+fn parity_error_message(result: InstructionResult) -> String {
+    String::from(match result {
+        InstructionResult::Revert => "Reverted",
+        InstructionResult::OutOfGas
+        | InstructionResult::MemoryOOG
+        | InstructionResult::MemoryLimitOOG
+        | InstructionResult::PrecompileOOG
+        | InstructionResult::InvalidOperandOOG => "Out of gas",
+        InstructionResult::OpcodeNotFound | InstructionResult::InvalidFEOpcode => "Bad instruction",
+        InstructionResult::InvalidJump => "Bad jump destination",
+        InstructionResult::StackUnderflow => "Stack underflow",
+        InstructionResult::StackOverflow | InstructionResult::CallTooDeep => "Out of stack",
+        InstructionResult::StateChangeDuringStaticCall => "Mutable Call In Static Context",
+        result => return format!("{result:?}"),
+    })
+}
+
+// Geth's error messages for the common halts.
+fn error_message(result: InstructionResult) -> String {
+    String::from(match result {
+        InstructionResult::OutOfGas
+        | InstructionResult::MemoryOOG
+        | InstructionResult::MemoryLimitOOG
+        | InstructionResult::PrecompileOOG
+        | InstructionResult::InvalidOperandOOG => "out of gas",
+        InstructionResult::OpcodeNotFound | InstructionResult::InvalidFEOpcode => "invalid opcode",
+        InstructionResult::InvalidJump => "invalid jump destination",
+        InstructionResult::StackUnderflow => "stack underflow",
+        InstructionResult::StackOverflow => "stack overflow",
+        InstructionResult::CallTooDeep => "max call depth exceeded",
+        InstructionResult::OutOfFunds => "insufficient balance for transfer",
+        InstructionResult::CreateCollision => "contract address collision",
+        InstructionResult::StateChangeDuringStaticCall => "write protection",
+        result => return format!("{result:?}"),
+    })
+}
+
+// Decode the message of a revert with `Error(string)`.
+fn decode_revert_reason(output: &Bytes) -> Option<String> {
+    const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+    let data = output.strip_prefix(&ERROR_SELECTOR)?;
+    let word = |offset: usize| -> Option<usize> {
+        let word = data.get(offset..offset.checked_add(32)?)?;
+        U256::from_be_slice(word).try_into().ok()
+    };
+    let offset = word(0)?;
+    let len = word(offset)?;
+    let start = offset.checked_add(32)?;
+    let message = data.get(start..start.checked_add(len)?)?;
+    String::from_utf8(message.to_vec()).ok()
+}
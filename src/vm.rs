@@ -1,22 +1,33 @@
 use ahash::{AHashMap, HashMapExt};
-use alloy_rpc_types::Receipt;
+use alloy_consensus::{Receipt, ReceiptEnvelope, ReceiptWithBloom, TxType};
+use alloy_rpc_types::{AccessList, AccessListItem};
 use dashmap::DashMap;
 use defer_drop::DeferDrop;
 use revm::{
+    handler::Handler,
+    inspector_handle_register,
+    inspectors::NoOpInspector,
+    precompile::PrecompileWithAddress,
     primitives::{
-        AccountInfo, Address, BlockEnv, Bytecode, CfgEnv, EVMError, Env, InvalidTransaction,
-        ResultAndState, SpecId, TransactTo, TxEnv, B256, KECCAK_EMPTY, U256,
+        AccountInfo, Address, BlockEnv, Bytecode, Bytes, CfgEnv, EVMError, Env, ExecutionResult,
+        InvalidTransaction, ResultAndState, SpecId, TransactTo, TxEnv, B256, KECCAK_EMPTY, U256,
     },
-    Context, Database, Evm, EvmContext,
+    Context, ContextPrecompile, Database, Evm, EvmContext, Inspector,
+};
+use std::{
+    collections::HashMap,
+    iter, mem,
+    sync::{Arc, Mutex},
 };
-use std::collections::HashMap;
 
 use crate::{
     chain::{PevmChain, RewardPolicy},
     mv_memory::MvMemory,
-    AccountBasic, BuildIdentityHasher, EvmAccount, MemoryEntry, MemoryLocation, MemoryLocationHash,
-    MemoryValue, NewLazyAddresses, ReadError, ReadOrigin, ReadSet, Storage, TxIdx, TxVersion,
-    WriteSet,
+    pevm::ExecutionMode,
+    scheduler::RetryPolicy,
+    AccountBasic, BuildAddressHasher, BuildFoldHasher, BuildIdentityHasher, EvmAccount,
+    MemoryEntry, MemoryLocation, MemoryLocationHash, MemoryValue, NewLazyAddresses, ReadError,
+    ReadOrigin, ReadSet, Storage, StorageError, TxIdx, TxVersion, WriteSet,
 };
 
 /// The execution error from the underlying EVM executor.
@@ -26,29 +37,88 @@ pub type ExecutionError = EVMError<ReadError>;
 /// Represents the state transitions of the EVM accounts after execution.
 /// If the value is [None], it indicates that the account is marked for removal.
 /// If the value is [Some(new_state)], it indicates that the account has become [new_state].
-type EvmStateTransitions = AHashMap<Address, Option<EvmAccount>>;
+pub type EvmStateTransitions = AHashMap<Address, Option<EvmAccount>>;
 
 /// Execution result of a transaction
 #[derive(Debug, Clone, PartialEq)]
 pub struct PevmTxExecutionResult {
-    /// Receipt of execution
-    // TODO: Consider promoting to [ReceiptEnvelope] if there is high demand
-    pub receipt: Receipt,
+    /// Typed receipt of execution
+    pub receipt: ReceiptEnvelope,
     /// State that got updated
     pub state: EvmStateTransitions,
+    /// Blob gas used by the transaction's blobs (EIP-4844), which is
+    /// accounted separately from the execution gas in [receipt].
+    pub blob_gas_used: u64,
+    /// Gas used by this transaction alone, after refunds.
+    pub gas_used: u64,
+    /// Gas refunded to the sender at the end of execution.
+    pub gas_refunded: u64,
+    /// The price per gas paid by the sender, which is capped by the base
+    /// fee plus the priority fee for EIP-1559 transactions.
+    pub effective_gas_price: u128,
+    /// Raw output of the transaction, like the returned data of a call or
+    /// the revert payload. Empty for halted transactions.
+    pub output: Bytes,
+    /// The decoded reason of a reverted transaction, for the standard
+    /// `Error(string)` and `Panic(uint256)` payloads.
+    pub revert_reason: Option<String>,
+    /// The accounts and storage slots accessed by the transaction, sorted,
+    /// excluding the sender and the beneficiary when none of their slots are
+    /// accessed. This can be used as the transaction's access list (EIP-2930)
+    /// or as dependency hints for re-execution.
+    pub access_list: AccessList,
 }
 
 impl PevmTxExecutionResult {
     /// Construct a Pevm execution result from a raw Revm result.
     /// Note that [cumulative_gas_used] is preset to the gas used of this transaction.
     /// It should be post-processed with the remaining transactions in the block.
-    pub fn from_revm(spec_id: SpecId, ResultAndState { result, state }: ResultAndState) -> Self {
+    pub fn from_revm(
+        spec_id: SpecId,
+        block_env: &BlockEnv,
+        tx: &TxEnv,
+        ResultAndState { result, state }: ResultAndState,
+    ) -> Self {
+        let gas_used = result.gas_used();
+        let gas_refunded = match &result {
+            ExecutionResult::Success { gas_refunded, .. } => *gas_refunded,
+            _ => 0,
+        };
+        let mut access_list: Vec<AccessListItem> = state
+            .iter()
+            .filter(|(address, account)| {
+                !account.storage.is_empty()
+                    || (**address != tx.caller && **address != block_env.coinbase)
+            })
+            .map(|(address, account)| {
+                let mut storage_keys: Vec<B256> = account
+                    .storage
+                    .keys()
+                    .map(|slot| B256::from(slot.to_be_bytes::<32>()))
+                    .collect();
+                storage_keys.sort_unstable();
+                AccessListItem {
+                    address: *address,
+                    storage_keys,
+                }
+            })
+            .collect();
+        access_list.sort_unstable_by_key(|item| item.address);
+        let output = result.output().cloned().unwrap_or_default();
+        let revert_reason = match &result {
+            ExecutionResult::Revert { .. } => decode_revert_reason(&output),
+            _ => None,
+        };
         Self {
-            receipt: Receipt {
-                status: result.is_success().into(),
-                cumulative_gas_used: result.gas_used() as u128,
-                logs: result.into_logs(),
-            },
+            receipt: with_tx_type(
+                Receipt {
+                    status: result.is_success().into(),
+                    cumulative_gas_used: gas_used as u128,
+                    logs: result.into_logs(),
+                }
+                .with_bloom(),
+                infer_tx_type(tx),
+            ),
             state: state
                 .into_iter()
                 .filter(|(_, account)| account.is_touched())
@@ -63,33 +133,133 @@ impl PevmTxExecutionResult {
                     }
                 })
                 .collect(),
+            blob_gas_used: tx.get_total_blob_gas(),
+            gas_used,
+            gas_refunded,
+            effective_gas_price: effective_gas_price(tx, block_env.basefee).saturating_to(),
+            output,
+            revert_reason,
+            access_list: AccessList(access_list),
         }
     }
 }
 
+// Decode the standard `Error(string)` and `Panic(uint256)` revert payloads.
+fn decode_revert_reason(output: &[u8]) -> Option<String> {
+    if output.len() < 4 {
+        return None;
+    }
+    let (selector, data) = output.split_at(4);
+    match selector {
+        // `Error(string)`
+        [0x08, 0xc3, 0x79, 0xa0] => {
+            let offset = usize::try_from(U256::try_from_be_slice(data.get(..32)?)?).ok()?;
+            let length_end = offset.checked_add(32)?;
+            let length =
+                usize::try_from(U256::try_from_be_slice(data.get(offset..length_end)?)?).ok()?;
+            let reason = data.get(length_end..length_end.checked_add(length)?)?;
+            Some(String::from_utf8_lossy(reason).into_owned())
+        }
+        // `Panic(uint256)`
+        [0x4e, 0x48, 0x7b, 0x71] => {
+            let code = U256::try_from_be_slice(data.get(..32)?)?;
+            Some(format!("panic code {code:#x}"))
+        }
+        _ => None,
+    }
+}
+
+// The price per gas paid by the sender (EIP-1559).
+fn effective_gas_price(tx: &TxEnv, basefee: U256) -> U256 {
+    if let Some(priority_fee) = tx.gas_priority_fee {
+        std::cmp::min(tx.gas_price, priority_fee + basefee)
+    } else {
+        tx.gas_price
+    }
+}
+
+// The type of a transaction inferred from its environment. EIP-2930
+// transactions with empty access lists are indistinguishable from legacy
+// ones here, so callers that know the actual types should re-tag them.
+fn infer_tx_type(tx: &TxEnv) -> TxType {
+    if !tx.blob_hashes.is_empty() {
+        TxType::Eip4844
+    } else if tx.gas_priority_fee.is_some() {
+        TxType::Eip1559
+    } else if !tx.access_list.is_empty() {
+        TxType::Eip2930
+    } else {
+        TxType::Legacy
+    }
+}
+
+pub(crate) fn with_tx_type(receipt: ReceiptWithBloom, tx_type: TxType) -> ReceiptEnvelope {
+    match tx_type {
+        TxType::Legacy => ReceiptEnvelope::Legacy(receipt),
+        TxType::Eip2930 => ReceiptEnvelope::Eip2930(receipt),
+        TxType::Eip1559 => ReceiptEnvelope::Eip1559(receipt),
+        TxType::Eip4844 => ReceiptEnvelope::Eip4844(receipt),
+    }
+}
+
+pub(crate) fn receipt_with_bloom_mut(receipt: &mut ReceiptEnvelope) -> &mut ReceiptWithBloom {
+    match receipt {
+        ReceiptEnvelope::Legacy(receipt)
+        | ReceiptEnvelope::Eip2930(receipt)
+        | ReceiptEnvelope::Eip1559(receipt)
+        | ReceiptEnvelope::Eip4844(receipt) => receipt,
+        // We only construct the above types via [with_tx_type].
+        _ => unreachable!(),
+    }
+}
+
+// The maximum number of codes to cache across executions, beyond which
+// new codes are read from storage every time.
+const MAX_CACHED_BYTECODES: usize = 100_000;
+
+// A concurrent cache of converted (and for EOF, decoded) codes by their
+// hashes, kept by [Pevm] across executions to only prepare each contract
+// once.
+#[derive(Debug, Default)]
+pub(crate) struct BytecodeCache(DashMap<B256, Bytecode>);
+
+impl BytecodeCache {
+    fn get(&self, code_hash: &B256) -> Option<Bytecode> {
+        self.0.get(code_hash).map(|code| code.clone())
+    }
+
+    fn insert(&self, code_hash: B256, code: Bytecode) {
+        if self.0.len() < MAX_CACHED_BYTECODES {
+            self.0.insert(code_hash, code);
+        }
+    }
+}
+
+// The number of times to optimistically retry a failing transaction
+// outside of [ExecutionMode::Sync], before treating it as invalid.
+const MAX_OPTIMISTIC_RETRIES: usize = 3;
+
 // TODO: Rewrite as [Result]
 pub(crate) enum VmExecutionResult {
     Retry,
-    FallbackToSequential,
+    FallbackToSequential(ReadError),
     ReadError {
         blocking_tx_idx: TxIdx,
     },
     ExecutionError(ExecutionError),
+    // The transaction is invalid on the state it has read, which is only
+    // final once its lower transactions are validated. We record its read
+    // set to re-execute it when that state changes.
+    InvalidTransaction {
+        error: ExecutionError,
+        read_set: ReadSet,
+        write_set: WriteSet,
+    },
     Ok {
         execution_result: PevmTxExecutionResult,
         read_set: ReadSet,
         write_set: WriteSet,
         lazy_addresses: NewLazyAddresses,
-        // From which transaction index do we need to validate from after
-        // this execution. This is [None] when no validation is required.
-        // For instance, for transactions that only read and write to the
-        // from and to addresses, which preprocessing & lazy evaluation has
-        // already covered. Note that this is used to set the min validation
-        // index in the scheduler, meaning a `None` here will still be validated
-        // if there was a lower transaction that has broken the preprocessed
-        // dependency chain and returned [Some].
-        // TODO: Better name & doc
-        next_validation_idx: Option<TxIdx>,
     },
 }
 
@@ -98,8 +268,8 @@ pub(crate) enum VmExecutionResult {
 // structure & storage, and tracks the read set of the current execution.
 // TODO: Simplify this type, like grouping [from] and [to] into a
 // [preprocessed_addresses] or a [preprocessed_locations] vector.
-struct VmDb<'a, S: Storage, C: PevmChain> {
-    vm: &'a Vm<'a, S, C>,
+struct VmDb<'a, S: Storage, C: PevmChain, X: ExternalFactory> {
+    vm: &'a Vm<'a, S, C, X>,
     tx_idx: &'a TxIdx,
     nonce: u64,
     from: &'a Address,
@@ -113,11 +283,17 @@ struct VmDb<'a, S: Storage, C: PevmChain> {
     read_set: ReadSet,
     // TODO: Clearer type for [AccountBasic] plus code hash
     read_accounts: HashMap<MemoryLocationHash, (AccountBasic, Option<B256>), BuildIdentityHasher>,
+    // The index of the lower transaction that last deployed or self-destructed
+    // each account read in this execution, which resets its storage.
+    storage_reset_idxs: HashMap<Address, Option<TxIdx>, BuildAddressHasher>,
+    // The storage location hashes computed in this execution, to not rehash
+    // the slots that are read then written.
+    storage_hashes: HashMap<(Address, U256), MemoryLocationHash, BuildFoldHasher>,
 }
 
-impl<'a, S: Storage, C: PevmChain> VmDb<'a, S, C> {
+impl<'a, S: Storage, C: PevmChain, X: ExternalFactory> VmDb<'a, S, C, X> {
     fn new(
-        vm: &'a Vm<'a, S, C>,
+        vm: &'a Vm<'a, S, C, X>,
         tx_idx: &'a TxIdx,
         nonce: u64,
         from: &'a Address,
@@ -139,16 +315,18 @@ impl<'a, S: Storage, C: PevmChain> VmDb<'a, S, C> {
             // read at least from the sender and recipient accounts.
             read_set: ReadSet::with_capacity(2),
             read_accounts: HashMap::with_capacity_and_hasher(2, BuildIdentityHasher::default()),
+            storage_reset_idxs: HashMap::with_hasher(BuildAddressHasher::default()),
+            storage_hashes: HashMap::with_hasher(BuildFoldHasher::default()),
         };
         // We only lazy update raw transfers that already have the sender
         // or recipient in [MvMemory] since sequentially evaluating memory
         // locations with only one entry is much costlier than fully
         // evaluating it concurrently.
-        // TODO: Only lazy update in block syncing mode, not for block
-        // building.
         if let Some(to) = to {
             db.to_code_hash = db.get_code_hash(*to)?;
-            db.is_lazy = db.to_code_hash.is_none()
+            db.is_lazy = vm.mode.lazy_updates()
+                && db.to_code_hash.is_none()
+                && !vm.precompiles.iter().any(|precompile| precompile.0 == *to)
                 && (vm.mv_memory.have_location(&from_hash)
                     || vm.mv_memory.have_location(&to_hash.unwrap()));
         }
@@ -165,8 +343,25 @@ impl<'a, S: Storage, C: PevmChain> VmDb<'a, S, C> {
         }
     }
 
-    fn get_code_hash(&mut self, address: Address) -> Result<Option<B256>, ReadError> {
-        let location_hash = self.vm.hasher.hash_one(MemoryLocation::CodeHash(address));
+    fn hash_storage(&mut self, address: Address, index: U256) -> MemoryLocationHash {
+        *self
+            .storage_hashes
+            .entry((address, index))
+            .or_insert_with(|| {
+                self.vm
+                    .hasher
+                    .hash_one(MemoryLocation::Storage(address, index))
+            })
+    }
+
+    // Read the latest code hash entry of an account in [MvMemory], which is
+    // written when a lower transaction deploys (Some) or self-destructs (None)
+    // the account. Return [None] when there is no such entry.
+    fn read_code_hash_entry(
+        &mut self,
+        address: Address,
+    ) -> Result<Option<(TxIdx, Option<B256>)>, ReadError> {
+        let location_hash = self.vm.hash_code_hash(&address);
         let read_origins = self.read_set.entry(location_hash).or_default();
         let prev_origin = read_origins.last();
 
@@ -174,26 +369,30 @@ impl<'a, S: Storage, C: PevmChain> VmDb<'a, S, C> {
         // TODO: Memoize read locations (expected to be small) here in [Vm] to avoid
         // contention in [MvMemory]
         if let Some(written_transactions) = self.vm.mv_memory.read_location(&location_hash) {
-            if let Some((
-                tx_idx,
-                MemoryEntry::Data(tx_incarnation, MemoryValue::CodeHash(code_hash)),
-            )) = written_transactions.range(..self.tx_idx).next_back()
+            if let Some((closest_idx, entry)) =
+                written_transactions.range(..self.tx_idx).next_back()
             {
-                if code_hash.is_none() {
-                    return Err(ReadError::SelfDestructedAccount);
-                }
-                let origin = ReadOrigin::MvMemory(TxVersion {
-                    tx_idx: *tx_idx,
-                    tx_incarnation: *tx_incarnation,
-                });
-                if let Some(prev_origin) = prev_origin {
-                    if prev_origin != &origin {
-                        return Err(ReadError::InconsistentRead);
+                match entry {
+                    MemoryEntry::Data(tx_incarnation, MemoryValue::CodeHash(code_hash)) => {
+                        let origin = ReadOrigin::MvMemory(TxVersion {
+                            tx_idx: *closest_idx,
+                            tx_incarnation: *tx_incarnation,
+                        });
+                        if let Some(prev_origin) = prev_origin {
+                            if prev_origin != &origin {
+                                return Err(ReadError::InconsistentRead);
+                            }
+                        } else {
+                            read_origins.push(origin);
+                        }
+                        return Ok(Some((*closest_idx, *code_hash)));
                     }
-                } else {
-                    read_origins.push(origin);
+                    MemoryEntry::Estimate => {
+                        self.vm.mv_memory.record_conflict(&location_hash);
+                        return Err(ReadError::BlockingIndex(*closest_idx));
+                    }
+                    _ => return Err(ReadError::InvalidMemoryLocationType),
                 }
-                return Ok(*code_hash);
             }
         };
 
@@ -205,14 +404,40 @@ impl<'a, S: Storage, C: PevmChain> VmDb<'a, S, C> {
         } else {
             read_origins.push(ReadOrigin::Storage);
         }
-        self.vm
-            .storage
-            .code_hash(&address)
-            .map_err(|err| ReadError::StorageError(err.to_string()))
+        Ok(None)
+    }
+
+    fn get_code_hash(&mut self, address: Address) -> Result<Option<B256>, ReadError> {
+        match self.read_code_hash_entry(address)? {
+            // A self-destructed account has no code.
+            Some((_, code_hash)) => Ok(code_hash),
+            None => self
+                .vm
+                .storage
+                .code_hash(&address)
+                .map_err(|err| ReadError::StorageError(StorageError::new(err))),
+        }
+    }
+
+    // Get the index of the lower transaction that last deployed or
+    // self-destructed an account. Storage slots that were not written after
+    // this index are cleared, instead of being read from storage.
+    fn get_storage_reset_idx(&mut self, address: Address) -> Result<Option<TxIdx>, ReadError> {
+        if self.tx_idx == &0 {
+            return Ok(None);
+        }
+        if let Some(reset_idx) = self.storage_reset_idxs.get(&address) {
+            return Ok(*reset_idx);
+        }
+        let reset_idx = self
+            .read_code_hash_entry(address)?
+            .map(|(tx_idx, _)| tx_idx);
+        self.storage_reset_idxs.insert(address, reset_idx);
+        Ok(reset_idx)
     }
 }
 
-impl<'a, S: Storage, C: PevmChain> Database for VmDb<'a, S, C> {
+impl<'a, S: Storage, C: PevmChain, X: ExternalFactory> Database for VmDb<'a, S, C, X> {
     type Error = ReadError;
 
     fn basic(&mut self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
@@ -241,6 +466,9 @@ impl<'a, S: Storage, C: PevmChain> Database for VmDb<'a, S, C> {
         let mut new_origins = Vec::new();
 
         let mut final_account = None;
+        // Whether a lower transaction has self-destructed this account, so
+        // we must not fall back to its pre-block state in storage.
+        let mut self_destructed = false;
         let mut balance_addition = U256::ZERO;
         // The sign of [balance_addition] since it can be negative for lazy senders.
         let mut positive_addition = true;
@@ -265,11 +493,12 @@ impl<'a, S: Storage, C: PevmChain> Database for VmDb<'a, S, C> {
                 loop {
                     match iter.next_back() {
                         Some((blocking_idx, MemoryEntry::Estimate)) => {
+                            self.vm.mv_memory.record_conflict(&location_hash);
                             return if need_consecutive_idxs {
                                 reschedule
                             } else {
                                 Err(ReadError::BlockingIndex(*blocking_idx))
-                            }
+                            };
                         }
                         Some((closest_idx, MemoryEntry::Data(tx_incarnation, value))) => {
                             if need_consecutive_idxs && closest_idx != &(current_idx - 1) {
@@ -294,10 +523,8 @@ impl<'a, S: Storage, C: PevmChain> Database for VmDb<'a, S, C> {
                             new_origins.push(origin);
                             match value {
                                 MemoryValue::Basic(basic) => {
-                                    if basic.is_none() {
-                                        return Err(ReadError::SelfDestructedAccount);
-                                    }
                                     final_account.clone_from(basic);
+                                    self_destructed = basic.is_none();
                                     break;
                                 }
                                 MemoryValue::LazyRecipient(addition) => {
@@ -335,8 +562,14 @@ impl<'a, S: Storage, C: PevmChain> Database for VmDb<'a, S, C> {
             }
         }
 
-        // Fall back to storage
-        if final_account.is_none() {
+        if self_destructed {
+            // A self-destructed account only exists again if it has received
+            // funds since.
+            if balance_addition > U256::ZERO {
+                final_account = Some(AccountBasic::default());
+            }
+        } else if final_account.is_none() {
+            // Fall back to storage
             // Populate [Storage] on the first read
             if !has_prev_origins {
                 new_origins.push(ReadOrigin::Storage);
@@ -357,7 +590,7 @@ impl<'a, S: Storage, C: PevmChain> Database for VmDb<'a, S, C> {
                         None
                     }
                 }
-                Err(err) => return Err(ReadError::StorageError(err.to_string())),
+                Err(err) => return Err(ReadError::StorageError(StorageError::new(err))),
             };
         }
 
@@ -372,9 +605,9 @@ impl<'a, S: Storage, C: PevmChain> Database for VmDb<'a, S, C> {
             account.nonce += nonce_addition;
             if location_hash == self.from_hash && account.nonce != self.nonce {
                 if self.tx_idx > &0 {
-                    // TODO: Better retry strategy -- immediately, to the
-                    // closest sender tx, to the missing sender tx, etc.
-                    return Err(ReadError::BlockingIndex(self.tx_idx - 1));
+                    return Err(ReadError::BlockingIndex(
+                        self.vm.nonce_blocking_idx(*self.tx_idx),
+                    ));
                 } else {
                     return Err(ReadError::InvalidNonce);
                 }
@@ -393,17 +626,9 @@ impl<'a, S: Storage, C: PevmChain> Database for VmDb<'a, S, C> {
             } else {
                 self.get_code_hash(address)?
             };
-            let code = if let Some(code_hash) = &code_hash {
-                if let Some(code) = self.vm.new_bytecodes.get(code_hash) {
-                    Some(code.clone())
-                } else {
-                    match self.vm.storage.code_by_hash(code_hash) {
-                        Ok(code) => code.map(Bytecode::from),
-                        Err(err) => return Err(ReadError::StorageError(err.to_string())),
-                    }
-                }
-            } else {
-                None
+            let code = match &code_hash {
+                Some(code_hash) => self.vm.code_by_hash(code_hash)?,
+                None => None,
             };
             self.read_accounts
                 .insert(location_hash, (account.clone(), code_hash));
@@ -421,24 +646,26 @@ impl<'a, S: Storage, C: PevmChain> Database for VmDb<'a, S, C> {
 
     fn code_by_hash(&mut self, code_hash: B256) -> Result<Bytecode, Self::Error> {
         self.vm
-            .storage
             .code_by_hash(&code_hash)
-            .map(|code| code.map(Bytecode::from).unwrap_or_default())
-            .map_err(|err| ReadError::StorageError(err.to_string()))
+            .map(Option::unwrap_or_default)
     }
 
     fn has_storage(&mut self, address: Address) -> Result<bool, Self::Error> {
+        // The pre-block storage has been cleared for accounts that were
+        // deployed or self-destructed in this block.
+        if self.get_storage_reset_idx(address)?.is_some() {
+            return Ok(false);
+        }
         self.vm
             .storage
             .has_storage(&address)
-            .map_err(|err| ReadError::StorageError(err.to_string()))
+            .map_err(|err| ReadError::StorageError(StorageError::new(err)))
     }
 
     fn storage(&mut self, address: Address, index: U256) -> Result<U256, Self::Error> {
-        let location_hash = self
-            .vm
-            .hasher
-            .hash_one(MemoryLocation::Storage(address, index));
+        let location_hash = self.hash_storage(address, index);
+
+        let storage_reset_idx = self.get_storage_reset_idx(address)?;
 
         let read_origins = self.read_set.entry(location_hash).or_default();
         let prev_origin = read_origins.last();
@@ -446,8 +673,12 @@ impl<'a, S: Storage, C: PevmChain> Database for VmDb<'a, S, C> {
         // Try reading from multi-version data
         if self.tx_idx > &0 {
             if let Some(written_transactions) = self.vm.mv_memory.read_location(&location_hash) {
-                if let Some((closest_idx, entry)) =
-                    written_transactions.range(..self.tx_idx).next_back()
+                // Writes before the account was last deployed or self-destructed
+                // have been cleared. The writes of the resetting transaction
+                // itself are kept, like of the deploying constructor.
+                if let Some((closest_idx, entry)) = written_transactions
+                    .range(storage_reset_idx.unwrap_or(0)..*self.tx_idx)
+                    .next_back()
                 {
                     match entry {
                         MemoryEntry::Data(tx_incarnation, MemoryValue::Storage(value)) => {
@@ -465,7 +696,8 @@ impl<'a, S: Storage, C: PevmChain> Database for VmDb<'a, S, C> {
                             return Ok(*value);
                         }
                         MemoryEntry::Estimate => {
-                            return Err(ReadError::BlockingIndex(*closest_idx))
+                            self.vm.mv_memory.record_conflict(&location_hash);
+                            return Err(ReadError::BlockingIndex(*closest_idx));
                         }
                         _ => return Err(ReadError::InvalidMemoryLocationType),
                     }
@@ -481,42 +713,95 @@ impl<'a, S: Storage, C: PevmChain> Database for VmDb<'a, S, C> {
         } else {
             read_origins.push(ReadOrigin::Storage);
         }
+        // The slot is cleared if the account was deployed or self-destructed
+        // in this block without writing to it since.
+        if storage_reset_idx.is_some() {
+            return Ok(U256::ZERO);
+        }
         self.vm
             .storage
             .storage(&address, &index)
-            .map_err(|err| ReadError::StorageError(err.to_string()))
+            .map_err(|err| ReadError::StorageError(StorageError::new(err)))
     }
 
     fn block_hash(&mut self, number: u64) -> Result<B256, Self::Error> {
         self.vm
             .storage
             .block_hash(&number)
-            .map_err(|err| ReadError::StorageError(err.to_string()))
+            .map_err(|err| ReadError::StorageError(StorageError::new(err)))
+    }
+}
+
+// The transaction environments of a block, lent to the EVM of each
+// incarnation instead of cloned for it, as large calldata, access lists and
+// blob hashes are costly to clone under heavy aborts. The scheduler never
+// runs two incarnations of a transaction at once, so the locks are never
+// contended.
+pub(crate) struct TxEnvs(Vec<Mutex<TxEnv>>);
+
+impl TxEnvs {
+    pub(crate) fn new(txs: Vec<TxEnv>) -> Self {
+        Self(txs.into_iter().map(Mutex::new).collect())
+    }
+
+    pub(crate) fn into_inner(self) -> Vec<TxEnv> {
+        self.0
+            .into_iter()
+            .map(|tx| tx.into_inner().unwrap())
+            .collect()
+    }
+
+    fn senders(&self) -> impl Iterator<Item = Address> + '_ {
+        self.0.iter().map(|tx| tx.lock().unwrap().caller)
+    }
+
+    // The senders and call recipients of the transactions.
+    fn accounts(&self) -> impl Iterator<Item = Address> + '_ {
+        self.0.iter().flat_map(|tx| {
+            let tx = tx.lock().unwrap();
+            iter::once(tx.caller).chain(tx.transact_to.to().copied())
+        })
     }
 }
 
-pub(crate) struct Vm<'a, S: Storage, C: PevmChain> {
+pub(crate) struct Vm<'a, S: Storage, C: PevmChain, X: ExternalFactory> {
     hasher: &'a ahash::RandomState,
     storage: &'a S,
     mv_memory: &'a MvMemory,
     chain: &'a C,
     block_env: &'a BlockEnv,
-    txs: &'a [TxEnv],
+    txs: &'a TxEnvs,
     spec_id: SpecId,
+    mode: ExecutionMode,
     beneficiary_location_hash: MemoryLocationHash,
     reward_policy: RewardPolicy,
+    retry_policy: RetryPolicy,
+    bytecode_cache: &'a BytecodeCache,
+    precompiles: &'a [PrecompileWithAddress],
+    externals: &'a X,
+    sender_dependencies: Option<Vec<Option<TxIdx>>>,
     new_bytecodes: DeferDrop<DashMap<B256, Bytecode>>,
+    // The precomputed basic & code hash location hashes of the beneficiary
+    // and the transactions' senders & recipients, which nearly every
+    // execution reads and writes.
+    account_hashes: HashMap<Address, (MemoryLocationHash, MemoryLocationHash), BuildAddressHasher>,
 }
 
-impl<'a, S: Storage, C: PevmChain> Vm<'a, S, C> {
+impl<'a, S: Storage, C: PevmChain, X: ExternalFactory> Vm<'a, S, C, X> {
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         hasher: &'a ahash::RandomState,
         storage: &'a S,
         mv_memory: &'a MvMemory,
         chain: &'a C,
         block_env: &'a BlockEnv,
-        txs: &'a [TxEnv],
+        txs: &'a TxEnvs,
         spec_id: SpecId,
+        mode: ExecutionMode,
+        retry_policy: RetryPolicy,
+        bytecode_cache: &'a BytecodeCache,
+        precompiles: &'a [PrecompileWithAddress],
+        externals: &'a X,
     ) -> Self {
         Self {
             hasher,
@@ -526,17 +811,78 @@ impl<'a, S: Storage, C: PevmChain> Vm<'a, S, C> {
             block_env,
             txs,
             spec_id,
+            mode,
             beneficiary_location_hash: hasher.hash_one(MemoryLocation::Basic(block_env.coinbase)),
             reward_policy: chain.get_reward_policy(hasher),
+            retry_policy,
+            sender_dependencies: retry_policy.sender_dependencies(txs.senders()),
+            bytecode_cache,
+            precompiles,
+            externals,
             // TODO: Fine-tune the number of shards, like to the next number of two from the
             // number of worker threads.
             new_bytecodes: DeferDrop::new(DashMap::default()),
+            account_hashes: iter::once(block_env.coinbase)
+                .chain(txs.accounts())
+                .map(|address| {
+                    (
+                        address,
+                        (
+                            hasher.hash_one(MemoryLocation::Basic(address)),
+                            hasher.hash_one(MemoryLocation::CodeHash(address)),
+                        ),
+                    )
+                })
+                .collect(),
         }
     }
 
     #[inline(always)]
     fn hash_basic(&self, address: &Address) -> MemoryLocationHash {
-        self.hasher.hash_one(MemoryLocation::Basic(*address))
+        match self.account_hashes.get(address) {
+            Some((basic_hash, _)) => *basic_hash,
+            None => self.hasher.hash_one(MemoryLocation::Basic(*address)),
+        }
+    }
+
+    #[inline(always)]
+    fn hash_code_hash(&self, address: &Address) -> MemoryLocationHash {
+        match self.account_hashes.get(address) {
+            Some((_, code_location_hash)) => *code_location_hash,
+            None => self.hasher.hash_one(MemoryLocation::CodeHash(*address)),
+        }
+    }
+
+    // Get a code by its hash, from the codes deployed in this block, the
+    // codes cached from previous reads, or storage.
+    fn code_by_hash(&self, code_hash: &B256) -> Result<Option<Bytecode>, ReadError> {
+        if let Some(code) = self.new_bytecodes.get(code_hash) {
+            return Ok(Some(code.clone()));
+        }
+        if let Some(code) = self.bytecode_cache.get(code_hash) {
+            return Ok(Some(code));
+        }
+        match self.storage.code_by_hash(code_hash) {
+            Ok(code) => Ok(code.map(|code| {
+                let code = Bytecode::from(code);
+                self.bytecode_cache.insert(*code_hash, code.clone());
+                code
+            })),
+            Err(err) => Err(ReadError::StorageError(StorageError::new(err))),
+        }
+    }
+
+    pub(crate) fn retry_policy(&self) -> RetryPolicy {
+        self.retry_policy
+    }
+
+    // The transaction to wait for when a (non-first) transaction's sender
+    // nonce is ahead of the lower transactions executed so far.
+    fn nonce_blocking_idx(&self, tx_idx: TxIdx) -> TxIdx {
+        self.sender_dependencies
+            .as_ref()
+            .and_then(|sender_dependencies| sender_dependencies[tx_idx])
+            .unwrap_or(tx_idx - 1)
     }
 
     // Execute a transaction. This can read from memory but cannot modify any state.
@@ -555,13 +901,28 @@ impl<'a, S: Storage, C: PevmChain> Vm<'a, S, C> {
     // value are added to the write set, possibly replacing a pair with a prior value
     // (if it is not the first time the transaction wrote to this location during the
     // execution).
-    pub(crate) fn execute(&self, tx_idx: TxIdx) -> VmExecutionResult {
+    //
+    // [attempt] counts the previous executions of this transaction, to bound
+    // the optimistic retries of failing transactions outside of
+    // [ExecutionMode::Sync].
+    //
+    // [inspection] attaches an inspector to the execution, and records its output
+    // when the execution succeeds.
+    pub(crate) fn execute<F: InspectorFactory>(
+        &self,
+        tx_idx: TxIdx,
+        attempt: usize,
+        inspection: Option<&Inspection<F>>,
+    ) -> VmExecutionResult {
+        // Closing this span times the execution, to spot slow transactions.
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!("vm_execute", tx_idx, attempt).entered();
         // SAFETY: A correct scheduler would guarantee this index to be inbound.
-        let tx = unsafe { self.txs.get_unchecked(tx_idx) };
-        let from = &tx.caller;
-        let from_hash = self.hash_basic(from);
-        let (to, to_hash) = match &tx.transact_to {
-            TransactTo::Call(address) => (Some(address), Some(self.hash_basic(address))),
+        let mut tx = unsafe { self.txs.0.get_unchecked(tx_idx) }.lock().unwrap();
+        let from = tx.caller;
+        let from_hash = self.hash_basic(&from);
+        let (to, to_hash) = match tx.transact_to {
+            TransactTo::Call(address) => (Some(address), Some(self.hash_basic(&address))),
             TransactTo::Create => (None, None),
         };
 
@@ -570,26 +931,62 @@ impl<'a, S: Storage, C: PevmChain> Vm<'a, S, C> {
             self,
             &tx_idx,
             tx.nonce.unwrap_or(1),
-            from,
+            &from,
             from_hash,
-            to,
+            to.as_ref(),
             to_hash,
         ) {
             Ok(db) => db,
+            Err(ReadError::BlockingIndex(blocking_tx_idx)) => {
+                return VmExecutionResult::ReadError { blocking_tx_idx }
+            }
             // TODO: Handle different errors differently
-            Err(_) => return VmExecutionResult::FallbackToSequential,
+            Err(err) => return VmExecutionResult::FallbackToSequential(err),
         };
+        // Inspectors may read the accounts from the database, like for their
+        // pre-transaction states, so they must see real balances instead of
+        // the mocks of lazy updates.
+        if inspection.is_some() {
+            db.is_lazy = false;
+        }
         // TODO: Share as much Evm, Context, Handler, etc. among threads as possible
         // as creating them is very expensive.
-        let mut evm = build_evm(
-            &mut db,
-            self.chain,
-            self.spec_id,
-            self.block_env.clone(),
-            false,
-        );
-        *evm.tx_mut() = tx.clone();
-        match evm.transact() {
+        let result = match inspection {
+            Some(inspection) => {
+                let mut evm = build_inspected_evm(
+                    &mut db,
+                    self.chain,
+                    self.spec_id,
+                    self.block_env.clone(),
+                    false,
+                    self.precompiles,
+                    inspection.factory.inspector::<&mut VmDb<S, C, X>>(tx_idx),
+                );
+                let result = transact(&mut evm, &mut tx);
+                // Later incarnations overwrite the outputs of aborted ones.
+                if result.is_ok() {
+                    *index_mutex!(inspection.outputs, tx_idx) = Some(
+                        inspection
+                            .factory
+                            .finish::<&mut VmDb<S, C, X>>(evm.context.external),
+                    );
+                }
+                result
+            }
+            None => {
+                let mut evm = build_evm(
+                    &mut db,
+                    self.chain,
+                    self.spec_id,
+                    self.block_env.clone(),
+                    false,
+                    self.precompiles,
+                    self.externals.external(tx_idx),
+                );
+                transact(&mut evm, &mut tx)
+            }
+        };
+        match result {
             Ok(result_and_state) => {
                 // There are at least three locations most of the time: the sender,
                 // the recipient, and the beneficiary accounts.
@@ -598,16 +995,13 @@ impl<'a, S: Storage, C: PevmChain> Vm<'a, S, C> {
                 for (address, account) in result_and_state.state.iter() {
                     if account.is_selfdestructed() {
                         write_set.push((self.hash_basic(address), MemoryValue::Basic(None)));
-                        write_set.push((
-                            self.hasher.hash_one(MemoryLocation::CodeHash(*address)),
-                            MemoryValue::CodeHash(None),
-                        ));
+                        write_set.push((self.hash_code_hash(address), MemoryValue::CodeHash(None)));
                         continue;
                     }
 
                     if account.is_touched() {
                         let account_location_hash = self.hash_basic(address);
-                        let read_account = evm.db().read_accounts.get(&account_location_hash);
+                        let read_account = db.read_accounts.get(&account_location_hash);
 
                         let has_code = !account.info.is_empty_code_hash();
                         let is_new_code = has_code
@@ -621,7 +1015,7 @@ impl<'a, S: Storage, C: PevmChain> Vm<'a, S, C> {
                                     || basic.balance != account.info.balance
                             })
                         {
-                            if evm.db().is_lazy {
+                            if db.is_lazy {
                                 if account_location_hash == from_hash {
                                     write_set.push((
                                         account_location_hash,
@@ -648,7 +1042,7 @@ impl<'a, S: Storage, C: PevmChain> Vm<'a, S, C> {
                         // Write new contract
                         if is_new_code {
                             write_set.push((
-                                self.hasher.hash_one(MemoryLocation::CodeHash(*address)),
+                                self.hash_code_hash(address),
                                 MemoryValue::CodeHash(Some(account.info.code_hash)),
                             ));
                             self.new_bytecodes
@@ -660,8 +1054,7 @@ impl<'a, S: Storage, C: PevmChain> Vm<'a, S, C> {
                     // TODO: We should move this changed check to our read set like for account info?
                     for (slot, value) in account.changed_storage_slots() {
                         write_set.push((
-                            self.hasher
-                                .hash_one(MemoryLocation::Storage(*address, *slot)),
+                            db.hash_storage(*address, *slot),
                             MemoryValue::Storage(value.present_value),
                         ));
                     }
@@ -669,42 +1062,34 @@ impl<'a, S: Storage, C: PevmChain> Vm<'a, S, C> {
 
                 self.apply_rewards(
                     &mut write_set,
-                    tx,
+                    &tx,
                     U256::from(result_and_state.result.gas_used()),
                 );
 
-                drop(evm); // release db
-
                 VmExecutionResult::Ok {
                     execution_result: PevmTxExecutionResult::from_revm(
                         self.spec_id,
+                        self.block_env,
+                        &tx,
                         result_and_state,
                     ),
                     read_set: db.read_set,
                     write_set,
                     lazy_addresses,
-                    next_validation_idx: if tx_idx == 0 || db.is_lazy {
-                        None
-                    } else {
-                        Some(tx_idx)
-                    },
                 }
             }
             Err(EVMError::Database(ReadError::InconsistentRead)) => VmExecutionResult::Retry,
-            Err(EVMError::Database(ReadError::SelfDestructedAccount)) => {
-                VmExecutionResult::FallbackToSequential
-            }
             Err(EVMError::Database(ReadError::BlockingIndex(blocking_tx_idx))) => {
                 VmExecutionResult::ReadError { blocking_tx_idx }
             }
             Err(err) => {
                 // Optimistically retry in case some previous internal transactions send
                 // more fund to the sender but hasn't been executed yet.
-                // TODO: Let users define this behaviour through a mode enum or something.
-                // Since this retry is safe for syncing canonical blocks but can deadlock
-                // on new or faulty blocks. We can skip the transaction for new blocks and
-                // error out after a number of tries for the latter.
+                // This retry is safe for syncing canonical blocks but can deadlock
+                // on new or faulty blocks, so other modes only retry a bounded number
+                // of times.
                 if tx_idx > 0
+                    && (self.mode == ExecutionMode::Sync || attempt < MAX_OPTIMISTIC_RETRIES)
                     && matches!(
                         err,
                         EVMError::Transaction(InvalidTransaction::LackOfFundForMaxFee { .. })
@@ -712,7 +1097,27 @@ impl<'a, S: Storage, C: PevmChain> Vm<'a, S, C> {
                     )
                 {
                     VmExecutionResult::ReadError {
-                        blocking_tx_idx: tx_idx - 1,
+                        blocking_tx_idx: match err {
+                            EVMError::Transaction(InvalidTransaction::NonceTooHigh { .. }) => {
+                                self.nonce_blocking_idx(tx_idx)
+                            }
+                            _ => tx_idx - 1,
+                        },
+                    }
+                } else if self.mode != ExecutionMode::Sync
+                    && matches!(err, EVMError::Transaction(_))
+                {
+                    VmExecutionResult::InvalidTransaction {
+                        error: err,
+                        read_set: db.read_set,
+                        // A zero reward keeps the beneficiary's lazy rewards
+                        // consecutive, so higher transactions that read the
+                        // beneficiary can still evaluate it instead of waiting
+                        // on this transaction forever.
+                        write_set: vec![(
+                            self.beneficiary_location_hash,
+                            MemoryValue::LazyRecipient(U256::ZERO),
+                        )],
                     }
                 } else {
                     VmExecutionResult::ExecutionError(err)
@@ -725,11 +1130,7 @@ impl<'a, S: Storage, C: PevmChain> Vm<'a, S, C> {
     fn apply_rewards(&self, write_set: &mut WriteSet, tx: &TxEnv, gas_used: U256) {
         let rewards: Vec<(MemoryLocationHash, U256)> = match self.reward_policy {
             RewardPolicy::Ethereum => {
-                let mut gas_price = if let Some(priority_fee) = tx.gas_priority_fee {
-                    std::cmp::min(tx.gas_price, priority_fee + self.block_env.basefee)
-                } else {
-                    tx.gas_price
-                };
+                let mut gas_price = effective_gas_price(tx, self.block_env.basefee);
                 if self.spec_id.is_enabled_in(SpecId::LONDON) {
                     gas_price = gas_price.saturating_sub(self.block_env.basefee);
                 }
@@ -757,15 +1158,68 @@ impl<'a, S: Storage, C: PevmChain> Vm<'a, S, C> {
     }
 }
 
-pub(crate) fn build_evm<'a, DB: Database, C: PevmChain>(
+pub(crate) fn build_evm<'a, EXT: ExternalContext, DB: Database, C: PevmChain>(
+    db: DB,
+    chain: &C,
+    spec_id: SpecId,
+    block_env: BlockEnv,
+    with_reward_beneficiary: bool,
+    precompiles: &[PrecompileWithAddress],
+    external: EXT,
+) -> Evm<'a, EXT, DB> {
+    let mut handler = chain.get_handler(spec_id, with_reward_beneficiary);
+    EXT::register_handles(&mut handler);
+    register_precompiles(&mut handler, precompiles);
+    Evm::new(build_context(db, chain, block_env, external), handler)
+}
+
+// Build an EVM like [build_evm] that calls [inspector] during execution.
+pub(crate) fn build_inspected_evm<'a, DB: Database, C: PevmChain, I: Inspector<DB>>(
     db: DB,
     chain: &C,
     spec_id: SpecId,
     block_env: BlockEnv,
     with_reward_beneficiary: bool,
-) -> Evm<'a, (), DB> {
+    precompiles: &[PrecompileWithAddress],
+    inspector: I,
+) -> Evm<'a, I, DB> {
+    let mut handler = chain.get_handler(spec_id, with_reward_beneficiary);
+    register_precompiles(&mut handler, precompiles);
+    handler.append_handler_register_plain(inspector_handle_register);
+    Evm::new(build_context(db, chain, block_env, inspector), handler)
+}
+
+// Load the custom precompiles on top of the chain's, overriding the
+// chain's precompiles at the same addresses.
+fn register_precompiles<'a, EXT, DB: Database>(
+    handler: &mut Handler<'a, Context<EXT, DB>, EXT, DB>,
+    precompiles: &[PrecompileWithAddress],
+) {
+    if precompiles.is_empty() {
+        return;
+    }
+    let precompiles = precompiles.to_vec();
+    let load_chain_precompiles = handler.pre_execution.load_precompiles.clone();
+    handler.pre_execution.load_precompiles = Arc::new(move || {
+        let mut context_precompiles = load_chain_precompiles();
+        context_precompiles.extend(precompiles.iter().map(|precompile| {
+            (
+                precompile.0,
+                ContextPrecompile::Ordinary(precompile.1.clone()),
+            )
+        }));
+        context_precompiles
+    });
+}
+
+fn build_context<EXT, DB: Database, C: PevmChain>(
+    db: DB,
+    chain: &C,
+    block_env: BlockEnv,
+    external: EXT,
+) -> Context<EXT, DB> {
     // This is much uglier than the builder interface but can be up to 50% faster!!
-    let context = Context {
+    Context {
         evm: EvmContext::new_with_env(
             db,
             Env::boxed(
@@ -774,9 +1228,102 @@ pub(crate) fn build_evm<'a, DB: Database, C: PevmChain>(
                 TxEnv::default(),
             ),
         ),
-        external: (),
-    };
+        external,
+    }
+}
+
+// Lend the transaction to the EVM and take it back after execution.
+fn transact<EXT, DB: Database>(
+    evm: &mut Evm<'_, EXT, DB>,
+    tx: &mut TxEnv,
+) -> Result<ResultAndState, EVMError<DB::Error>> {
+    *evm.tx_mut() = mem::take(tx);
+    let result = evm.transact();
+    *tx = mem::take(evm.tx_mut());
+    result
+}
+
+/// The external context of an EVM, for handler hooks that need
+/// side-channel data, like L1 block info providers or custom oracles.
+pub trait ExternalContext: Sized {
+    /// Register the handler hooks that read this context on top of the
+    /// chain's handler.
+    fn register_handles<'a, DB: Database>(_handler: &mut Handler<'a, Context<Self, DB>, Self, DB>) {
+    }
+}
+
+impl ExternalContext for () {}
 
-    let handler = chain.get_handler(spec_id, with_reward_beneficiary);
-    Evm::new(context, handler)
+/// Creates the external contexts of transaction executions via
+/// [crate::Pevm::execute_revm_parallel_with_external]. Transactions may
+/// execute several times in parallel, so each execution gets a new context.
+pub trait ExternalFactory: Sync {
+    /// The external context of an execution.
+    type External: ExternalContext;
+
+    /// Create the external context for an execution of the transaction at
+    /// [tx_idx].
+    fn external(&self, tx_idx: usize) -> Self::External;
 }
+
+// Executions without external contexts.
+impl ExternalFactory for () {
+    type External = ();
+
+    fn external(&self, _: usize) {}
+}
+
+/// Creates the revm inspectors to attach to transaction executions via
+/// [crate::Pevm::execute_revm_parallel_with_inspector], like call tracers,
+/// access list inspectors or gas profilers. Transactions may execute several
+/// times in parallel, so each execution gets a new inspector, and only the
+/// output of the final execution of each transaction is kept. Inspected
+/// executions don't lazy update accounts, so inspectors can read the real
+/// state of every account from the database.
+pub trait InspectorFactory: Sync {
+    /// The inspector of an execution, over the (internal) database that
+    /// the execution reads from.
+    type Inspector<DB: Database>: Inspector<DB>;
+    /// What to keep from the final execution of each transaction, like its
+    /// trace.
+    type Output: Send;
+
+    /// Create an inspector for an execution of the transaction at [tx_idx].
+    fn inspector<DB: Database>(&self, tx_idx: usize) -> Self::Inspector<DB>;
+
+    /// Extract the output of a successful execution from its inspector.
+    fn finish<DB: Database>(&self, inspector: Self::Inspector<DB>) -> Self::Output;
+}
+
+// An [InspectorFactory] for executions without inspectors.
+pub(crate) struct NoInspector;
+
+impl InspectorFactory for NoInspector {
+    type Inspector<DB: Database> = NoOpInspector;
+    type Output = ();
+
+    fn inspector<DB: Database>(&self, _: usize) -> Self::Inspector<DB> {
+        NoOpInspector
+    }
+
+    fn finish<DB: Database>(&self, _: Self::Inspector<DB>) -> Self::Output {}
+}
+
+// The inspector factory of an inspected block execution, and the output of
+// the last successful execution of each transaction.
+pub(crate) struct Inspection<'a, F: InspectorFactory> {
+    pub(crate) factory: &'a F,
+    pub(crate) outputs: Vec<Mutex<Option<F::Output>>>,
+}
+
+impl<'a, F: InspectorFactory> Inspection<'a, F> {
+    pub(crate) fn new(factory: &'a F, block_size: usize) -> Self {
+        Self {
+            factory,
+            outputs: (0..block_size).map(|_| Mutex::new(None)).collect(),
+        }
+    }
+}
+
+// For the executions without inspectors.
+pub(crate) const NO_INSPECTION: Option<&Inspection<'static, NoInspector>> = None;
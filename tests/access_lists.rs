@@ -0,0 +1,132 @@
+// Test generated access lists and their warm/cold gas accounting (EIP-2929 & EIP-2930).
+
+use std::{num::NonZeroUsize, thread};
+
+use alloy_rpc_types::AccessListItem;
+use pevm::{chain::PevmEthereum, Bytecodes, EvmAccount, EvmCode, InMemoryStorage};
+use revm::primitives::{
+    alloy_primitives::U160, env::TxEnv, Address, BlockEnv, Bytecode, Bytes, SpecId, TransactTo,
+    B256, U256,
+};
+
+pub mod common;
+
+#[test]
+fn generated_access_lists() {
+    let contract_address = Address::from(U160::from(1_000));
+    // `PUSH1 0 SLOAD POP STOP`
+    let code = Bytecode::new_raw(Bytes::from_static(&[0x60, 0x00, 0x54, 0x50, 0x00]));
+    let code_hash = code.hash_slow();
+    let code = EvmCode::from(code);
+    let mut bytecodes = Bytecodes::new();
+    bytecodes.insert(code_hash, code.clone());
+    let mut accounts: Vec<_> = (0..=2).map(common::mock_account).collect();
+    accounts.push((
+        contract_address,
+        EvmAccount {
+            code_hash: Some(code_hash),
+            code: Some(code),
+            storage: [(U256::ZERO, U256::from(1))].into_iter().collect(),
+            ..EvmAccount::default()
+        },
+    ));
+    let storage = InMemoryStorage::new(accounts, Some(&bytecodes), []);
+
+    let chain = PevmEthereum::mainnet();
+    let execute = |txs: Vec<TxEnv>| {
+        let sequential_result = pevm::execute_revm_sequential(
+            &storage,
+            &chain,
+            SpecId::LATEST,
+            BlockEnv::default(),
+            txs.clone(),
+        );
+        let parallel_result = pevm::execute_revm_parallel(
+            &storage,
+            &chain,
+            SpecId::LATEST,
+            BlockEnv::default(),
+            txs,
+            thread::available_parallelism().unwrap_or(NonZeroUsize::MIN),
+        );
+        common::assert_execution_result(&sequential_result, &parallel_result);
+        parallel_result.unwrap()
+    };
+    let tx = |i: usize| TxEnv {
+        caller: Address::from(U160::from(i)),
+        transact_to: TransactTo::Call(contract_address),
+        gas_limit: 100_000,
+        gas_price: U256::from(1),
+        ..TxEnv::default()
+    };
+
+    let access_list = execute(vec![tx(1)])[0].access_list.clone();
+    assert_eq!(
+        access_list.0,
+        vec![AccessListItem {
+            address: contract_address,
+            storage_keys: vec![B256::ZERO],
+        }]
+    );
+
+    let tx_results = execute(vec![
+        tx(1),
+        TxEnv {
+            access_list: access_list.0.clone(),
+            ..tx(2)
+        },
+    ]);
+    assert_eq!(tx_results[1].access_list, access_list);
+    // The access list costs 2400 per address and 1900 per slot, which makes
+    // the SLOAD warm (100) instead of cold (2100).
+    assert_eq!(
+        tx_results[1].gas_used,
+        tx_results[0].gas_used + 2400 + 1900 - 2000
+    );
+}
+
+#[test]
+fn contended_access_lists() {
+    let block_size = 100; // number of transactions
+    let contract_address = Address::from(U160::from(1_000));
+    let (accounts, bytecodes) = common::mock_counter_accounts(block_size, contract_address);
+    let storage = InMemoryStorage::new(accounts, Some(&bytecodes), []);
+
+    // Every transaction increments the same declared slot, and every other
+    // one is from the same sender.
+    let txs: Vec<TxEnv> = (1..=block_size)
+        .map(|i| TxEnv {
+            caller: Address::from(U160::from(if i % 2 == 0 { 1 } else { i })),
+            transact_to: TransactTo::Call(contract_address),
+            gas_limit: 100_000,
+            gas_price: U256::from(1),
+            access_list: vec![AccessListItem {
+                address: contract_address,
+                storage_keys: vec![B256::ZERO],
+            }],
+            ..TxEnv::default()
+        })
+        .collect();
+    let chain = PevmEthereum::mainnet();
+    let sequential_result = pevm::execute_revm_sequential(
+        &storage,
+        &chain,
+        SpecId::LATEST,
+        BlockEnv::default(),
+        txs.clone(),
+    );
+    let parallel_result = pevm::execute_revm_parallel(
+        &storage,
+        &chain,
+        SpecId::LATEST,
+        BlockEnv::default(),
+        txs,
+        thread::available_parallelism().unwrap_or(NonZeroUsize::MIN),
+    );
+    common::assert_execution_result(&sequential_result, &parallel_result);
+    let tx_results = parallel_result.unwrap();
+    let contract = tx_results[block_size - 1].state[&contract_address]
+        .as_ref()
+        .unwrap();
+    assert_eq!(contract.storage[&U256::ZERO], U256::from(block_size));
+}
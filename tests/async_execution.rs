@@ -0,0 +1,137 @@
+// Test executing blocks from async code on a background thread.
+
+use std::{num::NonZeroUsize, sync::Arc, thread};
+
+use alloy_rpc_types::{Block, BlockTransactions, Transaction};
+use futures::executor::block_on;
+use pevm::{
+    chain::PevmEthereum, ExecutionHook, ExecutionMode, InMemoryStorage, Pevm, PevmError,
+    PevmStrategy, PevmTxExecutionResult,
+};
+use revm::primitives::{alloy_primitives::U160, Address, U256};
+
+pub mod common;
+
+// A block of raw transfers to the next account.
+fn raw_transfers_block(block_size: usize) -> Block {
+    Block {
+        header: common::MOCK_BLOCK_HEADER.clone(),
+        transactions: BlockTransactions::Full(
+            (1..=block_size)
+                .map(|i| Transaction {
+                    transaction_type: Some(2),
+                    from: Address::from(U160::from(i)),
+                    to: Some(Address::from(U160::from(i % block_size + 1))),
+                    value: U256::from(1),
+                    gas: common::RAW_TRANSFER_GAS_LIMIT.into(),
+                    max_fee_per_gas: Some(1),
+                    nonce: 1,
+                    ..Transaction::default()
+                })
+                .collect(),
+        ),
+        ..Block::default()
+    }
+}
+
+#[test]
+fn execute_async_matches_execute() {
+    let block_size = 1_000; // number of transactions
+
+    // Mock the beneficiary account (`Address:ZERO`) and the next `block_size` user accounts.
+    let storage = Arc::new(InMemoryStorage::new(
+        (0..=block_size).map(common::mock_account),
+        None,
+        [],
+    ));
+    let chain = Arc::new(PevmEthereum::mainnet());
+    let block = raw_transfers_block(block_size);
+    let concurrency_level = thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+
+    let sequential_result = pevm::execute(
+        storage.as_ref(),
+        chain.as_ref(),
+        block.clone(),
+        concurrency_level,
+        true,
+    );
+    let mut pevm = Pevm::new(ExecutionMode::Sync);
+    let async_result =
+        block_on(pevm.execute_async(storage, chain, block, concurrency_level, false));
+    assert_eq!(sequential_result, async_result);
+}
+
+#[test]
+fn execute_async_cancelled() {
+    let block_size = 1_000; // number of transactions
+
+    // Mock the beneficiary account (`Address:ZERO`) and the next `block_size` user accounts.
+    let storage = Arc::new(InMemoryStorage::new(
+        (0..=block_size).map(common::mock_account),
+        None,
+        [],
+    ));
+    let chain = Arc::new(PevmEthereum::mainnet());
+    let block = raw_transfers_block(block_size);
+    let concurrency_level = thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+    let strategy = PevmStrategy {
+        adaptive_concurrency: true,
+        ..PevmStrategy::default()
+    };
+
+    let mut pevm = Pevm::new(ExecutionMode::Sync).with_strategy(strategy.clone());
+    // Drop the future before polling it.
+    drop(pevm.execute_async(
+        storage.clone(),
+        chain.clone(),
+        block.clone(),
+        concurrency_level,
+        false,
+    ));
+    // The engine keeps its configuration and still executes blocks.
+    let mut expected_pevm = Pevm::new(ExecutionMode::Sync).with_strategy(strategy);
+    let expected_result = expected_pevm.execute(
+        storage.as_ref(),
+        chain.as_ref(),
+        block.clone(),
+        concurrency_level,
+        false,
+    );
+    let async_result =
+        block_on(pevm.execute_async(storage, chain, block, concurrency_level, false));
+    assert_eq!(expected_result, async_result);
+    assert!(pevm.concurrency_level().is_some());
+}
+
+// A hook that panics on the first transaction result.
+#[derive(Debug)]
+struct PanickingHook;
+
+impl ExecutionHook for PanickingHook {
+    fn after_tx(&self, _tx_idx: usize, _tx_result: &PevmTxExecutionResult) {
+        panic!("panicking hook");
+    }
+}
+
+#[test]
+fn execute_async_panicked() {
+    let block_size = 10; // number of transactions
+
+    // Mock the beneficiary account (`Address:ZERO`) and the next `block_size` user accounts.
+    let storage = Arc::new(InMemoryStorage::new(
+        (0..=block_size).map(common::mock_account),
+        None,
+        [],
+    ));
+    let chain = Arc::new(PevmEthereum::mainnet());
+    let block = raw_transfers_block(block_size);
+    let concurrency_level = thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+
+    let mut pevm = Pevm::new(ExecutionMode::Sync).with_hook(Arc::new(PanickingHook));
+    let async_result =
+        block_on(pevm.execute_async(storage, chain, block, concurrency_level, false));
+    assert_eq!(
+        async_result,
+        Err(PevmError::ExecutionPanicked("panicking hook".to_string()))
+    );
+}
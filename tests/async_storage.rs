@@ -0,0 +1,65 @@
+// Test executing on an async storage via its sync bridge.
+
+use pevm::{AccountBasic, AsyncStorage, AsyncStorageBridge, EvmCode, InMemoryStorage, Storage};
+use revm::primitives::{alloy_primitives::U160, env::TxEnv, Address, TransactTo, B256, U256};
+
+pub mod common;
+
+// An in-memory storage that yields to the runtime on every read, like
+// waiting for network responses.
+#[derive(Debug, Clone)]
+struct YieldingStorage<'a>(InMemoryStorage<'a>);
+
+impl<'a> AsyncStorage for YieldingStorage<'a> {
+    type Error = u8;
+
+    async fn basic(&self, address: &Address) -> Result<Option<AccountBasic>, Self::Error> {
+        tokio::task::yield_now().await;
+        self.0.basic(address)
+    }
+
+    async fn code_hash(&self, address: &Address) -> Result<Option<B256>, Self::Error> {
+        tokio::task::yield_now().await;
+        self.0.code_hash(address)
+    }
+
+    async fn code_by_hash(&self, code_hash: &B256) -> Result<Option<EvmCode>, Self::Error> {
+        tokio::task::yield_now().await;
+        self.0.code_by_hash(code_hash)
+    }
+
+    async fn has_storage(&self, address: &Address) -> Result<bool, Self::Error> {
+        tokio::task::yield_now().await;
+        self.0.has_storage(address)
+    }
+
+    async fn storage(&self, address: &Address, index: &U256) -> Result<U256, Self::Error> {
+        tokio::task::yield_now().await;
+        self.0.storage(address, index)
+    }
+
+    async fn block_hash(&self, number: &u64) -> Result<B256, Self::Error> {
+        tokio::task::yield_now().await;
+        self.0.block_hash(number)
+    }
+}
+
+#[test]
+fn async_storage_bridge() {
+    let block_size = 1_000; // number of transactions
+    let storage = InMemoryStorage::new((0..=block_size).map(common::mock_account), None, []);
+    common::test_execute_revm(
+        AsyncStorageBridge::new(YieldingStorage(storage)).unwrap(),
+        // Mock `block_size` transactions sending some tokens to the next account.
+        (1..=block_size)
+            .map(|i| TxEnv {
+                caller: Address::from(U160::from(i)),
+                transact_to: TransactTo::Call(Address::from(U160::from(i % block_size + 1))),
+                value: U256::from(1),
+                gas_limit: common::RAW_TRANSFER_GAS_LIMIT,
+                gas_price: U256::from(1),
+                ..TxEnv::default()
+            })
+            .collect(),
+    );
+}
@@ -0,0 +1,89 @@
+// Test the blob gas validation of Cancun blocks (EIP-4844).
+
+use alloy_rpc_types::{Block, BlockTransactions, Header};
+use pevm::{chain::PevmEthereum, InMemoryStorage, Pevm, PevmError, PevmOptions};
+use revm::primitives::{GAS_PER_BLOB, TARGET_BLOB_GAS_PER_BLOCK};
+use std::num::NonZeroUsize;
+
+pub mod common;
+
+fn execute_cancun_block(blob_gas_used: Option<u128>) -> pevm::PevmBlockResult<PevmEthereum> {
+    pevm::execute(
+        &InMemoryStorage::default(),
+        &PevmEthereum::mainnet(),
+        Block {
+            header: Header {
+                blob_gas_used,
+                ..common::MOCK_BLOCK_HEADER.clone()
+            },
+            transactions: BlockTransactions::Full(Vec::new()),
+            ..Block::default()
+        },
+        NonZeroUsize::MIN,
+        true,
+    )
+}
+
+#[test]
+fn matching_blob_gas_used() {
+    assert!(execute_cancun_block(Some(0)).is_ok());
+}
+
+#[test]
+fn mismatched_blob_gas_used() {
+    assert_eq!(
+        execute_cancun_block(Some(GAS_PER_BLOB as u128)),
+        Err(PevmError::InvalidBlobGasUsed {
+            header: GAS_PER_BLOB as u128,
+            computed: 0,
+        })
+    );
+}
+
+#[test]
+fn missing_blob_gas_used() {
+    assert_eq!(
+        execute_cancun_block(None),
+        Err(PevmError::MissingHeaderData)
+    );
+}
+
+fn execute_with_parent(excess_blob_gas: u128) -> pevm::PevmBlockResult<PevmEthereum> {
+    let parent_header = Header {
+        excess_blob_gas: Some(TARGET_BLOB_GAS_PER_BLOCK as u128),
+        blob_gas_used: Some(2 * GAS_PER_BLOB as u128),
+        ..common::MOCK_BLOCK_HEADER.clone()
+    };
+    Pevm::default().execute_with(
+        &InMemoryStorage::default(),
+        &PevmEthereum::mainnet(),
+        Block {
+            header: Header {
+                excess_blob_gas: Some(excess_blob_gas),
+                ..common::MOCK_BLOCK_HEADER.clone()
+            },
+            transactions: BlockTransactions::Full(Vec::new()),
+            ..Block::default()
+        },
+        PevmOptions::default()
+            .with_concurrency_level(NonZeroUsize::MIN)
+            .with_force_sequential(true)
+            .with_parent_header(parent_header),
+    )
+}
+
+#[test]
+fn matching_excess_blob_gas() {
+    assert!(execute_with_parent(2 * GAS_PER_BLOB as u128).is_ok());
+}
+
+#[test]
+fn mismatched_excess_blob_gas() {
+    assert_eq!(
+        execute_with_parent(0),
+        Err(PevmError::InvalidExcessBlobGas {
+            header: 0,
+            computed: 2 * GAS_PER_BLOB as u128,
+        })
+    );
+}
@@ -0,0 +1,109 @@
+// Test executing consecutive blocks of a range, carrying their state forward.
+
+use std::num::NonZeroUsize;
+
+use alloy_rpc_types::{Block, BlockTransactions};
+use pevm::{chain::PevmEthereum, BlockRangeError, CommittedStorage, ExecutionMode, Pevm};
+
+pub mod common;
+
+// An empty block right after [block].
+fn next_empty_block(block: &Block) -> Block {
+    let mut empty_block = block.clone();
+    empty_block.header.number = block.header.number.map(|number| number + 1);
+    empty_block.header.hash = None;
+    empty_block.transactions = BlockTransactions::Full(Vec::new());
+    empty_block.withdrawals = None;
+    if empty_block.header.blob_gas_used.is_some() {
+        empty_block.header.blob_gas_used = Some(0);
+    }
+    empty_block
+}
+
+#[test]
+fn mainnet_block_ranges() {
+    let chain = PevmEthereum::mainnet();
+    let concurrency_level = NonZeroUsize::new(4).unwrap();
+    common::for_each_block_from_disk(|block, storage| {
+        let block_number = block.header.number.unwrap();
+        let empty_block = next_empty_block(&block);
+        let expected_result = Pevm::new(ExecutionMode::Validate)
+            .execute(&storage, &chain, block.clone(), concurrency_level, true)
+            .unwrap();
+        let mut committed_storage = CommittedStorage::new(&storage, &[]);
+        committed_storage.commit_block(&expected_result);
+        let expected_empty_result = Pevm::new(ExecutionMode::Validate)
+            .execute(
+                &committed_storage,
+                &chain,
+                empty_block.clone(),
+                concurrency_level,
+                true,
+            )
+            .unwrap();
+
+        // The blocks are committed in order, the second one on the state
+        // after the first one.
+        let fetch_block = |number| match number - block_number {
+            0 => Ok(block.clone()),
+            1 => Ok(empty_block.clone()),
+            _ => Err("unknown block"),
+        };
+        let mut committed = Vec::new();
+        Pevm::new(ExecutionMode::Validate)
+            .execute_range_of_blocks(
+                &storage,
+                &chain,
+                block_number..block_number + 2,
+                fetch_block,
+                |header, block_result| {
+                    committed.push((header.number.unwrap(), block_result));
+                    Ok(())
+                },
+                concurrency_level,
+            )
+            .unwrap();
+        assert_eq!(
+            committed,
+            vec![
+                (block_number, expected_result),
+                (block_number + 1, expected_empty_result)
+            ]
+        );
+
+        // The range stops at the first failing block.
+        let mut committed_numbers = Vec::new();
+        assert_eq!(
+            Pevm::new(ExecutionMode::Validate).execute_range_of_blocks(
+                &storage,
+                &chain,
+                block_number..block_number + 3,
+                fetch_block,
+                |header, _| {
+                    committed_numbers.push(header.number.unwrap());
+                    Ok(())
+                },
+                concurrency_level,
+            ),
+            Err(BlockRangeError::Fetch {
+                block_number: block_number + 2,
+                error: "unknown block",
+            })
+        );
+        assert_eq!(committed_numbers, vec![block_number, block_number + 1]);
+        assert_eq!(
+            Pevm::new(ExecutionMode::Validate).execute_range_of_blocks(
+                &storage,
+                &chain,
+                block_number..block_number + 2,
+                fetch_block,
+                |_, _| Err("read-only"),
+                concurrency_level,
+            ),
+            Err(BlockRangeError::Commit {
+                block_number,
+                error: "read-only",
+            })
+        );
+    });
+}
@@ -0,0 +1,95 @@
+// Test the static block and ommer rewards of pre-merge Ethereum blocks.
+
+use alloy_primitives::{Address, B256, U256};
+use alloy_rpc_types::{Block, BlockTransactions, Header};
+use pevm::{chain::PevmEthereum, InMemoryStorage, PevmError};
+use revm::primitives::alloy_primitives::U160;
+use std::num::NonZeroUsize;
+
+pub mod common;
+
+const ETH: u128 = 1_000_000_000_000_000_000;
+
+fn byzantium_block(beneficiary: Address, num_ommers: usize) -> Block {
+    Block {
+        header: Header {
+            number: Some(4_370_005),
+            timestamp: 1508131500,
+            miner: beneficiary,
+            ..common::MOCK_BLOCK_HEADER.clone()
+        },
+        uncles: vec![B256::ZERO; num_ommers],
+        transactions: BlockTransactions::Full(Vec::new()),
+        ..Block::default()
+    }
+}
+
+#[test]
+fn byzantium_block_and_ommer_rewards() {
+    let beneficiary = Address::from(U160::from(1));
+    let ommer_beneficiary = Address::from(U160::from(2));
+    let ommer = Header {
+        number: Some(4_370_004),
+        miner: ommer_beneficiary,
+        ..common::MOCK_BLOCK_HEADER.clone()
+    };
+    let result = pevm::execute_with_ommers(
+        &InMemoryStorage::default(),
+        &PevmEthereum::mainnet(),
+        byzantium_block(beneficiary, 1),
+        Some(&[ommer]),
+        NonZeroUsize::MIN,
+        true,
+    )
+    .unwrap();
+
+    let post_block_state = result.post_block_state;
+    assert_eq!(
+        post_block_state[&beneficiary].as_ref().unwrap().balance,
+        U256::from(3 * ETH + 3 * ETH / 32)
+    );
+    assert_eq!(
+        post_block_state[&ommer_beneficiary]
+            .as_ref()
+            .unwrap()
+            .balance,
+        U256::from(7 * 3 * ETH / 8)
+    );
+}
+
+#[test]
+fn mismatched_ommers() {
+    assert_eq!(
+        pevm::execute_with_ommers(
+            &InMemoryStorage::default(),
+            &PevmEthereum::mainnet(),
+            byzantium_block(Address::ZERO, 1),
+            Some(&[]),
+            NonZeroUsize::MIN,
+            true,
+        ),
+        Err(PevmError::MissingOmmerData)
+    );
+}
+
+#[test]
+fn invalid_ommers() {
+    // Ommers must be from the six blocks before the including block.
+    for ommer_number in [4_370_005, 4_370_006, 4_369_998, 0] {
+        let ommer = Header {
+            number: Some(ommer_number),
+            ..common::MOCK_BLOCK_HEADER.clone()
+        };
+        assert_eq!(
+            pevm::execute_with_ommers(
+                &InMemoryStorage::default(),
+                &PevmEthereum::mainnet(),
+                byzantium_block(Address::ZERO, 1),
+                Some(&[ommer]),
+                NonZeroUsize::MIN,
+                true,
+            ),
+            Err(PevmError::InvalidOmmer { ommer_idx: 0 })
+        );
+    }
+}
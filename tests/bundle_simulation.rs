@@ -0,0 +1,157 @@
+// Test simulating bundles of transactions for their coinbase payments.
+
+use std::{num::NonZeroUsize, thread};
+
+use pevm::{
+    chain::PevmEthereum, AccountOverride, BlockOverrides, BundleOptions, Bytecodes, EvmAccount,
+    EvmCode, InMemoryStorage, Pevm, PevmError, StateOverrides, Storage,
+};
+use revm::primitives::{
+    alloy_primitives::U160, env::TxEnv, Address, BlockEnv, Bytecode, Bytes, SpecId, TransactTo,
+    U256,
+};
+
+pub mod common;
+
+#[test]
+fn simulate_bundle_with_reverts() {
+    let bundle_size = 10; // number of transactions
+
+    let reverter_address = Address::from(U160::from(bundle_size + 1));
+    // `PUSH1 0 PUSH1 0 REVERT`: Always revert.
+    let code = Bytecode::new_raw(Bytes::from_static(&[0x60, 0x00, 0x60, 0x00, 0xfd]));
+    let code_hash = code.hash_slow();
+    let mut bytecodes = Bytecodes::new();
+    bytecodes.insert(code_hash, EvmCode::from(code));
+    let mut accounts: Vec<_> = (0..=bundle_size).map(common::mock_account).collect();
+    accounts.push((
+        reverter_address,
+        EvmAccount {
+            code_hash: Some(code_hash),
+            ..EvmAccount::default()
+        },
+    ));
+    let storage = InMemoryStorage::new(accounts, Some(&bytecodes), []);
+    // Raw transfers to the next account, with the first and last transactions
+    // paying the beneficiary (`Address::ZERO`) directly, and the fifth
+    // transaction calling the reverting contract.
+    let txs: Vec<TxEnv> = (1..=bundle_size)
+        .map(|i| {
+            let (to, value, gas_limit) = match i {
+                1 | 10 => (
+                    Address::ZERO,
+                    U256::from(1_000),
+                    common::RAW_TRANSFER_GAS_LIMIT,
+                ),
+                5 => (reverter_address, U256::ZERO, 100_000),
+                _ => (
+                    Address::from(U160::from(i % bundle_size + 1)),
+                    U256::from(1),
+                    common::RAW_TRANSFER_GAS_LIMIT,
+                ),
+            };
+            TxEnv {
+                caller: Address::from(U160::from(i)),
+                transact_to: TransactTo::Call(to),
+                value,
+                gas_limit,
+                gas_price: U256::from(2),
+                ..TxEnv::default()
+            }
+        })
+        .collect();
+    let chain = PevmEthereum::mainnet();
+    let mut options = BundleOptions {
+        spec_id: SpecId::LATEST,
+        reverting_tx_idxs: Vec::new(),
+        concurrency_level: thread::available_parallelism().unwrap_or(NonZeroUsize::MIN),
+        block_overrides: BlockOverrides::default(),
+        state_overrides: StateOverrides::default(),
+    };
+
+    let mut pevm = Pevm::default();
+    assert_eq!(
+        pevm.simulate_bundle(&storage, &chain, BlockEnv::default(), txs.clone(), &options),
+        Err(PevmError::BundleTxReverted { tx_idx: 4 })
+    );
+
+    options.reverting_tx_idxs.push(4);
+    let simulation = pevm
+        .simulate_bundle(&storage, &chain, BlockEnv::default(), txs, &options)
+        .unwrap();
+    assert_eq!(simulation.tx_results.len(), bundle_size);
+    assert!(!simulation.tx_results[4].receipt.status());
+    let gas_used: u64 = simulation
+        .tx_results
+        .iter()
+        .map(|tx_result| tx_result.gas_used)
+        .sum();
+    assert_eq!(simulation.gas_used, gas_used);
+    // The priority fees (without a base fee) and the direct payments.
+    assert_eq!(
+        simulation.coinbase_diff,
+        U256::from(gas_used * 2 + 2 * 1_000)
+    );
+}
+
+#[test]
+fn simulate_bundle_with_overrides() {
+    // Only the beneficiary (`Address::ZERO`) exists in storage.
+    let storage = InMemoryStorage::new([common::mock_account(0)], None, []);
+    let sender = Address::from(U160::from(1));
+    let coinbase = Address::from(U160::from(2));
+    let tx = TxEnv {
+        caller: sender,
+        transact_to: TransactTo::Call(Address::from(U160::from(3))),
+        value: U256::from(1),
+        gas_limit: common::RAW_TRANSFER_GAS_LIMIT,
+        gas_price: U256::from(2),
+        ..TxEnv::default()
+    };
+    let mut options = BundleOptions {
+        spec_id: SpecId::LATEST,
+        reverting_tx_idxs: Vec::new(),
+        concurrency_level: NonZeroUsize::MIN,
+        block_overrides: BlockOverrides {
+            coinbase: Some(coinbase),
+            ..BlockOverrides::default()
+        },
+        state_overrides: StateOverrides::default(),
+    };
+    let chain = PevmEthereum::mainnet();
+    let mut pevm = Pevm::default();
+
+    // The sender can't pay for gas without a balance override.
+    assert!(matches!(
+        pevm.simulate_bundle(
+            &storage,
+            &chain,
+            BlockEnv::default(),
+            vec![tx.clone()],
+            &options
+        ),
+        Err(PevmError::ExecutionError(_))
+    ));
+
+    options.state_overrides.insert(
+        sender,
+        AccountOverride {
+            balance: Some(U256::from(1_000_000)),
+            ..AccountOverride::default()
+        },
+    );
+    let simulation = pevm
+        .simulate_bundle(&storage, &chain, BlockEnv::default(), vec![tx], &options)
+        .unwrap();
+    assert_eq!(
+        simulation.coinbase_diff,
+        U256::from(common::RAW_TRANSFER_GAS_LIMIT * 2)
+    );
+    let sender_account = simulation.tx_results[0].state[&sender].as_ref().unwrap();
+    assert_eq!(
+        sender_account.balance,
+        U256::from(1_000_000 - common::RAW_TRANSFER_GAS_LIMIT * 2 - 1)
+    );
+    // The storage is unchanged.
+    assert_eq!(storage.basic(&sender), Ok(None));
+}
@@ -0,0 +1,86 @@
+// Test merging the state transitions of a block into a bundle state.
+
+use std::{num::NonZeroUsize, thread};
+
+use pevm::{
+    chain::PevmEthereum, CommittedStorage, InMemoryStorage, PevmBlockExecutionResult, Storage,
+};
+use revm::{
+    db::RevertToSlot,
+    primitives::{alloy_primitives::U160, env::TxEnv, Address, BlockEnv, SpecId, TransactTo, U256},
+};
+
+pub mod common;
+
+#[test]
+fn bundle_state_contended_counter() {
+    let block_size = 100; // number of transactions
+
+    let counter_address = Address::from(U160::from(block_size + 1));
+    let (mut accounts, bytecodes) = common::mock_counter_accounts(block_size, counter_address);
+    accounts.last_mut().unwrap().1.storage = [(U256::ZERO, U256::from(5))].into_iter().collect();
+    let storage = InMemoryStorage::new(accounts, Some(&bytecodes), []);
+    // Every transaction increments the shared counter from a different sender.
+    let txs: Vec<TxEnv> = (1..=block_size)
+        .map(|i| TxEnv {
+            caller: Address::from(U160::from(i)),
+            transact_to: TransactTo::Call(counter_address),
+            value: U256::ZERO,
+            gas_limit: 100_000,
+            gas_price: U256::from(1),
+            ..TxEnv::default()
+        })
+        .collect();
+    let tx_results = pevm::execute_revm_parallel(
+        &storage,
+        &PevmEthereum::mainnet(),
+        SpecId::LATEST,
+        BlockEnv::default(),
+        txs.clone(),
+        thread::available_parallelism().unwrap_or(NonZeroUsize::MIN),
+    )
+    .unwrap();
+    let block_result = PevmBlockExecutionResult {
+        pre_block_state: Default::default(),
+        tx_results,
+        post_block_state: Default::default(),
+    };
+
+    let bundle_state = pevm::build_bundle_state(&storage, &block_result).unwrap();
+    let counter = bundle_state.state.get(&counter_address).unwrap();
+    let slot = counter.storage.get(&U256::ZERO).unwrap();
+    assert_eq!(slot.previous_or_original_value, U256::from(5));
+    assert_eq!(slot.present_value, U256::from(5 + block_size));
+    assert_eq!(counter.original_info, counter.info);
+
+    // The pre-block changes, each transaction, then the post-block changes.
+    assert_eq!(bundle_state.reverts.len(), block_size + 2);
+    assert!(bundle_state.reverts[0].is_empty());
+    assert!(bundle_state.reverts[block_size + 1].is_empty());
+    for (i, tx) in txs.iter().enumerate() {
+        let tx_reverts = &bundle_state.reverts[i + 1];
+        let (_, counter_revert) = tx_reverts
+            .iter()
+            .find(|(address, _)| address == &counter_address)
+            .unwrap();
+        assert_eq!(
+            counter_revert.storage.get(&U256::ZERO),
+            Some(&RevertToSlot::Some(U256::from(5 + i)))
+        );
+        assert!(tx_reverts.iter().any(|(address, _)| address == &tx.caller));
+    }
+
+    // The bundled state matches the block's committed state.
+    let mut committed_storage = CommittedStorage::new(&storage, &[]);
+    committed_storage.commit_block(&block_result);
+    for (address, account) in bundle_state.state.iter() {
+        let basic = committed_storage.basic(address).unwrap().unwrap();
+        let info = account.info.as_ref().unwrap();
+        assert_eq!(info.balance, basic.balance);
+        assert_eq!(info.nonce, basic.nonce);
+        let original_basic = storage.basic(address).unwrap().unwrap();
+        let original_info = account.original_info.as_ref().unwrap();
+        assert_eq!(original_info.balance, original_basic.balance);
+        assert_eq!(original_info.nonce, original_basic.nonce);
+    }
+}
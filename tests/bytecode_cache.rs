@@ -0,0 +1,57 @@
+// Test that [Pevm] caches codes across executions, to only read and prepare
+// each contract once.
+
+use std::{num::NonZeroUsize, thread};
+
+use common::storage::CountingStorage;
+use pevm::{chain::PevmEthereum, InMemoryStorage, Pevm};
+use revm::primitives::{
+    alloy_primitives::U160, env::TxEnv, Address, BlockEnv, SpecId, TransactTo, U256,
+};
+
+pub mod common;
+
+#[test]
+fn bytecode_cache_across_executions() {
+    let block_size = 100; // number of transactions
+    let contract_address = Address::from(U160::from(block_size + 1));
+    let (accounts, bytecodes) = common::mock_counter_accounts(block_size, contract_address);
+    let storage = CountingStorage::new(InMemoryStorage::new(accounts, Some(&bytecodes), []));
+    let txs: Vec<TxEnv> = (1..=block_size)
+        .map(|i| TxEnv {
+            caller: Address::from(U160::from(i)),
+            transact_to: TransactTo::Call(contract_address),
+            gas_limit: 100_000,
+            gas_price: U256::from(1),
+            ..TxEnv::default()
+        })
+        .collect();
+
+    let chain = PevmEthereum::mainnet();
+    let sequential_result = pevm::execute_revm_sequential(
+        &storage,
+        &chain,
+        SpecId::LATEST,
+        BlockEnv::default(),
+        txs.clone(),
+    );
+    let sequential_code_reads = storage.code_reads();
+
+    let mut pevm = Pevm::default();
+    let mut execute = || {
+        let parallel_result = pevm.execute_revm_parallel(
+            &storage,
+            &chain,
+            SpecId::LATEST,
+            BlockEnv::default(),
+            txs.clone(),
+            thread::available_parallelism().unwrap_or(NonZeroUsize::MIN),
+        );
+        common::assert_execution_result(&sequential_result, &parallel_result);
+        storage.code_reads() - sequential_code_reads
+    };
+    let code_reads = execute();
+    assert!(code_reads > 0);
+    // Concurrent first reads may all miss, but later executions only hit.
+    assert_eq!(execute(), code_reads);
+}
@@ -0,0 +1,64 @@
+// Test bounded LRU caching over a storage.
+
+use std::{num::NonZeroUsize, thread};
+
+use common::storage::CountingStorage;
+use pevm::{chain::PevmEthereum, CachedStorage, InMemoryStorage};
+use revm::primitives::{
+    alloy_primitives::U160, env::TxEnv, Address, BlockEnv, SpecId, TransactTo, U256,
+};
+
+pub mod common;
+
+#[test]
+fn cached_storage() {
+    let block_size = 1_000; // number of transactions
+    let storage = InMemoryStorage::new((0..=block_size).map(common::mock_account), None, []);
+    // Mock `block_size` transactions sending some tokens to the next account.
+    let txs: Vec<TxEnv> = (1..=block_size)
+        .map(|i| TxEnv {
+            caller: Address::from(U160::from(i)),
+            transact_to: TransactTo::Call(Address::from(U160::from(i % block_size + 1))),
+            value: U256::from(1),
+            gas_limit: common::RAW_TRANSFER_GAS_LIMIT,
+            gas_price: U256::from(1),
+            ..TxEnv::default()
+        })
+        .collect();
+
+    let chain = PevmEthereum::mainnet();
+    let concurrency_level = thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+    let expected_result = pevm::execute_revm_sequential(
+        &storage,
+        &chain,
+        SpecId::LATEST,
+        BlockEnv::default(),
+        txs.clone(),
+    );
+    let execute = |storage: &CachedStorage<CountingStorage>| {
+        pevm::execute_revm_parallel(
+            storage,
+            &chain,
+            SpecId::LATEST,
+            BlockEnv::default(),
+            txs.clone(),
+            concurrency_level,
+        )
+    };
+
+    // Large enough caches serve all reads of a re-execution.
+    let large_cache = CachedStorage::with_capacity(
+        CountingStorage::new(storage.clone()),
+        NonZeroUsize::new(10_000).unwrap(),
+    );
+    common::assert_execution_result(&expected_result, &execute(&large_cache));
+    let lower_reads = large_cache.second().reads();
+    common::assert_execution_result(&expected_result, &execute(&large_cache));
+    assert_eq!(large_cache.second().reads(), lower_reads);
+
+    // Tiny caches evict but still read correctly.
+    let tiny_cache = CachedStorage::with_capacity(CountingStorage::new(storage), NonZeroUsize::MIN);
+    common::assert_execution_result(&expected_result, &execute(&tiny_cache));
+    common::assert_execution_result(&expected_result, &execute(&tiny_cache));
+    assert!(tiny_cache.second().reads() > lower_reads);
+}
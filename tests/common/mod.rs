@@ -1,89 +1,76 @@
 use std::{
-    collections::HashMap,
     fs::{self, File},
     io::BufReader,
 };
 
 use ahash::AHashMap;
-use alloy_primitives::{Address, Bloom, Bytes, B256, U256};
-use alloy_rpc_types::{Block, Header};
-use pevm::{Bytecodes, EvmAccount, InMemoryStorage};
+use alloy_primitives::{Address, Bytes, B256};
+use alloy_rpc_types::Block;
+use pevm::{BlockSnapshot, Bytecodes, EvmAccount, EvmCode, InMemoryStorage};
+use revm::primitives::Bytecode;
 
-pub mod runner;
-pub use runner::{assert_execution_result, mock_account, test_execute_alloy, test_execute_revm};
+pub use pevm::test_utils::{
+    assert_execution_result, mock_account, test_execute_alloy, test_execute_revm,
+    MOCK_BLOCK_HEADER, RAW_TRANSFER_GAS_LIMIT,
+};
 pub mod storage;
 
 pub type ChainState = AHashMap<Address, EvmAccount>;
 pub type BlockHashes = AHashMap<u64, B256>;
 
-pub static MOCK_ALLOY_BLOCK_HEADER: Header = Header {
-    // Minimal requirements for execution
-    number: Some(1),
-    timestamp: 1710338135,
-    mix_hash: Some(B256::ZERO),
-    excess_blob_gas: Some(0),
-    gas_limit: u128::MAX,
-    // Defaults
-    hash: None,
-    parent_hash: B256::ZERO,
-    uncles_hash: B256::ZERO,
-    miner: Address::ZERO,
-    state_root: B256::ZERO,
-    transactions_root: B256::ZERO,
-    receipts_root: B256::ZERO,
-    logs_bloom: Bloom::ZERO,
-    difficulty: U256::ZERO,
-    gas_used: 0,
-    total_difficulty: Some(U256::ZERO),
-    extra_data: Bytes::new(),
-    nonce: None,
-    base_fee_per_gas: None,
-    withdrawals_root: None,
-    blob_gas_used: None,
-    parent_beacon_block_root: None,
-    requests_root: None,
-};
+// `PUSH1 0 SLOAD PUSH1 1 ADD PUSH1 0 SSTORE STOP`: Increment slot 0.
+pub fn counter_contract() -> Bytecode {
+    Bytecode::new_raw(Bytes::from_static(&[
+        0x60, 0x00, 0x54, 0x60, 0x01, 0x01, 0x60, 0x00, 0x55, 0x00,
+    ]))
+}
 
-pub const RAW_TRANSFER_GAS_LIMIT: u64 = 21_000;
+// Mock the accounts `0..=num_accounts` and a [counter_contract] at
+// `contract_address`, with the bytecodes to build a storage from.
+pub fn mock_counter_accounts(
+    num_accounts: usize,
+    contract_address: Address,
+) -> (Vec<(Address, EvmAccount)>, Bytecodes) {
+    let code = counter_contract();
+    let code_hash = code.hash_slow();
+    let code = EvmCode::from(code);
+    let mut bytecodes = Bytecodes::new();
+    bytecodes.insert(code_hash, code.clone());
+    let mut accounts: Vec<_> = (0..=num_accounts).map(mock_account).collect();
+    accounts.push((
+        contract_address,
+        EvmAccount {
+            code_hash: Some(code_hash),
+            code: Some(code),
+            ..EvmAccount::default()
+        },
+    ));
+    (accounts, bytecodes)
+}
 
 // TODO: Put somewhere better?
-pub fn for_each_block_from_disk(mut handler: impl FnMut(Block, InMemoryStorage)) {
-    // Parse bytecodes
-    let bytecodes: Bytecodes = bincode::deserialize_from(BufReader::new(
-        File::open("data/bytecodes.bincode").unwrap(),
-    ))
-    .unwrap();
+pub fn for_each_snapshot_from_disk(mut handler: impl FnMut(BlockSnapshot)) {
+    // Blocks of the legacy layout share bytecodes, parsed on demand.
+    let mut legacy_bytecodes: Option<Bytecodes> = None;
 
     for block_path in fs::read_dir("data/blocks").unwrap() {
         let block_path = block_path.unwrap().path();
-        let block_number = block_path.file_name().unwrap().to_str().unwrap();
-
-        // Parse block
-        let block: Block = serde_json::from_reader(BufReader::new(
-            File::open(format!("data/blocks/{block_number}/block.json")).unwrap(),
-        ))
-        .unwrap();
-
-        // Parse state
-        let accounts: HashMap<Address, EvmAccount> = serde_json::from_reader(BufReader::new(
-            File::open(format!("data/blocks/{block_number}/pre_state.json")).unwrap(),
-        ))
-        .unwrap();
-
-        // Parse block hashes
-        let block_hashes: BlockHashes =
-            File::open(format!("data/blocks/{block_number}/block_hashes.json"))
-                .map(|file| {
-                    type SerializedFormat = HashMap<u64, B256, ahash::RandomState>;
-                    serde_json::from_reader::<_, SerializedFormat>(BufReader::new(file))
-                        .unwrap()
-                        .into()
-                })
-                .unwrap_or_default();
-
-        handler(
-            block,
-            InMemoryStorage::new(accounts, Some(&bytecodes), block_hashes),
-        );
+        let snapshot_path = block_path.join("snapshot.bin.zst");
+        let snapshot = if snapshot_path.exists() {
+            BlockSnapshot::read(snapshot_path).unwrap()
+        } else {
+            let bytecodes = legacy_bytecodes.get_or_insert_with(|| {
+                bincode::deserialize_from(BufReader::new(
+                    File::open("data/bytecodes.bincode").unwrap(),
+                ))
+                .unwrap()
+            });
+            BlockSnapshot::from_legacy_dir(&block_path, bytecodes).unwrap()
+        };
+        handler(snapshot);
     }
 }
+
+pub fn for_each_block_from_disk(mut handler: impl FnMut(Block, InMemoryStorage)) {
+    for_each_snapshot_from_disk(|snapshot| handler(snapshot.block.clone(), snapshot.storage()));
+}
@@ -1,4 +1,10 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
 use ahash::AHashMap;
+use pevm::{AccountBasic, EvmCode, InMemoryStorage, Storage};
 use revm::primitives::{
     alloy_primitives::U160, keccak256, ruint::UintTryFrom, Address, B256, I256, U256,
 };
@@ -83,3 +89,98 @@ pub fn from_tick(tick: i32) -> U256 {
     let encoded_as_i256 = I256::try_from(tick).unwrap();
     encoded_as_i256.into_raw()
 }
+
+// A storage that counts its reads, for testing storage decorators.
+// A batched lookup counts as one read. Code reads are also counted by
+// code, for testing code caches.
+#[derive(Debug, Clone)]
+pub struct CountingStorage<'a> {
+    storage: InMemoryStorage<'a>,
+    reads: Arc<AtomicUsize>,
+    batches: Arc<AtomicUsize>,
+    code_reads: Arc<AtomicUsize>,
+}
+
+impl<'a> CountingStorage<'a> {
+    pub fn new(storage: InMemoryStorage<'a>) -> Self {
+        CountingStorage {
+            storage,
+            reads: Arc::default(),
+            batches: Arc::default(),
+            code_reads: Arc::default(),
+        }
+    }
+
+    pub fn reads(&self) -> usize {
+        self.reads.load(Ordering::Relaxed)
+    }
+
+    pub fn batches(&self) -> usize {
+        self.batches.load(Ordering::Relaxed)
+    }
+
+    pub fn code_reads(&self) -> usize {
+        self.code_reads.load(Ordering::Relaxed)
+    }
+
+    fn count(&self) {
+        self.reads.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn count_batch(&self) {
+        self.count();
+        self.batches.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl<'a> Storage for CountingStorage<'a> {
+    type Error = u8;
+
+    fn basic(&self, address: &Address) -> Result<Option<AccountBasic>, Self::Error> {
+        self.count();
+        self.storage.basic(address)
+    }
+
+    fn code_hash(&self, address: &Address) -> Result<Option<B256>, Self::Error> {
+        self.count();
+        self.storage.code_hash(address)
+    }
+
+    fn code_by_hash(&self, code_hash: &B256) -> Result<Option<EvmCode>, Self::Error> {
+        self.count();
+        self.code_reads.fetch_add(1, Ordering::Relaxed);
+        self.storage.code_by_hash(code_hash)
+    }
+
+    fn has_storage(&self, address: &Address) -> Result<bool, Self::Error> {
+        self.count();
+        self.storage.has_storage(address)
+    }
+
+    fn storage(&self, address: &Address, index: &U256) -> Result<U256, Self::Error> {
+        self.count();
+        self.storage.storage(address, index)
+    }
+
+    fn block_hash(&self, number: &u64) -> Result<B256, Self::Error> {
+        self.count();
+        self.storage.block_hash(number)
+    }
+
+    fn basic_many(&self, addresses: &[Address]) -> Result<Vec<Option<AccountBasic>>, Self::Error> {
+        self.count_batch();
+        self.storage.basic_many(addresses)
+    }
+
+    fn code_by_hash_many(&self, code_hashes: &[B256]) -> Result<Vec<Option<EvmCode>>, Self::Error> {
+        self.count_batch();
+        self.code_reads
+            .fetch_add(code_hashes.len(), Ordering::Relaxed);
+        self.storage.code_by_hash_many(code_hashes)
+    }
+
+    fn storage_many(&self, slots: &[(Address, U256)]) -> Result<Vec<U256>, Self::Error> {
+        self.count_batch();
+        self.storage.storage_many(slots)
+    }
+}
@@ -0,0 +1,71 @@
+// Test the DAO hardfork's irregular state change on Ethereum Mainnet.
+
+use alloy_primitives::{address, U256};
+use alloy_rpc_types::{Block, BlockTransactions, Header};
+use pevm::{chain::PevmEthereum, EvmAccount, InMemoryStorage};
+use std::num::NonZeroUsize;
+
+pub mod common;
+
+#[test]
+fn dao_hardfork_refund() {
+    let the_dao = address!("bb9bc244d798123fde783fcc1c72d3bb8c189413");
+    let the_dao_extra_balance = address!("807640a13483f8ac783c557fcdf27be11ea4ac7a");
+    let refund_contract = address!("bf4ed7b27f1d666546e30d74d50d173d20bca754");
+    let storage = InMemoryStorage::new(
+        [
+            (
+                the_dao,
+                EvmAccount {
+                    balance: U256::from(1_000),
+                    ..EvmAccount::default()
+                },
+            ),
+            (
+                the_dao_extra_balance,
+                EvmAccount {
+                    balance: U256::from(42),
+                    ..EvmAccount::default()
+                },
+            ),
+        ],
+        None,
+        [],
+    );
+    let block = Block {
+        header: Header {
+            number: Some(1_920_000),
+            timestamp: 1469020840,
+            ..common::MOCK_BLOCK_HEADER.clone()
+        },
+        transactions: BlockTransactions::Full(Vec::new()),
+        ..Block::default()
+    };
+    let result = pevm::execute(
+        &storage,
+        &PevmEthereum::mainnet(),
+        block,
+        NonZeroUsize::MIN,
+        true,
+    )
+    .unwrap();
+
+    let pre_block_state = result.pre_block_state;
+    assert_eq!(
+        pre_block_state[&the_dao].as_ref().unwrap().balance,
+        U256::ZERO
+    );
+    assert_eq!(
+        pre_block_state[&the_dao_extra_balance]
+            .as_ref()
+            .unwrap()
+            .balance,
+        U256::ZERO
+    );
+    assert_eq!(
+        pre_block_state[&refund_contract].as_ref().unwrap().balance,
+        U256::from(1_042)
+    );
+    // Drained accounts that do not exist are not created
+    assert_eq!(pre_block_state.len(), 3);
+}
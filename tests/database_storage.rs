@@ -0,0 +1,30 @@
+// Test executing on a REVM database adapted as a storage.
+
+use pevm::{DatabaseAsStorage, InMemoryStorage, StorageWrapper};
+use revm::{
+    db::CacheDB,
+    primitives::{alloy_primitives::U160, env::TxEnv, Address, TransactTo, U256},
+};
+
+pub mod common;
+
+#[test]
+fn database_as_storage() {
+    let block_size = 1_000; // number of transactions
+    let storage = InMemoryStorage::new((0..=block_size).map(common::mock_account), None, []);
+    let wrapped_storage = StorageWrapper(&storage);
+    common::test_execute_revm(
+        DatabaseAsStorage(CacheDB::new(&wrapped_storage)),
+        // Mock `block_size` transactions sending some tokens to the next account.
+        (1..=block_size)
+            .map(|i| TxEnv {
+                caller: Address::from(U160::from(i)),
+                transact_to: TransactTo::Call(Address::from(U160::from(i % block_size + 1))),
+                value: U256::from(1),
+                gas_limit: common::RAW_TRANSFER_GAS_LIMIT,
+                gas_price: U256::from(1),
+                ..TxEnv::default()
+            })
+            .collect(),
+    );
+}
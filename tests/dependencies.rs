@@ -0,0 +1,99 @@
+// Test the realized dependency graph of parallel executions.
+
+use std::{num::NonZeroUsize, thread};
+
+use pevm::{chain::PevmEthereum, ExecutionMode, InMemoryStorage, Pevm, PevmStrategy};
+use revm::primitives::{
+    alloy_primitives::U160, env::TxEnv, Address, BlockEnv, SpecId, TransactTo, U256,
+};
+
+pub mod common;
+
+fn execute_with_dependencies(storage: &InMemoryStorage, txs: Vec<TxEnv>) -> Pevm {
+    let chain = PevmEthereum::mainnet();
+    let mut pevm = Pevm::new(ExecutionMode::Sync).with_strategy(PevmStrategy {
+        record_dependencies: true,
+        ..PevmStrategy::default()
+    });
+    let sequential_result = pevm::execute_revm_sequential(
+        storage,
+        &chain,
+        SpecId::LATEST,
+        BlockEnv::default(),
+        txs.clone(),
+    );
+    let parallel_result = pevm.execute_revm_parallel(
+        storage,
+        &chain,
+        SpecId::LATEST,
+        BlockEnv::default(),
+        txs,
+        thread::available_parallelism().unwrap_or(NonZeroUsize::MIN),
+    );
+    common::assert_execution_result(&sequential_result, &parallel_result);
+    pevm
+}
+
+#[test]
+fn independent_transfers() {
+    let block_size = 1_000; // number of transactions
+    let storage = InMemoryStorage::new((0..=block_size).map(common::mock_account), None, []);
+    // Mock `block_size` transactions sending some tokens to fresh accounts.
+    let txs: Vec<TxEnv> = (1..=block_size)
+        .map(|i| TxEnv {
+            caller: Address::from(U160::from(i)),
+            transact_to: TransactTo::Call(Address::from(U160::from(block_size + i))),
+            value: U256::from(1),
+            gas_limit: common::RAW_TRANSFER_GAS_LIMIT,
+            gas_price: U256::from(1),
+            ..TxEnv::default()
+        })
+        .collect();
+    let pevm = execute_with_dependencies(&storage, txs.clone());
+    let dependencies = pevm.dependencies().unwrap();
+    assert_eq!(dependencies.len(), block_size);
+    assert!(dependencies
+        .iter()
+        .all(|tx_dependencies| tx_dependencies.is_empty()));
+
+    // Dependencies are only recorded on request.
+    let mut pevm = Pevm::default();
+    pevm.execute_revm_parallel(
+        &storage,
+        &PevmEthereum::mainnet(),
+        SpecId::LATEST,
+        BlockEnv::default(),
+        txs,
+        thread::available_parallelism().unwrap_or(NonZeroUsize::MIN),
+    )
+    .unwrap();
+    assert_eq!(pevm.dependencies(), None);
+}
+
+#[test]
+fn shared_counter() {
+    let block_size = 100; // number of transactions
+    let contract_address = Address::from(U160::from(block_size + 1));
+    let (accounts, bytecodes) = common::mock_counter_accounts(block_size, contract_address);
+    let storage = InMemoryStorage::new(accounts, Some(&bytecodes), []);
+    let txs: Vec<TxEnv> = (1..=block_size)
+        .map(|i| TxEnv {
+            caller: Address::from(U160::from(i)),
+            transact_to: TransactTo::Call(contract_address),
+            gas_limit: 100_000,
+            gas_price: U256::from(1),
+            ..TxEnv::default()
+        })
+        .collect();
+    let pevm = execute_with_dependencies(&storage, txs);
+    // Every transaction reads the counter written by the previous one.
+    for (tx_idx, tx_dependencies) in pevm.dependencies().unwrap().iter().enumerate() {
+        assert!(tx_dependencies
+            .iter()
+            .all(|dependency| *dependency < tx_idx));
+        assert_eq!(
+            tx_idx > 0,
+            tx_dependencies.contains(&tx_idx.wrapping_sub(1))
+        );
+    }
+}
@@ -0,0 +1,188 @@
+// Test executing blocks of the consensus format.
+
+use std::num::NonZeroUsize;
+
+use alloy_consensus::{Header, SignableTransaction, TxEip1559, TxEnvelope};
+use alloy_primitives::{Signature, TxKind, B256, U256};
+use alloy_rlp::Encodable;
+use alloy_rpc_types::Withdrawal;
+use alloy_signer::SignerSync;
+use alloy_signer_local::PrivateKeySigner;
+use pevm::{chain::PevmEthereum, ConsensusBlock, EvmAccount, InMemoryStorage, Pevm, PevmError};
+
+pub mod common;
+
+// Encode a Cancun block of signed raw transfers between [signers].
+fn encode_block(signers: &[PrivateKeySigner]) -> Vec<u8> {
+    let header = Header {
+        number: 1,
+        timestamp: 1710338135,
+        gas_limit: 30_000_000,
+        base_fee_per_gas: Some(0),
+        withdrawals_root: Some(B256::ZERO),
+        blob_gas_used: Some(0),
+        excess_blob_gas: Some(0),
+        parent_beacon_block_root: Some(B256::ZERO),
+        ..Header::default()
+    };
+    let txs: Vec<TxEnvelope> = signers
+        .iter()
+        .enumerate()
+        .map(|(i, signer)| {
+            let tx = TxEip1559 {
+                chain_id: 1,
+                nonce: 0,
+                gas_limit: common::RAW_TRANSFER_GAS_LIMIT.into(),
+                max_fee_per_gas: 1,
+                max_priority_fee_per_gas: 1,
+                to: TxKind::Call(signers[(i + 1) % signers.len()].address()),
+                value: U256::from(1),
+                ..TxEip1559::default()
+            };
+            let signature = signer.sign_hash_sync(&tx.signature_hash()).unwrap();
+            TxEnvelope::from(tx.into_signed(signature))
+        })
+        .collect();
+    let ommers: Vec<Header> = Vec::new();
+    let withdrawals: Vec<Withdrawal> = Vec::new();
+
+    let mut payload = Vec::new();
+    header.encode(&mut payload);
+    txs.encode(&mut payload);
+    ommers.encode(&mut payload);
+    withdrawals.encode(&mut payload);
+    let mut block_rlp = Vec::new();
+    alloy_rlp::Header {
+        list: true,
+        payload_length: payload.len(),
+    }
+    .encode(&mut block_rlp);
+    block_rlp.extend(payload);
+    block_rlp
+}
+
+fn mock_signers() -> Vec<PrivateKeySigner> {
+    (1..=10)
+        .map(|i| PrivateKeySigner::from_bytes(&B256::with_last_byte(i)).unwrap())
+        .collect()
+}
+
+fn mock_storage(signers: &[PrivateKeySigner]) -> InMemoryStorage<'static> {
+    InMemoryStorage::new(
+        signers.iter().map(|signer| {
+            (
+                signer.address(),
+                EvmAccount {
+                    balance: U256::from(1_000_000),
+                    ..EvmAccount::default()
+                },
+            )
+        }),
+        None,
+        [],
+    )
+}
+
+#[test]
+fn encoded_raw_transfers() {
+    let signers = mock_signers();
+    let storage = mock_storage(&signers);
+    let chain = PevmEthereum::mainnet();
+    let block_rlp = encode_block(&signers);
+
+    let mut pevm = Pevm::default();
+    let block_result = pevm
+        .execute_encoded(
+            &storage,
+            &chain,
+            &block_rlp,
+            U256::ZERO,
+            NonZeroUsize::MIN,
+            true,
+        )
+        .unwrap();
+    assert_eq!(block_result.tx_results.len(), signers.len());
+    // The senders are recovered from the signatures.
+    for (signer, tx_result) in signers.iter().zip(&block_result.tx_results) {
+        assert!(tx_result.receipt.status());
+        let sender = tx_result.state[&signer.address()].as_ref().unwrap();
+        assert_eq!(sender.nonce, 1);
+    }
+
+    // Truncated blocks aren't valid encodings.
+    assert!(matches!(
+        pevm.execute_encoded(
+            &storage,
+            &chain,
+            &block_rlp[..block_rlp.len() - 1],
+            U256::ZERO,
+            NonZeroUsize::MIN,
+            true,
+        ),
+        Err(PevmError::InvalidBlockEncoding(_))
+    ));
+}
+
+#[test]
+fn consensus_block_with_senders() {
+    let signers = mock_signers();
+    let storage = mock_storage(&signers);
+    let chain = PevmEthereum::mainnet();
+    let block_rlp = encode_block(&signers);
+    let expected_result = Pevm::default()
+        .execute_encoded(
+            &storage,
+            &chain,
+            &block_rlp,
+            U256::ZERO,
+            NonZeroUsize::MIN,
+            true,
+        )
+        .unwrap();
+
+    // Known senders skip recovery.
+    let mut block = ConsensusBlock::decode(&block_rlp, U256::ZERO).unwrap();
+    block.senders = Some(signers.iter().map(|signer| signer.address()).collect());
+    let mut pevm = Pevm::default();
+    assert_eq!(
+        pevm.execute_block(&storage, &chain, block.clone(), NonZeroUsize::MIN, true),
+        Ok(expected_result)
+    );
+
+    // There must be a sender for every transaction.
+    block.senders.as_mut().unwrap().pop();
+    assert_eq!(
+        pevm.execute_block(&storage, &chain, block, NonZeroUsize::MIN, true),
+        Err(PevmError::MissingTransactionData)
+    );
+}
+
+#[test]
+fn high_s_signatures_are_invalid() {
+    let signers = mock_signers();
+    let storage = mock_storage(&signers);
+    let chain = PevmEthereum::mainnet();
+    let mut block = ConsensusBlock::decode(&encode_block(&signers), U256::ZERO).unwrap();
+    // The malleated signature `(r, n - s)` with the flipped parity recovers
+    // the same sender, but is invalid since Homestead (EIP-2).
+    let TxEnvelope::Eip1559(signed) = block.transactions[3].clone() else {
+        unreachable!();
+    };
+    let (tx, signature, _) = signed.into_parts();
+    let secp256k1n = U256::from_str_radix(
+        "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141",
+        16,
+    )
+    .unwrap();
+    let malleated = Signature::from_rs_and_parity(
+        signature.r(),
+        secp256k1n - signature.s(),
+        signature.v().inverted(),
+    )
+    .unwrap();
+    block.transactions[3] = TxEnvelope::from(tx.into_signed(malleated));
+    assert_eq!(
+        Pevm::default().execute_block(&storage, &chain, block, NonZeroUsize::new(4).unwrap(), true),
+        Err(PevmError::InvalidSignature { tx_idx: 3 })
+    );
+}
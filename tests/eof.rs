@@ -0,0 +1,111 @@
+// Test executing EOF (EIP-7692) contracts behind the `eof` feature.
+#![cfg(feature = "eof")]
+
+use std::{num::NonZeroUsize, thread};
+
+use pevm::{
+    chain::{PevmChain, PevmEthereum},
+    Bytecodes, EvmAccount, EvmCode, InMemoryStorage,
+};
+use revm::primitives::{
+    alloy_primitives::U160, env::TxEnv, Address, BlockEnv, Bytecode, Bytes, Eof, SpecId,
+    TransactTo, U256,
+};
+
+pub mod common;
+
+// A container with a single non-returning code section of `STOP`.
+const STOP_CONTAINER: [u8; 20] = [
+    0xef, 0x00, 0x01, // magic & version
+    0x01, 0x00, 0x04, // types section size
+    0x02, 0x00, 0x01, 0x00, 0x01, // one code section of one byte
+    0x04, 0x00, 0x00, // empty data section
+    0x00, // header terminator
+    0x00, 0x80, 0x00, 0x00, // types: 0 inputs, non-returning, 0 max stack height
+    0x00, // STOP
+];
+
+#[test]
+fn eof_spec_override() {
+    let header = common::MOCK_BLOCK_HEADER.clone();
+    assert_eq!(
+        PevmEthereum::mainnet().get_block_spec(&header),
+        Ok(SpecId::CANCUN)
+    );
+    assert_eq!(
+        PevmEthereum::mainnet()
+            .with_eof_timestamp(header.timestamp)
+            .get_block_spec(&header),
+        Ok(SpecId::PRAGUE_EOF)
+    );
+}
+
+#[test]
+fn eof_contract_calls() {
+    let code = Bytecode::Eof(
+        Eof::decode(Bytes::from_static(&STOP_CONTAINER))
+            .unwrap()
+            .into(),
+    );
+    let code_hash = code.hash_slow();
+    let code = EvmCode::from(code.clone());
+    assert_eq!(Bytecode::from(code.clone()).hash_slow(), code_hash);
+
+    let block_size = 10;
+    let contract_address = Address::from(U160::from(1_000));
+    let mut bytecodes = Bytecodes::new();
+    bytecodes.insert(code_hash, code.clone());
+    let mut accounts: Vec<_> = (0..=block_size).map(common::mock_account).collect();
+    accounts.push((
+        contract_address,
+        EvmAccount {
+            code_hash: Some(code_hash),
+            code: Some(code),
+            ..EvmAccount::default()
+        },
+    ));
+    let storage = InMemoryStorage::new(accounts, Some(&bytecodes), []);
+
+    let txs: Vec<TxEnv> = (1..=block_size)
+        .map(|i| TxEnv {
+            caller: Address::from(U160::from(i)),
+            transact_to: TransactTo::Call(contract_address),
+            value: U256::from(i),
+            gas_limit: 100_000,
+            gas_price: U256::from(1),
+            ..TxEnv::default()
+        })
+        .collect();
+
+    let chain = PevmEthereum::mainnet();
+    let sequential_result = pevm::execute_revm_sequential(
+        &storage,
+        &chain,
+        SpecId::PRAGUE_EOF,
+        BlockEnv::default(),
+        txs.clone(),
+    );
+    let parallel_result = pevm::execute_revm_parallel(
+        &storage,
+        &chain,
+        SpecId::PRAGUE_EOF,
+        BlockEnv::default(),
+        txs,
+        thread::available_parallelism().unwrap_or(NonZeroUsize::MIN),
+    );
+    common::assert_execution_result(&sequential_result, &parallel_result);
+    assert!(parallel_result
+        .unwrap()
+        .iter()
+        .all(|tx_result| tx_result.receipt.is_success()));
+}
+
+#[test]
+fn invalid_eof_container_is_legacy() {
+    // The magic prefix without a valid container falls back to legacy.
+    let raw = Bytes::from_static(&[0xef, 0x00, 0x00]);
+    let code = EvmCode::from(Bytecode::LegacyRaw(raw.clone()));
+    let code = Bytecode::from(code);
+    assert!(matches!(code, Bytecode::LegacyAnalyzed(_)));
+    assert_eq!(code.original_bytes(), raw);
+}
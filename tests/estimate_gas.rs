@@ -0,0 +1,93 @@
+// Test estimating the gas of transactions by binary search.
+
+use pevm::{chain::PevmEthereum, Bytecodes, EvmAccount, EvmCode, InMemoryStorage, Pevm, PevmError};
+use revm::primitives::{
+    alloy_primitives::U160, env::TxEnv, Address, BlockEnv, Bytecode, Bytes, SpecId, TransactTo,
+    U256,
+};
+
+pub mod common;
+
+#[test]
+fn estimate_gas_nested_calls() {
+    let counter_address = Address::from(U160::from(100));
+    let caller_address = Address::from(U160::from(101));
+    let reverter_address = Address::from(U160::from(102));
+    let counter_code = common::counter_contract();
+    // `PUSH1 0 PUSH1 0 PUSH1 0 PUSH1 0 PUSH1 0 PUSH20 <counter> GAS CALL
+    // ISZERO PUSH1 <revert> JUMPI STOP JUMPDEST PUSH1 0 PUSH1 0 REVERT`:
+    // Call the counter, reverting if the call fails.
+    let mut caller_code = vec![
+        0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x73,
+    ];
+    caller_code.extend_from_slice(counter_address.as_slice());
+    caller_code.extend_from_slice(&[0x5a, 0xf1, 0x15, 0x60, 0x26, 0x57, 0x00]);
+    caller_code.extend_from_slice(&[0x5b, 0x60, 0x00, 0x60, 0x00, 0xfd]);
+    let caller_code = Bytecode::new_raw(Bytes::from(caller_code));
+    // `PUSH1 0 PUSH1 0 REVERT`: Always revert.
+    let reverter_code = Bytecode::new_raw(Bytes::from_static(&[0x60, 0x00, 0x60, 0x00, 0xfd]));
+    let mut bytecodes = Bytecodes::new();
+    let mut accounts = vec![common::mock_account(1)];
+    for (address, code) in [
+        (counter_address, counter_code),
+        (caller_address, caller_code),
+        (reverter_address, reverter_code),
+    ] {
+        let code_hash = code.hash_slow();
+        bytecodes.insert(code_hash, EvmCode::from(code));
+        accounts.push((
+            address,
+            EvmAccount {
+                code_hash: Some(code_hash),
+                ..EvmAccount::default()
+            },
+        ));
+    }
+    let storage = InMemoryStorage::new(accounts, Some(&bytecodes), []);
+    let chain = PevmEthereum::mainnet();
+    let tx = |to: Address, gas_limit: u64| TxEnv {
+        caller: Address::from(U160::from(1)),
+        transact_to: TransactTo::Call(to),
+        value: U256::ZERO,
+        gas_limit,
+        gas_price: U256::from(1),
+        ..TxEnv::default()
+    };
+    let pevm = Pevm::default();
+    let estimate = |to: Address| {
+        pevm.estimate_gas(
+            &storage,
+            &chain,
+            SpecId::LATEST,
+            BlockEnv::default(),
+            tx(to, 1_000_000),
+        )
+    };
+    // Whether the transaction succeeds with a gas limit.
+    let succeeds = |to: Address, gas_limit: u64| {
+        pevm::execute_revm_sequential(
+            &storage,
+            &chain,
+            SpecId::LATEST,
+            BlockEnv::default(),
+            vec![tx(to, gas_limit)],
+        )
+        .is_ok_and(|tx_results| tx_results[0].receipt.status())
+    };
+
+    assert_eq!(
+        estimate(Address::from(U160::from(2))),
+        Ok(common::RAW_TRANSFER_GAS_LIMIT)
+    );
+    for to in [counter_address, caller_address] {
+        let gas_limit = estimate(to).unwrap();
+        assert!(succeeds(to, gas_limit));
+        assert!(!succeeds(to, gas_limit - 1));
+    }
+    assert_eq!(
+        estimate(reverter_address),
+        Err(PevmError::GasEstimationFailed {
+            output: Bytes::new()
+        })
+    );
+}
@@ -0,0 +1,33 @@
+// Test executing Alloy blocks with options.
+
+use std::num::NonZeroUsize;
+
+use pevm::{chain::PevmEthereum, Pevm, PevmOptions, PevmStrategy, SchedulingPolicy};
+
+pub mod common;
+
+#[test]
+fn mainnet_blocks_with_options() {
+    let chain = PevmEthereum::mainnet();
+    let concurrency_level = NonZeroUsize::new(4).unwrap();
+    common::for_each_block_from_disk(|block, storage| {
+        let expected_result = Pevm::default()
+            .execute(&storage, &chain, block.clone(), concurrency_level, true)
+            .unwrap();
+
+        for options in [
+            PevmOptions::default().with_concurrency_level(concurrency_level),
+            PevmOptions::default().with_force_sequential(true),
+            PevmOptions::default().with_strategy(PevmStrategy {
+                scheduling: SchedulingPolicy::GasWeighted,
+                record_dependencies: true,
+                ..PevmStrategy::default()
+            }),
+        ] {
+            let result = Pevm::default()
+                .execute_with(&storage, &chain, block.clone(), options)
+                .unwrap();
+            assert_eq!(result, expected_result);
+        }
+    });
+}
@@ -0,0 +1,122 @@
+// Test executing mainnet blocks in ranges of transactions, each on top of
+// the results of the previous ranges.
+
+use std::num::NonZeroUsize;
+
+use pevm::{chain::PevmEthereum, ExecutionMode, Pevm, PevmError};
+
+pub mod common;
+
+#[test]
+fn mainnet_blocks_in_ranges() {
+    let chain = PevmEthereum::mainnet();
+    let concurrency_level = NonZeroUsize::new(4).unwrap();
+    common::for_each_block_from_disk(|block, storage| {
+        let block_size = block.transactions.len();
+        let expected_tx_results = Pevm::default()
+            .execute(&storage, &chain, block.clone(), concurrency_level, true)
+            .unwrap()
+            .tx_results;
+
+        let mut pevm = Pevm::default();
+        let mut tx_results = Vec::new();
+        let range_size = block_size / 3 + 1;
+        for start in (0..block_size).step_by(range_size) {
+            let end = (start + range_size).min(block_size);
+            let range_results = pevm
+                .execute_range(
+                    &storage,
+                    &chain,
+                    block.clone(),
+                    start..end,
+                    &tx_results,
+                    &[],
+                    concurrency_level,
+                )
+                .unwrap();
+            assert_eq!(range_results.len(), end - start);
+            tx_results.extend(range_results);
+        }
+        assert_eq!(tx_results, expected_tx_results);
+
+        // Ranges must start right after the prior results.
+        assert!(matches!(
+            pevm.execute_range(
+                &storage,
+                &chain,
+                block.clone(),
+                block_size..block_size + 1,
+                &[],
+                &[],
+                concurrency_level,
+            ),
+            Err(PevmError::InvalidTransactionRange { .. })
+        ));
+
+        // Skipped transactions of [ExecutionMode::Build] are only excused
+        // when the caller lists them.
+        if block_size > 1 {
+            let mut pevm = Pevm::new(ExecutionMode::Build);
+            assert!(matches!(
+                pevm.execute_range(
+                    &storage,
+                    &chain,
+                    block.clone(),
+                    1..block_size,
+                    &[],
+                    &[],
+                    concurrency_level,
+                ),
+                Err(PevmError::InvalidTransactionRange { .. })
+            ));
+            assert!(pevm
+                .execute_range(
+                    &storage,
+                    &chain,
+                    block,
+                    1..block_size,
+                    &[],
+                    &[0],
+                    concurrency_level,
+                )
+                .is_ok());
+        }
+    });
+}
+
+#[test]
+fn mainnet_blocks_tx_at() {
+    let chain = PevmEthereum::mainnet();
+    let concurrency_level = NonZeroUsize::new(4).unwrap();
+    common::for_each_block_from_disk(|block, storage| {
+        let block_size = block.transactions.len();
+        let expected_tx_results = Pevm::default()
+            .execute(&storage, &chain, block.clone(), concurrency_level, true)
+            .unwrap()
+            .tx_results;
+
+        let mut pevm = Pevm::default();
+        for tx_idx in [0, block_size / 2, block_size.saturating_sub(1)] {
+            if tx_idx >= block_size {
+                continue;
+            }
+            let tx_result = pevm
+                .execute_tx_at(
+                    &storage,
+                    &chain,
+                    block.clone(),
+                    tx_idx,
+                    None,
+                    concurrency_level,
+                )
+                .unwrap();
+            assert_eq!(tx_result, expected_tx_results[tx_idx]);
+        }
+
+        // Only overrides can append a transaction to the block.
+        assert!(matches!(
+            pevm.execute_tx_at(&storage, &chain, block, block_size, None, concurrency_level,),
+            Err(PevmError::InvalidTransactionRange { .. })
+        ));
+    });
+}
@@ -0,0 +1,200 @@
+// Test hooking the final transaction results of executions.
+
+use std::{
+    num::NonZeroUsize,
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use pevm::{
+    chain::PevmEthereum, BlockOverrides, BundleOptions, ExecutionHook, InMemoryStorage, Pevm,
+    PevmTxExecutionResult, StateOverrides,
+};
+use revm::primitives::{
+    alloy_primitives::U160, env::TxEnv, Address, BlockEnv, SpecId, TransactTo, U256,
+};
+
+pub mod common;
+
+#[derive(Debug, Default)]
+struct RecordingHook {
+    // The hooked transaction indices, with the gas used by the transactions
+    // after their results are final.
+    events: Mutex<Vec<(usize, Option<u64>)>>,
+}
+
+impl ExecutionHook for RecordingHook {
+    fn before_tx(&self, tx_idx: usize, _tx: &TxEnv) {
+        self.events.lock().unwrap().push((tx_idx, None));
+    }
+
+    fn after_tx(&self, tx_idx: usize, tx_result: &PevmTxExecutionResult) {
+        self.events
+            .lock()
+            .unwrap()
+            .push((tx_idx, Some(tx_result.gas_used)));
+    }
+}
+
+// Record the progress reports of executions.
+#[derive(Debug, Default)]
+struct ProgressHook {
+    reports: Mutex<Vec<(usize, usize, usize)>>,
+}
+
+impl ExecutionHook for ProgressHook {
+    fn on_progress(&self, executed: usize, validated: usize, total: usize) {
+        self.reports
+            .lock()
+            .unwrap()
+            .push((executed, validated, total));
+    }
+}
+
+#[test]
+fn hooks_in_block_order() {
+    let block_size = 1_000; // number of transactions
+    let storage = InMemoryStorage::new((0..=block_size).map(common::mock_account), None, []);
+    // Independent raw transfers to the next account.
+    let txs: Vec<TxEnv> = (1..=block_size)
+        .map(|i| TxEnv {
+            caller: Address::from(U160::from(i)),
+            transact_to: TransactTo::Call(Address::from(U160::from(i % block_size + 1))),
+            value: U256::from(1),
+            gas_limit: common::RAW_TRANSFER_GAS_LIMIT,
+            gas_price: U256::from(1),
+            ..TxEnv::default()
+        })
+        .collect();
+    let chain = PevmEthereum::mainnet();
+    let concurrency_level = thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+    let hook = Arc::new(RecordingHook::default());
+    let mut pevm = Pevm::default().with_hook(hook.clone());
+
+    let tx_results = pevm
+        .execute_revm_parallel(
+            &storage,
+            &chain,
+            SpecId::LATEST,
+            BlockEnv::default(),
+            txs.clone(),
+            concurrency_level,
+        )
+        .unwrap();
+    let expected_events: Vec<_> = tx_results
+        .iter()
+        .enumerate()
+        .flat_map(|(tx_idx, tx_result)| [(tx_idx, None), (tx_idx, Some(tx_result.gas_used))])
+        .collect();
+    assert_eq!(*hook.events.lock().unwrap(), expected_events);
+
+    // Simulations aren't hooked.
+    hook.events.lock().unwrap().clear();
+    pevm.simulate_bundle(
+        &storage,
+        &chain,
+        BlockEnv::default(),
+        txs,
+        &BundleOptions {
+            spec_id: SpecId::LATEST,
+            reverting_tx_idxs: Vec::new(),
+            concurrency_level,
+            block_overrides: BlockOverrides::default(),
+            state_overrides: StateOverrides::default(),
+        },
+    )
+    .unwrap();
+    assert!(hook.events.lock().unwrap().is_empty());
+}
+
+#[test]
+fn hooks_on_mainnet_blocks() {
+    let chain = PevmEthereum::mainnet();
+    let concurrency_level = NonZeroUsize::new(4).unwrap();
+    common::for_each_block_from_disk(|block, storage| {
+        let hook = Arc::new(RecordingHook::default());
+        let block_result = Pevm::default()
+            .with_hook(hook.clone())
+            .execute(&storage, &chain, block, concurrency_level, false)
+            .unwrap();
+        let events = hook.events.lock().unwrap();
+        assert_eq!(events.len(), 2 * block_result.tx_results.len());
+        for (tx_idx, tx_result) in block_result.tx_results.iter().enumerate() {
+            assert_eq!(events[2 * tx_idx], (tx_idx, None));
+            assert_eq!(events[2 * tx_idx + 1], (tx_idx, Some(tx_result.gas_used)));
+        }
+    });
+}
+
+#[test]
+fn progress_on_mainnet_blocks() {
+    let chain = PevmEthereum::mainnet();
+    let concurrency_level = NonZeroUsize::new(4).unwrap();
+    common::for_each_block_from_disk(|block, storage| {
+        let hook = Arc::new(ProgressHook::default());
+        let mut pevm = Pevm::default().with_hook(hook.clone());
+        let block_result = pevm
+            .execute(&storage, &chain, block, concurrency_level, false)
+            .unwrap();
+        let reports = hook.reports.lock().unwrap();
+        // Small blocks execute sequentially without progress reports, as do
+        // blocks that fall back to sequential execution in the end.
+        let Some(last_report) = reports.last() else {
+            return;
+        };
+        if pevm.fallback().is_some() {
+            return;
+        }
+        let total = block_result.tx_results.len();
+        assert_eq!(*last_report, (total, total, total));
+        for (executed, validated, report_total) in reports.iter() {
+            assert_eq!(*report_total, total);
+            assert!(*executed <= total && *validated <= total);
+        }
+    });
+}
+
+#[test]
+fn hooks_on_incremental_executions() {
+    let block_size = 100; // number of transactions
+    let storage = InMemoryStorage::new((0..=block_size).map(common::mock_account), None, []);
+    // Raw transfers to the next account, sending more from [changed_idx] on.
+    let txs = |changed_idx: usize| -> Vec<TxEnv> {
+        (1..=block_size)
+            .map(|i| TxEnv {
+                caller: Address::from(U160::from(i)),
+                transact_to: TransactTo::Call(Address::from(U160::from(i % block_size + 1))),
+                value: U256::from(if i > changed_idx { 2 } else { 1 }),
+                gas_limit: common::RAW_TRANSFER_GAS_LIMIT,
+                gas_price: U256::from(1),
+                ..TxEnv::default()
+            })
+            .collect()
+    };
+    let chain = PevmEthereum::mainnet();
+    let concurrency_level = thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+    let hook = Arc::new(RecordingHook::default());
+    let mut pevm = Pevm::default().with_hook(hook.clone());
+
+    for changed_idx in [block_size, block_size / 2] {
+        hook.events.lock().unwrap().clear();
+        let tx_results = pevm
+            .execute_revm_parallel_incremental(
+                &storage,
+                &chain,
+                SpecId::LATEST,
+                BlockEnv::default(),
+                txs(changed_idx),
+                concurrency_level,
+            )
+            .unwrap();
+        // The reused prefix is hooked too, with the indices of the block.
+        let expected_events: Vec<_> = tx_results
+            .iter()
+            .enumerate()
+            .flat_map(|(tx_idx, tx_result)| [(tx_idx, None), (tx_idx, Some(tx_result.gas_used))])
+            .collect();
+        assert_eq!(*hook.events.lock().unwrap(), expected_events);
+    }
+    assert_eq!(pevm.reused_tx_count(), block_size / 2);
+}
@@ -0,0 +1,164 @@
+// Test the handling of invalid transactions in different execution modes.
+
+use std::num::NonZeroUsize;
+
+use pevm::{
+    chain::PevmEthereum, Bytecodes, EvmAccount, EvmCode, ExecutionMode, InMemoryStorage, Pevm,
+    PevmError, TxExecutionError,
+};
+use revm::primitives::{
+    alloy_primitives::U160, env::TxEnv, Address, BlockEnv, Bytecode, Bytes, SpecId, TransactTo,
+    U256,
+};
+
+pub mod common;
+
+// Three raw transfers, with the middle one sent from an account without
+// funds for gas.
+fn execute_in_mode(pevm: &mut Pevm) -> pevm::PevmResult<PevmEthereum> {
+    pevm.execute_revm_parallel(
+        &InMemoryStorage::new((0..=2).map(common::mock_account), None, []),
+        &PevmEthereum::mainnet(),
+        SpecId::LATEST,
+        BlockEnv::default(),
+        [1, 1_000, 2]
+            .into_iter()
+            .map(|i| {
+                let address = Address::from(U160::from(i));
+                TxEnv {
+                    caller: address,
+                    transact_to: TransactTo::Call(address),
+                    value: U256::from(1),
+                    gas_limit: common::RAW_TRANSFER_GAS_LIMIT,
+                    gas_price: U256::from(1),
+                    ..TxEnv::default()
+                }
+            })
+            .collect(),
+        NonZeroUsize::new(2).unwrap(),
+    )
+}
+
+#[test]
+fn build_mode_skips_invalid_transactions() {
+    let mut pevm = Pevm::new(ExecutionMode::Build);
+    let tx_results = execute_in_mode(&mut pevm).unwrap();
+    assert_eq!(tx_results.len(), 2);
+    assert_eq!(pevm.skipped_tx_idxs(), &[1]);
+    assert_eq!(
+        tx_results[1].receipt.cumulative_gas_used(),
+        2 * common::RAW_TRANSFER_GAS_LIMIT as u128
+    );
+}
+
+#[test]
+fn validate_mode_rejects_invalid_transactions() {
+    let mut pevm = Pevm::new(ExecutionMode::Validate);
+    assert!(matches!(
+        execute_in_mode(&mut pevm),
+        Err(PevmError::ExecutionError(TxExecutionError {
+            tx_idx: 1,
+            ..
+        }))
+    ));
+    assert!(pevm.skipped_tx_idxs().is_empty());
+}
+
+#[test]
+fn build_mode_skips_invalid_raw_transfers() {
+    // Raw transfers around two from the same account without funds for gas,
+    // which would be lazily updated when syncing.
+    let txs: Vec<TxEnv> = [(1, 2), (1_000, 3), (1_000, 4), (2, 1)]
+        .into_iter()
+        .map(|(from, to)| TxEnv {
+            caller: Address::from(U160::from(from)),
+            transact_to: TransactTo::Call(Address::from(U160::from(to))),
+            value: U256::from(1),
+            gas_limit: common::RAW_TRANSFER_GAS_LIMIT,
+            gas_price: U256::from(1),
+            ..TxEnv::default()
+        })
+        .collect();
+    let mut pevm = Pevm::new(ExecutionMode::Build);
+    let tx_results = pevm
+        .execute_revm_parallel(
+            &InMemoryStorage::new((0..=4).map(common::mock_account), None, []),
+            &PevmEthereum::mainnet(),
+            SpecId::LATEST,
+            BlockEnv::default(),
+            txs,
+            NonZeroUsize::new(2).unwrap(),
+        )
+        .unwrap();
+    assert_eq!(tx_results.len(), 2);
+    assert_eq!(pevm.skipped_tx_idxs(), &[1, 2]);
+    assert_eq!(
+        tx_results[1].receipt.cumulative_gas_used(),
+        2 * common::RAW_TRANSFER_GAS_LIMIT as u128
+    );
+}
+
+#[test]
+fn build_mode_reads_beneficiary_after_skipped_txs() {
+    let contract_address = Address::from(U160::from(100));
+    // `COINBASE BALANCE PUSH1 0 SSTORE STOP`: Store the beneficiary's balance.
+    let code = Bytecode::new_raw(Bytes::from_static(&[0x41, 0x31, 0x60, 0x00, 0x55, 0x00]));
+    let code_hash = code.hash_slow();
+    let mut bytecodes = Bytecodes::new();
+    bytecodes.insert(code_hash, EvmCode::from(code));
+    let mut accounts: Vec<_> = (0..=3).map(common::mock_account).collect();
+    accounts.push((
+        contract_address,
+        EvmAccount {
+            code_hash: Some(code_hash),
+            ..EvmAccount::default()
+        },
+    ));
+    let storage = InMemoryStorage::new(accounts, Some(&bytecodes), []);
+    // A raw transfer, one from an account without funds for gas, then a
+    // call that reads the beneficiary's rewards so far.
+    let txs: Vec<TxEnv> = [
+        (
+            1,
+            Address::from(U160::from(2)),
+            common::RAW_TRANSFER_GAS_LIMIT,
+        ),
+        (
+            1_000,
+            Address::from(U160::from(2)),
+            common::RAW_TRANSFER_GAS_LIMIT,
+        ),
+        (3, contract_address, 100_000),
+    ]
+    .into_iter()
+    .map(|(from, to, gas_limit)| TxEnv {
+        caller: Address::from(U160::from(from)),
+        transact_to: TransactTo::Call(to),
+        gas_limit,
+        gas_price: U256::from(1),
+        ..TxEnv::default()
+    })
+    .collect();
+    let chain = PevmEthereum::mainnet();
+    let expected_tx_results = pevm::execute_revm_sequential(
+        &storage,
+        &chain,
+        SpecId::LATEST,
+        BlockEnv::default(),
+        vec![txs[0].clone(), txs[2].clone()],
+    )
+    .unwrap();
+    let mut pevm = Pevm::new(ExecutionMode::Build);
+    let tx_results = pevm
+        .execute_revm_parallel(
+            &storage,
+            &chain,
+            SpecId::LATEST,
+            BlockEnv::default(),
+            txs,
+            NonZeroUsize::new(2).unwrap(),
+        )
+        .unwrap();
+    assert_eq!(pevm.skipped_tx_idxs(), &[1]);
+    assert_eq!(tx_results, expected_tx_results);
+}
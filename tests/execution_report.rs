@@ -0,0 +1,171 @@
+// Test the per-transaction scheduling statistics of parallel executions.
+
+use std::{num::NonZeroUsize, thread};
+
+use pevm::{
+    chain::PevmEthereum, ExecutionMode, InMemoryStorage, Pevm, PevmStrategy, ScheduleEvent,
+};
+use revm::primitives::{
+    alloy_primitives::U160, env::TxEnv, Address, BlockEnv, SpecId, TransactTo, U256,
+};
+
+pub mod common;
+
+#[test]
+fn report_contended_block() {
+    let block_size = 1_000; // number of transactions
+    let contract_address = Address::from(U160::from(block_size + 1));
+    let (accounts, bytecodes) = common::mock_counter_accounts(block_size, contract_address);
+    let storage = InMemoryStorage::new(accounts, Some(&bytecodes), []);
+    // Raw transfers to the next account, with every tenth transaction
+    // incrementing a shared counter instead.
+    let txs: Vec<TxEnv> = (1..=block_size)
+        .map(|i| {
+            let (to, value, gas_limit) = if i % 10 == 0 {
+                (contract_address, U256::ZERO, 100_000)
+            } else {
+                (
+                    Address::from(U160::from(i % block_size + 1)),
+                    U256::from(1),
+                    common::RAW_TRANSFER_GAS_LIMIT,
+                )
+            };
+            TxEnv {
+                caller: Address::from(U160::from(i)),
+                transact_to: TransactTo::Call(to),
+                value,
+                gas_limit,
+                gas_price: U256::from(1),
+                ..TxEnv::default()
+            }
+        })
+        .collect();
+    let chain = PevmEthereum::mainnet();
+    let concurrency_level = thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+    let mut pevm = Pevm::new(ExecutionMode::Sync).with_strategy(PevmStrategy {
+        record_schedule: true,
+        record_report: true,
+        ..PevmStrategy::default()
+    });
+    let sequential_result = pevm::execute_revm_sequential(
+        &storage,
+        &chain,
+        SpecId::LATEST,
+        BlockEnv::default(),
+        txs.clone(),
+    );
+    let parallel_result = pevm.execute_revm_parallel(
+        &storage,
+        &chain,
+        SpecId::LATEST,
+        BlockEnv::default(),
+        txs.clone(),
+        concurrency_level,
+    );
+    common::assert_execution_result(&sequential_result, &parallel_result);
+
+    // The report matches the recorded schedule.
+    let report = pevm.report().unwrap();
+    assert_eq!(report.txs.len(), block_size);
+    for (tx_idx, tx_report) in report.txs.iter().enumerate() {
+        let executions = pevm
+            .schedule()
+            .unwrap()
+            .iter()
+            .filter(|event| matches!(event, ScheduleEvent::Execute { tx_idx: idx, .. } if *idx == tx_idx))
+            .count();
+        let aborts: Vec<_> = pevm
+            .schedule()
+            .unwrap()
+            .iter()
+            .filter_map(|event| match event {
+                ScheduleEvent::Abort {
+                    tx_idx: idx, cause, ..
+                } if *idx == tx_idx => Some(*cause),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(tx_report.incarnations, executions);
+        assert_eq!(tx_report.incarnations, tx_report.aborts.len() + 1);
+        assert_eq!(tx_report.aborts, aborts);
+        assert!(tx_report.worker_idx < concurrency_level.get());
+        // Contract calls are never lazily updated.
+        if txs[tx_idx].transact_to == TransactTo::Call(contract_address) {
+            assert!(!tx_report.lazy);
+        }
+    }
+
+    // The report is only recorded on request.
+    let mut pevm = Pevm::default();
+    pevm.execute_revm_parallel(
+        &storage,
+        &chain,
+        SpecId::LATEST,
+        BlockEnv::default(),
+        txs,
+        concurrency_level,
+    )
+    .unwrap();
+    assert_eq!(pevm.report(), None);
+}
+
+#[test]
+fn critical_path_serial_counter() {
+    let block_size = 1_000; // number of transactions
+    let contract_address = Address::from(U160::from(block_size + 1));
+    let (accounts, bytecodes) = common::mock_counter_accounts(block_size, contract_address);
+    let storage = InMemoryStorage::new(accounts, Some(&bytecodes), []);
+    // Independent raw transfers to the sender itself, with every fifth
+    // transaction incrementing a shared counter after the previous one.
+    let txs: Vec<TxEnv> = (1..=block_size)
+        .map(|i| TxEnv {
+            caller: Address::from(U160::from(i)),
+            transact_to: TransactTo::Call(if i % 5 == 0 {
+                contract_address
+            } else {
+                Address::from(U160::from(i))
+            }),
+            value: U256::ZERO,
+            gas_limit: 100_000,
+            gas_price: U256::from(1),
+            ..TxEnv::default()
+        })
+        .collect();
+    let counter_tx_idxs: Vec<usize> = (0..block_size).filter(|i| (i + 1) % 5 == 0).collect();
+    let chain = PevmEthereum::mainnet();
+    let concurrency_level = thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+    let mut pevm = Pevm::new(ExecutionMode::Sync).with_strategy(PevmStrategy {
+        record_report: true,
+        ..PevmStrategy::default()
+    });
+    let tx_results = pevm
+        .execute_revm_parallel(
+            &storage,
+            &chain,
+            SpecId::LATEST,
+            BlockEnv::default(),
+            txs,
+            concurrency_level,
+        )
+        .unwrap();
+
+    // The counter increments chain up through the critical path.
+    let report = pevm.report().unwrap();
+    for (tx_report, tx_result) in report.txs.iter().zip(&tx_results) {
+        assert_eq!(tx_report.gas_used, tx_result.gas_used);
+    }
+    assert_eq!(report.critical_path, counter_tx_idxs);
+    assert_eq!(
+        report.critical_path_gas,
+        counter_tx_idxs
+            .iter()
+            .map(|tx_idx| tx_results[*tx_idx].gas_used)
+            .sum::<u64>()
+    );
+    assert!(report.critical_path_time > std::time::Duration::ZERO);
+    let gas_used: u64 = tx_results.iter().map(|tx_result| tx_result.gas_used).sum();
+    assert_eq!(
+        report.theoretical_speedup(),
+        Some(gas_used as f64 / report.critical_path_gas as f64)
+    );
+}
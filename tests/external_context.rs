@@ -0,0 +1,95 @@
+// Test executing blocks with the external contexts of custom handlers.
+
+use std::{
+    num::NonZeroUsize,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+};
+
+use pevm::{chain::PevmEthereum, ExternalContext, ExternalFactory, InMemoryStorage, Pevm};
+use revm::{
+    handler::Handler,
+    primitives::{
+        alloy_primitives::U160, env::TxEnv, Address, BlockEnv, EVMError, ResultAndState, SpecId,
+        TransactTo, U256,
+    },
+    Context, Database,
+};
+
+pub mod common;
+
+// Count the executions that reach the end of their handler.
+#[derive(Debug, Default)]
+struct ExecutionCounter {
+    executions: Arc<AtomicUsize>,
+}
+
+struct CounterContext {
+    executions: Arc<AtomicUsize>,
+}
+
+impl ExternalContext for CounterContext {
+    fn register_handles<'a, DB: Database>(handler: &mut Handler<'a, Context<Self, DB>, Self, DB>) {
+        let end = handler.post_execution.end.clone();
+        handler.post_execution.end = Arc::new(
+            move |context: &mut Context<Self, DB>,
+                  result: Result<ResultAndState, EVMError<DB::Error>>| {
+                context.external.executions.fetch_add(1, Ordering::Relaxed);
+                end(context, result)
+            },
+        );
+    }
+}
+
+impl ExternalFactory for ExecutionCounter {
+    type External = CounterContext;
+
+    fn external(&self, _tx_idx: usize) -> CounterContext {
+        CounterContext {
+            executions: self.executions.clone(),
+        }
+    }
+}
+
+#[test]
+fn external_context_handles() {
+    let block_size = 1_000; // number of transactions
+    let storage = InMemoryStorage::new((0..=block_size).map(common::mock_account), None, []);
+    // Raw transfers to the same recipient, to conflict on its balance.
+    let txs: Vec<TxEnv> = (1..=block_size)
+        .map(|i| TxEnv {
+            caller: Address::from(U160::from(i)),
+            transact_to: TransactTo::Call(Address::ZERO),
+            value: U256::from(1),
+            gas_limit: common::RAW_TRANSFER_GAS_LIMIT,
+            gas_price: U256::from(1),
+            ..TxEnv::default()
+        })
+        .collect();
+    let chain = PevmEthereum::mainnet();
+    let concurrency_level = thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+    let counter = ExecutionCounter::default();
+
+    let sequential_result = pevm::execute_revm_sequential(
+        &storage,
+        &chain,
+        SpecId::LATEST,
+        BlockEnv::default(),
+        txs.clone(),
+    );
+    let parallel_result = Pevm::default().execute_revm_parallel_with_external(
+        &storage,
+        &chain,
+        SpecId::LATEST,
+        BlockEnv::default(),
+        txs,
+        &counter,
+        concurrency_level,
+    );
+    common::assert_execution_result(&sequential_result, &parallel_result);
+    // Every transaction executed at least once with its context.
+    assert!(counter.executions.load(Ordering::Relaxed) >= block_size);
+}
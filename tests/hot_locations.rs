@@ -0,0 +1,101 @@
+// Test recording the contention on memory locations of parallel executions.
+
+use std::{num::NonZeroUsize, thread};
+
+use pevm::{
+    chain::PevmEthereum, ExecutionMode, HintedLocation, InMemoryStorage, Pevm, PevmStrategy,
+};
+use revm::primitives::{
+    alloy_primitives::U160, env::TxEnv, Address, BlockEnv, SpecId, TransactTo, U256,
+};
+
+pub mod common;
+
+#[test]
+fn hot_locations_shared_counter() {
+    let block_size = 1_000; // number of transactions
+    let contract_address = Address::from(U160::from(block_size + 1));
+    let (accounts, bytecodes) = common::mock_counter_accounts(block_size, contract_address);
+    let storage = InMemoryStorage::new(accounts, Some(&bytecodes), []);
+    // Raw transfers to the next account, with every tenth transaction
+    // incrementing a shared counter instead.
+    let txs: Vec<TxEnv> = (1..=block_size)
+        .map(|i| {
+            let (to, value, gas_limit) = if i % 10 == 0 {
+                (contract_address, U256::ZERO, 100_000)
+            } else {
+                (
+                    Address::from(U160::from(i % block_size + 1)),
+                    U256::from(1),
+                    common::RAW_TRANSFER_GAS_LIMIT,
+                )
+            };
+            TxEnv {
+                caller: Address::from(U160::from(i)),
+                transact_to: TransactTo::Call(to),
+                value,
+                gas_limit,
+                gas_price: U256::from(1),
+                ..TxEnv::default()
+            }
+        })
+        .collect();
+    let chain = PevmEthereum::mainnet();
+    let concurrency_level = thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+    let mut pevm = Pevm::new(ExecutionMode::Sync).with_strategy(PevmStrategy {
+        record_hot_locations: true,
+        ..PevmStrategy::default()
+    });
+    let sequential_result = pevm::execute_revm_sequential(
+        &storage,
+        &chain,
+        SpecId::LATEST,
+        BlockEnv::default(),
+        txs.clone(),
+    );
+    let parallel_result = pevm.execute_revm_parallel(
+        &storage,
+        &chain,
+        SpecId::LATEST,
+        BlockEnv::default(),
+        txs.clone(),
+        concurrency_level,
+    );
+    common::assert_execution_result(&sequential_result, &parallel_result);
+
+    // The locations are sorted by conflicts then writes.
+    let hot_locations = pevm.hot_locations(usize::MAX).unwrap();
+    assert!(hot_locations.windows(2).all(|pair| {
+        (pair[0].conflicts, pair[0].writes) >= (pair[1].conflicts, pair[1].writes)
+    }));
+    assert_eq!(pevm.hot_locations(3).unwrap(), &hot_locations[..3]);
+    // Every counter transaction writes to the counter slot, which maps
+    // back to the contract.
+    let counter = hot_locations
+        .iter()
+        .find(|hot_location| {
+            hot_location.location == Some(HintedLocation::Storage(contract_address, U256::ZERO))
+        })
+        .unwrap();
+    assert!(counter.writes >= block_size / 10);
+    // Every sender bumps its nonce.
+    for i in 1..=block_size {
+        let sender = HintedLocation::Account(Address::from(U160::from(i)));
+        assert!(hot_locations
+            .iter()
+            .any(|hot_location| hot_location.location == Some(sender) && hot_location.writes > 0));
+    }
+
+    // The locations are only recorded on request.
+    let mut pevm = Pevm::default();
+    pevm.execute_revm_parallel(
+        &storage,
+        &chain,
+        SpecId::LATEST,
+        BlockEnv::default(),
+        txs,
+        concurrency_level,
+    )
+    .unwrap();
+    assert_eq!(pevm.hot_locations(10), None);
+}
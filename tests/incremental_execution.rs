@@ -0,0 +1,66 @@
+// Test incremental execution -- reusing the unchanged prefix of the last
+// execution must match executing the whole block again.
+
+use std::{num::NonZeroUsize, thread};
+
+use pevm::{chain::PevmEthereum, ExecutionMode, InMemoryStorage, Pevm};
+use revm::primitives::{
+    alloy_primitives::U160, env::TxEnv, Address, BlockEnv, SpecId, TransactTo, U256,
+};
+
+pub mod common;
+
+// Mock `block_size` transactions sending some tokens to the next account,
+// with a different value from `changed_idx` on.
+fn transfers(block_size: usize, changed_idx: usize) -> Vec<TxEnv> {
+    (1..=block_size)
+        .map(|i| TxEnv {
+            caller: Address::from(U160::from(i)),
+            transact_to: TransactTo::Call(Address::from(U160::from(i % block_size + 1))),
+            value: U256::from(if i > changed_idx { 2 } else { 1 }),
+            gas_limit: common::RAW_TRANSFER_GAS_LIMIT,
+            gas_price: U256::from(1),
+            ..TxEnv::default()
+        })
+        .collect()
+}
+
+#[test]
+fn incremental_suffix_changes() {
+    let block_size = 1_000; // number of transactions
+    let storage = InMemoryStorage::new((0..=block_size + 1).map(common::mock_account), None, []);
+    let chain = PevmEthereum::mainnet();
+    let concurrency_level = thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+    let block_env = BlockEnv {
+        coinbase: Address::from(U160::from(block_size + 2)),
+        ..BlockEnv::default()
+    };
+
+    let mut pevm = Pevm::new(ExecutionMode::Sync);
+    let mut execute = |txs: Vec<TxEnv>| {
+        let expected_result = pevm::execute_revm_sequential(
+            &storage,
+            &chain,
+            SpecId::LATEST,
+            block_env.clone(),
+            txs.clone(),
+        );
+        let result = pevm.execute_revm_parallel_incremental(
+            &storage,
+            &chain,
+            SpecId::LATEST,
+            block_env.clone(),
+            txs,
+            concurrency_level,
+        );
+        common::assert_execution_result(&expected_result, &result);
+        pevm.reused_tx_count()
+    };
+
+    assert_eq!(execute(transfers(block_size, block_size)), 0);
+    assert_eq!(execute(transfers(block_size, 600)), 600);
+    assert_eq!(execute(transfers(block_size, 600)), block_size);
+    assert_eq!(execute(transfers(block_size + 1, 800)), 600);
+    // The last transaction sends to the first account instead.
+    assert_eq!(execute(transfers(block_size / 2, 800)), block_size / 2 - 1);
+}
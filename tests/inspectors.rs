@@ -0,0 +1,104 @@
+// Test attaching revm inspectors to parallel execution.
+
+use std::{num::NonZeroUsize, thread};
+
+use pevm::{
+    chain::PevmEthereum, ExecutionMode, InMemoryStorage, InspectorFactory, Pevm, PevmStrategy,
+    RetryPolicy,
+};
+use revm::{
+    interpreter::Interpreter,
+    primitives::{alloy_primitives::U160, env::TxEnv, Address, BlockEnv, SpecId, TransactTo, U256},
+    Database, EvmContext, Inspector,
+};
+
+pub mod common;
+
+// Count the executed opcodes.
+#[derive(Default)]
+struct OpcodeCounter(usize);
+
+impl<DB: Database> Inspector<DB> for OpcodeCounter {
+    fn step(&mut self, _interp: &mut Interpreter, _context: &mut EvmContext<DB>) {
+        self.0 += 1;
+    }
+}
+
+struct OpcodeCounters;
+
+impl InspectorFactory for OpcodeCounters {
+    type Inspector<DB: Database> = OpcodeCounter;
+    type Output = usize;
+
+    fn inspector<DB: Database>(&self, _tx_idx: usize) -> OpcodeCounter {
+        OpcodeCounter::default()
+    }
+
+    fn finish<DB: Database>(&self, inspector: OpcodeCounter) -> usize {
+        inspector.0
+    }
+}
+
+#[test]
+fn count_opcodes_contended_block() {
+    let block_size = 1_000; // number of transactions
+
+    let contract_address = Address::from(U160::from(block_size + 1));
+    let (accounts, bytecodes) = common::mock_counter_accounts(block_size, contract_address);
+    let storage = InMemoryStorage::new(accounts, Some(&bytecodes), []);
+    // Raw transfers to the next account, with every tenth transaction
+    // incrementing the shared counter instead.
+    let txs: Vec<TxEnv> = (1..=block_size)
+        .map(|i| {
+            let (to, gas_limit) = if i % 10 == 0 {
+                (contract_address, 100_000)
+            } else {
+                (
+                    Address::from(U160::from(i % block_size + 1)),
+                    common::RAW_TRANSFER_GAS_LIMIT,
+                )
+            };
+            TxEnv {
+                caller: Address::from(U160::from(i)),
+                transact_to: TransactTo::Call(to),
+                value: U256::from(i % 10 != 0),
+                gas_limit,
+                gas_price: U256::from(1),
+                ..TxEnv::default()
+            }
+        })
+        .collect();
+    let chain = PevmEthereum::mainnet();
+    let sequential_result = pevm::execute_revm_sequential(
+        &storage,
+        &chain,
+        SpecId::LATEST,
+        BlockEnv::default(),
+        txs.clone(),
+    );
+    let expected_counts: Vec<usize> = (1..=block_size)
+        .map(|i| if i % 10 == 0 { 7 } else { 0 })
+        .collect();
+    // The latter falls back to sequential execution on the first blocking read.
+    for retry in [
+        RetryPolicy::WaitForDependency,
+        RetryPolicy::BoundedThenSequential { max_retries: 0 },
+    ] {
+        let mut pevm = Pevm::new(ExecutionMode::Sync).with_strategy(PevmStrategy {
+            retry,
+            ..PevmStrategy::default()
+        });
+        let result = pevm.execute_revm_parallel_with_inspector(
+            &storage,
+            &chain,
+            SpecId::LATEST,
+            BlockEnv::default(),
+            txs.clone(),
+            &OpcodeCounters,
+            thread::available_parallelism().unwrap_or(NonZeroUsize::MIN),
+        );
+        let (tx_results, counts) = result.unwrap();
+        common::assert_execution_result(&sequential_result, &Ok(tx_results));
+        assert_eq!(counts, expected_counts);
+    }
+}
@@ -0,0 +1,74 @@
+// Test recording storage metrics during execution.
+
+use std::{num::NonZeroUsize, thread};
+
+use pevm::{chain::PevmEthereum, InMemoryStorage, InstrumentedStorage, MemoryTier, TieredStorage};
+use revm::primitives::{
+    alloy_primitives::U160, env::TxEnv, Address, BlockEnv, SpecId, TransactTo, U256,
+};
+
+pub mod common;
+
+#[test]
+fn instrumented_storage() {
+    let block_size = 1_000; // number of transactions
+    let storage = InMemoryStorage::new((0..=block_size).map(common::mock_account), None, []);
+    // Instrument both the cache tier and the whole storage.
+    let tiered_storage = InstrumentedStorage::new(TieredStorage::new(
+        InstrumentedStorage::new(MemoryTier::default()),
+        storage.clone(),
+    ));
+    // Mock `block_size` transactions sending some tokens to the next account.
+    let txs: Vec<TxEnv> = (1..=block_size)
+        .map(|i| TxEnv {
+            caller: Address::from(U160::from(i)),
+            transact_to: TransactTo::Call(Address::from(U160::from(i % block_size + 1))),
+            value: U256::from(1),
+            gas_limit: common::RAW_TRANSFER_GAS_LIMIT,
+            gas_price: U256::from(1),
+            ..TxEnv::default()
+        })
+        .collect();
+
+    let chain = PevmEthereum::mainnet();
+    let concurrency_level = thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+    let expected_result = pevm::execute_revm_sequential(
+        &storage,
+        &chain,
+        SpecId::LATEST,
+        BlockEnv::default(),
+        txs.clone(),
+    );
+    let execute = || {
+        pevm::execute_revm_parallel(
+            &tiered_storage,
+            &chain,
+            SpecId::LATEST,
+            BlockEnv::default(),
+            txs.clone(),
+            concurrency_level,
+        )
+    };
+
+    common::assert_execution_result(&expected_result, &execute());
+    let metrics = tiered_storage.metrics();
+    assert!(metrics.basic.calls + metrics.basic_many.calls > 0);
+    assert_eq!(metrics.basic.errors, 0);
+    assert!(metrics.total_latency() > std::time::Duration::ZERO);
+    for metrics in [&metrics.basic, &metrics.basic_many] {
+        assert_eq!(metrics.latency_histogram.iter().sum::<u64>(), metrics.calls);
+    }
+
+    // Everything has been filled into the cache tier, so re-executing only
+    // hits it.
+    let tier = tiered_storage.inner().first();
+    tier.reset_metrics();
+    common::assert_execution_result(&expected_result, &execute());
+    let tier_metrics = tier.metrics();
+    assert!(tier_metrics.basic.hits > 0);
+    assert_eq!(tier_metrics.basic.misses, 0);
+    assert_eq!(tier_metrics.basic.hit_ratio(), Some(1.0));
+
+    tiered_storage.reset_metrics();
+    assert_eq!(tiered_storage.metrics(), Default::default());
+}
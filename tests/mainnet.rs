@@ -1,11 +1,6 @@
-use std::{
-    collections::BTreeMap,
-    fs::{self, File},
-    io::BufReader,
-};
+use std::fs;
 
 use alloy_consensus::constants::KECCAK_EMPTY;
-use alloy_primitives::{Address, B256};
 use alloy_provider::{Provider, ProviderBuilder};
 use alloy_rpc_types::{BlockId, BlockTransactionsKind};
 use reqwest::Url;
@@ -14,7 +9,7 @@ use tokio::runtime::Runtime;
 
 use pevm::{
     chain::{PevmChain, PevmEthereum},
-    EvmAccount, EvmCode, RpcStorage, StorageWrapper,
+    BlockSnapshot, DatabaseAsStorage, RpcStorage, StorageWrapper,
 };
 
 pub mod common;
@@ -56,47 +51,30 @@ fn mainnet_blocks_from_rpc() {
         let spec_id = chain.get_block_spec(&block.header).unwrap();
         let rpc_storage = RpcStorage::new(provider, spec_id, BlockId::number(block_number - 1));
         let wrapped_storage = StorageWrapper(&rpc_storage);
-        let db = CacheDB::new(&wrapped_storage);
+        let db = DatabaseAsStorage(CacheDB::new(&wrapped_storage));
         common::test_execute_alloy(&db, &chain, block.clone(), true);
 
         // Snapshot blocks (for benchmark)
         // TODO: Port to a dedicated CLI instead?
-        // TODO: Binary formats to save disk?
         if std::env::var("SNAPSHOT_BLOCKS") == Ok("1".to_string()) {
             let dir = format!("data/blocks/{block_number}");
             fs::create_dir_all(dir.clone()).unwrap();
-            let file_block = File::create(format!("{dir}/block.json")).unwrap();
-            serde_json::to_writer(file_block, &block).unwrap();
-
-            // TODO: Snapshot with consistent ordering for ease of diffing.
-            // Currently [EvmStorage]'s storage ordering isn't consistent.
-            let mut state = BTreeMap::<Address, EvmAccount>::new();
-            let mut bytecodes: BTreeMap<B256, EvmCode> = match File::open("data/bytecodes.bincode")
-            {
-                Ok(file) => bincode::deserialize_from(BufReader::new(file)).unwrap(),
-                Err(_) => BTreeMap::new(),
-            };
-            bytecodes.extend(rpc_storage.get_cache_bytecodes());
-            for (address, mut account) in rpc_storage.get_cache_accounts() {
+            let mut bytecodes = rpc_storage.get_cache_bytecodes();
+            let mut accounts = rpc_storage.get_cache_accounts();
+            for account in accounts.values_mut() {
                 if let Some(code) = account.code.take() {
                     assert_ne!(account.code_hash.unwrap(), KECCAK_EMPTY);
                     bytecodes.insert(account.code_hash.unwrap(), code);
                 }
-                state.insert(address, account);
             }
-
-            let file_state = File::create(format!("{dir}/pre_state.json")).unwrap();
-            serde_json::to_writer(file_state, &state).unwrap();
-            let file_bytecodes = File::create("data/bytecodes.bincode").unwrap();
-            bincode::serialize_into(file_bytecodes, &bytecodes).unwrap();
-
-            // We convert to [BTreeMap] for consistent ordering & diffs between snapshots
-            let block_hashes: BTreeMap<u64, B256> =
-                rpc_storage.get_cache_block_hashes().into_iter().collect();
-            if !block_hashes.is_empty() {
-                let file = File::create(format!("{dir}/block_hashes.json")).unwrap();
-                serde_json::to_writer(file, &block_hashes).unwrap();
+            BlockSnapshot {
+                block,
+                accounts,
+                bytecodes,
+                block_hashes: rpc_storage.get_cache_block_hashes(),
             }
+            .write(format!("{dir}/snapshot.bin.zst"))
+            .unwrap();
         }
     }
 }
@@ -0,0 +1,63 @@
+// Test exporting execution metrics via the metrics facade.
+#![cfg(feature = "metrics")]
+
+use std::num::NonZeroUsize;
+
+use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+use pevm::{chain::PevmEthereum, metrics, InstrumentedStorage, Pevm};
+
+pub mod common;
+
+#[test]
+fn mainnet_blocks_metrics() {
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+    recorder.install().unwrap();
+    metrics::describe_metrics();
+
+    let chain = PevmEthereum::mainnet();
+    let concurrency_level = NonZeroUsize::new(4).unwrap();
+    let mut num_blocks = 0;
+    let mut num_txs = 0;
+    common::for_each_block_from_disk(|block, storage| {
+        let block_result = Pevm::default()
+            .execute(
+                &InstrumentedStorage::new(storage),
+                &chain,
+                block,
+                concurrency_level,
+                false,
+            )
+            .unwrap();
+        num_blocks += 1;
+        num_txs += block_result.tx_results.len() as u64;
+    });
+
+    let mut storage_methods = Vec::new();
+    for (key, _, _, value) in snapshotter.snapshot().into_vec() {
+        match (key.key().name(), value) {
+            (metrics::BLOCKS_EXECUTED, DebugValue::Counter(count)) => {
+                assert_eq!(count, num_blocks)
+            }
+            (metrics::TXS_EXECUTED, DebugValue::Counter(count)) => assert_eq!(count, num_txs),
+            (metrics::BLOCK_EXECUTION_TIME, DebugValue::Histogram(values)) => {
+                assert_eq!(values.len() as u64, num_blocks)
+            }
+            (metrics::ABORT_RATE, DebugValue::Histogram(values)) => {
+                assert!(values.iter().all(|rate| (0.0..=1.0).contains(&rate.0)))
+            }
+            (metrics::STORAGE_LATENCY, DebugValue::Histogram(values)) => {
+                assert!(!values.is_empty());
+                storage_methods.extend(
+                    key.key()
+                        .labels()
+                        .filter(|label| label.key() == "method")
+                        .map(|label| label.value().to_string()),
+                );
+            }
+            _ => {}
+        }
+    }
+    // Every block reads the accounts of its senders.
+    assert!(!storage_methods.is_empty());
+}
@@ -0,0 +1,69 @@
+// Test executing mainnet blocks on memory-mapped snapshots of their state.
+
+use std::{fs, io};
+
+use pevm::{chain::PevmEthereum, MmapStorage, Storage};
+
+pub mod common;
+
+#[test]
+fn mmap_storage() {
+    let dir = std::env::temp_dir().join(format!("pevm-mmap-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let chain = PevmEthereum::mainnet();
+    common::for_each_snapshot_from_disk(|snapshot| {
+        let path = dir.join(snapshot.block.header.number.unwrap().to_string());
+        MmapStorage::write_snapshot(
+            &path,
+            snapshot.accounts.clone(),
+            &snapshot.bytecodes,
+            snapshot.block_hashes.clone(),
+        )
+        .unwrap();
+        // SAFETY: Nothing else touches the temporary snapshot file.
+        let storage = unsafe { MmapStorage::open(&path) }.unwrap();
+
+        // Lookups match the in-memory storage of the same state.
+        let in_memory_storage = snapshot.storage();
+        for (address, account) in snapshot.accounts.iter() {
+            assert_eq!(
+                storage.basic(address).unwrap(),
+                in_memory_storage.basic(address).unwrap()
+            );
+            assert_eq!(storage.code_hash(address).unwrap(), account.code_hash);
+            if let Some(code_hash) = &account.code_hash {
+                assert_eq!(
+                    storage.code_by_hash(code_hash).unwrap(),
+                    in_memory_storage.code_by_hash(code_hash).unwrap()
+                );
+            }
+            assert_eq!(
+                storage.has_storage(address).unwrap(),
+                !account.storage.is_empty()
+            );
+            for (index, value) in account.storage.iter() {
+                assert_eq!(storage.storage(address, index).unwrap(), *value);
+            }
+        }
+        for (number, hash) in snapshot.block_hashes.iter() {
+            assert_eq!(storage.block_hash(number).unwrap(), *hash);
+        }
+
+        common::test_execute_alloy(&storage, &chain, snapshot.block, true);
+    });
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn mmap_storage_overflowing_header() {
+    let path = std::env::temp_dir().join(format!("pevm-mmap-overflow-{}", std::process::id()));
+    MmapStorage::write_snapshot(&path, [], &Default::default(), []).unwrap();
+    // Claim so many accounts that the section offsets overflow.
+    let mut bytes = fs::read(&path).unwrap();
+    bytes[12..20].copy_from_slice(&u64::MAX.to_le_bytes());
+    fs::write(&path, bytes).unwrap();
+    // SAFETY: Nothing else touches the temporary snapshot file.
+    let error = unsafe { MmapStorage::open(&path) }.unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,90 @@
+// Test advancing an in-memory storage across blocks by applying their results.
+
+use std::{num::NonZeroUsize, thread};
+
+use pevm::{chain::PevmEthereum, EvmCode, InMemoryStorage, Storage};
+use revm::primitives::{
+    alloy_primitives::U160, env::TxEnv, keccak256, Address, BlockEnv, Bytecode, Bytes, SpecId,
+    TransactTo, U256,
+};
+
+pub mod common;
+
+#[test]
+fn apply_results_across_blocks() {
+    let block_size = 100; // number of transactions
+    let num_blocks = 3;
+    let mut storage =
+        InMemoryStorage::new((0..=block_size + 1).map(common::mock_account), None, []);
+    let chain = PevmEthereum::mainnet();
+    let concurrency_level = thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+
+    // `PUSH1 0x33 PUSH1 0 MSTORE8 PUSH1 1 PUSH1 0 RETURN`: Deploy `CALLER`.
+    let init_code =
+        Bytes::from_static(&[0x60, 0x33, 0x60, 0x00, 0x53, 0x60, 0x01, 0x60, 0x00, 0xf3]);
+    let deployer = Address::from(U160::from(block_size + 1));
+    let deployed_address = deployer.create(1);
+
+    for block_number in 1..=num_blocks {
+        // A deployment in the first block, then `block_size` transactions
+        // sending some tokens to the next account.
+        let txs: Vec<TxEnv> = (block_number == 1)
+            .then(|| TxEnv {
+                caller: deployer,
+                transact_to: TransactTo::Create,
+                data: init_code.clone(),
+                gas_limit: 100_000,
+                gas_price: U256::from(1),
+                ..TxEnv::default()
+            })
+            .into_iter()
+            .chain((1..=block_size).map(|i| TxEnv {
+                caller: Address::from(U160::from(i)),
+                transact_to: TransactTo::Call(Address::from(U160::from(i % block_size + 1))),
+                value: U256::from(1),
+                gas_limit: common::RAW_TRANSFER_GAS_LIMIT,
+                gas_price: U256::from(1),
+                ..TxEnv::default()
+            }))
+            .collect();
+        let block_env = BlockEnv {
+            number: U256::from(block_number),
+            ..BlockEnv::default()
+        };
+        let sequential_result = pevm::execute_revm_sequential(
+            &storage,
+            &chain,
+            SpecId::LATEST,
+            block_env.clone(),
+            txs.clone(),
+        );
+        let parallel_result = pevm::execute_revm_parallel(
+            &storage,
+            &chain,
+            SpecId::LATEST,
+            block_env,
+            txs,
+            concurrency_level,
+        );
+        common::assert_execution_result(&sequential_result, &parallel_result);
+        storage.apply(&parallel_result.unwrap());
+    }
+
+    // Senders have sent one transaction per block.
+    for i in 1..=block_size {
+        let basic = storage
+            .basic(&Address::from(U160::from(i)))
+            .unwrap()
+            .unwrap();
+        assert_eq!(basic.nonce, 1 + num_blocks as u64);
+    }
+    // The deployed code is kept by the storage itself.
+    let code_hash = keccak256([0x33]);
+    assert_eq!(storage.code_hash(&deployed_address), Ok(Some(code_hash)));
+    assert_eq!(
+        storage.code_by_hash(&code_hash),
+        Ok(Some(EvmCode::from(Bytecode::new_raw(Bytes::from_static(
+            &[0x33]
+        )))))
+    );
+}
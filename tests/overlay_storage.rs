@@ -0,0 +1,89 @@
+// Test simulating transactions with state overrides on top of a storage.
+
+use std::{num::NonZeroUsize, thread};
+
+use pevm::{
+    chain::PevmEthereum, AccountOverride, EvmCode, InMemoryStorage, OverlayStorage, StateOverrides,
+    Storage,
+};
+use revm::primitives::{
+    alloy_primitives::U160, env::TxEnv, Address, BlockEnv, Bytecode, Bytes, SpecId, TransactTo,
+    U256,
+};
+
+pub mod common;
+
+#[test]
+fn overlay_storage() {
+    let storage = InMemoryStorage::new((0..=2).map(common::mock_account), None, []);
+    let sender = Address::from(U160::from(1));
+    let contract_address = Address::from(U160::from(1_000));
+    // `PUSH1 0 SLOAD PUSH1 1 SSTORE STOP`
+    let code = Bytecode::new_raw(Bytes::from_static(&[
+        0x60, 0x00, 0x54, 0x60, 0x01, 0x55, 0x00,
+    ]));
+    let code_hash = code.hash_slow();
+    let mut overrides = StateOverrides::default();
+    overrides.insert(
+        sender,
+        AccountOverride {
+            balance: Some(U256::from(1_000_000)),
+            nonce: Some(7),
+            ..AccountOverride::default()
+        },
+    );
+    overrides.insert(
+        contract_address,
+        AccountOverride {
+            code: Some(EvmCode::from(code)),
+            state_diff: [(U256::ZERO, U256::from(42))].into_iter().collect(),
+            ..AccountOverride::default()
+        },
+    );
+    let overlay_storage = OverlayStorage::new(storage, overrides);
+
+    let sender_basic = overlay_storage.basic(&sender).unwrap().unwrap();
+    assert_eq!(sender_basic.balance, U256::from(1_000_000));
+    assert_eq!(sender_basic.nonce, 7);
+    // Overridden accounts exist even if they don't in the underlying storage.
+    assert!(overlay_storage.basic(&contract_address).unwrap().is_some());
+    assert_eq!(
+        overlay_storage.code_hash(&contract_address).unwrap(),
+        Some(code_hash)
+    );
+    assert!(overlay_storage.code_by_hash(&code_hash).unwrap().is_some());
+    assert!(overlay_storage.has_storage(&contract_address).unwrap());
+
+    let txs = vec![TxEnv {
+        caller: sender,
+        transact_to: TransactTo::Call(contract_address),
+        gas_limit: 100_000,
+        gas_price: U256::from(1),
+        ..TxEnv::default()
+    }];
+    let chain = PevmEthereum::mainnet();
+    let sequential_result = pevm::execute_revm_sequential(
+        &overlay_storage,
+        &chain,
+        SpecId::LATEST,
+        BlockEnv::default(),
+        txs.clone(),
+    );
+    let parallel_result = pevm::execute_revm_parallel(
+        &overlay_storage,
+        &chain,
+        SpecId::LATEST,
+        BlockEnv::default(),
+        txs,
+        thread::available_parallelism().unwrap_or(NonZeroUsize::MIN),
+    );
+    common::assert_execution_result(&sequential_result, &parallel_result);
+
+    let tx_result = &parallel_result.unwrap()[0];
+    assert!(tx_result.receipt.is_success());
+    let contract = tx_result.state[&contract_address].as_ref().unwrap();
+    assert_eq!(contract.storage[&U256::from(1)], U256::from(42));
+    let sender = tx_result.state[&sender].as_ref().unwrap();
+    assert_eq!(sender.balance, U256::from(1_000_000 - tx_result.gas_used));
+    assert_eq!(sender.nonce, 8);
+}
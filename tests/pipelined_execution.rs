@@ -0,0 +1,161 @@
+// Test pipelined execution of consecutive blocks, where each next block is
+// speculated on the state before the current one.
+
+use std::{
+    num::NonZeroUsize,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use alloy_rpc_types::BlockTransactions;
+use pevm::{
+    chain::PevmEthereum, CommittedStorage, ExecutionHook, ExecutionMode, Pevm, PevmError,
+    PevmStrategy, PevmTxExecutionResult,
+};
+
+pub mod common;
+
+#[test]
+fn mainnet_blocks_pipelined() {
+    let chain = PevmEthereum::mainnet();
+    let concurrency_level = NonZeroUsize::new(4).unwrap();
+    common::for_each_block_from_disk(|block, storage| {
+        let expected_result = Pevm::new(ExecutionMode::Validate)
+            .execute(&storage, &chain, block.clone(), concurrency_level, true)
+            .unwrap();
+        let mut committed_storage = CommittedStorage::new(&storage, &[]);
+        committed_storage.commit_block(&expected_result);
+
+        // An empty next block reads nothing that the block changes, so its
+        // speculation holds.
+        let mut empty_block = block.clone();
+        empty_block.transactions = BlockTransactions::Full(Vec::new());
+        empty_block.withdrawals = None;
+        if empty_block.header.blob_gas_used.is_some() {
+            empty_block.header.blob_gas_used = Some(0);
+        }
+        let expected_empty_result = Pevm::new(ExecutionMode::Validate)
+            .execute(
+                &committed_storage,
+                &chain,
+                empty_block.clone(),
+                concurrency_level,
+                true,
+            )
+            .unwrap();
+        let block_results = Pevm::new(ExecutionMode::Validate).execute_pipelined(
+            &storage,
+            &chain,
+            vec![block.clone(), empty_block],
+            concurrency_level,
+        );
+        assert_eq!(
+            block_results,
+            vec![Ok(expected_result.clone()), Ok(expected_empty_result)]
+        );
+
+        // Re-executing the same block speculates fine on the state before
+        // the first execution, but must fail on the state after it.
+        if block.transactions.is_empty() {
+            return;
+        }
+        let block_results = Pevm::new(ExecutionMode::Validate).execute_pipelined(
+            &storage,
+            &chain,
+            vec![block.clone(), block.clone(), block],
+            concurrency_level,
+        );
+        assert_eq!(block_results.len(), 2);
+        assert_eq!(block_results[0], Ok(expected_result));
+        assert!(matches!(
+            block_results[1],
+            Err(PevmError::ExecutionError(_))
+        ));
+    });
+}
+
+// Count the hooked transaction results.
+#[derive(Debug, Default)]
+struct CountingHook(AtomicUsize);
+
+impl ExecutionHook for CountingHook {
+    fn after_tx(&self, _tx_idx: usize, _tx_result: &PevmTxExecutionResult) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[test]
+fn speculated_blocks_run_hooks() {
+    let chain = PevmEthereum::mainnet();
+    let concurrency_level = NonZeroUsize::new(4).unwrap();
+    common::for_each_block_from_disk(|block, storage| {
+        // An empty block before Cancun changes no state, so the speculation
+        // of the block after it holds.
+        if block.header.blob_gas_used.is_some() {
+            return;
+        }
+        let mut empty_block = block.clone();
+        empty_block.transactions = BlockTransactions::Full(Vec::new());
+        empty_block.withdrawals = None;
+        let expected_result = Pevm::new(ExecutionMode::Validate)
+            .execute(&storage, &chain, block.clone(), concurrency_level, true)
+            .unwrap();
+
+        let hook = Arc::new(CountingHook::default());
+        let block_results = Pevm::new(ExecutionMode::Validate)
+            .with_hook(hook.clone())
+            .execute_pipelined(
+                &storage,
+                &chain,
+                vec![empty_block, block],
+                concurrency_level,
+            );
+        assert_eq!(block_results.len(), 2);
+        assert_eq!(block_results[1], Ok(expected_result.clone()));
+        assert_eq!(
+            hook.0.load(Ordering::Relaxed),
+            expected_result.tx_results.len()
+        );
+    });
+}
+
+#[test]
+fn speculated_blocks_keep_execution_state() {
+    let chain = PevmEthereum::mainnet();
+    let concurrency_level = NonZeroUsize::new(4).unwrap();
+    let strategy = PevmStrategy {
+        record_report: true,
+        ..PevmStrategy::default()
+    };
+    common::for_each_block_from_disk(|block, storage| {
+        // An empty block before Cancun changes no state, so the speculation
+        // of the block after it holds.
+        if block.header.blob_gas_used.is_some() {
+            return;
+        }
+        let mut empty_block = block.clone();
+        empty_block.transactions = BlockTransactions::Full(Vec::new());
+        empty_block.withdrawals = None;
+        let mut expected_pevm = Pevm::new(ExecutionMode::Validate).with_strategy(strategy.clone());
+        expected_pevm
+            .execute(&storage, &chain, block.clone(), concurrency_level, false)
+            .unwrap();
+
+        // The accessors refer to the speculated block, not the empty one.
+        let mut pevm = Pevm::new(ExecutionMode::Validate).with_strategy(strategy.clone());
+        let block_results = pevm.execute_pipelined(
+            &storage,
+            &chain,
+            vec![empty_block, block],
+            concurrency_level,
+        );
+        assert!(block_results.iter().all(Result::is_ok));
+        assert_eq!(pevm.skipped_tx_idxs(), expected_pevm.skipped_tx_idxs());
+        assert_eq!(
+            pevm.report().map(|report| report.txs.len()),
+            expected_pevm.report().map(|report| report.txs.len())
+        );
+    });
+}
@@ -0,0 +1,122 @@
+// Test executing blocks with custom precompiles.
+
+use std::{num::NonZeroUsize, thread};
+
+use alloy_rpc_types::{Block, BlockTransactions};
+use pevm::{chain::PevmEthereum, InMemoryStorage, Pevm};
+use revm::{
+    precompile::{identity, PrecompileWithAddress},
+    primitives::{
+        alloy_primitives::U160, env::TxEnv, Address, BlockEnv, Bytes, SpecId, TransactTo, U256,
+    },
+};
+
+pub mod common;
+
+#[test]
+fn custom_identity_precompile() {
+    let block_size = 100; // number of transactions
+    let cheatcode_address = Address::from(U160::from(0x1337));
+    let identity_address = identity::FUN.0;
+    let storage = InMemoryStorage::new((0..=block_size).map(common::mock_account), None, []);
+    // Every transaction calls the identity precompile at either address.
+    let txs: Vec<TxEnv> = (1..=block_size)
+        .map(|i| TxEnv {
+            caller: Address::from(U160::from(i)),
+            transact_to: TransactTo::Call(if i % 2 == 0 {
+                cheatcode_address
+            } else {
+                identity_address
+            }),
+            value: U256::from(1),
+            gas_limit: 100_000,
+            gas_price: U256::from(1),
+            data: Bytes::from(vec![0xff; 64]),
+            ..TxEnv::default()
+        })
+        .collect();
+    let chain = PevmEthereum::mainnet();
+    let concurrency_level = thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+    let PrecompileWithAddress(_, identity) = identity::FUN;
+    let mut pevm = Pevm::default().with_precompile(cheatcode_address, identity);
+
+    // The custom precompile costs the same as the original.
+    let tx_results = pevm
+        .execute_revm_parallel(
+            &storage,
+            &chain,
+            SpecId::LATEST,
+            BlockEnv::default(),
+            txs.clone(),
+            concurrency_level,
+        )
+        .unwrap();
+    for tx_result in &tx_results {
+        assert!(tx_result.receipt.status());
+        assert_eq!(tx_result.gas_used, tx_results[0].gas_used);
+    }
+    assert_eq!(
+        pevm.estimate_gas(
+            &storage,
+            &chain,
+            SpecId::LATEST,
+            BlockEnv::default(),
+            txs[1].clone(),
+        ),
+        Ok(tx_results[0].gas_used)
+    );
+
+    // Without the custom precompile, the address has no code to run.
+    pevm.clear_precompiles();
+    let tx_results = pevm
+        .execute_revm_parallel(
+            &storage,
+            &chain,
+            SpecId::LATEST,
+            BlockEnv::default(),
+            txs,
+            concurrency_level,
+        )
+        .unwrap();
+    assert!(tx_results[1].gas_used < tx_results[0].gas_used);
+}
+
+#[test]
+fn custom_precompile_at_tx() {
+    let cheatcode_address = Address::from(U160::from(0x1337));
+    let identity_address = identity::FUN.0;
+    let storage = InMemoryStorage::new((0..=1).map(common::mock_account), None, []);
+    let block = Block {
+        header: common::MOCK_BLOCK_HEADER.clone(),
+        transactions: BlockTransactions::Full(Vec::new()),
+        ..Block::default()
+    };
+    let tx = |address| TxEnv {
+        caller: Address::from(U160::from(1)),
+        transact_to: TransactTo::Call(address),
+        value: U256::from(1),
+        gas_limit: 100_000,
+        gas_price: U256::from(1),
+        data: Bytes::from(vec![0xff; 64]),
+        ..TxEnv::default()
+    };
+    let chain = PevmEthereum::mainnet();
+    let PrecompileWithAddress(_, identity) = identity::FUN;
+    let mut pevm = Pevm::default().with_precompile(cheatcode_address, identity);
+
+    // The appended transaction calls the custom precompile like the original.
+    let [identity_result, cheatcode_result] =
+        [identity_address, cheatcode_address].map(|address| {
+            pevm.execute_tx_at(
+                &storage,
+                &chain,
+                block.clone(),
+                0,
+                Some(tx(address)),
+                NonZeroUsize::MIN,
+            )
+            .unwrap()
+        });
+    assert!(cheatcode_result.receipt.status());
+    assert_eq!(cheatcode_result.gas_used, identity_result.gas_used);
+}
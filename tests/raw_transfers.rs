@@ -75,7 +75,7 @@ fn raw_transfers_independent_alloy() {
         &InMemoryStorage::new((0..=block_size).map(common::mock_account), None, []),
         &PevmEthereum::mainnet(),
         Block {
-            header: common::MOCK_ALLOY_BLOCK_HEADER.clone(),
+            header: common::MOCK_BLOCK_HEADER.clone(),
             transactions: BlockTransactions::Full(
                 (1..block_size)
                     .map(|i| {
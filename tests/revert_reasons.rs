@@ -0,0 +1,91 @@
+// Test decoding the standard revert payloads of reverted transactions.
+
+use std::{num::NonZeroUsize, thread};
+
+use pevm::{chain::PevmEthereum, Bytecodes, EvmAccount, EvmCode, InMemoryStorage};
+use revm::primitives::{
+    alloy_primitives::U160, env::TxEnv, Address, BlockEnv, Bytecode, Bytes, SpecId, TransactTo,
+    U256,
+};
+
+pub mod common;
+
+// Build a contract that reverts with a payload:
+// `PUSH1 len PUSH1 12 PUSH1 0 CODECOPY PUSH1 len PUSH1 0 REVERT payload`
+fn reverting_code(payload: &[u8]) -> Bytecode {
+    let len = payload.len() as u8;
+    let mut code = vec![
+        0x60, len, 0x60, 0x0c, 0x60, 0x00, 0x39, 0x60, len, 0x60, 0x00, 0xfd,
+    ];
+    code.extend_from_slice(payload);
+    Bytecode::new_raw(Bytes::from(code))
+}
+
+#[test]
+fn revert_reasons() {
+    let mut error_payload = vec![0x08, 0xc3, 0x79, 0xa0];
+    error_payload.extend_from_slice(&U256::from(0x20).to_be_bytes::<32>());
+    error_payload.extend_from_slice(&U256::from(4).to_be_bytes::<32>());
+    error_payload.extend_from_slice(&[b"nope".as_slice(), &[0; 28][..]].concat());
+    let mut panic_payload = vec![0x4e, 0x48, 0x7b, 0x71];
+    panic_payload.extend_from_slice(&U256::from(0x11).to_be_bytes::<32>());
+    let payloads = [error_payload, panic_payload, vec![0xde, 0xad]];
+
+    let mut bytecodes = Bytecodes::new();
+    let mut accounts: Vec<_> = (0..=payloads.len()).map(common::mock_account).collect();
+    for (i, payload) in payloads.iter().enumerate() {
+        let code = reverting_code(payload);
+        let code_hash = code.hash_slow();
+        let code = EvmCode::from(code);
+        bytecodes.insert(code_hash, code.clone());
+        accounts.push((
+            Address::from(U160::from(1_000 + i)),
+            EvmAccount {
+                code_hash: Some(code_hash),
+                code: Some(code),
+                ..EvmAccount::default()
+            },
+        ));
+    }
+    let storage = InMemoryStorage::new(accounts, Some(&bytecodes), []);
+
+    let txs: Vec<TxEnv> = (0..payloads.len())
+        .map(|i| TxEnv {
+            caller: Address::from(U160::from(i + 1)),
+            transact_to: TransactTo::Call(Address::from(U160::from(1_000 + i))),
+            gas_limit: 100_000,
+            gas_price: U256::from(1),
+            ..TxEnv::default()
+        })
+        .collect();
+
+    let chain = PevmEthereum::mainnet();
+    let sequential_result = pevm::execute_revm_sequential(
+        &storage,
+        &chain,
+        SpecId::LATEST,
+        BlockEnv::default(),
+        txs.clone(),
+    );
+    let parallel_result = pevm::execute_revm_parallel(
+        &storage,
+        &chain,
+        SpecId::LATEST,
+        BlockEnv::default(),
+        txs,
+        thread::available_parallelism().unwrap_or(NonZeroUsize::MIN),
+    );
+    common::assert_execution_result(&sequential_result, &parallel_result);
+
+    let tx_results = parallel_result.unwrap();
+    for (tx_result, payload) in tx_results.iter().zip(payloads.iter()) {
+        assert!(!tx_result.receipt.is_success());
+        assert_eq!(tx_result.output.as_ref(), payload.as_slice());
+    }
+    assert_eq!(tx_results[0].revert_reason.as_deref(), Some("nope"));
+    assert_eq!(
+        tx_results[1].revert_reason.as_deref(),
+        Some("panic code 0x11")
+    );
+    assert_eq!(tx_results[2].revert_reason, None);
+}
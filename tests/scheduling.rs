@@ -0,0 +1,466 @@
+// Test that scheduling policies don't change execution results.
+
+use std::{num::NonZeroUsize, thread};
+
+use pevm::{
+    chain::PevmEthereum, Bytecodes, EvmAccount, EvmCode, ExecutionHints, ExecutionMode,
+    FallbackReason, HintedLocation, InMemoryStorage, MemoryBudget, Pevm, PevmError, PevmStrategy,
+    RetryPolicy, ScheduleEvent, SchedulingPolicy, SequentialFallback, ThreadPinning,
+    TxDependencies,
+};
+use revm::primitives::{
+    alloy_primitives::U160, env::TxEnv, Address, BlockEnv, Bytecode, Bytes, SpecId, TransactTo,
+    U256,
+};
+
+pub mod common;
+
+fn gas_weighted_pevm() -> Pevm {
+    Pevm::new(ExecutionMode::Sync).with_strategy(PevmStrategy {
+        scheduling: SchedulingPolicy::GasWeighted,
+        ..PevmStrategy::default()
+    })
+}
+
+// A block of raw transfers to the next account, with every tenth
+// transaction incrementing a shared counter with a higher gas limit.
+fn contended_block(block_size: usize) -> (Vec<(Address, EvmAccount)>, Bytecodes, Vec<TxEnv>) {
+    let contract_address = Address::from(U160::from(block_size + 1));
+    let (accounts, bytecodes) = common::mock_counter_accounts(block_size, contract_address);
+    let txs: Vec<TxEnv> = (1..=block_size)
+        .map(|i| {
+            let (to, value, gas_limit) = if i % 10 == 0 {
+                (contract_address, U256::ZERO, 100_000 + i as u64)
+            } else {
+                (
+                    Address::from(U160::from(i % block_size + 1)),
+                    U256::from(1),
+                    common::RAW_TRANSFER_GAS_LIMIT,
+                )
+            };
+            TxEnv {
+                caller: Address::from(U160::from(i)),
+                transact_to: TransactTo::Call(to),
+                value,
+                gas_limit,
+                gas_price: U256::from(1),
+                ..TxEnv::default()
+            }
+        })
+        .collect();
+    (accounts, bytecodes, txs)
+}
+
+fn execute_contended_block(pevm: &mut Pevm) -> Vec<pevm::PevmTxExecutionResult> {
+    let (accounts, bytecodes, txs) = contended_block(1_000);
+    let storage = InMemoryStorage::new(accounts, Some(&bytecodes), []);
+    let chain = PevmEthereum::mainnet();
+    let sequential_result = pevm::execute_revm_sequential(
+        &storage,
+        &chain,
+        SpecId::LATEST,
+        BlockEnv::default(),
+        txs.clone(),
+    );
+    let parallel_result = pevm.execute_revm_parallel(
+        &storage,
+        &chain,
+        SpecId::LATEST,
+        BlockEnv::default(),
+        txs,
+        thread::available_parallelism().unwrap_or(NonZeroUsize::MIN),
+    );
+    common::assert_execution_result(&sequential_result, &parallel_result);
+    parallel_result.unwrap()
+}
+
+#[test]
+fn gas_weighted_contended_block() {
+    execute_contended_block(&mut gas_weighted_pevm());
+}
+
+#[test]
+fn record_and_replay_schedule() {
+    let mut pevm = Pevm::new(ExecutionMode::Sync).with_strategy(PevmStrategy {
+        record_schedule: true,
+        ..PevmStrategy::default()
+    });
+    let tx_results = execute_contended_block(&mut pevm);
+    let recorded_schedule = pevm.schedule().unwrap().to_vec();
+    assert!(recorded_schedule.len() >= tx_results.len());
+
+    let (accounts, bytecodes, txs) = contended_block(1_000);
+    let storage = InMemoryStorage::new(accounts, Some(&bytecodes), []);
+    let chain = PevmEthereum::mainnet();
+    let replay = |schedule: &[ScheduleEvent]| {
+        let mut pevm = Pevm::default();
+        let result = pevm.replay_revm_parallel(
+            &storage,
+            &chain,
+            SpecId::LATEST,
+            BlockEnv::default(),
+            txs.clone(),
+            schedule,
+        );
+        (result, pevm.schedule().unwrap().to_vec())
+    };
+
+    // Replays run whole tasks one at a time, so they diverge from recorded
+    // schedules where concurrent tasks interleaved within each other.
+    let (replayed_result, replayed_schedule) = replay(&recorded_schedule);
+    match replayed_result {
+        Ok(replayed_tx_results) => assert_eq!(replayed_tx_results, tx_results),
+        Err(err) => assert!(matches!(err, PevmError::ScheduleDiverged { .. })),
+    }
+    // Replaying the (possibly diverged) replay finishes the block, and
+    // replaying a complete single-threaded schedule is deterministic.
+    let (result, schedule) = replay(&replayed_schedule);
+    assert_eq!(result.unwrap(), tx_results);
+    let (result, replayed_schedule) = replay(&schedule);
+    assert_eq!(result.unwrap(), tx_results);
+    assert_eq!(replayed_schedule, schedule);
+
+    // Validating an incarnation that is never executed diverges.
+    let (result, _) = replay(&[ScheduleEvent::Validate {
+        tx_idx: 0,
+        tx_incarnation: 1,
+    }]);
+    assert!(matches!(
+        result,
+        Err(PevmError::ScheduleDiverged { event_idx: 0 })
+    ));
+}
+
+#[test]
+fn adaptive_concurrency_contended_block() {
+    let mut pevm = Pevm::new(ExecutionMode::Sync).with_strategy(PevmStrategy {
+        adaptive_concurrency: true,
+        ..PevmStrategy::default()
+    });
+    execute_contended_block(&mut pevm);
+    let max_concurrency_level = thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+    assert!(pevm.concurrency_level().unwrap() <= max_concurrency_level);
+
+    // The level is only reported for adaptive executions.
+    let mut pevm = Pevm::default();
+    execute_contended_block(&mut pevm);
+    assert_eq!(pevm.concurrency_level(), None);
+}
+
+#[test]
+fn memory_budget_contended_block() {
+    let memory_budget_pevm = |max_bytes, fallback_to_sequential| {
+        Pevm::new(ExecutionMode::Sync).with_strategy(PevmStrategy {
+            memory_budget: Some(MemoryBudget {
+                max_bytes,
+                fallback_to_sequential,
+            }),
+            ..PevmStrategy::default()
+        })
+    };
+    // Both a generous budget and falling back on a tiny one match the
+    // sequential execution.
+    let mut pevm = memory_budget_pevm(usize::MAX, false);
+    execute_contended_block(&mut pevm);
+    assert_eq!(pevm.fallback(), None);
+    let mut pevm = memory_budget_pevm(1, true);
+    execute_contended_block(&mut pevm);
+    assert!(matches!(
+        pevm.fallback(),
+        Some(SequentialFallback {
+            reason: FallbackReason::MemoryBudgetExceeded,
+            ..
+        })
+    ));
+
+    let (accounts, bytecodes, txs) = contended_block(1_000);
+    let storage = InMemoryStorage::new(accounts, Some(&bytecodes), []);
+    let result = memory_budget_pevm(1, false).execute_revm_parallel(
+        &storage,
+        &PevmEthereum::mainnet(),
+        SpecId::LATEST,
+        BlockEnv::default(),
+        txs,
+        thread::available_parallelism().unwrap_or(NonZeroUsize::MIN),
+    );
+    assert!(matches!(
+        result,
+        Err(PevmError::MemoryBudgetExceeded { max_bytes: 1 })
+    ));
+}
+
+#[test]
+fn memory_usage_contended_block() {
+    let mut pevm = Pevm::default();
+    execute_contended_block(&mut pevm);
+    let memory_usage = pevm.memory_usage().unwrap();
+    assert!(memory_usage.peak_mv_memory > 0);
+    assert!(memory_usage.results > 0);
+    assert_eq!(
+        memory_usage.peak_total(),
+        memory_usage.peak_mv_memory + memory_usage.results
+    );
+
+    // Executions that fall back to sequential execution aren't accounted.
+    let mut pevm = Pevm::new(ExecutionMode::Sync).with_strategy(PevmStrategy {
+        memory_budget: Some(MemoryBudget {
+            max_bytes: 1,
+            fallback_to_sequential: true,
+        }),
+        ..PevmStrategy::default()
+    });
+    execute_contended_block(&mut pevm);
+    assert_eq!(pevm.memory_usage(), None);
+}
+
+const RETRY_POLICIES: [RetryPolicy; 6] = [
+    RetryPolicy::WaitForDependency,
+    RetryPolicy::WaitForSender,
+    RetryPolicy::Immediate { max_retries: 3 },
+    RetryPolicy::WaitInPlace,
+    RetryPolicy::BoundedThenSequential { max_retries: 2 },
+    // Falls back on the first blocking read.
+    RetryPolicy::BoundedThenSequential { max_retries: 0 },
+];
+
+fn retry_pevm(retry: RetryPolicy) -> Pevm {
+    Pevm::new(ExecutionMode::Sync).with_strategy(PevmStrategy {
+        retry,
+        ..PevmStrategy::default()
+    })
+}
+
+#[test]
+fn retry_policies_contended_block() {
+    for retry in RETRY_POLICIES {
+        execute_contended_block(&mut retry_pevm(retry));
+    }
+}
+
+#[test]
+fn retry_policies_same_sender() {
+    let block_size = 1_000; // number of transactions
+    let storage = InMemoryStorage::new((0..=block_size + 1).map(common::mock_account), None, []);
+    // Every other transaction is from the same sender, with increasing nonces
+    // from the mock accounts' nonce of 1.
+    let txs: Vec<TxEnv> = (1..=block_size)
+        .map(|i| {
+            let (caller, nonce) = if i % 2 == 0 {
+                (
+                    Address::from(U160::from(block_size + 1)),
+                    Some(i as u64 / 2),
+                )
+            } else {
+                (Address::from(U160::from(i)), None)
+            };
+            TxEnv {
+                caller,
+                nonce,
+                transact_to: TransactTo::Call(Address::from(U160::from(i % block_size + 1))),
+                value: U256::from(1),
+                gas_limit: common::RAW_TRANSFER_GAS_LIMIT,
+                gas_price: U256::from(1),
+                ..TxEnv::default()
+            }
+        })
+        .collect();
+    let chain = PevmEthereum::mainnet();
+    let sequential_result = pevm::execute_revm_sequential(
+        &storage,
+        &chain,
+        SpecId::LATEST,
+        BlockEnv::default(),
+        txs.clone(),
+    );
+    for retry in RETRY_POLICIES {
+        let parallel_result = retry_pevm(retry).execute_revm_parallel(
+            &storage,
+            &chain,
+            SpecId::LATEST,
+            BlockEnv::default(),
+            txs.clone(),
+            thread::available_parallelism().unwrap_or(NonZeroUsize::MIN),
+        );
+        common::assert_execution_result(&sequential_result, &parallel_result);
+    }
+}
+
+#[test]
+fn thread_pinning_contended_block() {
+    for thread_pinning in [ThreadPinning::LogicalCores, ThreadPinning::PhysicalCores] {
+        execute_contended_block(
+            &mut Pevm::new(ExecutionMode::Sync).with_strategy(PevmStrategy {
+                thread_pinning,
+                ..PevmStrategy::default()
+            }),
+        );
+    }
+}
+
+#[test]
+fn execution_hints_contended_block() {
+    let block_size = 1_000; // number of transactions
+    let (accounts, bytecodes, txs) = contended_block(block_size);
+    let storage = InMemoryStorage::new(accounts, Some(&bytecodes), []);
+    let chain = PevmEthereum::mainnet();
+    let contract_address = Address::from(U160::from(block_size + 1));
+    let counter_tx_idxs: Vec<usize> = (9..block_size).step_by(10).collect();
+    let mut dependencies = vec![TxDependencies::new(); block_size];
+    for (prev_tx_idx, tx_idx) in counter_tx_idxs.iter().zip(counter_tx_idxs.iter().skip(1)) {
+        dependencies[*tx_idx].push(*prev_tx_idx);
+    }
+    // Wrong hints only cost some parallelism.
+    dependencies[500].push(499);
+    dependencies[600].push(700);
+    let hints = ExecutionHints {
+        hot_locations: vec![
+            (
+                HintedLocation::Storage(contract_address, U256::ZERO),
+                counter_tx_idxs,
+            ),
+            (
+                HintedLocation::Account(Address::from(U160::from(1))),
+                vec![42],
+            ),
+        ],
+        dependencies,
+    };
+    let sequential_result = pevm::execute_revm_sequential(
+        &storage,
+        &chain,
+        SpecId::LATEST,
+        BlockEnv::default(),
+        txs.clone(),
+    );
+    for scheduling in [SchedulingPolicy::InOrder, SchedulingPolicy::GasWeighted] {
+        let parallel_result = Pevm::new(ExecutionMode::Sync)
+            .with_strategy(PevmStrategy {
+                scheduling,
+                ..PevmStrategy::default()
+            })
+            .execute_revm_parallel_with_hints(
+                &storage,
+                &chain,
+                SpecId::LATEST,
+                BlockEnv::default(),
+                txs.clone(),
+                &hints,
+                thread::available_parallelism().unwrap_or(NonZeroUsize::MIN),
+            );
+        common::assert_execution_result(&sequential_result, &parallel_result);
+    }
+}
+
+#[test]
+fn lazy_transfers_then_balance_reads() {
+    let block_size = 1_000; // number of transactions
+
+    let recipient = Address::from(U160::from(block_size + 1));
+    let contract_address = Address::from(U160::from(block_size + 2));
+    // `PUSH20 <recipient> BALANCE CALLER SSTORE STOP`: Store the recipient's
+    // balance at the caller's slot.
+    let mut code = vec![0x73];
+    code.extend_from_slice(recipient.as_slice());
+    code.extend_from_slice(&[0x31, 0x33, 0x55, 0x00]);
+    let code = Bytecode::new_raw(Bytes::from(code));
+    let code_hash = code.hash_slow();
+    let mut bytecodes = Bytecodes::new();
+    bytecodes.insert(code_hash, EvmCode::from(code));
+    let mut accounts: Vec<_> = (0..=block_size + 1).map(common::mock_account).collect();
+    accounts.push((
+        contract_address,
+        EvmAccount {
+            code_hash: Some(code_hash),
+            ..EvmAccount::default()
+        },
+    ));
+    let storage = InMemoryStorage::new(accounts, Some(&bytecodes), []);
+    // Lazy transfers to the same recipient, with the last one breaking the
+    // preprocessed dependency chain, then calls that read the recipient.
+    let txs: Vec<TxEnv> = (1..=block_size)
+        .map(|i| {
+            let (to, gas_limit) = if i <= block_size / 2 {
+                (recipient, common::RAW_TRANSFER_GAS_LIMIT)
+            } else {
+                (contract_address, 100_000)
+            };
+            TxEnv {
+                caller: Address::from(U160::from(i)),
+                transact_to: TransactTo::Call(to),
+                value: U256::from(if to == recipient { i } else { 0 }),
+                gas_limit,
+                gas_price: U256::from(1),
+                ..TxEnv::default()
+            }
+        })
+        .collect();
+    let chain = PevmEthereum::mainnet();
+    let sequential_result = pevm::execute_revm_sequential(
+        &storage,
+        &chain,
+        SpecId::LATEST,
+        BlockEnv::default(),
+        txs.clone(),
+    );
+    let mut pevm = Pevm::new(ExecutionMode::Sync);
+    for _ in 0..10 {
+        let parallel_result = pevm.execute_revm_parallel(
+            &storage,
+            &chain,
+            SpecId::LATEST,
+            BlockEnv::default(),
+            txs.clone(),
+            thread::available_parallelism().unwrap_or(NonZeroUsize::MIN),
+        );
+        common::assert_execution_result(&sequential_result, &parallel_result);
+    }
+}
+
+#[test]
+fn gas_weighted_mainnet_blocks() {
+    let chain = PevmEthereum::mainnet();
+    let concurrency_level = thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+    common::for_each_block_from_disk(|block, storage| {
+        let sequential_result =
+            Pevm::default().execute(&storage, &chain, block.clone(), concurrency_level, true);
+        let parallel_result =
+            gas_weighted_pevm().execute(&storage, &chain, block, concurrency_level, false);
+        assert_eq!(sequential_result, parallel_result);
+    });
+}
+
+fn chunked_pevm(chunk_size: usize) -> Pevm {
+    Pevm::new(ExecutionMode::Sync).with_strategy(PevmStrategy {
+        chunk_size: NonZeroUsize::new(chunk_size),
+        record_dependencies: true,
+        ..PevmStrategy::default()
+    })
+}
+
+#[test]
+fn chunked_contended_block() {
+    for chunk_size in [1, 7, 100, 999, 1_000] {
+        let mut pevm = chunked_pevm(chunk_size);
+        execute_contended_block(&mut pevm);
+        let dependencies = pevm.dependencies().unwrap();
+        assert_eq!(dependencies.len(), 1_000);
+        for (tx_idx, tx_dependencies) in dependencies.iter().enumerate() {
+            assert!(tx_dependencies.iter().all(|dependency| {
+                *dependency < tx_idx && *dependency >= tx_idx - tx_idx % chunk_size
+            }));
+        }
+    }
+}
+
+#[test]
+fn chunked_mainnet_blocks() {
+    let chain = PevmEthereum::mainnet();
+    let concurrency_level = thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+    common::for_each_block_from_disk(|block, storage| {
+        let sequential_result =
+            Pevm::default().execute(&storage, &chain, block.clone(), concurrency_level, true);
+        let parallel_result =
+            chunked_pevm(16).execute(&storage, &chain, block, concurrency_level, false);
+        assert_eq!(sequential_result, parallel_result);
+    });
+}
@@ -0,0 +1,161 @@
+// Test self-destructed and deployed accounts -- later transactions must see
+// their storage as cleared, except for the writes of the deploying
+// transaction, instead of falling back to sequential execution.
+
+use std::{num::NonZeroUsize, thread};
+
+use pevm::{chain::PevmEthereum, Bytecodes, EvmAccount, EvmCode, InMemoryStorage};
+use revm::primitives::{
+    alloy_primitives::U160, env::TxEnv, Address, BlockEnv, Bytecode, Bytes, SpecId, TransactTo,
+    U256,
+};
+
+pub mod common;
+
+#[test]
+fn selfdestructed_account_is_cleared() {
+    let destructible_address = Address::from(U160::from(1_000));
+    let reader_address = Address::from(U160::from(1_001));
+
+    // `CALLER SELFDESTRUCT`
+    let destructible_code = Bytecode::new_raw(Bytes::from_static(&[0x33, 0xff]));
+    // Store the balance of the destructible account at slot 0 and its
+    // code hash at slot 1.
+    let mut reader_code = Vec::new();
+    for (opcode, slot) in [(0x31, 0x00), (0x3f, 0x01)] {
+        reader_code.push(0x73); // PUSH20
+        reader_code.extend_from_slice(destructible_address.as_slice());
+        reader_code.extend_from_slice(&[opcode, 0x60, slot, 0x55]); // PUSH1 slot SSTORE
+    }
+    let reader_code = Bytecode::new_raw(Bytes::from(reader_code));
+
+    let mut bytecodes = Bytecodes::new();
+    let mut accounts: Vec<_> = (0..=5).map(common::mock_account).collect();
+    for (address, code, storage) in [
+        (
+            destructible_address,
+            destructible_code,
+            [(U256::ZERO, U256::from(42))].into_iter().collect(),
+        ),
+        (reader_address, reader_code, Default::default()),
+    ] {
+        let code_hash = code.hash_slow();
+        let code = EvmCode::from(code);
+        bytecodes.insert(code_hash, code.clone());
+        accounts.push((
+            address,
+            EvmAccount {
+                balance: U256::from(1_000_000),
+                code_hash: Some(code_hash),
+                code: Some(code),
+                storage,
+                ..EvmAccount::default()
+            },
+        ));
+    }
+    let storage = InMemoryStorage::new(accounts, Some(&bytecodes), []);
+
+    let txs: Vec<TxEnv> = [
+        // Self-destruct the account
+        (destructible_address, U256::ZERO),
+        (reader_address, U256::ZERO),
+        // Revive the account as an EOA
+        (destructible_address, U256::from(1)),
+        (reader_address, U256::ZERO),
+        (destructible_address, U256::ZERO),
+    ]
+    .into_iter()
+    .enumerate()
+    .map(|(i, (to, value))| TxEnv {
+        caller: Address::from(U160::from(i + 1)),
+        transact_to: TransactTo::Call(to),
+        value,
+        gas_limit: 100_000,
+        gas_price: U256::from(1),
+        ..TxEnv::default()
+    })
+    .collect();
+
+    // Self-destruct only clears accounts created in the same transaction since Cancun.
+    let chain = PevmEthereum::mainnet();
+    let sequential_result = pevm::execute_revm_sequential(
+        &storage,
+        &chain,
+        SpecId::SHANGHAI,
+        BlockEnv::default(),
+        txs.clone(),
+    );
+    let parallel_result = pevm::execute_revm_parallel(
+        &storage,
+        &chain,
+        SpecId::SHANGHAI,
+        BlockEnv::default(),
+        txs,
+        thread::available_parallelism().unwrap_or(NonZeroUsize::MIN),
+    );
+    common::assert_execution_result(&sequential_result, &parallel_result);
+
+    let tx_results = parallel_result.unwrap();
+    assert_eq!(tx_results[0].state.get(&destructible_address), Some(&None));
+    let revived_account = tx_results[2].state[&destructible_address].as_ref().unwrap();
+    assert_eq!(revived_account.balance, U256::from(1));
+    assert_eq!(revived_account.code_hash, None);
+    assert!(revived_account.storage.is_empty());
+}
+
+#[test]
+fn constructor_storage_is_read_after_deployment() {
+    let deployer_address = Address::from(U160::from(1));
+    // `PUSH1 0 SLOAD PUSH1 1 SSTORE STOP`: Copy slot 0 to slot 1.
+    let runtime_code = [0x60, 0x00, 0x54, 0x60, 0x01, 0x55, 0x00];
+    // `PUSH1 42 PUSH1 0 SSTORE`, then return the runtime code appended after
+    // these 17 bytes via `PUSH1 7 PUSH1 17 PUSH1 0 CODECOPY PUSH1 7 PUSH1 0 RETURN`.
+    let mut init_code = vec![
+        0x60, 0x2a, 0x60, 0x00, 0x55, 0x60, 0x07, 0x60, 0x11, 0x60, 0x00, 0x39, 0x60, 0x07, 0x60,
+        0x00, 0xf3,
+    ];
+    init_code.extend_from_slice(&runtime_code);
+    let contract_address = deployer_address.create(1);
+
+    let storage = InMemoryStorage::new((0..=5).map(common::mock_account), None, []);
+    let txs = vec![
+        TxEnv {
+            caller: deployer_address,
+            transact_to: TransactTo::Create,
+            data: Bytes::from(init_code),
+            gas_limit: 200_000,
+            gas_price: U256::from(1),
+            ..TxEnv::default()
+        },
+        TxEnv {
+            caller: Address::from(U160::from(2)),
+            transact_to: TransactTo::Call(contract_address),
+            gas_limit: 100_000,
+            gas_price: U256::from(1),
+            ..TxEnv::default()
+        },
+    ];
+
+    let chain = PevmEthereum::mainnet();
+    let sequential_result = pevm::execute_revm_sequential(
+        &storage,
+        &chain,
+        SpecId::LATEST,
+        BlockEnv::default(),
+        txs.clone(),
+    );
+    let parallel_result = pevm::execute_revm_parallel(
+        &storage,
+        &chain,
+        SpecId::LATEST,
+        BlockEnv::default(),
+        txs,
+        thread::available_parallelism().unwrap_or(NonZeroUsize::MIN),
+    );
+    common::assert_execution_result(&sequential_result, &parallel_result);
+
+    // The later transaction reads the slot written by the constructor.
+    let tx_results = parallel_result.unwrap();
+    let contract = tx_results[1].state[&contract_address].as_ref().unwrap();
+    assert_eq!(contract.storage.get(&U256::from(1)), Some(&U256::from(42)));
+}
@@ -0,0 +1,103 @@
+// Test simulating consecutive blocks of calls with block overrides.
+
+use std::{num::NonZeroUsize, thread};
+
+use pevm::{
+    chain::PevmEthereum, BlockOverrides, Bytecodes, EvmAccount, EvmCode, InMemoryStorage, Pevm,
+    SimulatedBlock,
+};
+use revm::primitives::{
+    alloy_primitives::U160, env::TxEnv, Address, BlockEnv, Bytecode, Bytes, SpecId, TransactTo,
+    U256,
+};
+
+pub mod common;
+
+#[test]
+fn simulate_blocks_with_overrides() {
+    let block_size = 10; // number of transactions
+
+    let recorder_address = Address::from(U160::from(block_size + 1));
+    // `TIMESTAMP PUSH1 0 SSTORE NUMBER PUSH1 1 SSTORE STOP`: Record the
+    // block's timestamp at slot 0 and number at slot 1.
+    let code = Bytecode::new_raw(Bytes::from_static(&[
+        0x42, 0x60, 0x00, 0x55, 0x43, 0x60, 0x01, 0x55, 0x00,
+    ]));
+    let code_hash = code.hash_slow();
+    let mut bytecodes = Bytecodes::new();
+    bytecodes.insert(code_hash, EvmCode::from(code));
+    let mut accounts: Vec<_> = (0..=block_size).map(common::mock_account).collect();
+    accounts.push((
+        recorder_address,
+        EvmAccount {
+            code_hash: Some(code_hash),
+            ..EvmAccount::default()
+        },
+    ));
+    let storage = InMemoryStorage::new(accounts, Some(&bytecodes), []);
+    // Each block has raw transfers to the next account, with a final call
+    // to the recorder.
+    let calls: Vec<TxEnv> = (1..=block_size)
+        .map(|i| {
+            let (to, gas_limit) = if i == block_size {
+                (recorder_address, 100_000)
+            } else {
+                (
+                    Address::from(U160::from(i + 1)),
+                    common::RAW_TRANSFER_GAS_LIMIT,
+                )
+            };
+            TxEnv {
+                caller: Address::from(U160::from(i)),
+                transact_to: TransactTo::Call(to),
+                value: U256::from(i != block_size),
+                gas_limit,
+                gas_price: U256::from(1),
+                ..TxEnv::default()
+            }
+        })
+        .collect();
+    let blocks = vec![
+        SimulatedBlock {
+            block_overrides: BlockOverrides {
+                timestamp: Some(U256::from(100)),
+                ..BlockOverrides::default()
+            },
+            calls: calls.clone(),
+        },
+        SimulatedBlock {
+            block_overrides: BlockOverrides::default(),
+            calls,
+        },
+    ];
+    let block_env = BlockEnv {
+        number: U256::from(1_000),
+        ..BlockEnv::default()
+    };
+
+    let block_results = Pevm::default()
+        .simulate_blocks(
+            &storage,
+            &PevmEthereum::mainnet(),
+            SpecId::LATEST,
+            block_env,
+            blocks,
+            thread::available_parallelism().unwrap_or(NonZeroUsize::MIN),
+        )
+        .unwrap();
+    assert_eq!(block_results.len(), 2);
+    for (i, (timestamp, number)) in [(100, 1_001), (112, 1_002)].into_iter().enumerate() {
+        let tx_results = &block_results[i];
+        assert_eq!(tx_results.len(), block_size);
+        let recorder = tx_results[block_size - 1].state[&recorder_address]
+            .as_ref()
+            .unwrap();
+        assert_eq!(recorder.storage[&U256::ZERO], U256::from(timestamp));
+        assert_eq!(recorder.storage[&U256::from(1)], U256::from(number));
+        // Each block executes on the state of the previous one.
+        let sender = tx_results[0].state[&Address::from(U160::from(1))]
+            .as_ref()
+            .unwrap();
+        assert_eq!(sender.nonce, i as u64 + 2);
+    }
+}
@@ -14,7 +14,7 @@ fn empty_alloy_block() {
         &InMemoryStorage::default(),
         &PevmEthereum::mainnet(),
         Block {
-            header: common::MOCK_ALLOY_BLOCK_HEADER.clone(),
+            header: common::MOCK_BLOCK_HEADER.clone(),
             transactions: BlockTransactions::Full(Vec::new()),
             ..Block::default()
         },
@@ -34,7 +34,7 @@ fn one_tx_alloy_block() {
         &PevmEthereum::mainnet(),
         Block {
             // Legit header but with no transactions
-            header: common::MOCK_ALLOY_BLOCK_HEADER.clone(),
+            header: common::MOCK_BLOCK_HEADER.clone(),
             transactions: BlockTransactions::Full(vec![Transaction {
                 transaction_type: Some(2),
                 nonce: 1,
@@ -0,0 +1,136 @@
+// Test converting legacy block fixtures to snapshots and reading them back.
+
+use std::{
+    fs::{self, File},
+    io::BufReader,
+    num::NonZeroUsize,
+    thread,
+};
+
+use pevm::{chain::PevmEthereum, BlockRangeSnapshot, BlockSnapshot, Bytecodes, Pevm};
+
+pub mod common;
+
+#[test]
+fn snapshot_round_trip() {
+    let bytecodes: Bytecodes = bincode::deserialize_from(BufReader::new(
+        File::open("data/bytecodes.bincode").unwrap(),
+    ))
+    .unwrap();
+    let dir = std::env::temp_dir().join(format!("pevm-snapshot-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    for block_path in fs::read_dir("data/blocks").unwrap().take(5) {
+        let block_path = block_path.unwrap().path();
+        if !block_path.join("block.json").exists() {
+            continue;
+        }
+        let snapshot = BlockSnapshot::from_legacy_dir(&block_path, &bytecodes).unwrap();
+        // Snapshots are self-contained with all the codes of their accounts.
+        for account in snapshot.accounts.values() {
+            assert!(account.code.is_none());
+            if let Some(code_hash) = &account.code_hash {
+                assert!(snapshot.bytecodes.contains_key(code_hash));
+            }
+        }
+
+        let snapshot_path = dir.join(block_path.file_name().unwrap());
+        snapshot.write(&snapshot_path).unwrap();
+        assert_eq!(BlockSnapshot::read(&snapshot_path).unwrap(), snapshot);
+        assert!(
+            fs::metadata(&snapshot_path).unwrap().len()
+                < fs::metadata(block_path.join("pre_state.json"))
+                    .unwrap()
+                    .len()
+                    + fs::metadata(block_path.join("block.json")).unwrap().len()
+        );
+    }
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn snapshot_invalid_files() {
+    let path = std::env::temp_dir().join(format!("pevm-invalid-snapshot-{}", std::process::id()));
+    fs::write(&path, b"not a snapshot").unwrap();
+    assert!(matches!(
+        BlockSnapshot::read(&path),
+        Err(pevm::SnapshotError::InvalidMagic)
+    ));
+    fs::write(
+        &path,
+        [b"PEVMSNAP".as_slice(), &99u32.to_le_bytes()].concat(),
+    )
+    .unwrap();
+    assert!(matches!(
+        BlockSnapshot::read(&path),
+        Err(pevm::SnapshotError::UnsupportedVersion(99))
+    ));
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn range_snapshot_of_consecutive_blocks() {
+    let bytecodes: Bytecodes = bincode::deserialize_from(BufReader::new(
+        File::open("data/bytecodes.bincode").unwrap(),
+    ))
+    .unwrap();
+    let chain = PevmEthereum::mainnet();
+    let concurrency_level = thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+    let snapshot_and_execute = |block_number: u64| {
+        let snapshot =
+            BlockSnapshot::from_legacy_dir(format!("data/blocks/{block_number}"), &bytecodes)
+                .unwrap();
+        let block_result = Pevm::default()
+            .execute(
+                &snapshot.storage(),
+                &chain,
+                snapshot.block.clone(),
+                concurrency_level,
+                false,
+            )
+            .unwrap();
+        (snapshot, block_result)
+    };
+    let snapshots = vec![
+        snapshot_and_execute(19426586),
+        snapshot_and_execute(19426587),
+    ];
+    let block_results: Vec<_> = snapshots
+        .iter()
+        .map(|(_, block_result)| block_result.clone())
+        .collect();
+    let range = BlockRangeSnapshot::from_block_snapshots(snapshots.clone()).unwrap();
+    assert_eq!(range.blocks.len(), 2);
+
+    let path = std::env::temp_dir().join(format!("pevm-range-snapshot-{}", std::process::id()));
+    range.write(&path).unwrap();
+    assert_eq!(BlockRangeSnapshot::read(&path).unwrap(), range);
+    fs::remove_file(path).unwrap();
+
+    // The blocks execute on top of each other like on their own snapshots.
+    let mut range_results = Vec::new();
+    Pevm::default()
+        .execute_range_of_blocks(
+            &range.storage(),
+            &chain,
+            19426586..19426588,
+            |block_number| Ok::<_, ()>(range.blocks[(block_number - 19426586) as usize].clone()),
+            |_, block_result| {
+                range_results.push(block_result);
+                Ok(())
+            },
+            concurrency_level,
+        )
+        .unwrap();
+    assert_eq!(range_results, block_results);
+
+    // Only consecutive blocks make a range.
+    let mut snapshots = snapshots;
+    snapshots.reverse();
+    assert!(matches!(
+        BlockRangeSnapshot::from_block_snapshots(snapshots),
+        Err(pevm::SnapshotError::NonConsecutiveBlocks {
+            expected: 19426587,
+            found: 19426586,
+        })
+    ));
+}
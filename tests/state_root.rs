@@ -0,0 +1,104 @@
+// Test post-block state root computation from Merkle proofs of the pre-block state.
+#![cfg(feature = "state-root")]
+
+use std::{num::NonZeroUsize, thread};
+
+use pevm::{
+    chain::PevmEthereum, Bytecodes, EvmAccount, EvmCode, InMemoryStorage, PevmBlockExecutionResult,
+    ProofStorage, Storage, StorageTier,
+};
+use revm::primitives::{
+    alloy_primitives::U160, env::TxEnv, Address, BlockEnv, Bytecode, Bytes, SpecId, TransactTo,
+    U256,
+};
+
+pub mod common;
+
+#[test]
+fn state_root_matches_rebuilt_trie() {
+    let block_size = 100;
+    let contract_address = Address::from(U160::from(1_000));
+    let empty_address = Address::from(U160::from(1_001));
+    let new_address = Address::from(U160::from(1_002));
+
+    // `PUSH1 0 PUSH1 0 SSTORE CALLER PUSH1 1 SSTORE`: Clear slot 0 and
+    // store the caller at slot 1.
+    let code = Bytecode::new_raw(Bytes::from_static(&[
+        0x60, 0x00, 0x60, 0x00, 0x55, 0x33, 0x60, 0x01, 0x55,
+    ]));
+    let code_hash = code.hash_slow();
+    let code = EvmCode::from(code);
+    let mut bytecodes = Bytecodes::new();
+    bytecodes.insert(code_hash, code.clone());
+
+    let mut chain_state: common::ChainState = (0..=block_size).map(common::mock_account).collect();
+    chain_state.insert(
+        contract_address,
+        EvmAccount {
+            code_hash: Some(code_hash),
+            code: Some(code),
+            storage: (0..10)
+                .map(|slot| (U256::from(slot), U256::from(slot + 1)))
+                .collect(),
+            ..EvmAccount::default()
+        },
+    );
+    // An empty account that is removed when touched (EIP-161)
+    chain_state.insert(empty_address, EvmAccount::default());
+    let storage = InMemoryStorage::new(chain_state, Some(&bytecodes), []);
+
+    let txs: Vec<TxEnv> = (1..=block_size)
+        .map(|i| {
+            let (to, value, gas_limit) = match i {
+                1 => (contract_address, U256::ZERO, 100_000),
+                2 => (empty_address, U256::ZERO, common::RAW_TRANSFER_GAS_LIMIT),
+                3 => (new_address, U256::from(1), common::RAW_TRANSFER_GAS_LIMIT),
+                _ => (
+                    Address::from(U160::from(block_size + 1 - i)),
+                    U256::from(i),
+                    common::RAW_TRANSFER_GAS_LIMIT,
+                ),
+            };
+            TxEnv {
+                caller: Address::from(U160::from(i)),
+                transact_to: TransactTo::Call(to),
+                value,
+                gas_limit,
+                gas_price: U256::from(1),
+                ..TxEnv::default()
+            }
+        })
+        .collect();
+
+    let tx_results = pevm::execute_revm_parallel(
+        &storage,
+        &PevmEthereum::mainnet(),
+        SpecId::LATEST,
+        BlockEnv::default(),
+        txs,
+        thread::available_parallelism().unwrap_or(NonZeroUsize::MIN),
+    )
+    .unwrap();
+
+    // Apply the state transitions to rebuild the post-block tries from scratch.
+    let mut post_block_storage = storage.clone();
+    post_block_storage.apply(&tx_results);
+    assert_eq!(post_block_storage.basic(&empty_address), Ok(None));
+    assert!(post_block_storage.basic(&new_address).unwrap().is_some());
+    assert_eq!(
+        post_block_storage.cached_storage(&contract_address, &U256::ZERO),
+        None
+    );
+    let expected_state_root = post_block_storage.state_root().unwrap();
+    assert_ne!(expected_state_root, storage.state_root().unwrap());
+
+    let block_result = PevmBlockExecutionResult {
+        pre_block_state: Default::default(),
+        tx_results,
+        post_block_state: Default::default(),
+    };
+    assert_eq!(
+        pevm::compute_state_root(&storage, &block_result),
+        Ok(expected_state_root)
+    );
+}
@@ -0,0 +1,89 @@
+// Test batched storage lookups and the prefetch pass before parallel execution.
+
+use std::{num::NonZeroUsize, thread};
+
+use common::storage::CountingStorage;
+use pevm::{chain::PevmEthereum, InMemoryStorage, MemoryTier, Storage, TieredStorage};
+use revm::primitives::{
+    alloy_primitives::U160, env::TxEnv, Address, BlockEnv, SpecId, TransactTo, U256,
+};
+
+pub mod common;
+
+#[test]
+fn batched_lookups_match_single_lookups() {
+    let storage = InMemoryStorage::new((0..=10).map(common::mock_account), None, []);
+    let tiered_storage =
+        TieredStorage::new(MemoryTier::default(), CountingStorage::new(storage.clone()));
+    // Include a missing account and a duplicate.
+    let addresses: Vec<Address> = [1, 5, 20, 5, 10]
+        .into_iter()
+        .map(|i: usize| Address::from(U160::from(i)))
+        .collect();
+    let expected_basics: Vec<_> = addresses
+        .iter()
+        .map(|address| storage.basic(address).unwrap())
+        .collect();
+    assert_eq!(storage.basic_many(&addresses).unwrap(), expected_basics);
+
+    assert_eq!(
+        tiered_storage.basic_many(&addresses).unwrap(),
+        expected_basics
+    );
+    assert_eq!(tiered_storage.second().batches(), 1);
+    // All accounts have been filled into the first tier.
+    assert_eq!(
+        tiered_storage.basic_many(&addresses).unwrap(),
+        expected_basics
+    );
+    assert_eq!(tiered_storage.second().batches(), 1);
+    // Only misses are forwarded.
+    let more_addresses = [addresses[0], Address::from(U160::from(7))];
+    tiered_storage.basic_many(&more_addresses).unwrap();
+    assert_eq!(tiered_storage.second().batches(), 2);
+    assert_eq!(tiered_storage.second().reads(), 2);
+
+    let slots = [(addresses[0], U256::ZERO), (addresses[2], U256::from(1))];
+    assert_eq!(
+        tiered_storage.storage_many(&slots).unwrap(),
+        vec![U256::ZERO, U256::ZERO]
+    );
+}
+
+#[test]
+fn prefetch_before_parallel_execution() {
+    let block_size = 1_000; // number of transactions
+    let storage = InMemoryStorage::new((0..=block_size).map(common::mock_account), None, []);
+    let counting_storage = CountingStorage::new(storage.clone());
+    // Mock `block_size` transactions sending some tokens to the next account.
+    let txs: Vec<TxEnv> = (1..=block_size)
+        .map(|i| TxEnv {
+            caller: Address::from(U160::from(i)),
+            transact_to: TransactTo::Call(Address::from(U160::from(i % block_size + 1))),
+            value: U256::from(1),
+            gas_limit: common::RAW_TRANSFER_GAS_LIMIT,
+            gas_price: U256::from(1),
+            ..TxEnv::default()
+        })
+        .collect();
+
+    let chain = PevmEthereum::mainnet();
+    let expected_result = pevm::execute_revm_sequential(
+        &storage,
+        &chain,
+        SpecId::LATEST,
+        BlockEnv::default(),
+        txs.clone(),
+    );
+    let parallel_result = pevm::execute_revm_parallel(
+        &counting_storage,
+        &chain,
+        SpecId::LATEST,
+        BlockEnv::default(),
+        txs,
+        thread::available_parallelism().unwrap_or(NonZeroUsize::MIN),
+    );
+    common::assert_execution_result(&expected_result, &parallel_result);
+    // All senders & recipients are prefetched in one batch.
+    assert_eq!(counting_storage.batches(), 1);
+}
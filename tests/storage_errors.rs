@@ -0,0 +1,119 @@
+// Test that storage errors keep their types through execution, so callers
+// can tell transient failures from permanent ones.
+
+use std::{fmt, num::NonZeroUsize, thread};
+
+use pevm::{
+    chain::PevmEthereum, AccountBasic, EvmCode, InMemoryStorage, PevmError, ReadError, Storage,
+    TxExecutionError,
+};
+use revm::primitives::{
+    alloy_primitives::U160, env::TxEnv, Address, BlockEnv, EVMError, SpecId, TransactTo, B256, U256,
+};
+
+pub mod common;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FlakyError {
+    Timeout,
+}
+
+impl fmt::Display for FlakyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+// A storage that times out on reading an account.
+#[derive(Debug)]
+struct FlakyStorage<'a> {
+    storage: InMemoryStorage<'a>,
+    flaky_address: Address,
+}
+
+impl<'a> Storage for FlakyStorage<'a> {
+    type Error = FlakyError;
+
+    fn basic(&self, address: &Address) -> Result<Option<AccountBasic>, Self::Error> {
+        if *address == self.flaky_address {
+            return Err(FlakyError::Timeout);
+        }
+        Ok(self.storage.basic(address).unwrap())
+    }
+
+    fn code_hash(&self, address: &Address) -> Result<Option<B256>, Self::Error> {
+        Ok(self.storage.code_hash(address).unwrap())
+    }
+
+    fn code_by_hash(&self, code_hash: &B256) -> Result<Option<EvmCode>, Self::Error> {
+        Ok(self.storage.code_by_hash(code_hash).unwrap())
+    }
+
+    fn has_storage(&self, address: &Address) -> Result<bool, Self::Error> {
+        Ok(self.storage.has_storage(address).unwrap())
+    }
+
+    fn storage(&self, address: &Address, index: &U256) -> Result<U256, Self::Error> {
+        Ok(self.storage.storage(address, index).unwrap())
+    }
+
+    fn block_hash(&self, number: &u64) -> Result<B256, Self::Error> {
+        Ok(self.storage.block_hash(number).unwrap())
+    }
+}
+
+#[test]
+fn typed_storage_errors() {
+    let block_size = 10;
+    let flaky_address = Address::from(U160::from(block_size + 1));
+    let storage = FlakyStorage {
+        storage: InMemoryStorage::new((0..=block_size).map(common::mock_account), None, []),
+        flaky_address,
+    };
+    // Only the first transaction sends to the flaky account.
+    let txs: Vec<TxEnv> = (1..=block_size)
+        .map(|i| TxEnv {
+            caller: Address::from(U160::from(i)),
+            transact_to: TransactTo::Call(if i == 1 {
+                flaky_address
+            } else {
+                Address::from(U160::from(i))
+            }),
+            value: U256::from(1),
+            gas_limit: common::RAW_TRANSFER_GAS_LIMIT,
+            gas_price: U256::from(1),
+            ..TxEnv::default()
+        })
+        .collect();
+
+    let chain = PevmEthereum::mainnet();
+    let sequential_result = pevm::execute_revm_sequential(
+        &storage,
+        &chain,
+        SpecId::LATEST,
+        BlockEnv::default(),
+        txs.clone(),
+    );
+    let parallel_result = pevm::execute_revm_parallel(
+        &storage,
+        &chain,
+        SpecId::LATEST,
+        BlockEnv::default(),
+        txs,
+        thread::available_parallelism().unwrap_or(NonZeroUsize::MIN),
+    );
+    for result in [sequential_result, parallel_result] {
+        // Lazily updated accounts are only read after execution.
+        let err = match &result {
+            Err(PevmError::ExecutionError(TxExecutionError {
+                tx_idx: 0,
+                error: EVMError::Database(ReadError::StorageError(err)),
+                ..
+            }))
+            | Err(PevmError::StorageError(err)) => err,
+            _ => panic!("Unexpected result: {result:?}"),
+        };
+        assert_eq!(err.downcast_ref::<FlakyError>(), Some(&FlakyError::Timeout));
+        assert_eq!(err.to_string(), "Timeout");
+    }
+}
@@ -0,0 +1,59 @@
+// Test streaming the results of transactions as soon as they are final.
+
+use std::{num::NonZeroUsize, sync::mpsc, thread};
+
+use pevm::{chain::PevmEthereum, ExecutionMode, InMemoryStorage, Pevm, PevmStrategy};
+use revm::primitives::{
+    alloy_primitives::U160, env::TxEnv, Address, BlockEnv, SpecId, TransactTo, U256,
+};
+
+pub mod common;
+
+#[test]
+fn streaming_raw_transfers() {
+    let block_size = 1_000; // number of transactions
+
+    // Mock the beneficiary account (`Address:ZERO`) and the next `block_size` user accounts.
+    let storage = InMemoryStorage::new((0..=block_size).map(common::mock_account), None, []);
+    // Mock `block_size` raw transfers to the next account, with every tenth
+    // transaction from the same sender.
+    let txs: Vec<TxEnv> = (1..=block_size)
+        .map(|i| TxEnv {
+            caller: Address::from(U160::from(if i % 10 == 0 { 1 } else { i })),
+            transact_to: TransactTo::Call(Address::from(U160::from(i % block_size + 1))),
+            value: U256::from(1),
+            gas_limit: common::RAW_TRANSFER_GAS_LIMIT,
+            gas_price: U256::from(1),
+            ..TxEnv::default()
+        })
+        .collect();
+    let chain = PevmEthereum::mainnet();
+    let sequential_result = pevm::execute_revm_sequential(
+        &storage,
+        &chain,
+        SpecId::LATEST,
+        BlockEnv::default(),
+        txs.clone(),
+    );
+    for chunk_size in [None, Some(1), Some(100)] {
+        let mut pevm = Pevm::new(ExecutionMode::Sync).with_strategy(PevmStrategy {
+            chunk_size: chunk_size.and_then(NonZeroUsize::new),
+            ..PevmStrategy::default()
+        });
+        let (sender, receiver) = mpsc::channel();
+        let parallel_result = pevm.execute_streaming(
+            &storage,
+            &chain,
+            SpecId::LATEST,
+            BlockEnv::default(),
+            txs.clone(),
+            thread::available_parallelism().unwrap_or(NonZeroUsize::MIN),
+            |tx_idx, tx_result| sender.send((tx_idx, tx_result.clone())).unwrap(),
+        );
+        common::assert_execution_result(&sequential_result, &parallel_result);
+        drop(sender);
+        let (tx_idxs, streamed_results): (Vec<_>, Vec<_>) = receiver.into_iter().unzip();
+        assert_eq!(tx_idxs, (0..block_size).collect::<Vec<_>>());
+        assert_eq!(streamed_results, parallel_result.unwrap());
+    }
+}
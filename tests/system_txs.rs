@@ -0,0 +1,150 @@
+// Test executing the top-of-block system transactions before the rest of the block.
+
+use std::{num::NonZeroUsize, thread};
+
+use alloy_rpc_types::{BlockTransactions, Header, Transaction};
+use pevm::{
+    chain::{PevmChain, PevmEthereum, RewardPolicy},
+    InMemoryStorage, MvMemory, Pevm, PevmStrategy, PevmTxExecutionResult, ScheduleEvent,
+};
+use revm::{
+    primitives::{
+        alloy_primitives::U160, env::TxEnv, Address, BlockEnv, SpecId, TransactTo, B256, U256,
+    },
+    Handler,
+};
+
+pub mod common;
+
+// Ethereum, with the transactions of a system caller as the top-of-block
+// system transactions.
+#[derive(Debug, Clone, PartialEq)]
+struct SystemChain {
+    chain: PevmEthereum,
+    system_caller: Address,
+}
+
+impl PevmChain for SystemChain {
+    type BlockSpecError = <PevmEthereum as PevmChain>::BlockSpecError;
+    type GasPriceError = <PevmEthereum as PevmChain>::GasPriceError;
+
+    fn id(&self) -> u64 {
+        self.chain.id()
+    }
+
+    fn get_block_spec(&self, header: &Header) -> Result<SpecId, Self::BlockSpecError> {
+        self.chain.get_block_spec(header)
+    }
+
+    fn get_gas_price(&self, tx: &Transaction) -> Result<U256, Self::GasPriceError> {
+        self.chain.get_gas_price(tx)
+    }
+
+    fn build_mv_memory(
+        &self,
+        hasher: &ahash::RandomState,
+        block_env: &BlockEnv,
+        txs: &[TxEnv],
+    ) -> MvMemory {
+        self.chain.build_mv_memory(hasher, block_env, txs)
+    }
+
+    fn is_top_of_block_system_tx(&self, tx: &TxEnv) -> bool {
+        tx.caller == self.system_caller
+    }
+
+    fn get_handler<'a, EXT, DB: revm::Database>(
+        &self,
+        spec_id: SpecId,
+        with_reward_beneficiary: bool,
+    ) -> Handler<'a, revm::Context<EXT, DB>, EXT, DB> {
+        self.chain.get_handler(spec_id, with_reward_beneficiary)
+    }
+
+    fn get_reward_policy(&self, hasher: &ahash::RandomState) -> RewardPolicy {
+        self.chain.get_reward_policy(hasher)
+    }
+
+    fn calculate_receipt_root(
+        &self,
+        spec_id: SpecId,
+        txs: &BlockTransactions<Transaction>,
+        tx_results: &[PevmTxExecutionResult],
+    ) -> B256 {
+        self.chain.calculate_receipt_root(spec_id, txs, tx_results)
+    }
+}
+
+#[test]
+fn top_of_block_system_txs() {
+    let block_size = 1_000; // number of user transactions
+    let num_system_txs = 10;
+    let system_caller = Address::from(U160::from(block_size + 1));
+    let storage = InMemoryStorage::new((0..=block_size + 1).map(common::mock_account), None, []);
+    // System transfers to the first users, then raw transfers between users,
+    // then another transfer from the system caller that isn't at the top.
+    let transfer = |caller: Address, to: Address, nonce: u64| TxEnv {
+        caller,
+        transact_to: TransactTo::Call(to),
+        value: U256::from(1),
+        gas_limit: common::RAW_TRANSFER_GAS_LIMIT,
+        gas_price: U256::from(1),
+        nonce: Some(nonce),
+        ..TxEnv::default()
+    };
+    let txs: Vec<TxEnv> = (1..=num_system_txs)
+        .map(|i| transfer(system_caller, Address::from(U160::from(i)), i as u64))
+        .chain((1..=block_size).map(|i| {
+            transfer(
+                Address::from(U160::from(i)),
+                Address::from(U160::from(i % block_size + 1)),
+                1,
+            )
+        }))
+        .chain(std::iter::once(transfer(
+            system_caller,
+            Address::ZERO,
+            num_system_txs as u64 + 1,
+        )))
+        .collect();
+    let chain = SystemChain {
+        chain: PevmEthereum::mainnet(),
+        system_caller,
+    };
+    let concurrency_level = thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+    let mut pevm = Pevm::default().with_strategy(PevmStrategy {
+        record_schedule: true,
+        ..PevmStrategy::default()
+    });
+
+    let sequential_result = pevm::execute_revm_sequential(
+        &storage,
+        &chain,
+        SpecId::LATEST,
+        BlockEnv::default(),
+        txs.clone(),
+    );
+    let parallel_result = pevm.execute_revm_parallel(
+        &storage,
+        &chain,
+        SpecId::LATEST,
+        BlockEnv::default(),
+        txs,
+        concurrency_level,
+    );
+    common::assert_execution_result(&sequential_result, &parallel_result);
+
+    // The system transactions execute once in order before any other task.
+    let schedule = pevm.schedule().unwrap();
+    let expected_events: Vec<_> = (0..num_system_txs)
+        .map(|tx_idx| ScheduleEvent::Execute {
+            tx_idx,
+            tx_incarnation: 0,
+        })
+        .collect();
+    assert_eq!(schedule[..num_system_txs], expected_events);
+    assert!(!schedule[num_system_txs..].iter().any(|event| matches!(
+        event,
+        ScheduleEvent::Execute { tx_idx, .. } if *tx_idx < num_system_txs
+    )));
+}
@@ -0,0 +1,118 @@
+// Test recording the timed tasks of parallel executions.
+
+use std::{num::NonZeroUsize, thread};
+
+use pevm::{
+    chain::PevmEthereum, ExecutionMode, InMemoryStorage, Pevm, PevmStrategy, TaskEvent, TaskKind,
+};
+use revm::primitives::{
+    alloy_primitives::U160, env::TxEnv, Address, BlockEnv, SpecId, TransactTo, U256,
+};
+
+pub mod common;
+
+#[test]
+fn events_contended_block() {
+    let block_size = 1_000; // number of transactions
+    let contract_address = Address::from(U160::from(block_size + 1));
+    let (accounts, bytecodes) = common::mock_counter_accounts(block_size, contract_address);
+    let storage = InMemoryStorage::new(accounts, Some(&bytecodes), []);
+    // Raw transfers to the next account, with every tenth transaction
+    // incrementing a shared counter instead.
+    let txs: Vec<TxEnv> = (1..=block_size)
+        .map(|i| {
+            let (to, value, gas_limit) = if i % 10 == 0 {
+                (contract_address, U256::ZERO, 100_000)
+            } else {
+                (
+                    Address::from(U160::from(i % block_size + 1)),
+                    U256::from(1),
+                    common::RAW_TRANSFER_GAS_LIMIT,
+                )
+            };
+            TxEnv {
+                caller: Address::from(U160::from(i)),
+                transact_to: TransactTo::Call(to),
+                value,
+                gas_limit,
+                gas_price: U256::from(1),
+                ..TxEnv::default()
+            }
+        })
+        .collect();
+    let chain = PevmEthereum::mainnet();
+    let concurrency_level = thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+    let mut pevm = Pevm::new(ExecutionMode::Sync).with_strategy(PevmStrategy {
+        record_report: true,
+        record_events: true,
+        ..PevmStrategy::default()
+    });
+    let sequential_result = pevm::execute_revm_sequential(
+        &storage,
+        &chain,
+        SpecId::LATEST,
+        BlockEnv::default(),
+        txs.clone(),
+    );
+    let parallel_result = pevm.execute_revm_parallel(
+        &storage,
+        &chain,
+        SpecId::LATEST,
+        BlockEnv::default(),
+        txs.clone(),
+        concurrency_level,
+    );
+    common::assert_execution_result(&sequential_result, &parallel_result);
+
+    // The events match the report, in the order they started.
+    let events = pevm.take_events().unwrap();
+    assert_eq!(pevm.take_events(), None);
+    assert!(events
+        .windows(2)
+        .all(|pair| pair[0].start_ns <= pair[1].start_ns));
+    for event in &events {
+        assert!(event.start_ns <= event.end_ns);
+        assert!(event.worker_idx < concurrency_level.get());
+    }
+    for (tx_idx, tx_report) in pevm.report().unwrap().txs.iter().enumerate() {
+        let mut incarnations: Vec<_> = events
+            .iter()
+            .filter(|event| event.tx_idx == tx_idx && event.kind == TaskKind::Execution)
+            .map(|event| event.tx_incarnation)
+            .collect();
+        incarnations.sort_unstable();
+        assert_eq!(
+            incarnations,
+            (0..tx_report.incarnations).collect::<Vec<_>>()
+        );
+    }
+
+    // The events export to JSON and CSV.
+    let json = serde_json::to_string(&events).unwrap();
+    assert_eq!(
+        serde_json::from_str::<Vec<TaskEvent>>(&json).unwrap(),
+        events
+    );
+    let first_row = events[0].to_csv_row();
+    assert_eq!(
+        first_row.split(',').count(),
+        TaskEvent::CSV_HEADER.split(',').count()
+    );
+    assert!(first_row.starts_with(&format!(
+        "{},{},",
+        events[0].tx_idx, events[0].tx_incarnation
+    )));
+
+    // The events are only recorded on request.
+    let mut pevm = Pevm::default();
+    pevm.execute_revm_parallel(
+        &storage,
+        &chain,
+        SpecId::LATEST,
+        BlockEnv::default(),
+        txs,
+        concurrency_level,
+    )
+    .unwrap();
+    assert_eq!(pevm.take_events(), None);
+}
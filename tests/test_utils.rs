@@ -0,0 +1,24 @@
+// Test the public helpers for downstream correctness tests.
+
+use pevm::{chain::PevmEthereum, test_utils};
+
+pub mod common;
+
+#[test]
+fn mock_raw_transfers() {
+    let block_size = 1_000; // number of transactions
+    let storage = test_utils::mock_storage(block_size + 1);
+    let txs = test_utils::mock_raw_transfers(block_size);
+    assert_eq!(txs.len(), block_size);
+    assert!(txs
+        .iter()
+        .all(|tx| tx.gas_limit == test_utils::RAW_TRANSFER_GAS_LIMIT));
+    test_utils::test_execute_revm(storage, txs);
+}
+
+#[test]
+fn mainnet_blocks_match_headers() {
+    common::for_each_block_from_disk(|block, storage| {
+        test_utils::test_execute_alloy(&storage, &PevmEthereum::mainnet(), block, true);
+    });
+}
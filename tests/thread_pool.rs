@@ -0,0 +1,60 @@
+// Test parallel execution on an external rayon thread pool.
+#![cfg(feature = "rayon")]
+
+use std::{num::NonZeroUsize, sync::Arc};
+
+use pevm::{chain::PevmEthereum, ExecutionMode, InMemoryStorage, Pevm};
+use revm::primitives::{
+    alloy_primitives::U160, env::TxEnv, Address, BlockEnv, SpecId, TransactTo, U256,
+};
+
+pub mod common;
+
+#[test]
+fn thread_pool_raw_transfers() {
+    let block_size = 10_000; // number of transactions
+
+    // Mock the beneficiary account (`Address:ZERO`) and the next `block_size` user accounts.
+    let storage = InMemoryStorage::new((0..=block_size).map(common::mock_account), None, []);
+    // Mock `block_size` raw transfers to the next account.
+    let txs: Vec<TxEnv> = (1..=block_size)
+        .map(|i| TxEnv {
+            caller: Address::from(U160::from(i)),
+            transact_to: TransactTo::Call(Address::from(U160::from(i % block_size + 1))),
+            value: U256::from(1),
+            gas_limit: common::RAW_TRANSFER_GAS_LIMIT,
+            gas_price: U256::from(1),
+            ..TxEnv::default()
+        })
+        .collect();
+    let chain = PevmEthereum::mainnet();
+    let sequential_result = pevm::execute_revm_sequential(
+        &storage,
+        &chain,
+        SpecId::LATEST,
+        BlockEnv::default(),
+        txs.clone(),
+    );
+
+    let thread_pool = Arc::new(
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(4)
+            .build()
+            .unwrap(),
+    );
+    let mut pevm = Pevm::new(ExecutionMode::Sync).with_thread_pool(thread_pool.clone());
+    // More workers than pool threads, and from within the pool itself.
+    for concurrency_level in [2, 16] {
+        let parallel_result = thread_pool.install(|| {
+            pevm.execute_revm_parallel(
+                &storage,
+                &chain,
+                SpecId::LATEST,
+                BlockEnv::default(),
+                txs.clone(),
+                NonZeroUsize::new(concurrency_level).unwrap(),
+            )
+        });
+        common::assert_execution_result(&sequential_result, &parallel_result);
+    }
+}
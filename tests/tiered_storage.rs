@@ -0,0 +1,58 @@
+// Test tiered storage -- misses in the first tier fall through to the next
+// and fill back.
+
+use std::{num::NonZeroUsize, thread};
+
+use common::storage::CountingStorage;
+use pevm::{chain::PevmEthereum, InMemoryStorage, MemoryTier, TieredStorage};
+use revm::primitives::{
+    alloy_primitives::U160, env::TxEnv, Address, BlockEnv, SpecId, TransactTo, U256,
+};
+
+pub mod common;
+
+#[test]
+fn tiered_storage_fills_back() {
+    let block_size = 1_000; // number of transactions
+    let storage = InMemoryStorage::new((0..=block_size).map(common::mock_account), None, []);
+    let tiered_storage =
+        TieredStorage::new(MemoryTier::default(), CountingStorage::new(storage.clone()));
+    // Mock `block_size` transactions sending some tokens to the next account.
+    let txs: Vec<TxEnv> = (1..=block_size)
+        .map(|i| TxEnv {
+            caller: Address::from(U160::from(i)),
+            transact_to: TransactTo::Call(Address::from(U160::from(i % block_size + 1))),
+            value: U256::from(1),
+            gas_limit: common::RAW_TRANSFER_GAS_LIMIT,
+            gas_price: U256::from(1),
+            ..TxEnv::default()
+        })
+        .collect();
+
+    let chain = PevmEthereum::mainnet();
+    let concurrency_level = thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+    let expected_result = pevm::execute_revm_sequential(
+        &storage,
+        &chain,
+        SpecId::LATEST,
+        BlockEnv::default(),
+        txs.clone(),
+    );
+    let execute = || {
+        pevm::execute_revm_parallel(
+            &tiered_storage,
+            &chain,
+            SpecId::LATEST,
+            BlockEnv::default(),
+            txs.clone(),
+            concurrency_level,
+        )
+    };
+
+    common::assert_execution_result(&expected_result, &execute());
+    let lower_reads = tiered_storage.second().reads();
+    assert!(lower_reads > 0);
+    // Everything has been filled into the first tier.
+    common::assert_execution_result(&expected_result, &execute());
+    assert_eq!(tiered_storage.second().reads(), lower_reads);
+}
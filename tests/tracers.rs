@@ -0,0 +1,369 @@
+// Test the built-in tracers on parallel execution.
+
+use std::{collections::BTreeMap, num::NonZeroUsize, thread};
+
+use alloy_rpc_types_trace::{
+    geth::{AccountState, CallFrame, PreStateFrame, PreStateMode},
+    parity::{Action, CallAction, CallOutput, CallType, TraceOutput, TransactionTrace},
+};
+use pevm::{
+    chain::PevmEthereum, Bytecodes, CallTracer, EvmAccount, EvmCode, ExecutionMode,
+    InMemoryStorage, ParityTracer, Pevm, PevmStrategy, PrestateTracer, RetryPolicy,
+};
+use revm::primitives::{
+    alloy_primitives::U160, env::TxEnv, Address, BlockEnv, Bytecode, Bytes, SpecId, TransactTo,
+    B256, U256,
+};
+
+pub mod common;
+
+#[test]
+fn call_tracer_nested_calls() {
+    let block_size = 100; // number of transactions
+
+    let counter_address = Address::from(U160::from(block_size + 1));
+    let caller_address = Address::from(U160::from(block_size + 2));
+    let counter_code = common::counter_contract();
+    // `PUSH1 0 PUSH1 0 PUSH1 0 PUSH1 0 PUSH1 0 PUSH20 <counter> GAS CALL POP STOP`:
+    // Call the counter.
+    let mut caller_code = vec![
+        0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x73,
+    ];
+    caller_code.extend_from_slice(counter_address.as_slice());
+    caller_code.extend_from_slice(&[0x5a, 0xf1, 0x50, 0x00]);
+    let caller_code = Bytecode::new_raw(Bytes::from(caller_code));
+    let mut bytecodes = Bytecodes::new();
+    let mut accounts: Vec<_> = (0..=block_size).map(common::mock_account).collect();
+    for (address, code) in [
+        (counter_address, counter_code),
+        (caller_address, caller_code),
+    ] {
+        let code_hash = code.hash_slow();
+        bytecodes.insert(code_hash, EvmCode::from(code));
+        accounts.push((
+            address,
+            EvmAccount {
+                code_hash: Some(code_hash),
+                ..EvmAccount::default()
+            },
+        ));
+    }
+    let storage = InMemoryStorage::new(accounts, Some(&bytecodes), []);
+    // Raw transfers to the next account, with every other transaction
+    // calling the counter through the caller contract instead.
+    let txs: Vec<TxEnv> = (1..=block_size)
+        .map(|i| {
+            let (to, value, gas_limit) = if i % 2 == 0 {
+                (caller_address, U256::ZERO, 100_000)
+            } else {
+                (
+                    Address::from(U160::from(i % block_size + 1)),
+                    U256::from(1),
+                    common::RAW_TRANSFER_GAS_LIMIT,
+                )
+            };
+            TxEnv {
+                caller: Address::from(U160::from(i)),
+                transact_to: TransactTo::Call(to),
+                value,
+                gas_limit,
+                gas_price: U256::from(1),
+                ..TxEnv::default()
+            }
+        })
+        .collect();
+    let chain = PevmEthereum::mainnet();
+    let concurrency_level = thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+
+    let (tx_results, traces) = Pevm::default()
+        .execute_revm_parallel_with_inspector(
+            &storage,
+            &chain,
+            SpecId::LATEST,
+            BlockEnv::default(),
+            txs.clone(),
+            &CallTracer::default(),
+            concurrency_level,
+        )
+        .unwrap();
+    for (i, (tx_result, trace)) in tx_results.iter().zip(&traces).enumerate() {
+        let tx = &txs[i];
+        assert_eq!(trace.from, tx.caller);
+        assert_eq!(trace.to, tx.transact_to.to().copied());
+        assert_eq!(trace.gas, U256::from(tx.gas_limit));
+        assert_eq!(trace.gas_used, U256::from(tx_result.gas_used));
+        assert_eq!(trace.typ, "CALL");
+        assert_eq!(trace.error, None);
+        if tx.transact_to.to() == Some(&caller_address) {
+            assert_eq!(trace.calls.len(), 1);
+            let nested_call = &trace.calls[0];
+            assert_eq!(nested_call.from, caller_address);
+            assert_eq!(nested_call.to, Some(counter_address));
+            assert_eq!(nested_call.value, Some(U256::ZERO));
+            assert_eq!(nested_call.typ, "CALL");
+            assert!(nested_call.calls.is_empty());
+        } else {
+            assert_eq!(
+                trace,
+                &CallFrame {
+                    from: tx.caller,
+                    gas: U256::from(common::RAW_TRANSFER_GAS_LIMIT),
+                    gas_used: U256::from(common::RAW_TRANSFER_GAS_LIMIT),
+                    to: tx.transact_to.to().copied(),
+                    value: Some(U256::from(1)),
+                    typ: String::from("CALL"),
+                    ..CallFrame::default()
+                }
+            );
+        }
+    }
+
+    // The same traces from a sequential fallback, and only the top calls
+    // with [CallTracer::only_top_call].
+    let (_, sequential_traces) = Pevm::new(ExecutionMode::Sync)
+        .with_strategy(PevmStrategy {
+            retry: RetryPolicy::BoundedThenSequential { max_retries: 0 },
+            ..PevmStrategy::default()
+        })
+        .execute_revm_parallel_with_inspector(
+            &storage,
+            &chain,
+            SpecId::LATEST,
+            BlockEnv::default(),
+            txs.clone(),
+            &CallTracer::default(),
+            concurrency_level,
+        )
+        .unwrap();
+    assert_eq!(sequential_traces, traces);
+    let (_, top_call_traces) = Pevm::default()
+        .execute_revm_parallel_with_inspector(
+            &storage,
+            &chain,
+            SpecId::LATEST,
+            BlockEnv::default(),
+            txs,
+            &CallTracer {
+                only_top_call: true,
+            },
+            concurrency_level,
+        )
+        .unwrap();
+    for (trace, top_call_trace) in traces.into_iter().zip(top_call_traces) {
+        assert_eq!(
+            top_call_trace,
+            CallFrame {
+                calls: Vec::new(),
+                ..trace
+            }
+        );
+    }
+}
+
+#[test]
+fn prestate_tracer_contended_counter() {
+    let block_size = 100; // number of transactions
+
+    let counter_address = Address::from(U160::from(block_size + 1));
+    let (accounts, bytecodes) = common::mock_counter_accounts(block_size, counter_address);
+    let counter_bytes = common::counter_contract().original_bytes();
+    let storage = InMemoryStorage::new(accounts, Some(&bytecodes), []);
+    // Every transaction increments the shared counter from a different sender.
+    let txs: Vec<TxEnv> = (1..=block_size)
+        .map(|i| TxEnv {
+            caller: Address::from(U160::from(i)),
+            transact_to: TransactTo::Call(counter_address),
+            value: U256::ZERO,
+            gas_limit: 100_000,
+            gas_price: U256::from(1),
+            ..TxEnv::default()
+        })
+        .collect();
+    let chain = PevmEthereum::mainnet();
+    let concurrency_level = thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+
+    let (_, traces) = Pevm::default()
+        .execute_revm_parallel_with_inspector(
+            &storage,
+            &chain,
+            SpecId::LATEST,
+            BlockEnv::default(),
+            txs.clone(),
+            &PrestateTracer,
+            concurrency_level,
+        )
+        .unwrap();
+    for (i, trace) in traces.iter().enumerate() {
+        let PreStateFrame::Default(PreStateMode(prestate)) = trace else {
+            panic!("Unexpected prestate diff");
+        };
+        assert_eq!(
+            prestate.get(&txs[i].caller),
+            Some(&AccountState {
+                balance: Some(U256::MAX.div_ceil(U256::from(2))),
+                nonce: Some(1),
+                ..AccountState::default()
+            })
+        );
+        // Each transaction reads the counter incremented by the previous ones.
+        assert_eq!(
+            prestate.get(&counter_address),
+            Some(&AccountState {
+                balance: Some(U256::ZERO),
+                code: Some(counter_bytes.clone()),
+                storage: BTreeMap::from([(B256::ZERO, B256::from(U256::from(i)))]),
+                ..AccountState::default()
+            })
+        );
+    }
+
+    // The same prestates from a sequential fallback.
+    let (_, sequential_traces) = Pevm::new(ExecutionMode::Sync)
+        .with_strategy(PevmStrategy {
+            retry: RetryPolicy::BoundedThenSequential { max_retries: 0 },
+            ..PevmStrategy::default()
+        })
+        .execute_revm_parallel_with_inspector(
+            &storage,
+            &chain,
+            SpecId::LATEST,
+            BlockEnv::default(),
+            txs,
+            &PrestateTracer,
+            concurrency_level,
+        )
+        .unwrap();
+    assert_eq!(sequential_traces, traces);
+}
+
+#[test]
+fn parity_tracer_nested_calls() {
+    let block_size = 100; // number of transactions
+
+    let counter_address = Address::from(U160::from(block_size + 1));
+    let caller_address = Address::from(U160::from(block_size + 2));
+    let counter_code = common::counter_contract();
+    // `PUSH1 0 PUSH1 0 PUSH1 0 PUSH1 0 PUSH1 0 PUSH20 <counter> GAS CALL POP STOP`:
+    // Call the counter.
+    let mut caller_code = vec![
+        0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x73,
+    ];
+    caller_code.extend_from_slice(counter_address.as_slice());
+    caller_code.extend_from_slice(&[0x5a, 0xf1, 0x50, 0x00]);
+    let caller_code = Bytecode::new_raw(Bytes::from(caller_code));
+    let mut bytecodes = Bytecodes::new();
+    let mut accounts: Vec<_> = (0..=block_size).map(common::mock_account).collect();
+    for (address, code) in [
+        (counter_address, counter_code),
+        (caller_address, caller_code),
+    ] {
+        let code_hash = code.hash_slow();
+        bytecodes.insert(code_hash, EvmCode::from(code));
+        accounts.push((
+            address,
+            EvmAccount {
+                code_hash: Some(code_hash),
+                ..EvmAccount::default()
+            },
+        ));
+    }
+    let storage = InMemoryStorage::new(accounts, Some(&bytecodes), []);
+    // Raw transfers to the next account, with every other transaction
+    // calling the counter through the caller contract instead.
+    let txs: Vec<TxEnv> = (1..=block_size)
+        .map(|i| {
+            let (to, value, gas_limit) = if i % 2 == 0 {
+                (caller_address, U256::ZERO, 100_000)
+            } else {
+                (
+                    Address::from(U160::from(i % block_size + 1)),
+                    U256::from(1),
+                    common::RAW_TRANSFER_GAS_LIMIT,
+                )
+            };
+            TxEnv {
+                caller: Address::from(U160::from(i)),
+                transact_to: TransactTo::Call(to),
+                value,
+                gas_limit,
+                gas_price: U256::from(1),
+                ..TxEnv::default()
+            }
+        })
+        .collect();
+    let chain = PevmEthereum::mainnet();
+    let concurrency_level = thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+
+    let (_, traces) = Pevm::default()
+        .execute_revm_parallel_with_inspector(
+            &storage,
+            &chain,
+            SpecId::LATEST,
+            BlockEnv::default(),
+            txs.clone(),
+            &ParityTracer,
+            concurrency_level,
+        )
+        .unwrap();
+    for (tx, tx_traces) in txs.iter().zip(&traces) {
+        let to = *tx.transact_to.to().unwrap();
+        // The top-level call gets the gas left after the intrinsic gas.
+        let top_call = Action::Call(CallAction {
+            from: tx.caller,
+            call_type: CallType::Call,
+            gas: tx.gas_limit - common::RAW_TRANSFER_GAS_LIMIT,
+            input: Bytes::new(),
+            to,
+            value: tx.value,
+        });
+        if to == caller_address {
+            assert_eq!(tx_traces.len(), 2);
+            assert_eq!(tx_traces[0].action, top_call);
+            assert_eq!(tx_traces[0].subtraces, 1);
+            assert!(tx_traces[0].trace_address.is_empty());
+            assert!(tx_traces[0].error.is_none());
+            let nested_trace = &tx_traces[1];
+            let Action::Call(nested_call) = &nested_trace.action else {
+                panic!("Unexpected nested action");
+            };
+            assert_eq!(nested_call.from, caller_address);
+            assert_eq!(nested_call.to, counter_address);
+            assert_eq!(nested_call.call_type, CallType::Call);
+            assert_eq!(nested_trace.subtraces, 0);
+            assert_eq!(nested_trace.trace_address, vec![0]);
+            assert!(matches!(nested_trace.result, Some(TraceOutput::Call(_))));
+        } else {
+            assert_eq!(
+                tx_traces,
+                &vec![TransactionTrace {
+                    action: top_call,
+                    error: None,
+                    result: Some(TraceOutput::Call(CallOutput {
+                        gas_used: 0,
+                        output: Bytes::new(),
+                    })),
+                    subtraces: 0,
+                    trace_address: Vec::new(),
+                }]
+            );
+        }
+    }
+
+    // The same traces from a sequential fallback.
+    let (_, sequential_traces) = Pevm::new(ExecutionMode::Sync)
+        .with_strategy(PevmStrategy {
+            retry: RetryPolicy::BoundedThenSequential { max_retries: 0 },
+            ..PevmStrategy::default()
+        })
+        .execute_revm_parallel_with_inspector(
+            &storage,
+            &chain,
+            SpecId::LATEST,
+            BlockEnv::default(),
+            txs,
+            &ParityTracer,
+            concurrency_level,
+        )
+        .unwrap();
+    assert_eq!(sequential_traces, traces);
+}
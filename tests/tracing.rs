@@ -0,0 +1,75 @@
+// Test instrumenting executions with tracing spans.
+#![cfg(feature = "tracing")]
+
+use std::{
+    num::NonZeroUsize,
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use pevm::{chain::PevmEthereum, InMemoryStorage, Pevm};
+use revm::primitives::{
+    alloy_primitives::U160, env::TxEnv, Address, BlockEnv, SpecId, TransactTo, U256,
+};
+use tracing::{span, Subscriber};
+use tracing_subscriber::{layer::Context, prelude::*, registry::LookupSpan, Layer};
+
+pub mod common;
+
+// Record the names of the new spans.
+#[derive(Debug, Default, Clone)]
+struct SpanNames(Arc<Mutex<Vec<&'static str>>>);
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for SpanNames {
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, _id: &span::Id, _ctx: Context<'_, S>) {
+        self.0.lock().unwrap().push(attrs.metadata().name());
+    }
+}
+
+#[test]
+fn execution_spans() {
+    let span_names = SpanNames::default();
+    tracing_subscriber::registry()
+        .with(span_names.clone())
+        .init();
+
+    let block_size = 1_000; // number of transactions
+    let storage = InMemoryStorage::new((0..=block_size).map(common::mock_account), None, []);
+    // Mock `block_size` transactions sending some tokens to the next account.
+    let txs: Vec<TxEnv> = (1..=block_size)
+        .map(|i| TxEnv {
+            caller: Address::from(U160::from(i)),
+            transact_to: TransactTo::Call(Address::from(U160::from(i % block_size + 1))),
+            value: U256::from(1),
+            gas_limit: common::RAW_TRANSFER_GAS_LIMIT,
+            gas_price: U256::from(1),
+            ..TxEnv::default()
+        })
+        .collect();
+    let chain = PevmEthereum::mainnet();
+    let concurrency_level = thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+    Pevm::default()
+        .execute_revm_parallel(
+            &storage,
+            &chain,
+            SpecId::LATEST,
+            BlockEnv::default(),
+            txs,
+            concurrency_level,
+        )
+        .unwrap();
+
+    let span_names = span_names.0.lock().unwrap();
+    for name in ["execute_parallel", "worker", "execution", "vm_execute"] {
+        assert!(span_names.contains(&name), "missing span {name}");
+    }
+    // Every transaction executes at least once.
+    let count = |name: &str| {
+        span_names
+            .iter()
+            .filter(|span_name| **span_name == name)
+            .count()
+    };
+    assert!(count("execution") >= block_size);
+    assert!(count("vm_execute") >= count("execution"));
+}
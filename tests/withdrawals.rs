@@ -0,0 +1,85 @@
+// Test withdrawals (EIP-4895) that are credited to accounts after all transactions.
+
+use alloy_primitives::{Address, U256};
+use alloy_rpc_types::{Block, BlockTransactions, Transaction, Withdrawal};
+use pevm::{chain::PevmEthereum, InMemoryStorage};
+use revm::primitives::alloy_primitives::U160;
+use std::num::NonZeroUsize;
+
+pub mod common;
+
+#[test]
+fn withdrawals_after_transactions() {
+    let (sender, sender_account) = common::mock_account(1);
+    let recipient = Address::from(U160::from(2));
+    let validator = Address::from(U160::from(3));
+    let storage = InMemoryStorage::new(
+        [common::mock_account(0), (sender, sender_account)],
+        None,
+        [],
+    );
+    let block = Block {
+        header: common::MOCK_BLOCK_HEADER.clone(),
+        transactions: BlockTransactions::Full(vec![Transaction {
+            transaction_type: Some(2),
+            nonce: 1,
+            from: sender,
+            to: Some(recipient),
+            value: U256::from(1),
+            max_fee_per_gas: Some(1),
+            gas: common::RAW_TRANSFER_GAS_LIMIT.into(),
+            ..Transaction::default()
+        }]),
+        withdrawals: Some(vec![
+            // Top up the transfer recipient
+            Withdrawal {
+                index: 0,
+                validator_index: 0,
+                address: recipient,
+                amount: 2,
+            },
+            // Multiple withdrawals to the same fresh account
+            Withdrawal {
+                index: 1,
+                validator_index: 1,
+                address: validator,
+                amount: 3,
+            },
+            Withdrawal {
+                index: 2,
+                validator_index: 1,
+                address: validator,
+                amount: 4,
+            },
+            // Zero-amount withdrawals must not create empty accounts
+            Withdrawal {
+                index: 3,
+                validator_index: 2,
+                address: Address::from(U160::from(4)),
+                amount: 0,
+            },
+        ]),
+        ..Block::default()
+    };
+    common::test_execute_alloy(&storage, &PevmEthereum::mainnet(), block.clone(), false);
+
+    let result = pevm::execute(
+        &storage,
+        &PevmEthereum::mainnet(),
+        block,
+        NonZeroUsize::MIN,
+        true,
+    )
+    .unwrap();
+    let gwei = U256::from(1_000_000_000);
+    let post_block_state = result.post_block_state;
+    assert_eq!(post_block_state.len(), 2);
+    assert_eq!(
+        post_block_state[&recipient].as_ref().unwrap().balance,
+        U256::from(1) + U256::from(2) * gwei
+    );
+    assert_eq!(
+        post_block_state[&validator].as_ref().unwrap().balance,
+        U256::from(7) * gwei
+    );
+}
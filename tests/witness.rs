@@ -0,0 +1,75 @@
+// Test recording the execution witness of blocks.
+
+use std::{collections::BTreeSet, num::NonZeroUsize};
+
+use pevm::{chain::PevmEthereum, InMemoryStorage, Pevm, Storage, WitnessStorage};
+use revm::primitives::{
+    alloy_primitives::U160, env::TxEnv, Address, BlockEnv, SpecId, TransactTo, U256,
+};
+
+pub mod common;
+
+#[test]
+fn witness_contended_counter() {
+    let block_size = 100; // number of transactions
+    let contract_address = Address::from(U160::from(block_size + 1));
+    let (accounts, bytecodes) = common::mock_counter_accounts(block_size, contract_address);
+    let storage = InMemoryStorage::new(accounts, Some(&bytecodes), []);
+    // Every transaction increments the same counter.
+    let txs: Vec<TxEnv> = (1..=block_size)
+        .map(|i| TxEnv {
+            caller: Address::from(U160::from(i)),
+            transact_to: TransactTo::Call(contract_address),
+            gas_limit: 100_000,
+            gas_price: U256::from(1),
+            ..TxEnv::default()
+        })
+        .collect();
+    let chain = PevmEthereum::mainnet();
+    let witness_storage = WitnessStorage::new(&storage);
+    let sequential_result = pevm::execute_revm_sequential(
+        &storage,
+        &chain,
+        SpecId::LATEST,
+        BlockEnv::default(),
+        txs.clone(),
+    );
+    let parallel_result = Pevm::default().execute_revm_parallel(
+        &witness_storage,
+        &chain,
+        SpecId::LATEST,
+        BlockEnv::default(),
+        txs,
+        NonZeroUsize::new(8).unwrap(),
+    );
+    common::assert_execution_result(&sequential_result, &parallel_result);
+
+    let witness = witness_storage.witness();
+    for i in 1..=block_size {
+        assert!(witness.accounts.contains(&Address::from(U160::from(i))));
+    }
+    assert!(witness.accounts.contains(&contract_address));
+    // Only the counter's pre-block value is read from storage.
+    assert_eq!(
+        witness.storage.get(&contract_address),
+        Some(&BTreeSet::from([U256::ZERO]))
+    );
+    assert!(witness
+        .code_hashes
+        .contains(&common::counter_contract().hash_slow()));
+    assert!(witness.block_hashes.is_empty());
+
+    // The witness round-trips through JSON for stateless clients.
+    let json = serde_json::to_string(&witness).unwrap();
+    assert_eq!(
+        serde_json::from_str::<pevm::ExecutionWitness>(&json).unwrap(),
+        witness
+    );
+
+    // Cleared witnesses record the next block from scratch.
+    witness_storage.clear();
+    witness_storage.block_hash(&7).unwrap();
+    let witness = witness_storage.witness();
+    assert!(witness.accounts.is_empty());
+    assert_eq!(witness.block_hashes, BTreeSet::from([7]));
+}